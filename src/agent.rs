@@ -0,0 +1,70 @@
+/// Best-effort name of the program driving gh-guard, e.g. "claude-code".
+/// `GH_GUARD_AGENT` wins; then well-known agent env vars; then the parent
+/// process name (typically the shell or tool that spawned us).
+pub fn detect() -> String {
+    if let Ok(name) = std::env::var("GH_GUARD_AGENT") {
+        if !name.trim().is_empty() {
+            return name.trim().to_string();
+        }
+    }
+    let known = [
+        ("CLAUDECODE", "claude-code"),
+        ("CURSOR_TRACE_ID", "cursor"),
+        ("CODEX_SANDBOX", "codex"),
+        ("AIDER_MODEL", "aider"),
+        ("GITHUB_ACTIONS", "github-actions"),
+    ];
+    for (var, name) in known {
+        if std::env::var_os(var).is_some() {
+            return name.to_string();
+        }
+    }
+    parent_process_name().unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Short hostname of this machine.
+pub fn hostname() -> String {
+    std::process::Command::new("hostname")
+        .arg("-s")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown-host".to_string())
+}
+
+/// The working directory, as `~/…` when under the home directory.
+pub fn workdir() -> String {
+    let Ok(dir) = std::env::current_dir() else {
+        return "?".to_string();
+    };
+    let home = crate::settings::home_dir();
+    if home.as_os_str().is_empty() {
+        return dir.display().to_string();
+    }
+    match dir.strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.display()),
+        _ => dir.display().to_string(),
+    }
+}
+
+/// "host/agent" — the key used to attribute requests to a source.
+pub fn source() -> String {
+    format!("{}/{}", hostname(), detect())
+}
+
+fn parent_process_name() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    let out = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8(out.stdout).ok()?;
+    // `comm` may be a full path on macOS.
+    let name = name.trim().rsplit('/').next()?.trim_start_matches('-');
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,48 @@
+//! "🔓 Allow all": with `[allow_all]` set, cards offer a button that
+//! approves and then lets guarded commands up to `max_severity` run
+//! without asking for `minutes`. It ends on its own; the audit log has the
+//! request it was tapped on and, with reason "allow all", what ran under it.
+
+use crate::guard::Request;
+use crate::settings::AllowAll;
+use crate::state;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "allow_all.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Window {
+    /// Unix time it ends.
+    until: Option<i64>,
+    /// Who tapped the button.
+    by: Option<String>,
+}
+
+/// When the current window ends, if one is open.
+pub fn until() -> Option<DateTime<Local>> {
+    let until = state::load::<Window>(STATE_FILE).until?;
+    let until = DateTime::from_timestamp(until, 0)?.with_timezone(&Local);
+    (until > Local::now()).then_some(until)
+}
+
+/// Open a window of `minutes` from now, for `by`. Returns its end.
+pub fn start(minutes: u64, by: Option<&str>) -> Result<DateTime<Local>> {
+    let secs = i64::try_from(minutes.saturating_mul(60)).unwrap_or(i64::MAX);
+    let until = Local::now().timestamp().saturating_add(secs);
+    let window = Window {
+        until: Some(until),
+        by: by.map(str::to_string),
+    };
+    state::save(STATE_FILE, &window)?;
+    Ok(DateTime::from_timestamp(until, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local))
+}
+
+/// Whether `req` may run without asking under `policy`: a window is open,
+/// it covers the severity, and nothing insists on asking.
+pub fn covers(policy: &AllowAll, req: &Request) -> bool {
+    !req.always_ask && req.severity <= policy.max_severity && until().is_some()
+}
@@ -1,3 +1,12 @@
+use crate::flags::{self, flag, Arg, Flag};
+use crate::pr;
+use crate::settings::ApiRule;
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
 /// Parsed metadata from a `gh api` invocation.
 #[derive(Debug, Default)]
 pub struct ApiArgs {
@@ -7,10 +16,39 @@ pub struct ApiArgs {
     pub endpoint: Option<String>,
     /// key=value pairs collected from --field / -f / --raw-field / -F.
     pub fields: Vec<String>,
-    /// True for POST, PATCH, PUT, DELETE — the methods that mutate state.
+    /// Indexes into `fields` of `-F key=@file` values, which gh reads from
+    /// the file.
+    pub file_fields: Vec<usize>,
+    /// `--input`: file (`-` for stdin) whose contents gh sends as the body.
+    pub input: Option<String>,
+    /// The `--input` file's contents, when they are JSON.
+    pub input_json: Option<serde_json::Value>,
+    /// `--hostname`: the GitHub host gh sends the request to instead of the
+    /// default one.
+    pub hostname: Option<String>,
+    /// True for every method but GET and HEAD.
     pub is_mutating: bool,
 }
 
+/// `gh api` flags, so values are not mistaken for the endpoint.
+const FLAGS: &[Flag] = &[
+    flag("method", Some('X'), true),
+    flag("field", Some('F'), true),
+    flag("raw-field", Some('f'), true),
+    flag("header", Some('H'), true),
+    flag("jq", Some('q'), true),
+    flag("template", Some('t'), true),
+    flag("preview", Some('p'), true),
+    flag("input", None, true),
+    flag("cache", None, true),
+    flag("hostname", None, true),
+    flag("include", Some('i'), false),
+    flag("paginate", None, false),
+    flag("slurp", None, false),
+    flag("silent", None, false),
+    flag("verbose", None, false),
+];
+
 /// Scan raw `gh api` flags (everything after the "api" token) to extract
 /// the method, endpoint, and fields we show in the approval notification.
 /// Unknown flags are silently ignored; the original slice is always passed
@@ -19,60 +57,1014 @@ pub fn parse_api_args(args: &[String]) -> ApiArgs {
     let mut method = String::new();
     let mut endpoint: Option<String> = None;
     let mut fields: Vec<String> = Vec::new();
-    let mut i = 0;
-
-    while i < args.len() {
-        let arg = args[i].as_str();
-        let next = || args.get(i + 1).map(String::as_str);
+    let mut file_fields = Vec::new();
+    let mut hostname = None;
+    let mut input = None;
 
+    for arg in flags::split(args, FLAGS) {
         match arg {
-            "--method" | "-X" => {
-                if let Some(v) = next() {
-                    method = v.to_uppercase();
-                    i += 2;
-                    continue;
+            // Repeated: the last one wins, as in gh.
+            Arg::Flag {
+                name: "method",
+                value: Some(v),
+            } => method = v.trim().to_uppercase(),
+            Arg::Flag {
+                name: name @ ("field" | "raw-field"),
+                value: Some(v),
+            } => {
+                // Only typed fields read files; -f sends "@path" as text.
+                let from_file = v.split_once('=').is_some_and(|(_, v)| v.starts_with('@'));
+                if name == "field" && from_file {
+                    file_fields.push(fields.len());
                 }
+                fields.push(v.to_string());
             }
-            "--field" | "-f" | "--raw-field" | "-F" => {
-                if let Some(v) = next() {
-                    fields.push(v.to_string());
-                    i += 2;
-                    continue;
-                }
+            Arg::Flag {
+                name: "hostname",
+                value: Some(v),
+            } => hostname = Some(crate::host::normalize(v)).filter(|h| !h.is_empty()),
+            Arg::Flag {
+                name: "input",
+                value: Some(v),
+            } => input = Some(v.to_string()),
+            // First positional is the endpoint.
+            Arg::Positional(p) if endpoint.is_none() => endpoint = Some(p.to_string()),
+            _ => {}
+        }
+    }
+
+    // Without -X, gh sends POST when there is a body to send: fields or
+    // --input. An explicit GET keeps the fields, as query parameters.
+    if method.is_empty() {
+        method = if fields.is_empty() && input.is_none() {
+            "GET".to_string()
+        } else {
+            "POST".to_string()
+        };
+    }
+
+    // gh sends whatever method it is given, so only the safe ones pass.
+    let is_mutating = !matches!(method.as_str(), "GET" | "HEAD");
+
+    ApiArgs {
+        method,
+        endpoint,
+        fields,
+        file_fields,
+        input,
+        input_json: None,
+        hostname,
+        is_mutating,
+    }
+}
+
+impl ApiArgs {
+    /// The fields and `--input` as a card shows them, one `key=value` each.
+    pub fn shown_fields(&self) -> Vec<String> {
+        let input = self.input.iter().map(|i| format!("--input=@{i}"));
+        self.fields.iter().cloned().chain(input).collect()
+    }
+
+    /// The body's value at `path` (e.g. `["config", "url"]`): from a field
+    /// keyed the way gh nests them (`config[url]=…`), else from the
+    /// `--input` JSON.
+    pub fn body_value(&self, path: &[&str]) -> Option<String> {
+        let (first, rest) = path.split_first()?;
+        let key: String = std::iter::once(first.to_string())
+            .chain(rest.iter().map(|p| format!("[{p}]")))
+            .collect();
+        let field = self
+            .fields
+            .iter()
+            .find_map(|f| f.split_once('=').filter(|(k, _)| *k == key));
+        if let Some((_, value)) = field {
+            return Some(value.to_string());
+        }
+        let value = path
+            .iter()
+            .try_fold(self.input_json.as_ref()?, |v, p| v.get(p))?;
+        Some(match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+/// Largest `--input` file read whole to look inside its JSON.
+const INPUT_JSON_BYTES: u64 = 1 << 20;
+
+/// Most of a file shown on a card; the card cuts values shorter still.
+const FILE_PREVIEW_BYTES: u64 = 4096;
+
+/// Replace each `-F key=@file` value, and the `--input` file, with the
+/// file's size and contents, so the approver sees the payload rather than
+/// a path. `-` (stdin) is left alone: reading it here would take it away
+/// from gh.
+pub fn read_field_files(parsed: &mut ApiArgs) {
+    if let Some(input) = parsed.input.as_mut().filter(|i| *i != "-") {
+        parsed.input_json = std::fs::metadata(&*input)
+            .ok()
+            .filter(|m| m.len() <= INPUT_JSON_BYTES)
+            .and_then(|_| std::fs::read(&*input).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        *input = format!("{input} {}", describe_file(Path::new(input)));
+    }
+    for &i in &parsed.file_fields {
+        let Some((key, path)) = parsed.fields[i]
+            .split_once("=@")
+            .filter(|(_, path)| *path != "-")
+        else {
+            continue;
+        };
+        parsed.fields[i] = format!("{key}=@{path} {}", describe_file(Path::new(path)));
+    }
+}
+
+/// "(1234 B)" and the start of the file, or why it cannot be shown.
+fn describe_file(path: &Path) -> String {
+    let read = || -> std::io::Result<(u64, Vec<u8>)> {
+        let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let mut head = Vec::new();
+        file.take(FILE_PREVIEW_BYTES).read_to_end(&mut head)?;
+        Ok((size, head))
+    };
+    match read() {
+        Ok((size, head)) => match std::str::from_utf8(&head) {
+            Ok(text) => format!("({size} B)\n{text}"),
+            // Cut inside a character: show what is whole.
+            Err(e) if e.error_len().is_none() => {
+                format!(
+                    "({size} B)\n{}",
+                    String::from_utf8_lossy(&head[..e.valid_up_to()])
+                )
             }
-            // Flags that consume a value but whose value we don't care about.
-            "--header" | "-H" | "--jq" | "-q" | "--template" | "-t"
-            | "--input" | "--cache" => {
-                i += 2;
-                continue;
+            Err(_) => format!("({size} B, binary)"),
+        },
+        Err(e) => format!("(unreadable: {e})"),
+    }
+}
+
+/// Path segments of an endpoint, ignoring any query string or fragment
+/// (e.g. "/repos/o/r/pulls?state=open" → ["repos", "o", "r", "pulls"]).
+/// Full URLs are reduced to their API path, so
+/// "https://ghe.example.com/api/v3/repos/o/r" gives ["repos", "o", "r"].
+pub fn endpoint_segments(endpoint: &str) -> Vec<&str> {
+    let path = endpoint.split(['?', '#']).next().unwrap_or("");
+    let path = match path.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map_or("", |(_, p)| p),
+        None => path,
+    };
+    let path = path.trim_start_matches('/');
+    let path = path.strip_prefix("api/v3/").unwrap_or(path);
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// `endpoint` as GitHub reads its path: percent-escapes decoded and "."
+/// and ".." segments resolved, so "repos/o/x/../%72" is "repos/o/r".
+/// Escaped "/", "?", "#" and "%" stay escaped, as they would otherwise
+/// change where segments (or the path) end. Query and fragment are kept.
+pub fn normalize_endpoint(endpoint: &str) -> String {
+    let end = endpoint.find(['?', '#']).unwrap_or(endpoint.len());
+    let (path, tail) = endpoint.split_at(end);
+    let start = match path.find("://") {
+        Some(i) => path[i + 3..].find('/').map_or(path.len(), |j| i + 3 + j),
+        None => 0,
+    };
+    let (origin, path) = path.split_at(start);
+    let (slash, path) = match path.strip_prefix('/') {
+        Some(rest) => ("/", rest),
+        None => ("", path),
+    };
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/') {
+        match percent_decode(segment).as_str() {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s.to_string()),
+        }
+    }
+    format!("{origin}{slash}{}{tail}", segments.join("/"))
+}
+
+/// `segment` with its "%XX" escapes decoded, bar those of `/?#%`.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = segment.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(b) if !b"/?#%".contains(&b) => {
+                out.push(b);
+                i += 3;
             }
             _ => {
-                // --flag=value forms.
-                if let Some(v) = arg.strip_prefix("--method=") {
-                    method = v.to_uppercase();
-                } else if let Some(v) = arg.strip_prefix("--field=") {
-                    fields.push(v.to_string());
-                } else if let Some(v) = arg.strip_prefix("--raw-field=") {
-                    fields.push(v.to_string());
-                } else if !arg.starts_with('-') && endpoint.is_none() {
-                    // First non-flag token is the endpoint.
-                    endpoint = Some(arg.to_string());
-                }
+                out.push(bytes[i]);
+                i += 1;
             }
         }
-        i += 1;
     }
+    String::from_utf8_lossy(&out).into_owned()
+}
 
-    // gh defaults to POST when --field flags are present, GET otherwise.
-    if method.is_empty() {
-        method = if fields.is_empty() {
-            "GET".to_string()
+/// `owner/name` of the repository a `DELETE /repos/{owner}/{repo}` removes.
+pub fn deleted_repo(api: &ApiArgs) -> Option<String> {
+    if api.method != "DELETE" {
+        return None;
+    }
+    match endpoint_segments(api.endpoint.as_deref()?)[..] {
+        ["repos", owner, name] => Some(format!("{owner}/{name}")),
+        _ => None,
+    }
+}
+
+/// True for a repository's or organisation's webhooks, which send events
+/// to whatever URL they are given.
+pub fn is_webhook(segments: &[&str]) -> bool {
+    matches!(
+        segments,
+        ["repos", _, _, "hooks", ..] | ["orgs", _, "hooks", ..]
+    )
+}
+
+/// What a call to a branch's protection (`/repos/*/branches/*/protection`)
+/// does, as far as the endpoint and body tell.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProtectionChange {
+    pub branch: String,
+    /// DELETE of the whole protection.
+    pub all_removed: bool,
+    /// Protections turned off: set to `null` or `false` (`true` for the
+    /// `allow_*` ones), or a DELETE of their endpoint.
+    pub removed: Vec<String>,
+    /// Protections set to something else.
+    pub changed: Vec<String>,
+}
+
+/// The branch-protection change `api` makes, or `None` for other endpoints.
+pub fn protection_change(api: &ApiArgs) -> Option<ProtectionChange> {
+    let segments = endpoint_segments(api.endpoint.as_deref().unwrap_or(""));
+    let ["repos", _, _, "branches", branch, "protection", rest @ ..] = segments.as_slice() else {
+        return None;
+    };
+    let mut change = ProtectionChange {
+        branch: branch.to_string(),
+        ..Default::default()
+    };
+    if !rest.is_empty() {
+        let part = rest.join("/");
+        if api.method == "DELETE" {
+            change.removed.push(part);
         } else {
-            "POST".to_string()
+            change.changed.push(part);
+        }
+        return Some(change);
+    }
+    if api.method == "DELETE" {
+        change.all_removed = true;
+        return Some(change);
+    }
+    // The body's top-level keys, from fields (`a[b]=…` sets `a`) and --input.
+    let mut keys: Vec<String> = api
+        .fields
+        .iter()
+        .filter_map(|f| f.split_once('=').map(|(k, _)| k))
+        .map(|k| k.split('[').next().unwrap_or(k).to_string())
+        .collect();
+    if let Some(body) = api.input_json.as_ref().and_then(|v| v.as_object()) {
+        keys.extend(body.keys().cloned());
+    }
+    let mut seen = std::collections::HashSet::new();
+    keys.retain(|k| seen.insert(k.clone()));
+    for key in keys {
+        let off = if key.starts_with("allow_") {
+            "true"
+        } else {
+            "false"
         };
+        match api.body_value(&[&key]).as_deref() {
+            Some(v) if v == "null" || v == off => change.removed.push(key),
+            _ => change.changed.push(key),
+        }
+    }
+    Some(change)
+}
+
+/// Who gets (or loses) access to a repository through its collaborators
+/// or a team's repositories.
+#[derive(Debug, PartialEq)]
+pub struct AccessChange {
+    /// A user's login, or `org/team-slug` (`team` set).
+    pub grantee: String,
+    pub team: bool,
+    pub repo: String,
+    /// `pull`, `push`, `admin`, …; `None` when the call leaves it to
+    /// GitHub's default.
+    pub permission: Option<String>,
+    /// DELETE: access is taken away.
+    pub removed: bool,
+}
+
+/// The access change `api` makes on `/repos/*/collaborators/*` or
+/// `/orgs/*/teams/*/repos/*/*` (`/teams/*/repos/*/*` for the legacy ID
+/// form), or `None` for other endpoints.
+pub fn access_change(api: &ApiArgs) -> Option<AccessChange> {
+    let segments = endpoint_segments(api.endpoint.as_deref().unwrap_or(""));
+    let (grantee, team, repo) = match segments.as_slice() {
+        ["repos", owner, repo, "collaborators", user] => (user.to_string(), false, (owner, repo)),
+        ["orgs", org, "teams", slug, "repos", owner, repo] => {
+            (format!("{org}/{slug}"), true, (owner, repo))
+        }
+        ["teams", id, "repos", owner, repo] => (id.to_string(), true, (owner, repo)),
+        _ => return None,
+    };
+    Some(AccessChange {
+        grantee,
+        team,
+        repo: format!("{}/{}", repo.0, repo.1),
+        permission: api.body_value(&["permission"]),
+        removed: api.method == "DELETE",
+    })
+}
+
+/// A workflow file written through the contents API: code that CI runs
+/// with the repository's secrets.
+#[derive(Debug, PartialEq)]
+pub struct WorkflowWrite {
+    /// `.github/workflows/…` within the repository.
+    pub path: String,
+    /// The new contents, decoded from the body's base64 `content`; `None`
+    /// when they are not in the fields or `--input`.
+    pub content: Option<String>,
+}
+
+/// The workflow file a PUT to `/repos/*/contents/.github/workflows/*`
+/// writes, or `None` for other calls.
+pub fn workflow_write(api: &ApiArgs) -> Option<WorkflowWrite> {
+    let segments = endpoint_segments(api.endpoint.as_deref().unwrap_or(""));
+    let ["repos", _, _, "contents", ".github", "workflows", file @ ..] = segments.as_slice() else {
+        return None;
+    };
+    if api.method != "PUT" || file.is_empty() {
+        return None;
+    }
+    let content = api.body_value(&["content"]).and_then(|value| {
+        // `-F content=@file` shows as "@file (N B)" and the file's start.
+        let encoded = match value.split_once('\n') {
+            Some((_, text)) if value.starts_with('@') => text.to_string(),
+            _ => value,
+        };
+        decode_base64(&encoded)
+    });
+    Some(WorkflowWrite {
+        path: format!(".github/workflows/{}", file.join("/")),
+        content,
+    })
+}
+
+/// Text from base64 as the contents API takes it, wrapped or not. A start
+/// cut off mid-group decodes as far as it goes.
+fn decode_base64(encoded: &str) -> Option<String> {
+    let mut clean: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+    if !clean.is_ascii() {
+        return None;
     }
+    if !clean.ends_with('=') {
+        clean.truncate(clean.len() - clean.len() % 4);
+    }
+    if clean.is_empty() {
+        return None;
+    }
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(clean)
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
 
-    let is_mutating = matches!(method.as_str(), "POST" | "PATCH" | "PUT" | "DELETE");
+/// The first of the `[api] deny` patterns that `method` on `endpoint`
+/// matches, if any. They are matched against "METHOD /repos/o/r/…", with
+/// the endpoint normalized first so no spelling of a path slips past.
+pub fn denied<'a>(patterns: &'a [String], method: &str, endpoint: &str) -> Option<&'a str> {
+    let endpoint = normalize_endpoint(endpoint);
+    let call = format!("{method} /{}", endpoint_segments(&endpoint).join("/"));
+    patterns
+        .iter()
+        .map(String::as_str)
+        .find(|p| crate::profile::wildcard_matches(p, &call))
+}
 
-    ApiArgs { method, endpoint, fields, is_mutating }
+/// The first of `rules` that covers `method` on `endpoint`, if any. Their
+/// patterns are matched against the normalized path as "/repos/o/r/…".
+pub fn matching_rule<'a>(
+    rules: &'a [ApiRule],
+    method: &str,
+    endpoint: &str,
+) -> Result<Option<&'a ApiRule>> {
+    let endpoint = normalize_endpoint(endpoint);
+    let path = format!("/{}", endpoint_segments(&endpoint).join("/"));
+    for rule in rules {
+        let methods = &rule.methods;
+        if !methods.is_empty() && !methods.iter().any(|m| m.eq_ignore_ascii_case(method)) {
+            continue;
+        }
+        let re = regex::Regex::new(&rule.endpoint)
+            .with_context(|| format!("Invalid [[api_rule]] endpoint {:?}", rule.endpoint))?;
+        if re.is_match(&path) {
+            return Ok(Some(rule));
+        }
+    }
+    Ok(None)
+}
+
+/// True for who belongs to an organisation or its teams:
+/// `/orgs/*/memberships/*`, `/orgs/*/members/*` and `/orgs/*/teams/…`
+/// (bar a team's repositories, which [`access_change`] covers).
+pub fn is_membership(segments: &[&str]) -> bool {
+    match segments {
+        ["orgs", _, "memberships" | "members", _] => true,
+        ["orgs", _, "teams", _, "repos", ..] => false,
+        ["orgs", _, "teams", ..] => true,
+        _ => false,
+    }
+}
+
+/// A change to an organisation's or team's members, or to a team itself.
+#[derive(Debug, PartialEq)]
+pub struct MembershipChange {
+    /// The user added or removed; `None` when the team itself is created,
+    /// changed or deleted.
+    pub user: Option<String>,
+    /// `org`, or `org/team-slug` (`team` set).
+    pub group: String,
+    pub team: bool,
+    /// `role` from the body: `admin` / `member`, `maintainer` / `member`.
+    pub role: Option<String>,
+    pub removed: bool,
+    /// POST creating the team.
+    pub created: bool,
+}
+
+/// The membership change `api` makes, as far as its endpoint says, or
+/// `None` for other endpoints (and team endpoints it cannot describe).
+pub fn membership_change(api: &ApiArgs) -> Option<MembershipChange> {
+    let segments = endpoint_segments(api.endpoint.as_deref().unwrap_or(""));
+    let (user, group, team) = match segments.as_slice() {
+        ["orgs", org, "memberships" | "members", user] => (Some(*user), org.to_string(), false),
+        ["orgs", org, "teams", slug, "memberships", user] => {
+            (Some(*user), format!("{org}/{slug}"), true)
+        }
+        ["orgs", org, "teams", slug] => (None, format!("{org}/{slug}"), true),
+        ["orgs", org, "teams"] if api.method == "POST" => {
+            let name = api.body_value(&["name"]).unwrap_or_default();
+            (None, format!("{org}/{name}"), true)
+        }
+        _ => return None,
+    };
+    Some(MembershipChange {
+        user: user.map(str::to_string),
+        group,
+        team,
+        role: api.body_value(&["role"]),
+        removed: api.method == "DELETE",
+        created: user.is_none() && api.method == "POST",
+    })
+}
+
+/// `owner/repo` targeted by a `/repos/{owner}/{repo}/...` endpoint, or by
+/// a team's access to it, if any.
+pub fn repo_from_endpoint(endpoint: &str) -> Option<String> {
+    match endpoint_segments(endpoint).as_slice() {
+        ["repos", owner, repo, ..]
+        | ["orgs", _, "teams", _, "repos", owner, repo]
+        | ["teams", _, "repos", owner, repo] => Some(format!("{owner}/{repo}")),
+        _ => None,
+    }
+}
+
+/// `endpoint` with gh's `{owner}`, `{repo}` and `{branch}` placeholders
+/// filled in the way gh will: from `GH_REPO` or the checkout.
+pub fn expand_placeholders(endpoint: &str) -> String {
+    if !endpoint.contains('{') {
+        return endpoint.to_string();
+    }
+    let mut out = endpoint.to_string();
+    if let Some((owner, repo)) = pr::target_repo(None)
+        .as_deref()
+        .and_then(|r| r.split_once('/'))
+    {
+        out = out.replace("{owner}", owner).replace("{repo}", repo);
+    }
+    if let Some(branch) = pr::current_branch() {
+        out = out.replace("{branch}", &branch);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn method_spellings_gh_accepts() {
+        for form in [
+            &["-X", "delete", "repos/o/r"][..],
+            &["-XDELETE", "repos/o/r"],
+            &["-X=DELETE", "repos/o/r"],
+            &["-iX", "DELETE", "repos/o/r"],
+            &["repos/o/r", "--method=DELETE"],
+            &["-X", "GET", "repos/o/r", "-X", "DELETE"],
+        ] {
+            let parsed = parse_api_args(&args(form));
+            assert_eq!(parsed.method, "DELETE", "{form:?}");
+            assert!(parsed.is_mutating, "{form:?}");
+            assert_eq!(parsed.endpoint.as_deref(), Some("repos/o/r"), "{form:?}");
+        }
+    }
+
+    #[test]
+    fn method_is_inferred_as_gh_does() {
+        let method = |form: &[&str]| {
+            let parsed = parse_api_args(&args(form));
+            (parsed.method, parsed.is_mutating)
+        };
+        assert_eq!(method(&["repos/o/r"]), ("GET".to_string(), false));
+        assert_eq!(
+            method(&["repos/o/r/issues", "-f", "title=x"]),
+            ("POST".to_string(), true)
+        );
+        assert_eq!(
+            method(&["repos/o/r/issues", "--input", "issue.json"]),
+            ("POST".to_string(), true)
+        );
+        assert_eq!(
+            method(&["search/issues", "-X", "GET", "-f", "q=bug"]),
+            ("GET".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn flag_values_are_not_the_endpoint() {
+        let parsed = parse_api_args(&args(&["-p", "nebula", "--jq", ".x", "user"]));
+        assert_eq!(parsed.endpoint.as_deref(), Some("user"));
+        assert!(!parsed.is_mutating);
+    }
+
+    #[test]
+    fn typed_fields_from_files_are_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.md");
+        std::fs::write(&notes, "## Summary\nAll <good>.").unwrap();
+        let bin = dir.path().join("x.bin");
+        std::fs::write(&bin, [0xff, 0xfe, 0]).unwrap();
+        let at = |p: &Path| format!("@{}", p.display());
+        let raw = args(&[
+            "-F",
+            &format!("body={}", at(&notes)),
+            "-f",
+            &format!("note={}", at(&notes)),
+            "-F",
+            &format!("blob={}", at(&bin)),
+            "-F",
+            "gone=@/nonexistent/x",
+            "-F",
+            "stdin=@-",
+            "repos/o/r/issues",
+        ]);
+
+        let mut parsed = parse_api_args(&raw);
+        assert_eq!(parsed.file_fields, [0, 2, 3, 4]);
+        read_field_files(&mut parsed);
+
+        assert_eq!(
+            parsed.fields[0],
+            format!("body={} (22 B)\n## Summary\nAll <good>.", at(&notes))
+        );
+        assert_eq!(parsed.fields[1], format!("note={}", at(&notes)));
+        assert_eq!(parsed.fields[2], format!("blob={} (3 B, binary)", at(&bin)));
+        assert!(parsed.fields[3].starts_with("gone=@/nonexistent/x (unreadable: "));
+        assert_eq!(parsed.fields[4], "stdin=@-");
+    }
+
+    #[test]
+    fn body_values_from_fields_or_input() {
+        let parsed = parse_api_args(&args(&[
+            "repos/o/r/hooks",
+            "-f",
+            "config[url]=https://evil.example/x",
+        ]));
+        assert_eq!(
+            parsed.body_value(&["config", "url"]).as_deref(),
+            Some("https://evil.example/x")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let body = dir.path().join("hook.json");
+        std::fs::write(
+            &body,
+            r#"{"config": {"url": "https://x.test", "insecure_ssl": 1}}"#,
+        )
+        .unwrap();
+        let mut parsed = parse_api_args(&args(&[
+            "orgs/acme/hooks",
+            "--input",
+            &body.display().to_string(),
+        ]));
+        read_field_files(&mut parsed);
+        assert_eq!(
+            parsed.body_value(&["config", "url"]).as_deref(),
+            Some("https://x.test")
+        );
+        assert_eq!(
+            parsed.body_value(&["config", "insecure_ssl"]).as_deref(),
+            Some("1")
+        );
+        assert_eq!(parsed.body_value(&["events"]), None);
+        assert!(is_webhook(&endpoint_segments("orgs/acme/hooks")));
+        assert!(!is_webhook(&endpoint_segments("repos/o/r/hooksy")));
+    }
+
+    #[test]
+    fn protection_changes_are_spelled_out() {
+        let change = |list: &[&str]| protection_change(&parse_api_args(&args(list)));
+        assert_eq!(change(&["-X", "PUT", "repos/o/r/branches/main"]), None);
+        assert_eq!(
+            change(&["-X", "DELETE", "repos/o/r/branches/main/protection"]),
+            Some(ProtectionChange {
+                branch: "main".into(),
+                all_removed: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            change(&[
+                "-X",
+                "DELETE",
+                "repos/o/r/branches/main/protection/enforce_admins"
+            ])
+            .unwrap()
+            .removed,
+            ["enforce_admins"]
+        );
+        let put = change(&[
+            "-X",
+            "PUT",
+            "repos/o/r/branches/main/protection",
+            "-F",
+            "required_status_checks=null",
+            "-F",
+            "enforce_admins=false",
+            "-F",
+            "required_pull_request_reviews[required_approving_review_count]=1",
+            "-F",
+            "allow_force_pushes=true",
+            "-F",
+            "allow_deletions=false",
+        ])
+        .unwrap();
+        assert_eq!(
+            put.removed,
+            [
+                "required_status_checks",
+                "enforce_admins",
+                "allow_force_pushes"
+            ]
+        );
+        assert_eq!(
+            put.changed,
+            ["required_pull_request_reviews", "allow_deletions"]
+        );
+    }
+
+    #[test]
+    fn access_changes_name_who_gets_what() {
+        let change = |list: &[&str]| access_change(&parse_api_args(&args(list)));
+        assert_eq!(
+            change(&[
+                "-X",
+                "PUT",
+                "repos/o/r/collaborators/eve",
+                "-f",
+                "permission=admin"
+            ]),
+            Some(AccessChange {
+                grantee: "eve".into(),
+                team: false,
+                repo: "o/r".into(),
+                permission: Some("admin".into()),
+                removed: false,
+            })
+        );
+        let team = change(&["-X", "DELETE", "orgs/acme/teams/devs/repos/acme/api"]).unwrap();
+        assert_eq!((team.grantee.as_str(), team.team), ("acme/devs", true));
+        assert_eq!((team.repo.as_str(), team.removed), ("acme/api", true));
+        assert_eq!(change(&["-X", "PUT", "repos/o/r/collaborators"]), None);
+    }
+
+    #[test]
+    fn membership_changes_name_who_and_where() {
+        let change = |list: &[&str]| membership_change(&parse_api_args(&args(list)));
+        assert_eq!(
+            change(&["-X", "PUT", "orgs/acme/memberships/eve", "-f", "role=admin"]),
+            Some(MembershipChange {
+                user: Some("eve".into()),
+                group: "acme".into(),
+                team: false,
+                role: Some("admin".into()),
+                removed: false,
+                created: false,
+            })
+        );
+        let removal = change(&["-X", "DELETE", "orgs/acme/teams/devs/memberships/eve"]).unwrap();
+        assert_eq!(
+            (removal.group.as_str(), removal.removed),
+            ("acme/devs", true)
+        );
+        let created = change(&["orgs/acme/teams", "-f", "name=ops"]).unwrap();
+        assert_eq!(
+            (created.group.as_str(), created.created),
+            ("acme/ops", true)
+        );
+
+        assert!(is_membership(&endpoint_segments(
+            "orgs/acme/teams/devs/discussions"
+        )));
+        assert!(!is_membership(&endpoint_segments(
+            "orgs/acme/teams/devs/repos/acme/api"
+        )));
+        assert_eq!(
+            change(&["-X", "POST", "orgs/acme/teams/devs/discussions"]),
+            None
+        );
+    }
+
+    #[test]
+    fn workflow_writes_are_decoded() {
+        let write = |list: &[&str]| workflow_write(&parse_api_args(&args(list)));
+        // "on: push\njobs: {}\n", wrapped as the API's own responses are.
+        let put = write(&[
+            "-X",
+            "PUT",
+            "repos/o/r/contents/.github/workflows/ci.yml",
+            "-f",
+            "message=ci",
+            "-f",
+            "content=b246IHB1c2gK\nam9iczoge30K",
+        ]);
+        assert_eq!(
+            put,
+            Some(WorkflowWrite {
+                path: ".github/workflows/ci.yml".into(),
+                content: Some("on: push\njobs: {}\n".into()),
+            })
+        );
+        let bad = write(&[
+            "-X",
+            "PUT",
+            "repos/o/r/contents/.github/workflows/ci.yml",
+            "-f",
+            "content=%%%",
+        ]);
+        assert_eq!(bad.unwrap().content, None);
+        assert_eq!(write(&["-X", "PUT", "repos/o/r/contents/README.md"]), None);
+        assert_eq!(
+            write(&[
+                "-X",
+                "DELETE",
+                "repos/o/r/contents/.github/workflows/ci.yml"
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn hostname_is_kept() {
+        let raw = args(&["--hostname", "GHE.corp", "-X", "POST", "repos/o/r/issues"]);
+        let parsed = parse_api_args(&raw);
+        assert_eq!(parsed.hostname.as_deref(), Some("ghe.corp"));
+        assert_eq!(parsed.endpoint.as_deref(), Some("repos/o/r/issues"));
+        assert_eq!(parse_api_args(&args(&["user"])).hostname, None);
+    }
+
+    #[test]
+    fn unusual_methods_need_approval() {
+        assert!(parse_api_args(&args(&["-X", "PURGE", "x"])).is_mutating);
+        assert!(!parse_api_args(&args(&["-X", "head", "x"])).is_mutating);
+    }
+
+    #[test]
+    fn repository_deletions_name_the_repository() {
+        let deleted = |list: &[&str]| deleted_repo(&parse_api_args(&args(list)));
+        assert_eq!(
+            deleted(&["-X", "DELETE", "/repos/acme/api"]).as_deref(),
+            Some("acme/api")
+        );
+        assert_eq!(deleted(&["-X", "DELETE", "repos/acme/api/hooks/1"]), None);
+        assert_eq!(deleted(&["-X", "PATCH", "repos/acme/api"]), None);
+    }
+
+    #[test]
+    fn deny_patterns_cover_method_and_path() {
+        let deny = [
+            "DELETE /repos/my-org/*".to_string(),
+            "* /admin/*".to_string(),
+        ];
+        assert_eq!(
+            denied(&deny, "DELETE", "repos/My-Org/api/hooks/1"),
+            Some("DELETE /repos/my-org/*")
+        );
+        assert_eq!(denied(&deny, "PATCH", "repos/my-org/api"), None);
+        assert_eq!(denied(&deny, "DELETE", "repos/my-org-2/api"), None);
+        assert_eq!(denied(&deny, "PUT", "/admin/users/eve"), Some("* /admin/*"));
+
+        let rule = Some("DELETE /repos/my-org/*");
+        assert_eq!(
+            denied(&deny, "DELETE", "repos/other/x/../../my-org/app"),
+            rule
+        );
+        assert_eq!(denied(&deny, "DELETE", "repos/./my%2Dorg/app"), rule);
+        assert_eq!(denied(&deny, "DELETE", "repos/x/%2e%2E/my-org/app"), rule);
+        assert_eq!(
+            denied(&deny, "PUT", "/repos/%2e./admin/users"),
+            Some("* /admin/*")
+        );
+    }
+
+    #[test]
+    fn endpoints_are_normalized_as_github_reads_them() {
+        assert_eq!(
+            normalize_endpoint("/repos/o/x/../%72?a=%2e"),
+            "/repos/o/r?a=%2e"
+        );
+        assert_eq!(normalize_endpoint("repos/o/r/./pulls/"), "repos/o/r/pulls/");
+        assert_eq!(normalize_endpoint("../../user"), "user");
+        assert_eq!(
+            normalize_endpoint("repos/o/r/branches/feature%2Fx/protection"),
+            "repos/o/r/branches/feature%2Fx/protection"
+        );
+        assert_eq!(
+            normalize_endpoint("repos/o/r/%3F%25%zz"),
+            "repos/o/r/%3F%25%zz"
+        );
+        assert_eq!(
+            normalize_endpoint("https://ghe.example.com/api/v3/repos/o/x/%2E%2E/r"),
+            "https://ghe.example.com/api/v3/repos/o/r"
+        );
+    }
+
+    #[test]
+    fn first_matching_rule_applies() {
+        use crate::settings::ApiAction;
+        let rule = |endpoint: &str, methods: &[&str], action| ApiRule {
+            endpoint: endpoint.to_string(),
+            methods: methods.iter().map(|m| m.to_string()).collect(),
+            action,
+        };
+        let rules = [
+            rule(
+                r"^/repos/[^/]+/[^/]+/issues/\d+/labels$",
+                &["post"],
+                ApiAction::AutoApprove,
+            ),
+            rule("^/admin(/|$)", &[], ApiAction::Require),
+        ];
+        let action = |method, endpoint| {
+            matching_rule(&rules, method, endpoint)
+                .unwrap()
+                .map(|r| r.action)
+        };
+        assert_eq!(
+            action("POST", "repos/o/r/issues/7/labels?x=1"),
+            Some(ApiAction::AutoApprove)
+        );
+        assert_eq!(action("DELETE", "repos/o/r/issues/7/labels"), None);
+        assert_eq!(
+            action("PATCH", "/admin/users/eve"),
+            Some(ApiAction::Require)
+        );
+        assert_eq!(action("PATCH", "/administrators"), None);
+        assert_eq!(
+            action("PATCH", "/users/../%61dmin/users/eve"),
+            Some(ApiAction::Require)
+        );
+
+        let bad = [rule("(", &[], ApiAction::Deny)];
+        assert!(matching_rule(&bad, "POST", "x").is_err());
+    }
+
+    /// Anything at all, biased towards tokens the parser reacts to.
+    fn token() -> impl Strategy<Value = String> {
+        prop_oneof![
+            ".*",
+            "-{1,2}[a-zA-Z=-]{0,6}",
+            Just("--".to_string()),
+            Just("-X".to_string()),
+            Just("-f".to_string()),
+        ]
+    }
+
+    /// Arguments that cannot set the method, each kept whole.
+    fn unit() -> impl Strategy<Value = Vec<String>> {
+        let value_flag = prop_oneof![
+            Just("-H"),
+            Just("--header"),
+            Just("-q"),
+            Just("--jq"),
+            Just("-t"),
+            Just("-p"),
+            Just("--input"),
+            Just("--cache"),
+            Just("--hostname"),
+        ];
+        prop_oneof![
+            (value_flag, ".*").prop_map(|(f, v)| vec![f.to_string(), v]),
+            "[a-z0-9/]{1,12}".prop_map(|p| vec![p]),
+            prop_oneof![
+                Just("-i"),
+                Just("--include"),
+                Just("--paginate"),
+                Just("--silent")
+            ]
+            .prop_map(|f| vec![f.to_string()]),
+            "--zz[a-z]{0,4}".prop_map(|f| vec![f]),
+        ]
+    }
+
+    /// A method flag in one of the spellings gh accepts.
+    fn method_unit(m: &str, form: usize) -> Vec<String> {
+        match form % 6 {
+            0 => args(&["-X", m]),
+            1 => vec![format!("-X{m}")],
+            2 => vec![format!("-X={m}")],
+            3 => args(&["--method", m]),
+            4 => vec![format!("--method={m}")],
+            _ => args(&["-iX", m]),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(raw in proptest::collection::vec(token(), 0..12)) {
+            let parsed = parse_api_args(&raw);
+            prop_assert!(!parsed.method.is_empty());
+        }
+
+        #[test]
+        fn mutating_methods_are_never_read_only(
+            units in proptest::collection::vec(unit(), 0..8),
+            at in any::<prop::sample::Index>(),
+            method in prop_oneof![
+                Just("POST".to_string()),
+                Just("patch".to_string()),
+                Just("Put".to_string()),
+                Just("DELETE".to_string()),
+                "[A-Z]{3,8}".prop_filter("safe method", |m| m != "GET" && m != "HEAD"),
+            ],
+            form in 0usize..6,
+        ) {
+            let mut units = units;
+            units.insert(at.index(units.len() + 1), method_unit(&method, form));
+            let raw: Vec<String> = units.concat();
+            let parsed = parse_api_args(&raw);
+            prop_assert_eq!(parsed.method, method.to_uppercase());
+            prop_assert!(parsed.is_mutating);
+        }
+
+        #[test]
+        fn fields_are_kept_and_imply_post(
+            units in proptest::collection::vec(unit(), 0..6),
+            fields in proptest::collection::vec(("[a-z]{1,6}", ".*"), 1..4),
+            flag in prop_oneof![Just("-f"), Just("-F"), Just("--field"), Just("--raw-field")],
+        ) {
+            let mut raw: Vec<String> = units.concat();
+            let expected: Vec<String> = fields.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            for f in &expected {
+                raw.extend([flag.to_string(), f.clone()]);
+            }
+            let parsed = parse_api_args(&raw);
+            prop_assert_eq!(&parsed.fields, &expected);
+            prop_assert_eq!(parsed.method, "POST");
+        }
+
+        #[test]
+        fn nothing_after_double_dash_is_a_flag(
+            units in proptest::collection::vec(unit(), 0..6),
+            tail in proptest::collection::vec(token(), 0..6),
+        ) {
+            let head: Vec<String> = units.concat();
+            let mut raw = head.clone();
+            raw.push("--".to_string());
+            raw.extend(tail);
+            let (with_tail, without) = (parse_api_args(&raw), parse_api_args(&head));
+            prop_assert_eq!(with_tail.method, without.method);
+            prop_assert_eq!(with_tail.fields, without.fields);
+        }
+    }
 }
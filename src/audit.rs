@@ -0,0 +1,185 @@
+use crate::risk::Severity;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+const LOG_FILE: &str = "audit.jsonl";
+
+/// Outcome of an intercepted command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decision {
+    Approved,
+    Rejected,
+    Timeout,
+    /// Refused locally by policy (quiet hours, rate limit, lockout…).
+    AutoRejected,
+    /// The approver triggered `/panic` instead of deciding.
+    Panic,
+    /// Withdrawn with Ctrl+C before anyone decided.
+    Cancelled,
+    /// Not guarded — handed straight to gh.
+    Passthrough,
+    /// Telegram was unreachable; held for the daemon to deliver. The final
+    /// decision is logged as a separate entry.
+    Queued,
+    /// Run without asking, with `--guard-bypass` and a reason.
+    Bypassed,
+    /// Run without asking because a policy allows it; the reason says which.
+    AutoApproved,
+    /// Failed before anything was decided, or gh could not be run; the
+    /// reason is the error.
+    Error,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Approved => "approved",
+            Decision::Rejected => "rejected",
+            Decision::Timeout => "timeout",
+            Decision::AutoRejected => "auto-rejected",
+            Decision::Panic => "panic",
+            Decision::Cancelled => "cancelled",
+            Decision::Passthrough => "passthrough",
+            Decision::Queued => "queued",
+            Decision::Bypassed => "bypassed",
+            Decision::AutoApproved => "auto-approved",
+            Decision::Error => "error",
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Entry {
+    /// Local time, RFC 3339.
+    pub ts: String,
+    /// Telegram request ID for approvals, a fresh short ID otherwise.
+    pub id: String,
+    /// "host/agent" that issued the command.
+    pub source: String,
+    /// Arguments as passed to gh (secret values redacted).
+    pub args: Vec<String>,
+    pub repo: Option<String>,
+    pub severity: Option<Severity>,
+    pub decision: Decision,
+    /// Why an auto-rejection happened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Telegram user who tapped the button.
+    pub approver: Option<String>,
+    /// Who delegated the request, then the `[delegate]` it went to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delegation: Vec<String>,
+    /// Milliseconds from notification to decision.
+    pub latency_ms: Option<u64>,
+    /// gh's exit code, when gh ran as a child process.
+    pub exit_code: Option<i32>,
+    /// Named profile in effect, if not the default one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// `[accounts]` entry whose PAT gh was given, if not the profile's own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+impl Entry {
+    pub fn new(args: &[String], decision: Decision) -> Self {
+        let uid = uuid::Uuid::new_v4().to_string();
+        Entry {
+            ts: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            id: uid[..8].to_string(),
+            source: crate::agent::source(),
+            args: redact(args),
+            repo: None,
+            severity: None,
+            decision,
+            reason: None,
+            approver: None,
+            delegation: Vec::new(),
+            latency_ms: None,
+            exit_code: None,
+            profile: crate::profile::active().map(str::to_string),
+            account: None,
+        }
+    }
+
+    /// Append to the log. Failure only warns: auditing must never be the
+    /// reason a command silently stops working.
+    ///
+    /// Guarded decisions are also pushed to the remote sink right away;
+    /// passthrough entries wait for the next push so plain `gh` stays fast.
+    pub fn write(&self) {
+        if let Err(e) = append(self) {
+            tracing::warn!(error = %e, "could not write audit log");
+            return;
+        }
+        if self.decision != Decision::Passthrough {
+            let settings = crate::settings::load().unwrap_or_default();
+            if let Err(e) = crate::audit_sync::flush(settings.audit_sync.as_ref()) {
+                tracing::warn!(error = %e, "audit sync failed, will retry");
+            }
+        }
+    }
+}
+
+pub fn path() -> PathBuf {
+    crate::state::dir().join(LOG_FILE)
+}
+
+fn append(entry: &Entry) -> Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // O_APPEND keeps concurrent writers from interleaving within a line.
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("Cannot open {}", path.display()))?
+        .write_all(line.as_bytes())
+        .with_context(|| format!("Cannot write {}", path.display()))
+}
+
+/// Read every entry in order, skipping lines that fail to parse.
+pub fn read_all() -> Result<Vec<Entry>> {
+    let path = path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+
+/// Mask values that are secrets by definition (`gh secret set --body …`).
+fn redact(args: &[String]) -> Vec<String> {
+    let is_secret = args.first().map(String::as_str) == Some("secret");
+    let mut out = Vec::with_capacity(args.len());
+    let mut mask_next = false;
+    for a in args {
+        if mask_next {
+            out.push("***".to_string());
+            mask_next = false;
+        } else if is_secret && (a == "--body" || a == "-b") {
+            out.push(a.clone());
+            mask_next = true;
+        } else if is_secret && a.starts_with("--body=") {
+            out.push("--body=***".to_string());
+        } else {
+            out.push(a.clone());
+        }
+    }
+    out
+}
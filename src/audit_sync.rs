@@ -0,0 +1,220 @@
+use crate::audit::Entry;
+use crate::settings::AuditSync;
+use anyhow::{anyhow, Context, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const STATE_FILE: &str = "audit_sync.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct SyncState {
+    /// Number of audit log lines already shipped to the remote sink.
+    shipped: usize,
+}
+
+/// Ship every audit entry not yet delivered to the configured sink.
+/// Cheap no-op when no sink is configured or nothing is pending. Called after
+/// each guarded decision and on every daemon tick, so a failed push is
+/// retried later.
+pub fn flush(cfg: Option<&AuditSync>) -> Result<()> {
+    let Some(cfg) = cfg else {
+        return Ok(());
+    };
+    let entries = crate::audit::read_all()?;
+    let mut state: SyncState = crate::state::load(STATE_FILE);
+    // The local log was rotated or truncated — start over.
+    if state.shipped > entries.len() {
+        state.shipped = 0;
+    }
+    let pending = &entries[state.shipped..];
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    match cfg {
+        AuditSync::Webhook { url } => push_webhook(url, pending)?,
+        AuditSync::Gist { gist_id } => push_gist(gist_id, &entries, pending)?,
+        AuditSync::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key_id,
+            prefix,
+        } => push_s3(endpoint, bucket, region, access_key_id, prefix, pending)?,
+    }
+
+    state.shipped = entries.len();
+    crate::state::save(STATE_FILE, &state)
+}
+
+fn agent(url: &str) -> Result<ureq::Agent> {
+    Ok(crate::http::builder(url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(15))
+        .build())
+}
+
+fn to_jsonl(entries: &[Entry]) -> Result<String> {
+    let mut out = String::new();
+    for e in entries {
+        out.push_str(&serde_json::to_string(e)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// ── Webhook ──────────────────────────────────────────────────────────────────
+
+/// POST the batch as a JSON array, signed with HMAC-SHA256 over the raw body
+/// in `X-GH-Guard-Signature: sha256=<hex>` (same scheme as GitHub webhooks).
+fn push_webhook(url: &str, pending: &[Entry]) -> Result<()> {
+    let secret = crate::config::get_audit_sync_secret()?;
+    let body = serde_json::to_string(pending)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow!("Invalid HMAC key: {e}"))?;
+    mac.update(body.as_bytes());
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    agent(url)?
+        .post(url)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", "gh-guard/0.1")
+        .set("X-GH-Guard-Signature", &format!("sha256={signature}"))
+        .send_string(&body)
+        .map_err(|e| anyhow!("Audit webhook: {e}"))?;
+    Ok(())
+}
+
+// ── Gist ─────────────────────────────────────────────────────────────────────
+
+/// Rewrite one `audit-YYYY-MM-DD.jsonl` file per day touched by `pending`.
+/// The gist's revision history keeps every earlier version, so wiping the
+/// local log cannot erase what was already shipped.
+fn push_gist(gist_id: &str, all: &[Entry], pending: &[Entry]) -> Result<()> {
+    let day = |e: &Entry| e.ts.get(..10).unwrap_or("unknown").to_string();
+    let mut days: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    for e in pending {
+        days.entry(day(e)).or_default();
+    }
+    for e in all {
+        if let Some(v) = days.get_mut(&day(e)) {
+            v.push(e.clone());
+        }
+    }
+
+    let mut files = serde_json::Map::new();
+    for (d, entries) in &days {
+        files.insert(
+            format!("audit-{d}.jsonl"),
+            serde_json::json!({ "content": to_jsonl(entries)? }),
+        );
+    }
+
+    let pat = crate::config::get_pat()?;
+    let url = crate::host::api_url(&format!("/gists/{gist_id}"));
+    agent(&url)?
+        .request("PATCH", &url)
+        .set("Authorization", &format!("Bearer {pat}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .send_json(serde_json::json!({ "files": files }))
+        .map_err(|e| anyhow!("Audit gist: {e}"))?;
+    Ok(())
+}
+
+// ── S3-compatible bucket ─────────────────────────────────────────────────────
+
+/// PUT the batch as a new object (`<prefix><host>/<timestamp>-<id>.jsonl`),
+/// signed with AWS Signature V4. Works with AWS, R2, MinIO, B2 and friends
+/// via a path-style endpoint URL.
+fn push_s3(
+    endpoint: &str,
+    bucket: &str,
+    region: &str,
+    access_key_id: &str,
+    prefix: &str,
+    pending: &[Entry],
+) -> Result<()> {
+    let secret = crate::config::get_audit_sync_secret()?;
+    let body = to_jsonl(pending)?;
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let host = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, rest)| rest)
+        .trim_end_matches('/');
+    let safe = |s: &str| -> String {
+        s.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    };
+    let first_id = pending.first().map_or("batch", |e| e.id.as_str());
+    let key = format!(
+        "{}{}/{}-{}.jsonl",
+        prefix.trim_start_matches('/'),
+        safe(&crate::agent::hostname()),
+        amz_date,
+        safe(first_id)
+    );
+    let uri = format!("/{bucket}/{key}");
+
+    let payload_hash = hex::encode(Sha256::digest(body.as_bytes()));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{uri}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n\
+         {signed_headers}\n{payload_hash}"
+    );
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{secret}").as_bytes(), date.as_bytes())?;
+    let k_region = hmac_sha256(&k_date, region.as_bytes())?;
+    let k_service = hmac_sha256(&k_region, b"s3")?;
+    let k_signing = hmac_sha256(&k_service, b"aws4_request")?;
+    let signature = hex::encode(hmac_sha256(&k_signing, string_to_sign.as_bytes())?);
+
+    let scheme = if endpoint.starts_with("http://") {
+        "http"
+    } else {
+        "https"
+    };
+    let url = format!("{scheme}://{host}{uri}");
+    agent(&url)?
+        .put(&url)
+        .set("x-amz-date", &amz_date)
+        .set("x-amz-content-sha256", &payload_hash)
+        .set(
+            "Authorization",
+            &format!(
+                "AWS4-HMAC-SHA256 Credential={access_key_id}/{scope}, \
+                 SignedHeaders={signed_headers}, Signature={signature}"
+            ),
+        )
+        .set("Content-Type", "application/x-ndjson")
+        .send_string(&body)
+        .map_err(|e| anyhow!("Audit S3 upload: {e}"))
+        .context("Check endpoint, bucket, region and keys in [audit_sync]")?;
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow!("Invalid HMAC key: {e}"))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
@@ -0,0 +1,153 @@
+//! CI state of a PR's head branch, from GitHub's combined commit status
+//! and its check runs, asked for before the approval card goes out.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// What the checks on a commit add up to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiState {
+    Passing,
+    Failing,
+    Pending,
+    /// No statuses and no check runs at all.
+    None,
+}
+
+impl CiState {
+    /// Stable name, as card templates see it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CiState::Passing => "passing",
+            CiState::Failing => "failing",
+            CiState::Pending => "pending",
+            CiState::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CombinedStatus {
+    statuses: Vec<Status>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    state: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRun {
+    status: String,
+    conclusion: Option<String>,
+}
+
+/// CI state of `head` (a branch, or `owner:branch` for a fork) in `repo`.
+/// Fails when GitHub does not know the branch, e.g. before it is pushed.
+pub fn state(token: &str, repo: &str, head: &str) -> Result<CiState> {
+    let (repo, branch) = match head.split_once(':') {
+        Some((owner, branch)) => {
+            let name = repo.split_once('/').map_or(repo, |(_, name)| name);
+            (format!("{owner}/{name}"), branch)
+        }
+        None => (repo.to_string(), head),
+    };
+    let commit = format!("/repos/{repo}/commits/{}", crate::pr::url_escape(branch));
+    let status: CombinedStatus = get(token, &format!("{commit}/status?per_page=100"))?;
+    let runs: CheckRuns = get(token, &format!("{commit}/check-runs?per_page=100"))?;
+    Ok(combine(&status, &runs))
+}
+
+/// Any failure makes the branch red; otherwise anything unfinished keeps
+/// it pending. Skipped and neutral runs count as passing.
+fn combine(status: &CombinedStatus, runs: &CheckRuns) -> CiState {
+    if status.statuses.is_empty() && runs.check_runs.is_empty() {
+        return CiState::None;
+    }
+    let failed = status
+        .statuses
+        .iter()
+        .any(|s| matches!(s.state.as_str(), "failure" | "error"))
+        || runs.check_runs.iter().any(|r| {
+            matches!(
+                r.conclusion.as_deref(),
+                Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure")
+            )
+        });
+    let pending = status.statuses.iter().any(|s| s.state == "pending")
+        || runs.check_runs.iter().any(|r| r.status != "completed");
+    if failed {
+        CiState::Failing
+    } else if pending {
+        CiState::Pending
+    } else {
+        CiState::Passing
+    }
+}
+
+fn get<T: serde::de::DeserializeOwned>(token: &str, path: &str) -> Result<T> {
+    let url = crate::host::api_url(path);
+    let resp = crate::http::builder(&url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(10))
+        .build()
+        .get(&url)
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("GitHub API: {e}"))?;
+    Ok(resp.into_json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_of(statuses: &[&str], runs: &[(&str, Option<&str>)]) -> CiState {
+        let status = CombinedStatus {
+            statuses: statuses
+                .iter()
+                .map(|s| Status {
+                    state: s.to_string(),
+                })
+                .collect(),
+        };
+        let runs = CheckRuns {
+            check_runs: runs
+                .iter()
+                .map(|(status, conclusion)| CheckRun {
+                    status: status.to_string(),
+                    conclusion: conclusion.map(str::to_string),
+                })
+                .collect(),
+        };
+        combine(&status, &runs)
+    }
+
+    #[test]
+    fn failures_outweigh_pending_checks() {
+        assert_eq!(state_of(&[], &[]), CiState::None);
+        assert_eq!(
+            state_of(&["success"], &[("completed", Some("skipped"))]),
+            CiState::Passing
+        );
+        assert_eq!(
+            state_of(&["success"], &[("in_progress", None)]),
+            CiState::Pending
+        );
+        assert_eq!(
+            state_of(&["pending"], &[("completed", Some("timed_out"))]),
+            CiState::Failing
+        );
+        assert_eq!(state_of(&["error"], &[]), CiState::Failing);
+    }
+}
@@ -0,0 +1,346 @@
+//! Guarded commands under CI (`CI` set), where no one may be at the phone.
+//! `[ci] policy` picks what happens instead of a card: ask anyway, pass
+//! through, refuse, or run on an approval token issued beforehand with
+//! `gh-guard ci-token`.
+//!
+//! Tokens are grants signed with Ed25519. The signing key stays in the
+//! approver's secret store and CI gets only the public key, so a job can
+//! check tokens but not mint them.
+
+use crate::error::Error;
+use crate::i18n::Text;
+use crate::notify::{self, ApprovalResult, TgConfig};
+use crate::settings::{Ci, CiPolicy};
+use crate::{config, guard, i18n, mailbox, panic};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use colored::Colorize;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+/// Leads every token, so a later format can be told apart.
+const PREFIX: &str = "ghguard1";
+
+/// `gh-guard ci-token` options.
+#[derive(Debug, Args)]
+pub struct TokenArgs {
+    /// Repositories the token covers: owner/repo, owner/* or *
+    #[arg(long, value_name = "PATTERN", required_unless_present = "public_key")]
+    pub repo: Option<String>,
+    /// Hours the token stays valid
+    #[arg(long, value_name = "N", default_value_t = 24)]
+    pub hours: u64,
+    /// Print the key tokens are checked against, without issuing one
+    #[arg(long, conflicts_with = "repo")]
+    pub public_key: bool,
+}
+
+/// What an approval token allows.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Grant {
+    /// Short ID, quoted in the audit log.
+    pub id: String,
+    /// `owner/repo`, `owner/*` or `*`.
+    pub repo: String,
+    /// Unix seconds from which the token no longer counts.
+    pub expires: u64,
+}
+
+/// What the CI policy makes of a guarded command, when it does not ask.
+pub enum Verdict {
+    /// Run gh without asking (`passthrough`).
+    PassThrough,
+    /// Run gh on the approval token holding this grant.
+    Granted(Grant),
+    Refused(Refusal),
+}
+
+/// Why a command is refused under CI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    /// `policy = "reject"`.
+    Policy,
+    /// No approval token given.
+    Missing,
+    /// Malformed, or not signed with the configured key.
+    Invalid,
+    Expired,
+    OtherRepo,
+}
+
+impl Refusal {
+    /// As in the audit log.
+    pub fn reason(self) -> &'static str {
+        match self {
+            Refusal::Policy => "ci",
+            Refusal::Missing => "ci token missing",
+            Refusal::Invalid => "ci token invalid",
+            Refusal::Expired => "ci token expired",
+            Refusal::OtherRepo => "ci token for another repo",
+        }
+    }
+
+    pub fn why(self, t: &Text) -> &'static str {
+        match self {
+            Refusal::Policy => t.ci_rejected,
+            Refusal::Missing => t.ci_token_missing,
+            Refusal::Invalid => t.ci_token_invalid,
+            Refusal::Expired => t.ci_token_expired,
+            Refusal::OtherRepo => t.ci_token_other_repo,
+        }
+    }
+}
+
+/// The verdict on a command against `repo`, or `None` to ask as usual
+/// (always, outside CI).
+pub fn decide(cfg: Option<&Ci>, repo: &str) -> Result<Option<Verdict>> {
+    if !detected() {
+        return Ok(None);
+    }
+    Ok(Some(match policy(cfg)? {
+        CiPolicy::Ask => return Ok(None),
+        CiPolicy::Passthrough => Verdict::PassThrough,
+        CiPolicy::Reject => Verdict::Refused(Refusal::Policy),
+        CiPolicy::Token => match check(&public_key(cfg)?, repo) {
+            Ok(grant) => Verdict::Granted(grant),
+            Err(refusal) => Verdict::Refused(refusal),
+        },
+    }))
+}
+
+/// True when `CI` is set to anything but "", "0" or "false", as CI
+/// services do.
+fn detected() -> bool {
+    std::env::var("CI")
+        .is_ok_and(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "" | "0" | "false"))
+}
+
+/// `[ci] policy` (ask when unset), or `GH_GUARD_CI_POLICY` where that is
+/// stricter. Whatever runs gh-guard can set the variable, so it may
+/// tighten the config but never loosen it.
+fn policy(cfg: Option<&Ci>) -> Result<CiPolicy> {
+    let configured = cfg.map_or_else(CiPolicy::default, |c| c.policy);
+    let Some(var) = env_value("GH_GUARD_CI_POLICY") else {
+        return Ok(configured);
+    };
+    let requested = match var.as_str() {
+        "ask" => CiPolicy::Ask,
+        "passthrough" => CiPolicy::Passthrough,
+        "reject" => CiPolicy::Reject,
+        "token" => CiPolicy::Token,
+        other => {
+            bail!("GH_GUARD_CI_POLICY must be ask, passthrough, reject or token, not {other:?}")
+        }
+    };
+    if strictness(requested) < strictness(configured) {
+        tracing::warn!(%var, "GH_GUARD_CI_POLICY is looser than [ci] policy; ignored");
+        return Ok(configured);
+    }
+    Ok(requested)
+}
+
+/// How much a policy holds back: passing through least, a token that was
+/// approved beforehand next, asking each time more, refusing most.
+fn strictness(policy: CiPolicy) -> u8 {
+    match policy {
+        CiPolicy::Passthrough => 0,
+        CiPolicy::Token => 1,
+        CiPolicy::Ask => 2,
+        CiPolicy::Reject => 3,
+    }
+}
+
+/// `[ci] public_key`. Only the config file names it: a key from the
+/// environment would let whatever runs gh-guard sign its own tokens.
+fn public_key(cfg: Option<&Ci>) -> Result<Vec<u8>> {
+    let key = cfg.and_then(|c| c.public_key.clone()).ok_or_else(|| {
+        Error::ConfigMissing(
+            "[ci] policy = \"token\" needs [ci] public_key \
+             (see `gh-guard ci-token --public-key`)"
+                .to_string(),
+        )
+    })?;
+    hex::decode(key.trim()).context("The CI public key is not hex")
+}
+
+/// The grant in `GH_GUARD_APPROVAL_TOKEN`, if it lets a command against
+/// `repo` run now.
+fn check(public_key: &[u8], repo: &str) -> Result<Grant, Refusal> {
+    let token = env_value("GH_GUARD_APPROVAL_TOKEN").ok_or(Refusal::Missing)?;
+    verify(&token, public_key, repo, mailbox::now())
+}
+
+/// `<prefix>.<grant>.<signature>`, both hex; the signature covers the
+/// first two parts.
+fn sign(grant: &Grant, key: &Ed25519KeyPair) -> Result<String> {
+    let signed = format!("{PREFIX}.{}", hex::encode(serde_json::to_vec(grant)?));
+    let signature = key.sign(signed.as_bytes());
+    Ok(format!("{signed}.{}", hex::encode(signature)))
+}
+
+fn verify(token: &str, public_key: &[u8], repo: &str, now: u64) -> Result<Grant, Refusal> {
+    let (signed, signature) = token.rsplit_once('.').ok_or(Refusal::Invalid)?;
+    let signature = hex::decode(signature).map_err(|_| Refusal::Invalid)?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(signed.as_bytes(), &signature)
+        .map_err(|_| Refusal::Invalid)?;
+    let grant: Grant = signed
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|payload| hex::decode(payload).ok())
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or(Refusal::Invalid)?;
+    if now >= grant.expires {
+        return Err(Refusal::Expired);
+    }
+    if !crate::profile::wildcard_matches(&grant.repo, repo) {
+        return Err(Refusal::OtherRepo);
+    }
+    Ok(grant)
+}
+
+/// `gh-guard ci-token` entry point: print a token once it is approved on
+/// the phone.
+pub fn run(args: &TokenArgs) -> Result<()> {
+    let t = i18n::text();
+    if args.public_key {
+        println!("{}", hex::encode(signing_key()?.public_key()));
+        return Ok(());
+    }
+    let repo = args.repo.as_deref().unwrap_or("*");
+    let settings = crate::settings::load()?;
+    let deps = guard::Deps::live();
+    let tg = TgConfig {
+        members: settings.members(),
+        ..deps.credentials.telegram()?
+    };
+
+    eprintln!("{}", t.sending_ci_token);
+    let html = (t.ci_token_card)(
+        &notify::escape_html(repo),
+        args.hours,
+        &notify::escape_html(&crate::agent::source()),
+    );
+    let (request_id, message_id) =
+        deps.notifier
+            .send_with_approval(&tg, &html, &[], &[], None, false)?;
+    let timeout = settings.approval_timeout();
+    eprintln!("{}", (t.waiting)(&guard::duration_label(timeout)));
+
+    let approval = deps
+        .notifier
+        .poll_for_approval(&tg, &request_id, message_id, timeout, 1)?;
+    match approval.result {
+        ApprovalResult::Approved => {
+            let key = signing_key()?;
+            let uid = uuid::Uuid::new_v4().to_string();
+            let grant = Grant {
+                id: uid[..8].to_string(),
+                repo: repo.to_string(),
+                expires: mailbox::now() + args.hours * 3600,
+            };
+            eprintln!("{}", (t.ci_public_key)(&hex::encode(key.public_key())));
+            println!("{}", sign(&grant, &key)?);
+            Ok(())
+        }
+        ApprovalResult::Rejected => {
+            eprintln!("{}", t.ci_token_rejected.red().bold());
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Timeout => {
+            eprintln!("{}", t.ci_token_timed_out.yellow());
+            Err(Error::ApprovalTimedOut.into())
+        }
+        ApprovalResult::Panic { revoke } => {
+            panic::run(revoke)?;
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Cancelled => {
+            eprintln!("{}", t.ci_token_cancelled.yellow());
+            Err(Error::Cancelled.into())
+        }
+    }
+}
+
+/// The key tokens are signed with, created and stored on first use.
+fn signing_key() -> Result<Ed25519KeyPair> {
+    let pkcs8 = match config::get_ci_signing_key()? {
+        Some(key) => hex::decode(key.trim()).context("The stored CI signing key is not hex")?,
+        None => {
+            let doc = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| anyhow!("Cannot generate a CI signing key"))?;
+            config::set_ci_signing_key(&hex::encode(doc.as_ref()))?;
+            doc.as_ref().to_vec()
+        }
+    };
+    Ed25519KeyPair::from_pkcs8(&pkcs8).map_err(|_| anyhow!("The stored CI signing key is invalid"))
+}
+
+fn env_value(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_key() -> Ed25519KeyPair {
+        let doc = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(doc.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn tokens_cover_their_repos_until_they_expire() {
+        let key = new_key();
+        let public = key.public_key().as_ref();
+        let grant = Grant {
+            id: "a1b2c3d4".to_string(),
+            repo: "acme/*".to_string(),
+            expires: 1_000,
+        };
+        let token = sign(&grant, &key).unwrap();
+
+        assert_eq!(
+            verify(&token, public, "acme/api", 999).unwrap().id,
+            "a1b2c3d4"
+        );
+        assert_eq!(
+            verify(&token, public, "acme/api", 1_000).unwrap_err(),
+            Refusal::Expired
+        );
+        assert_eq!(
+            verify(&token, public, "evil/api", 999).unwrap_err(),
+            Refusal::OtherRepo
+        );
+    }
+
+    #[test]
+    fn only_the_key_holder_can_issue_tokens() {
+        let key = new_key();
+        let grant = Grant {
+            id: "a1b2c3d4".to_string(),
+            repo: "acme/api".to_string(),
+            expires: 1_000,
+        };
+        let token = sign(&grant, &key).unwrap();
+        let other = new_key();
+        let widened = token.replacen(
+            &hex::encode(br#""repo":"acme/api""#),
+            &hex::encode(br#""repo":"*""#),
+            1,
+        );
+
+        assert_ne!(widened, token);
+        for forged in [
+            verify(&token, other.public_key().as_ref(), "acme/api", 0),
+            verify(&widened, key.public_key().as_ref(), "acme/api", 0),
+            verify("ghguard1.00.00", key.public_key().as_ref(), "acme/api", 0),
+        ] {
+            assert_eq!(forged.unwrap_err(), Refusal::Invalid);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! `[cleanup]`: settled approval cards deleted, or collapsed to one line,
+//! some hours after the decision. Done by `gh-guard daemon` and, failing
+//! that, by the next request sent to the same chat.
+
+use crate::notify::{self, escape_html, TgConfig};
+use crate::settings::{Cleanup, CleanupAction};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "settled_cards.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Settled {
+    chat_id: String,
+    /// The card, then the files sent in reply to it.
+    message_ids: Vec<i64>,
+    /// Unix seconds when the card is due.
+    due_at: u64,
+    /// Outcome label and request summary, for a collapsed card.
+    label: String,
+    summary: String,
+}
+
+/// Note a settled card for cleanup, if `[cleanup]` asks for it.
+pub fn record(
+    cfg: Option<&Cleanup>,
+    tg: &TgConfig,
+    message_ids: Vec<i64>,
+    label: &str,
+    summary: &str,
+) {
+    let Some(cfg) = cfg else {
+        return;
+    };
+    let card = Settled {
+        chat_id: tg.chat_id.clone(),
+        message_ids,
+        due_at: crate::mailbox::now() + cfg.after_hours * 3600,
+        label: label.to_string(),
+        summary: summary.to_string(),
+    };
+    let recorded = crate::state::update(STATE_FILE, |cards: &mut Vec<Settled>| cards.push(card));
+    if let Err(e) = recorded {
+        tracing::warn!(error = %e, "could not record the card for cleanup");
+    }
+}
+
+/// `gh-guard daemon`'s job: [`tick`] for the configured chat, reading the
+/// bot token only when some card is due.
+pub fn flush(cfg: Option<&Cleanup>) -> Result<()> {
+    let now = crate::mailbox::now();
+    let pending: Vec<Settled> = crate::state::load(STATE_FILE);
+    if cfg.is_none() || !pending.iter().any(|c| c.due_at <= now) {
+        return Ok(());
+    }
+    let tg = crate::guard::Deps::live().credentials.telegram()?;
+    tick(cfg, &tg)
+}
+
+/// Delete or collapse this chat's cards that are due. A card Telegram
+/// refuses to change (deleted by hand, too old) is logged and forgotten.
+pub fn tick(cfg: Option<&Cleanup>, tg: &TgConfig) -> Result<()> {
+    // Texts cannot be deleted, and cards from before `[sms]` need the bot.
+    if crate::sms::configured().is_some() {
+        return Ok(());
+    }
+    let now = crate::mailbox::now();
+    let is_due = |c: &Settled| c.chat_id == tg.chat_id && c.due_at <= now;
+    let pending: Vec<Settled> = crate::state::load(STATE_FILE);
+    if !pending.iter().any(is_due) {
+        return Ok(());
+    }
+    let due = crate::state::update(STATE_FILE, |cards: &mut Vec<Settled>| {
+        let (due, rest): (Vec<_>, Vec<_>) = std::mem::take(cards).into_iter().partition(is_due);
+        *cards = rest;
+        due
+    })?;
+    // Without [cleanup] any more, the cards are simply left alone.
+    let Some(cfg) = cfg else {
+        return Ok(());
+    };
+    for card in due {
+        let (card_id, files) = card.message_ids.split_first().unwrap_or((&0, &[]));
+        let card_done = match cfg.action {
+            CleanupAction::Delete => notify::delete_message(tg, *card_id),
+            CleanupAction::Collapse => {
+                let html = format!("{} · {}", card.label, escape_html(&card.summary));
+                notify::collapse_card(tg, *card_id, &html)
+            }
+        };
+        let files_done = files
+            .iter()
+            .map(|id| notify::delete_message(tg, *id))
+            .collect::<Result<Vec<_>>>();
+        if let Err(e) = card_done.and(files_done) {
+            tracing::warn!(error = %e, message_id = card_id, "could not clean up a card");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use crate::output::Verbosity;
+use crate::{ci_policy, history, queue, readonly, setup, stats};
+use anyhow::{anyhow, Result};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+/// gh-guard's own command surface. Anything that is not one of these
+/// subcommands is forwarded to gh (with `pr create` and `api` mutations
+/// requiring approval first).
+#[derive(Debug, Parser)]
+#[command(
+    name = "gh-guard",
+    disable_help_subcommand = true,
+    disable_version_flag = true,
+    about = "Secure gh wrapper with phone approval for PR creation and API mutations",
+    after_help = "Any other command is passed through to gh. `gh pr create` and mutating \
+                  `gh api` calls wait for approval on Telegram first."
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub guard: GuardFlags,
+
+    /// Profile to act on (same as --guard-profile)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Flags that belong to gh-guard but may appear on any gh command line.
+/// They are stripped before argv reaches gh; names must start with `--guard-`.
+#[derive(Debug, Default, Args)]
+pub struct GuardFlags {
+    /// Print the outcome as one JSON object (also GH_GUARD_OUTPUT=json)
+    #[arg(long, global = true)]
+    pub guard_json: bool,
+
+    /// Use a named profile's credentials and policies (also GH_GUARD_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub guard_profile: Option<String>,
+
+    /// Print without colours (also NO_COLOR)
+    #[arg(long, global = true, visible_alias = "no-color")]
+    pub guard_no_color: bool,
+
+    /// Also show request IDs, Telegram polls and timings
+    #[arg(long, global = true, conflicts_with = "guard_quiet")]
+    pub guard_verbose: bool,
+
+    /// Print the outcome only: no banner, progress or countdown
+    #[arg(long, global = true)]
+    pub guard_quiet: bool,
+
+    // Hidden: agents read --guard-help too. Refused unless `[bypass]` is set.
+    /// Run a guarded command without asking, for REASON (audited and
+    /// reported on Telegram)
+    #[arg(long, global = true, hide = true, value_name = "REASON", value_parser = reason)]
+    pub guard_bypass: Option<String>,
+
+    /// Show what would be sent and decided, without contacting Telegram or
+    /// running gh (also GH_GUARD_DRY_RUN=1)
+    #[arg(long, global = true)]
+    pub guard_dry_run: bool,
+
+    /// Show gh-guard's own commands and flags
+    #[arg(long)]
+    pub guard_help: bool,
+}
+
+impl GuardFlags {
+    pub fn verbosity(&self) -> Verbosity {
+        if self.guard_quiet {
+            Verbosity::Quiet
+        } else if self.guard_verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Store credentials in the OS keyring and configure Telegram
+    Setup {
+        #[command(subcommand)]
+        step: Option<setup::Step>,
+    },
+    /// Run scheduled jobs (weekly digest, audit sync retries, offline queue)
+    Daemon,
+    /// Browse the audit log
+    Log(history::LogArgs),
+    /// Summarise the audit log
+    Stats(stats::StatsArgs),
+    /// Delete the stored PAT (and optionally revoke it on GitHub)
+    Panic {
+        /// Revoke the token via GitHub's credential revocation API first
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Leave locked mode (requires approval on your phone)
+    Unlock,
+    /// Issue an approval token for guarded commands under CI (requires
+    /// approval on your phone)
+    CiToken(ci_policy::TokenArgs),
+    /// Refuse every mutating command, e.g. while an untrusted agent explores
+    Readonly {
+        #[command(subcommand)]
+        action: Option<readonly::Action>,
+    },
+    /// Show how gh-guard would handle a gh command, without running it
+    Explain {
+        /// The gh command line, without `gh`
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "GH_ARGS"
+        )]
+        args: Vec<String>,
+    },
+    /// Answer a coding agent's pre-tool-use hook (JSON on stdin) for the gh
+    /// commands it is about to run
+    Hook,
+    /// List or drop requests queued while Telegram was unreachable
+    Queue {
+        #[command(subcommand)]
+        action: Option<queue::Action>,
+    },
+}
+
+impl Cli {
+    /// Is `name` one of gh-guard's own subcommands rather than a gh command?
+    pub fn owns(name: &str) -> bool {
+        Cli::command().find_subcommand(name).is_some()
+    }
+
+    /// Parse argv for one of gh-guard's own subcommands. Prints clap's
+    /// help/usage and exits on `--help` or a usage error.
+    pub fn parse_own(args: &[String]) -> Self {
+        let argv = std::iter::once("gh-guard").chain(args.iter().map(String::as_str));
+        Cli::try_parse_from(argv).unwrap_or_else(|e| {
+            let _ = e.print();
+            let code = if e.use_stderr() {
+                crate::exit::ERROR
+            } else {
+                0
+            };
+            std::process::exit(code)
+        })
+    }
+
+    pub fn print_help() {
+        let _ = Cli::command().print_help();
+    }
+}
+
+/// Remove every `--guard-*` flag (and its value, if it takes one) from argv,
+/// up to a literal `--`, and parse them. The rest of argv is left for gh.
+pub fn take_guard_flags(args: &mut Vec<String>) -> Result<GuardFlags> {
+    let spec = GuardFlags::augment_args(clap::Command::new("gh-guard").no_binary_name(true));
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let tail = args.split_off(end);
+
+    let mut ours = Vec::new();
+    let mut rest = Vec::new();
+    let mut iter = std::mem::take(args).into_iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with("--guard-") {
+            rest.push(arg);
+            continue;
+        }
+        let name = arg[2..].split('=').next().unwrap_or_default();
+        let takes_value = !arg.contains('=')
+            && spec
+                .get_arguments()
+                .find(|a| a.get_long() == Some(name))
+                .is_some_and(|a| a.get_action().takes_values());
+        ours.push(arg);
+        if takes_value {
+            ours.extend(iter.next());
+        }
+    }
+    *args = rest;
+    args.extend(tail);
+
+    let matches = spec.try_get_matches_from(ours).map_err(|e| {
+        let rendered = e.render().to_string();
+        let first = rendered.lines().next().unwrap_or_default();
+        anyhow!(
+            "{} (see `gh-guard --guard-help`)",
+            first.trim_start_matches("error: ")
+        )
+    })?;
+    Ok(GuardFlags::from_arg_matches(&matches)?)
+}
+
+/// `--guard-bypass` must say why.
+fn reason(s: &str) -> Result<String> {
+    let reason = s.trim();
+    if reason.is_empty() {
+        return Err(anyhow!(
+            "a bypass needs a reason, e.g. --guard-bypass=\"hotfix: prod down\""
+        ));
+    }
+    Ok(reason.to_string())
+}
@@ -1,53 +1,532 @@
-use anyhow::{Context, Result};
+use crate::error::Error;
+use crate::settings::{SecretBackend, Secrets};
+use anyhow::{anyhow, bail, Context, Result};
 use keyring::Entry;
 
 const SERVICE: &str = "gh-guard";
 const PAT_USER: &str = "github-pat";
 const TG_TOKEN_USER: &str = "telegram-bot-token";
 const TG_CHAT_USER: &str = "telegram-chat-id";
+const AUDIT_SYNC_USER: &str = "audit-sync-secret";
+const TWILIO_TOKEN_USER: &str = "twilio-auth-token";
+const APP_KEY_USER: &str = "github-app-key";
+const APP_TOKEN_USER: &str = "github-app-token";
+const OAUTH_REFRESH_USER: &str = "github-oauth-refresh";
+const CI_KEY_USER: &str = "ci-signing-key";
+
+/// Environment variables that take precedence over the configured store,
+/// for CI and throwaway containers.
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("GH_GUARD_PAT", PAT_USER),
+    ("GH_GUARD_TG_TOKEN", TG_TOKEN_USER),
+    ("GH_GUARD_TG_CHAT", TG_CHAT_USER),
+    ("GH_GUARD_TWILIO_TOKEN", TWILIO_TOKEN_USER),
+];
+
+// ── Secret stores ────────────────────────────────────────────────────────────
+
+/// A place secrets are kept, addressed by name (`github-pat`,
+/// `telegram-bot-token`, …).
+pub trait SecretStore {
+    /// The stored value, or `None` if there is none.
+    fn get(&self, name: &str) -> Result<Option<String>>;
+    fn set(&self, name: &str, value: &str) -> Result<()>;
+    /// Remove a secret. Succeeds if it was already absent.
+    fn delete(&self, name: &str) -> Result<()>;
+    /// Human-readable location, for messages.
+    fn describe(&self) -> String;
+    /// True when the store does not survive a reboot.
+    fn is_volatile(&self) -> bool {
+        false
+    }
+}
+
+/// The store selected by `[secrets] backend`.
+pub fn store() -> Result<Box<dyn SecretStore>> {
+    let cfg: Secrets = crate::settings::load()?.secrets;
+    Ok(match cfg.backend {
+        SecretBackend::Keyring => Box::new(KeyringStore),
+        SecretBackend::Env => Box::new(EnvStore),
+        SecretBackend::File => Box::new(crate::secret_file::FileStore::new(&cfg)),
+        SecretBackend::Pass => Box::new(crate::secret_pass::PassStore::new(cfg.pass)),
+        SecretBackend::Vault => match cfg.vault {
+            Some(v) => Box::new(crate::secret_vault::VaultStore::new(v)),
+            None => bail!("backend = \"vault\" needs a [secrets.vault] section"),
+        },
+    })
+}
+
+/// Human-readable name of the configured secret store, for messages.
+pub fn store_name() -> String {
+    match store() {
+        Ok(s) => s.describe(),
+        Err(e) => format!("(unavailable: {e})"),
+    }
+}
+
+/// True when secrets live in the kernel keyring, which is cleared on reboot.
+pub fn store_is_volatile() -> bool {
+    store().is_ok_and(|s| s.is_volatile())
+}
+
+/// Set once a Secret Service lookup failed and the kernel keyring took over.
+#[cfg(target_os = "linux")]
+static KEYUTILS_FALLBACK: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+
+/// The OS keyring: macOS Keychain, Windows Credential Manager, or on Linux
+/// the Secret Service (GNOME Keyring, KWallet). When no D-Bus session or
+/// keyring daemon is available — dev containers, SSH sessions, headless
+/// boxes — the kernel keyutils store is used instead.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn with_entry<T>(
+        &self,
+        name: &str,
+        op: impl Fn(&Entry) -> keyring::Result<T>,
+    ) -> keyring::Result<T> {
+        #[cfg(target_os = "linux")]
+        if KEYUTILS_FALLBACK.get().is_some() {
+            return op(&keyutils_entry(name)?);
+        }
+
+        let result = Entry::new(SERVICE, name).and_then(|e| op(&e));
+
+        #[cfg(target_os = "linux")]
+        if let Err(keyring::Error::PlatformFailure(e) | keyring::Error::NoStorageAccess(e)) =
+            &result
+        {
+            tracing::debug!(error = %e, "Secret Service unavailable, using kernel keyring");
+            let _ = KEYUTILS_FALLBACK.set(());
+            return op(&keyutils_entry(name)?);
+        }
+
+        result
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn keyutils_entry(name: &str) -> keyring::Result<Entry> {
+    let cred = keyring::keyutils::KeyutilsCredential::new_with_target(None, SERVICE, name)?;
+    Ok(Entry::new_with_credential(Box::new(cred)))
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        match self.with_entry(name, |e| e.get_password()) {
+            Ok(v) => Ok(Some(v)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Cannot read {}", self.describe())),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        Ok(self.with_entry(name, |e| e.set_password(value))?)
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        match self.with_entry(name, |e| e.delete_password()) {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        #[cfg(target_os = "linux")]
+        if KEYUTILS_FALLBACK.get().is_some() {
+            return "kernel keyring".to_string();
+        }
+        if cfg!(target_os = "macos") {
+            "macOS Keychain"
+        } else if cfg!(target_os = "windows") {
+            "Windows Credential Manager"
+        } else {
+            "Secret Service keyring"
+        }
+        .to_string()
+    }
+
+    fn is_volatile(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return KEYUTILS_FALLBACK.get().is_some();
+        #[cfg(not(target_os = "linux"))]
+        false
+    }
+}
+
+/// Read-only store backed by the [`ENV_OVERRIDES`] variables. Used on its
+/// own with `backend = "env"`, and in front of every other store.
+pub struct EnvStore;
+
+impl EnvStore {
+    fn var(name: &str) -> Result<&'static str> {
+        ENV_OVERRIDES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(var, _)| *var)
+            .ok_or_else(|| anyhow!("{name} cannot be set from the environment"))
+    }
+}
+
+impl SecretStore for EnvStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(Self::var(name).ok().and_then(env_value))
+    }
+
+    fn set(&self, name: &str, _value: &str) -> Result<()> {
+        bail!(
+            "The env backend is read-only; export {} instead",
+            Self::var(name)?
+        )
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        match self.get(name)? {
+            Some(_) => bail!(
+                "The env backend is read-only; unset {} instead",
+                Self::var(name)?
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        "environment".to_string()
+    }
+}
+
+/// Environment variables from [`ENV_OVERRIDES`] that are currently set.
+pub fn active_env_overrides() -> Vec<&'static str> {
+    ENV_OVERRIDES
+        .iter()
+        .filter(|(var, _)| env_value(var).is_some())
+        .map(|(var, _)| *var)
+        .collect()
+}
+
+fn env_value(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Name under which the active profile keeps secret `name`: unchanged for
+/// the default profile, `<profile>/<name>` otherwise.
+fn scoped(name: &str) -> String {
+    match crate::profile::active() {
+        Some(p) => format!("{p}/{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Look up a secret: its environment override if set, else the configured
+/// store.
+fn get(name: &str, missing: &str) -> Result<String> {
+    if let Some(v) = EnvStore.get(name)? {
+        tracing::debug!(name, "secret taken from environment");
+        return Ok(v);
+    }
+    let value = stored(&scoped(name))?.ok_or_else(|| match crate::profile::active() {
+        Some(p) => Error::ConfigMissing(format!(
+            "{missing} (profile {p}: run `gh-guard setup --profile {p}`)"
+        )),
+        None => Error::ConfigMissing(missing.to_string()),
+    })?;
+    Ok(value)
+}
+
+/// A secret from the daemon's cache (`[secrets] cache_minutes`), else from
+/// the store — in which case it is handed to the daemon for next time.
+fn stored(key: &str) -> Result<Option<String>> {
+    let miss = match crate::secret_cache::get(key) {
+        crate::secret_cache::Cached::Hit(v) => {
+            tracing::debug!(key, "secret taken from daemon cache");
+            return Ok(Some(v));
+        }
+        crate::secret_cache::Cached::Miss(miss) => miss,
+    };
+    let value = store()?.get(key)?;
+    if let Some(v) = &value {
+        miss.put(v);
+    }
+    Ok(value)
+}
+
+fn set(name: &str, value: &str, what: &str) -> Result<()> {
+    let store = store()?;
+    crate::secret_cache::forget(&scoped(name));
+    store
+        .set(&scoped(name), value)
+        .with_context(|| format!("Failed to store {what} in {}", store.describe()))
+}
+
+fn delete(name: &str, what: &str) -> Result<()> {
+    let store = store()?;
+    crate::secret_cache::forget(&scoped(name));
+    store
+        .delete(&scoped(name))
+        .with_context(|| format!("Failed to delete {what} from {}", store.describe()))
+}
 
 // ── GitHub PAT ───────────────────────────────────────────────────────────────
 
+/// Token for gh and GitHub API calls: `GH_GUARD_PAT` if set, else the PAT
+/// of the `[accounts]` entry mapped to the target repository, else an
+/// installation token when `[github_app]` is configured, else the stored
+/// PAT or device-flow token (refreshed first if it is about to expire).
 pub fn get_pat() -> Result<String> {
-    Entry::new(SERVICE, PAT_USER)
-        .context("Cannot open macOS Keychain")?
-        .get_password()
-        .context("GitHub PAT not found. Run `gh-guard setup` first.")
+    if let Some(v) = EnvStore.get(PAT_USER)? {
+        return Ok(v);
+    }
+    if let Some(name) = account() {
+        tracing::debug!(account = %name, "PAT of a mapped account");
+        let pat = get_account_pat(&name)?;
+        crate::expiry::check(&pat)?;
+        return Ok(pat);
+    }
+    if let Some(app) = crate::settings::load()?.github_app {
+        return crate::github_app::token(&app);
+    }
+    let pat = get(
+        PAT_USER,
+        "GitHub PAT not found. Run `gh-guard setup` first.",
+    )?;
+    match get_oauth_refresh() {
+        Some(refresh) => crate::oauth::fresh(pat, refresh),
+        None => {
+            crate::expiry::check(&pat)?;
+            Ok(pat)
+        }
+    }
 }
 
 pub fn set_pat(token: &str) -> Result<()> {
-    Entry::new(SERVICE, PAT_USER)
-        .context("Cannot open macOS Keychain")?
-        .set_password(token)
-        .context("Failed to store PAT in macOS Keychain")
+    set(PAT_USER, token, "PAT")
+}
+
+/// Remove the PAT from the secret store. Succeeds if it was already absent.
+pub fn delete_pat() -> Result<()> {
+    delete_oauth_refresh()?;
+    delete(PAT_USER, "PAT")
+}
+
+/// Refresh data stored alongside a device-flow token that expires.
+pub fn get_oauth_refresh() -> Option<crate::oauth::Refresh> {
+    let json = stored(&scoped(OAUTH_REFRESH_USER)).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn set_oauth_refresh(json: &str) -> Result<()> {
+    set(OAUTH_REFRESH_USER, json, "GitHub refresh token")
+}
+
+pub fn delete_oauth_refresh() -> Result<()> {
+    delete(OAUTH_REFRESH_USER, "GitHub refresh token")
+}
+
+// ── GitHub accounts ──────────────────────────────────────────────────────────
+
+/// The `[accounts]` entry whose PAT [`get_pat`] hands out for the target
+/// repository, if any. `GH_GUARD_PAT` takes precedence over every account.
+pub fn account() -> Option<String> {
+    if env_value("GH_GUARD_PAT").is_some() {
+        return None;
+    }
+    let settings = crate::settings::load().ok()?;
+    if settings.accounts.is_empty() {
+        return None;
+    }
+    let repo = crate::profile::target_repo()?;
+    settings.account_for(&repo).map(str::to_string)
+}
+
+/// Name under which account `name`'s PAT is kept.
+fn account_pat(name: &str) -> String {
+    format!("{PAT_USER}@{name}")
+}
+
+pub fn get_account_pat(name: &str) -> Result<String> {
+    get(
+        &account_pat(name),
+        &format!("PAT of account {name} not found. Run `gh-guard setup account {name}` first."),
+    )
+}
+
+pub fn set_account_pat(name: &str, token: &str) -> Result<()> {
+    set(&account_pat(name), token, &format!("PAT of account {name}"))
+}
+
+/// Remove an account's PAT. Succeeds if it was already absent.
+pub fn delete_account_pat(name: &str) -> Result<()> {
+    delete(&account_pat(name), &format!("PAT of account {name}"))
+}
+
+// ── GitHub App ───────────────────────────────────────────────────────────────
+
+/// The app's PEM private key. Stored with newlines escaped, so
+/// line-oriented stores such as pass keep it on one line.
+pub fn get_app_key() -> Result<String> {
+    get(
+        APP_KEY_USER,
+        "GitHub App key not found. Run `gh-guard setup app` first.",
+    )
+    .map(|k| k.replace("\\n", "\n"))
+}
+
+pub fn set_app_key(pem: &str) -> Result<()> {
+    set(
+        APP_KEY_USER,
+        &pem.trim().replace('\n', "\\n"),
+        "GitHub App key",
+    )
+}
+
+/// Remove the app key and any cached installation token.
+pub fn delete_app_credentials() -> Result<()> {
+    delete(APP_TOKEN_USER, "GitHub App token")?;
+    delete(APP_KEY_USER, "GitHub App key")
+}
+
+/// Last minted installation token (JSON, see `github_app`), if any.
+pub fn get_app_token_cache() -> Option<String> {
+    stored(&scoped(APP_TOKEN_USER)).ok().flatten()
+}
+
+pub fn set_app_token_cache(json: &str) -> Result<()> {
+    set(APP_TOKEN_USER, json, "GitHub App token")
 }
 
 // ── Telegram ─────────────────────────────────────────────────────────────────
 
 pub fn get_telegram_token() -> Result<String> {
-    Entry::new(SERVICE, TG_TOKEN_USER)
-        .context("Cannot open macOS Keychain")?
-        .get_password()
-        .context("Telegram bot token not found. Run `gh-guard setup` first.")
+    get(
+        TG_TOKEN_USER,
+        "Telegram bot token not found. Run `gh-guard setup` first.",
+    )
 }
 
 pub fn set_telegram_token(token: &str) -> Result<()> {
-    Entry::new(SERVICE, TG_TOKEN_USER)
-        .context("Cannot open macOS Keychain")?
-        .set_password(token)
-        .context("Failed to store Telegram token in macOS Keychain")
+    set(TG_TOKEN_USER, token, "Telegram token")
 }
 
 pub fn get_telegram_chat_id() -> Result<String> {
-    Entry::new(SERVICE, TG_CHAT_USER)
-        .context("Cannot open macOS Keychain")?
-        .get_password()
-        .context("Telegram chat ID not found. Run `gh-guard setup` first.")
+    get(
+        TG_CHAT_USER,
+        "Telegram chat ID not found. Run `gh-guard setup` first.",
+    )
 }
 
 pub fn set_telegram_chat_id(id: &str) -> Result<()> {
-    Entry::new(SERVICE, TG_CHAT_USER)
-        .context("Cannot open macOS Keychain")?
-        .set_password(id)
-        .context("Failed to store Telegram chat ID in macOS Keychain")
+    set(TG_CHAT_USER, id, "Telegram chat ID")
+}
+
+// ── Twilio ───────────────────────────────────────────────────────────────────
+
+/// Auth token of the `[sms]` Twilio account.
+pub fn get_twilio_token() -> Result<String> {
+    get(
+        TWILIO_TOKEN_USER,
+        "Twilio auth token not found. Run `gh-guard setup sms` first.",
+    )
+}
+
+pub fn set_twilio_token(token: &str) -> Result<()> {
+    set(TWILIO_TOKEN_USER, token, "Twilio auth token")
+}
+
+// ── Audit sync ───────────────────────────────────────────────────────────────
+
+/// HMAC key for the webhook sink, or the secret access key for the S3 sink.
+pub fn get_audit_sync_secret() -> Result<String> {
+    get(
+        AUDIT_SYNC_USER,
+        "Audit sync secret not found. Run `gh-guard setup audit-sync` first.",
+    )
+}
+
+pub fn set_audit_sync_secret(secret: &str) -> Result<()> {
+    set(AUDIT_SYNC_USER, secret, "audit sync secret")
+}
+
+// ── CI approval tokens ───────────────────────────────────────────────────────
+
+/// Ed25519 key (PKCS#8, hex) that signs `gh-guard ci-token` grants, once
+/// the first token has been issued.
+pub fn get_ci_signing_key() -> Result<Option<String>> {
+    stored(&scoped(CI_KEY_USER))
+}
+
+pub fn set_ci_signing_key(key: &str) -> Result<()> {
+    set(CI_KEY_USER, key, "CI signing key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret_file::FileStore;
+
+    fn file_store(dir: &std::path::Path) -> FileStore {
+        std::fs::write(dir.join("key"), [7u8; 32]).unwrap();
+        FileStore::new(&Secrets {
+            file: Some(dir.join("secrets.enc")),
+            key_file: Some(dir.join("key")),
+            ..Secrets::default()
+        })
+    }
+
+    #[test]
+    fn stored_secrets_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = file_store(dir.path());
+        store.set(PAT_USER, "ghp_one").unwrap();
+        store.set(TG_TOKEN_USER, "123:abc").unwrap();
+        store.set(PAT_USER, "ghp_two").unwrap();
+
+        let reopened = file_store(dir.path());
+        assert_eq!(reopened.get(PAT_USER).unwrap().as_deref(), Some("ghp_two"));
+        assert_eq!(
+            reopened.get(TG_TOKEN_USER).unwrap().as_deref(),
+            Some("123:abc")
+        );
+        reopened.delete(PAT_USER).unwrap();
+        assert_eq!(store.get(PAT_USER).unwrap(), None);
+        assert_eq!(
+            store.get(TG_TOKEN_USER).unwrap().as_deref(),
+            Some("123:abc")
+        );
+    }
+
+    #[test]
+    fn missing_secrets_are_none_not_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = file_store(dir.path());
+        assert_eq!(store.get(PAT_USER).unwrap(), None);
+        store.delete(PAT_USER).unwrap();
+        store.set(TG_CHAT_USER, "42").unwrap();
+        assert_eq!(store.get(PAT_USER).unwrap(), None);
+        store.delete(PAT_USER).unwrap();
+
+        assert_eq!(EnvStore.get(CI_KEY_USER).unwrap(), None);
+        EnvStore.delete(CI_KEY_USER).unwrap();
+    }
+
+    #[test]
+    fn environment_overrides_come_first() {
+        // Only this test touches the variable.
+        std::env::set_var("GH_GUARD_TWILIO_TOKEN", "  tw_env \n");
+        assert_eq!(
+            EnvStore.get(TWILIO_TOKEN_USER).unwrap().as_deref(),
+            Some("tw_env")
+        );
+        // Taken before any store (or the config naming one) is looked at.
+        assert_eq!(get_twilio_token().unwrap(), "tw_env");
+        assert!(active_env_overrides().contains(&"GH_GUARD_TWILIO_TOKEN"));
+        assert!(EnvStore.set(TWILIO_TOKEN_USER, "x").is_err());
+        assert!(EnvStore.delete(TWILIO_TOKEN_USER).is_err());
+
+        std::env::set_var("GH_GUARD_TWILIO_TOKEN", " ");
+        assert_eq!(EnvStore.get(TWILIO_TOKEN_USER).unwrap(), None);
+        std::env::remove_var("GH_GUARD_TWILIO_TOKEN");
+    }
 }
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::time::Duration;
+
+/// How often scheduled jobs are checked.
+const TICK: Duration = Duration::from_secs(60);
+
+/// `gh-guard daemon` — a long-running foreground process for scheduled jobs
+/// (weekly digest, retrying remote audit sync, PAT expiry reminders,
+/// delivering requests queued while offline, cleaning up settled cards),
+/// which also serves the secret cache when `[secrets] cache_minutes` is set
+/// and Prometheus metrics when `[metrics]` is.
+/// Run it under launchd/systemd; it holds no state in memory that a restart
+/// would lose, and re-reads the config file every tick.
+pub fn run() -> Result<()> {
+    eprintln!("{}", "gh-guard daemon started (Ctrl+C to stop)".cyan());
+    let settings = crate::settings::load()?;
+    match settings.secrets.cache_minutes {
+        Some(_) if !crate::secret_cache::can_serve(settings.secrets.backend) => {
+            eprintln!(
+                "{}",
+                "Not caching keychain secrets: this system cannot tell gh-guard from other programs asking for them."
+                    .yellow()
+            );
+        }
+        Some(mins) => {
+            crate::secret_cache::serve(Duration::from_secs(mins * 60))?;
+            eprintln!("Caching unlocked secrets for {mins} min.");
+        }
+        None => {}
+    }
+    if let Some(metrics) = &settings.metrics {
+        crate::metrics::serve(metrics)?;
+        eprintln!("Metrics on http://127.0.0.1:{}/metrics", metrics.port);
+    }
+
+    loop {
+        match crate::settings::load() {
+            Ok(settings) => {
+                let now = chrono::Local::now();
+                let _tick = tracing::debug_span!("tick").entered();
+                let digest_topic = settings.topics.as_ref().and_then(|t| t.digest);
+                if let Err(e) = crate::digest::tick(&settings.digest, digest_topic, now) {
+                    tracing::warn!(error = %e, "weekly digest failed");
+                }
+                if let Err(e) = crate::audit_sync::flush(settings.audit_sync.as_ref()) {
+                    tracing::warn!(error = %e, "audit sync failed");
+                }
+                if let Err(e) = crate::expiry::tick(now) {
+                    tracing::warn!(error = %e, "PAT expiry check failed");
+                }
+                if let Err(e) = crate::queue::tick(settings.offline_queue.as_ref(), now) {
+                    tracing::warn!(error = %e, "offline queue delivery failed");
+                }
+                if let Err(e) = crate::cleanup::flush(settings.cleanup.as_ref()) {
+                    tracing::warn!(error = %e, "card cleanup failed");
+                }
+            }
+            Err(e) => tracing::warn!(error = format!("{e:#}"), "config error, skipping jobs"),
+        }
+        std::thread::sleep(TICK);
+    }
+}
@@ -0,0 +1,62 @@
+//! `gh repo delete`: which repository goes. There is no undoing it, so the
+//! approver types the repository's name before Approve counts.
+
+use crate::flags::{self, flag, Arg, Flag};
+
+/// Parsed metadata from `gh repo delete` flags (everything after "delete").
+#[derive(Debug, Default)]
+pub struct DeleteArgs {
+    /// The repository to delete, as given; the checkout's when unset.
+    pub repo: Option<String>,
+    /// `--yes`: gh's own confirmation prompt is skipped.
+    pub yes: bool,
+    pub help: bool,
+}
+
+/// `gh repo delete` flags; `--confirm` is the old name of `--yes`.
+const FLAGS: &[Flag] = &[
+    flag("yes", None, false),
+    flag("confirm", None, false),
+    flag("help", Some('h'), false),
+];
+
+/// Scan raw `gh repo delete` flags.
+pub fn parse_delete_args(args: &[String]) -> DeleteArgs {
+    let mut out = DeleteArgs::default();
+    for arg in flags::split(args, FLAGS) {
+        match arg {
+            Arg::Flag {
+                name: "yes" | "confirm",
+                value,
+            } => out.yes = flags::enabled(value),
+            Arg::Flag {
+                name: "help",
+                value,
+            } => out.help = flags::enabled(value),
+            Arg::Positional(p) if out.repo.is_none() => out.repo = Some(p.to_string()),
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> DeleteArgs {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_delete_args(&args)
+    }
+
+    #[test]
+    fn repository_and_yes() {
+        let delete = parse(&["acme/api", "--yes"]);
+        assert_eq!(delete.repo.as_deref(), Some("acme/api"));
+        assert!(delete.yes);
+
+        let delete = parse(&["--confirm"]);
+        assert_eq!(delete.repo, None);
+        assert!(delete.yes);
+    }
+}
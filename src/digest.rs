@@ -0,0 +1,113 @@
+use crate::notify::{escape_html, TgConfig};
+use crate::settings::Digest;
+use crate::stats::{self, Summary};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "digest.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct DigestState {
+    /// ISO week ("2026-W42") of the last digest sent.
+    last_week: Option<String>,
+}
+
+/// Called once per daemon tick: send the weekly digest, to forum topic
+/// `topic` if given, if it is due and has not been sent for the current
+/// ISO week yet.
+pub fn tick(cfg: &Digest, topic: Option<i64>, now: DateTime<Local>) -> Result<()> {
+    if !cfg.enabled {
+        return Ok(());
+    }
+    let day: Weekday = cfg
+        .day
+        .parse()
+        .map_err(|_| anyhow!("Invalid digest day {:?} (e.g. \"sun\")", cfg.day))?;
+    let at = NaiveTime::parse_from_str(cfg.at.trim(), "%H:%M")
+        .map_err(|_| anyhow!("Invalid digest time {:?} (expected HH:MM)", cfg.at))?;
+
+    if now.weekday() != day || now.time() < at {
+        return Ok(());
+    }
+    let week = {
+        let w = now.iso_week();
+        format!("{}-W{:02}", w.year(), w.week())
+    };
+    let mut state: DigestState = crate::state::load(STATE_FILE);
+    if state.last_week.as_deref() == Some(week.as_str()) {
+        return Ok(());
+    }
+
+    send(now, topic)?;
+    state.last_week = Some(week);
+    crate::state::save(STATE_FILE, &state)
+}
+
+/// Summarise the last seven days of the audit log and post it to Telegram.
+pub fn send(now: DateTime<Local>, topic: Option<i64>) -> Result<()> {
+    let since = now - Duration::days(7);
+    let entries = crate::audit::read_all()?;
+    let recent = entries
+        .iter()
+        .filter(|e| DateTime::parse_from_rfc3339(&e.ts).is_ok_and(|t| t >= since && t <= now));
+    let summary = Summary::compute(recent);
+
+    let deps = crate::guard::Deps::live();
+    let tg = TgConfig {
+        thread_id: topic,
+        ..deps.credentials.telegram()?
+    };
+    let html = format_digest(&summary, since, now);
+    deps.notifier.send_note(&tg, &html)
+}
+
+fn format_digest(s: &Summary, since: DateTime<Local>, now: DateTime<Local>) -> String {
+    let mut html = format!(
+        "📊 <b>gh-guard weekly digest</b>\n<i>{} – {}</i>\n\n",
+        since.format("%b %-d"),
+        now.format("%b %-d"),
+    );
+    if s.total == 0 {
+        html.push_str("No guarded requests this week.");
+        return html;
+    }
+
+    html.push_str(&format!(
+        "<b>Requests</b>  {} ({} approved · {} rejected · {} timed out · {} auto-rejected)\n",
+        s.total, s.approved, s.rejected, s.timeouts, s.auto_rejected
+    ));
+    if s.bypassed > 0 {
+        html.push_str(&format!("<b>Bypassed</b>  {}\n", s.bypassed));
+    }
+    if s.auto_approved > 0 {
+        html.push_str(&format!("<b>Auto-approved</b>  {}\n", s.auto_approved));
+    }
+    if let Some(rate) = s.approval_rate() {
+        html.push_str(&format!("<b>Approval rate</b>  {:.0}%\n", rate * 100.0));
+    }
+    if let Some(ms) = s.avg_latency_ms {
+        html.push_str(&format!(
+            "<b>Avg response</b>  {}\n",
+            stats::fmt_duration_ms(ms)
+        ));
+    }
+
+    if !s.top_repos.is_empty() {
+        html.push_str("\n<b>Top repos</b>\n");
+        for (repo, n) in &s.top_repos {
+            html.push_str(&format!("{} — {n}\n", escape_html(repo)));
+        }
+    }
+
+    if !s.rejected_actions.is_empty() {
+        html.push_str("\n<b>Rejected</b>\n");
+        for action in s.rejected_actions.iter().rev().take(10) {
+            html.push_str(&format!("• <code>{}</code>\n", escape_html(action)));
+        }
+        if s.rejected_actions.len() > 10 {
+            html.push_str(&format!("…and {} more\n", s.rejected_actions.len() - 10));
+        }
+    }
+    html
+}
@@ -0,0 +1,94 @@
+use crate::exit;
+use std::path::PathBuf;
+
+/// Failures and decisions worth telling apart. Raised where they happen and
+/// carried inside `anyhow::Error`, so context added on the way up is kept;
+/// [`find`] digs them out again for the exit code and `--guard-json`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A secret gh-guard cannot work without is not stored anywhere.
+    #[error("{0}")]
+    ConfigMissing(String),
+    #[error("Invalid config in {}", path.display())]
+    ConfigInvalid {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    /// No connection at all, as opposed to Telegram refusing a request.
+    #[error("Failed to reach Telegram API ({0})")]
+    TelegramUnreachable(String),
+    /// Telegram answered with an error: bad token, unknown chat, …
+    #[error("{0}")]
+    TelegramApi(String),
+    #[error(
+        "Could not find the real `gh` binary in PATH.\n\
+         Install the GitHub CLI: https://cli.github.com"
+    )]
+    GhNotFound,
+    /// The command would prompt, and there is no terminal for gh-guard to
+    /// ask on instead.
+    #[error(
+        "gh-guard cannot ask for the PR details without a terminal.\n\
+         Add --title \"...\" (and optionally --body \"...\") to your command,\n\
+         or use --fill to auto-fill from commit messages."
+    )]
+    NotInterceptable,
+
+    // Decisions: already reported on the terminal, in the audit log and as
+    // JSON by the time they are returned.
+    /// Rejected on Telegram, or stopped with `/panic`.
+    #[error("rejected")]
+    ApprovalRejected,
+    #[error("timed out waiting for approval")]
+    ApprovalTimedOut,
+    /// Withdrawn with Ctrl+C.
+    #[error("cancelled")]
+    Cancelled,
+    /// Refused without asking anyone; the reason is as in the audit log.
+    #[error("auto-rejected ({0})")]
+    PolicyRejected(&'static str),
+    /// Held for `gh-guard daemon`, under this queue ID.
+    #[error("queued as {0}")]
+    Queued(String),
+}
+
+impl Error {
+    /// Stable name for `--guard-json`. Decisions use the audit log's words.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::ConfigMissing(_) => "config-missing",
+            Error::ConfigInvalid { .. } => "config-invalid",
+            Error::TelegramUnreachable(_) => "telegram-unreachable",
+            Error::TelegramApi(_) => "telegram-api",
+            Error::GhNotFound => "gh-not-found",
+            Error::NotInterceptable => "not-interceptable",
+            Error::ApprovalRejected => "rejected",
+            Error::ApprovalTimedOut => "timeout",
+            Error::Cancelled => "cancelled",
+            Error::PolicyRejected(_) => "auto-rejected",
+            Error::Queued(_) => "queued",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::ApprovalRejected => exit::REJECTED,
+            Error::ApprovalTimedOut => exit::TIMEOUT,
+            Error::Cancelled => exit::CANCELLED,
+            Error::PolicyRejected(_) => exit::POLICY,
+            Error::Queued(_) => exit::QUEUED,
+            _ => exit::ERROR,
+        }
+    }
+
+    /// True for outcomes someone decided on, which need no error message.
+    pub fn is_decision(&self) -> bool {
+        self.exit_code() != exit::ERROR
+    }
+}
+
+/// The first [`Error`] in `e`'s chain, if any.
+pub fn find(e: &anyhow::Error) -> Option<&Error> {
+    e.chain().find_map(|cause| cause.downcast_ref())
+}
@@ -0,0 +1,21 @@
+/// A human tapped Reject (or hit the panic button).
+pub const REJECTED: i32 = 3;
+
+/// Nobody answered before the approval window closed.
+pub const TIMEOUT: i32 = 4;
+
+/// gh-guard could not do its job: missing credentials, network or config
+/// errors, un-interceptable invocations.
+pub const ERROR: i32 = 5;
+
+/// Refused without asking anyone: read-only or locked mode, quiet hours,
+/// rate limit or CI policy.
+pub const POLICY: i32 = 6;
+
+/// Telegram was unreachable; the request was queued for `gh-guard daemon`
+/// to deliver, and gh has not run yet.
+pub const QUEUED: i32 = 7;
+
+/// Withdrawn with Ctrl+C while waiting for approval (128 + SIGINT, as a
+/// shell would report it).
+pub const CANCELLED: i32 = 130;
@@ -0,0 +1,212 @@
+use crate::notify::escape_html;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Once;
+
+const STATE_FILE: &str = "pat_expiry.json";
+
+/// Start warning this long before the PAT expires.
+const WARN_DAYS: i64 = 7;
+
+/// What is known about each profile's stored PAT, keyed by profile name.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ExpiryState {
+    #[serde(flatten)]
+    profiles: BTreeMap<String, Known>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Known {
+    /// Short SHA-256 of the token, so a replaced PAT is not judged by the
+    /// old one's date.
+    fingerprint: String,
+    /// RFC 3339; `None` for tokens without an expiration.
+    expires_at: Option<String>,
+    /// RFC 3339 time of the last check against GitHub.
+    checked_at: String,
+    /// RFC 3339 time of the last Telegram reminder.
+    #[serde(default)]
+    reminded_at: Option<String>,
+}
+
+/// Parse GitHub's `github-authentication-token-expiration` header, e.g.
+/// "2026-11-01 12:00:00 UTC" or "2026-11-01 12:00:00 +0100".
+pub fn parse_header(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Some(naive) = value.strip_suffix(" UTC") {
+        let t = NaiveDateTime::parse_from_str(naive, "%Y-%m-%d %H:%M:%S").ok()?;
+        return Some(t.and_utc().fixed_offset());
+    }
+    DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z").ok()
+}
+
+/// Remember `token`'s expiration for the active profile.
+pub fn record(token: &str, expires_at: Option<DateTime<FixedOffset>>) {
+    let mut state: ExpiryState = crate::state::load(STATE_FILE);
+    let entry = state.profiles.entry(profile_key()).or_default();
+    if entry.fingerprint != fingerprint(token) {
+        entry.reminded_at = None;
+    }
+    entry.fingerprint = fingerprint(token);
+    entry.expires_at = expires_at.map(|t| t.to_rfc3339());
+    entry.checked_at = Utc::now().to_rfc3339();
+    if let Err(e) = crate::state::save(STATE_FILE, &state) {
+        tracing::warn!(error = %e, "cannot record PAT expiry");
+    }
+}
+
+/// Fail with a clear message if `token` is known to have expired, and warn
+/// (once per run) when it expires within a week.
+pub fn check(token: &str) -> Result<()> {
+    let Some(expires) = known_expiry(token) else {
+        return Ok(());
+    };
+    let left = expires - Utc::now().fixed_offset();
+    if left <= Duration::zero() {
+        bail!(
+            "GitHub PAT expired on {}. Run `gh-guard setup pat` to store a new one.",
+            expires.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+    }
+    if left < Duration::days(WARN_DAYS) {
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            eprintln!(
+                "{}",
+                format!(
+                    "gh-guard: GitHub PAT expires {} — run `gh-guard setup pat` to renew it.",
+                    in_words(left)
+                )
+                .yellow()
+            );
+        });
+    }
+    Ok(())
+}
+
+/// Called once per daemon tick: re-check the stored PAT with GitHub about
+/// once a day, and send a Telegram reminder (at most daily) in its last
+/// week or once it has stopped working.
+pub fn tick(now: DateTime<Local>) -> Result<()> {
+    let settings = crate::settings::load()?;
+    if settings.github_app.is_some()
+        || crate::config::get_oauth_refresh().is_some()
+        || !crate::config::active_env_overrides().is_empty()
+    {
+        return Ok(());
+    }
+
+    let state: ExpiryState = crate::state::load(STATE_FILE);
+    let known = state.profiles.get(&profile_key());
+    let due = known
+        .and_then(|k| DateTime::parse_from_rfc3339(&k.checked_at).ok())
+        .is_none_or(|t| now.fixed_offset() - t >= Duration::hours(24));
+
+    let problem = match crate::config::get_pat() {
+        // get_pat refuses a PAT that is known to have expired.
+        Err(e) if known.is_some_and(|k| is_past(k, now)) => Some(e.to_string()),
+        Err(_) => return Ok(()), // not configured
+        Ok(token) if due => match query(&token) {
+            Ok(expires) => {
+                record(&token, expires);
+                expires
+                    .map(|t| t - now.fixed_offset())
+                    .filter(|left| *left < Duration::days(WARN_DAYS))
+                    .map(|left| format!("GitHub PAT expires {}.", in_words(left)))
+            }
+            Err(e) => Some(format!("{e:#}")),
+        },
+        Ok(_) => known
+            .and_then(|k| k.expires_at.as_deref())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t - now.fixed_offset())
+            .filter(|left| *left < Duration::days(WARN_DAYS))
+            .map(|left| format!("GitHub PAT expires {}.", in_words(left))),
+    };
+    let Some(problem) = problem else {
+        return Ok(());
+    };
+
+    let mut state: ExpiryState = crate::state::load(STATE_FILE);
+    let entry = state.profiles.entry(profile_key()).or_default();
+    let reminded_recently = entry
+        .reminded_at
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .is_some_and(|t| now.fixed_offset() - t < Duration::hours(24));
+    if reminded_recently {
+        return Ok(());
+    }
+
+    let deps = crate::guard::Deps::live();
+    let tg = deps.credentials.telegram()?;
+    deps.notifier.send_note(
+        &tg,
+        &format!(
+            "🔑 <b>gh-guard</b> on <code>{}</code>: {}\n\nRun <code>gh-guard setup pat</code> to renew it.",
+            escape_html(&crate::agent::hostname()),
+            escape_html(&problem)
+        ),
+    )?;
+    entry.reminded_at = Some(now.to_rfc3339());
+    crate::state::save(STATE_FILE, &state)
+}
+
+/// Ask GitHub for `token`'s expiration (`None`: never expires).
+fn query(token: &str) -> Result<Option<DateTime<FixedOffset>>> {
+    let url = crate::host::api_url("/user");
+    let resp = crate::http::builder(&url)?
+        .timeout(std::time::Duration::from_secs(15))
+        .build()
+        .get(&url)
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("User-Agent", "gh-guard/0.1")
+        .call();
+    match resp {
+        Ok(r) => Ok(r
+            .header("github-authentication-token-expiration")
+            .and_then(parse_header)),
+        Err(ureq::Error::Status(401, _)) => {
+            bail!("GitHub no longer accepts the stored PAT (expired or revoked).")
+        }
+        Err(e) => Err(anyhow!("GitHub API: {e}")),
+    }
+}
+
+fn is_past(known: &Known, now: DateTime<Local>) -> bool {
+    known
+        .expires_at
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .is_some_and(|t| t <= now.fixed_offset())
+}
+
+fn known_expiry(token: &str) -> Option<DateTime<FixedOffset>> {
+    let state: ExpiryState = crate::state::load(STATE_FILE);
+    let known = state.profiles.get(&profile_key())?;
+    if known.fingerprint != fingerprint(token) {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(known.expires_at.as_deref()?).ok()
+}
+
+fn profile_key() -> String {
+    crate::profile::active().unwrap_or("default").to_string()
+}
+
+pub fn fingerprint(token: &str) -> String {
+    hex::encode(&Sha256::digest(token.as_bytes())[..8])
+}
+
+/// "in 3 days", "tomorrow", "in 5 hours".
+fn in_words(left: Duration) -> String {
+    match left.num_hours() {
+        h if h < 24 => format!("in {} hours", h.max(1)),
+        h if h < 48 => "tomorrow".to_string(),
+        h => format!("in {} days", (h + 12) / 24),
+    }
+}
@@ -0,0 +1,180 @@
+/// A flag gh knows, by long name.
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub takes_value: bool,
+}
+
+/// Shorthand for the flag tables.
+pub const fn flag(long: &'static str, short: Option<char>, takes_value: bool) -> Flag {
+    Flag {
+        long,
+        short,
+        takes_value,
+    }
+}
+
+/// One argument after splitting.
+#[derive(Debug, PartialEq)]
+pub enum Arg<'a> {
+    /// A flag by long name (or as written, if unknown). `value` is `None`
+    /// for booleans given bare, and for a value flag with nothing after it.
+    Flag {
+        name: &'a str,
+        value: Option<&'a str>,
+    },
+    Positional(&'a str),
+}
+
+/// Split `args` against the command's flag table the way gh's flag parser
+/// (pflag) does: `-XPOST`, `-X=POST`, clusters like `-iX POST`, `--flag=v`,
+/// and nothing but positionals after `--`. Unknown flags are taken to be
+/// booleans, as guessing otherwise could swallow the next argument.
+pub fn split<'a>(args: &'a [String], table: &[Flag]) -> Vec<Arg<'a>> {
+    let mut out = Vec::new();
+    let mut rest = args.iter().map(String::as_str);
+
+    while let Some(arg) = rest.next() {
+        if arg == "--" {
+            out.extend(rest.by_ref().map(Arg::Positional));
+            break;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((n, v)) => (n, Some(v)),
+                None => (long, None),
+            };
+            let known = table.iter().find(|f| f.long == name);
+            let value = match known {
+                Some(f) if f.takes_value && inline.is_none() => rest.next(),
+                _ => inline,
+            };
+            out.push(Arg::Flag {
+                name: known.map_or(name, |f| f.long),
+                value,
+            });
+            continue;
+        }
+        let Some(cluster) = arg.strip_prefix('-').filter(|c| !c.is_empty()) else {
+            // Includes a lone "-" (stdin).
+            out.push(Arg::Positional(arg));
+            continue;
+        };
+
+        // -abc: booleans until the first flag that takes a value, which
+        // gets the rest of the token or else the next argument.
+        for (i, c) in cluster.char_indices() {
+            let after = &cluster[i + c.len_utf8()..];
+            let known = table.iter().find(|f| f.short == Some(c));
+            let name = known.map_or(&cluster[i..i + c.len_utf8()], |f| f.long);
+            if known.is_some_and(|f| f.takes_value) {
+                let value = match after {
+                    "" => rest.next(),
+                    v => Some(v.strip_prefix('=').unwrap_or(v)),
+                };
+                out.push(Arg::Flag { name, value });
+                break;
+            }
+            if let Some(v) = after.strip_prefix('=') {
+                // -d=false
+                out.push(Arg::Flag {
+                    name,
+                    value: Some(v),
+                });
+                break;
+            }
+            out.push(Arg::Flag { name, value: None });
+        }
+    }
+    out
+}
+
+/// Whether a boolean flag given as `value` is on: bare, or anything but a
+/// false spelling strconv.ParseBool accepts.
+pub fn enabled(value: Option<&str>) -> bool {
+    !matches!(value, Some("false" | "FALSE" | "False" | "f" | "F" | "0"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &[Flag] = &[
+        flag("method", Some('X'), true),
+        flag("include", Some('i'), false),
+    ];
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn method(value: &str) -> Arg<'_> {
+        Arg::Flag {
+            name: "method",
+            value: Some(value),
+        }
+    }
+
+    #[test]
+    fn short_values_attached_or_separate() {
+        for form in [
+            &["-XPUT"][..],
+            &["-X=PUT"],
+            &["-X", "PUT"],
+            &["--method=PUT"],
+        ] {
+            let a = args(form);
+            assert_eq!(split(&a, TABLE), [method("PUT")], "{form:?}");
+        }
+    }
+
+    #[test]
+    fn clusters_end_at_the_first_value_flag() {
+        let a = args(&["-iX", "DELETE", "x"]);
+        let include = Arg::Flag {
+            name: "include",
+            value: None,
+        };
+        assert_eq!(
+            split(&a, TABLE),
+            [include, method("DELETE"), Arg::Positional("x")]
+        );
+    }
+
+    #[test]
+    fn value_flags_take_the_next_argument_whatever_it_is() {
+        let a = args(&["--method", "--", "-X"]);
+        assert_eq!(
+            split(&a, TABLE),
+            [
+                method("--"),
+                Arg::Flag {
+                    name: "method",
+                    value: None
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_a_flag_after_double_dash() {
+        let a = args(&["a", "--", "-XPOST", "--", "-"]);
+        assert_eq!(
+            split(&a, TABLE),
+            [
+                Arg::Positional("a"),
+                Arg::Positional("-XPOST"),
+                Arg::Positional("--"),
+                Arg::Positional("-")
+            ]
+        );
+    }
+
+    #[test]
+    fn bool_spellings() {
+        assert!(enabled(None));
+        assert!(enabled(Some("true")));
+        assert!(!enabled(Some("false")));
+        assert!(!enabled(Some("0")));
+    }
+}
@@ -0,0 +1,134 @@
+//! `gh repo fork`: which repository is copied where. A private repository
+//! forked out of its organisation takes its code along, so the card names
+//! both ends.
+
+use crate::flags::{self, flag, Arg, Flag};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Parsed metadata from `gh repo fork` flags (everything after "fork").
+#[derive(Debug, Default)]
+pub struct ForkArgs {
+    /// The repository to fork, as given; the checkout's when unset.
+    pub repo: Option<String>,
+    /// `--org`: the organisation that gets the fork instead of the
+    /// authenticated user.
+    pub org: Option<String>,
+    /// `--fork-name`; the source's name when unset.
+    pub fork_name: Option<String>,
+    pub clone: bool,
+    pub default_branch_only: bool,
+    pub help: bool,
+}
+
+/// `gh repo fork` flags, so values are not mistaken for the repository.
+const FLAGS: &[Flag] = &[
+    flag("org", None, true),
+    flag("fork-name", None, true),
+    flag("remote-name", None, true),
+    flag("clone", None, false),
+    flag("remote", None, false),
+    flag("default-branch-only", None, false),
+    flag("help", Some('h'), false),
+];
+
+/// Scan raw `gh repo fork` flags. Arguments after `--` go to `git clone`.
+pub fn parse_fork_args(args: &[String]) -> ForkArgs {
+    let mut out = ForkArgs::default();
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    for arg in flags::split(&args[..end], FLAGS) {
+        match arg {
+            Arg::Flag {
+                name: "org",
+                value: Some(v),
+            } => out.org = Some(v.to_string()),
+            Arg::Flag {
+                name: "fork-name",
+                value: Some(v),
+            } => out.fork_name = Some(v.to_string()),
+            Arg::Flag {
+                name: "clone",
+                value,
+            } => out.clone = flags::enabled(value),
+            Arg::Flag {
+                name: "default-branch-only",
+                value,
+            } => out.default_branch_only = flags::enabled(value),
+            Arg::Flag {
+                name: "help",
+                value,
+            } => out.help = flags::enabled(value),
+            Arg::Positional(p) if out.repo.is_none() => out.repo = Some(p.to_string()),
+            _ => {}
+        }
+    }
+    out
+}
+
+impl ForkArgs {
+    /// `owner/name` the fork will have, given the owner it goes to.
+    pub fn destination(&self, source: &str, owner: &str) -> String {
+        let name = source.split_once('/').map_or(source, |(_, name)| name);
+        format!("{owner}/{}", self.fork_name.as_deref().unwrap_or(name))
+    }
+}
+
+/// True when `source` already belongs to `owner`, so the fork stays with
+/// the same organisation or user.
+pub fn same_owner(source: &str, owner: &str) -> bool {
+    source
+        .split_once('/')
+        .is_some_and(|(o, _)| o.eq_ignore_ascii_case(owner))
+}
+
+#[derive(Deserialize)]
+struct Repo {
+    private: bool,
+}
+
+/// Whether `repo` is private (or internal), asked of GitHub.
+pub fn is_private(token: &str, repo: &str) -> Result<bool> {
+    let url = crate::host::api_url(&format!("/repos/{repo}"));
+    let repo: Repo = crate::http::builder(&url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(10))
+        .build()
+        .get(&url)
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("GitHub API: {e}"))?
+        .into_json()?;
+    Ok(repo.private)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> ForkArgs {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_fork_args(&args)
+    }
+
+    #[test]
+    fn repository_and_destination() {
+        let fork = parse(&["acme/api", "--org=me-org", "--clone", "--", "--depth", "1"]);
+        assert_eq!(fork.repo.as_deref(), Some("acme/api"));
+        assert_eq!(fork.org.as_deref(), Some("me-org"));
+        assert!(fork.clone);
+        assert_eq!(fork.destination("acme/api", "me-org"), "me-org/api");
+
+        let fork = parse(&["--fork-name", "api-copy", "--remote"]);
+        assert_eq!(fork.repo, None);
+        assert_eq!(fork.destination("acme/api", "me"), "me/api-copy");
+    }
+
+    #[test]
+    fn owners_compare_without_case() {
+        assert!(same_owner("Acme/api", "acme"));
+        assert!(!same_owner("acme/api", "acme-forks"));
+    }
+}
@@ -1,16 +1,95 @@
+use crate::error::Error;
 use anyhow::{bail, Result};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use std::process;
 
+const CACHE_FILE: &str = "gh_path.json";
+
+/// Where the last search found gh. Nothing in here is trusted as a path:
+/// the hit is re-derived from the current PATH and only accepted while
+/// every directory searched, and gh itself, is unchanged.
+#[derive(Default, Deserialize, Serialize)]
+struct Cache {
+    path_var: String,
+    self_exe: PathBuf,
+    /// The PATH entries up to and including the one gh was found in. A `gh`
+    /// added to any of them changes its mtime.
+    dirs: Vec<Stamp>,
+    gh: Stamp,
+}
+
+/// Identity and last change of a file or directory.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+struct Stamp {
+    dev: u64,
+    ino: u64,
+    mtime_ns: i64,
+    /// Also moves on chmod, which can make gh non-executable.
+    ctime_ns: i64,
+}
+
 /// Search PATH for the real `gh` binary, skipping our own executable.
 /// This prevents an infinite loop when gh-guard is installed as 'gh'.
+///
+/// The answer is cached in the state directory, so the common passthrough
+/// case costs a few stats instead of a walk over PATH.
 pub fn find_real_gh() -> Result<PathBuf> {
     let self_exe = std::env::current_exe()?;
-    let self_resolved = self_exe.canonicalize().unwrap_or(self_exe);
-
     let path_var = std::env::var("PATH").unwrap_or_default();
 
-    for dir in path_var.split(':') {
+    if let Some(gh) = cached(&path_var, &self_exe) {
+        return Ok(gh);
+    }
+    let (index, gh) = search(&path_var, &self_exe)?;
+
+    let dirs = path_var.split(':').take(index + 1).map(dir_stamp).collect();
+    if let (Some(dirs), Some(gh_stamp)) = (dirs, stamp(&gh)) {
+        let cache = Cache {
+            path_var,
+            self_exe,
+            dirs,
+            gh: gh_stamp,
+        };
+        if let Err(e) = crate::state::save(CACHE_FILE, &cache) {
+            tracing::debug!(error = %e, "cannot cache gh path");
+        }
+    }
+    Ok(gh)
+}
+
+/// The cached gh, if PATH, our executable and everything searched are as
+/// they were when it was found.
+fn cached(path_var: &str, self_exe: &Path) -> Option<PathBuf> {
+    let cache: Cache = crate::state::load(CACHE_FILE);
+    if cache.path_var != path_var || cache.self_exe != self_exe || cache.dirs.is_empty() {
+        return None;
+    }
+    let dirs: Vec<&str> = path_var.split(':').take(cache.dirs.len()).collect();
+    if dirs.len() != cache.dirs.len() {
+        return None;
+    }
+    for (dir, expected) in dirs.iter().zip(&cache.dirs) {
+        if dir_stamp(dir) != Some(*expected) {
+            return None;
+        }
+    }
+    let gh = PathBuf::from(dirs.last()?).join("gh");
+    if stamp(&gh) != Some(cache.gh) {
+        return None;
+    }
+    tracing::debug!(gh = %gh.display(), "gh path from cache");
+    Some(gh)
+}
+
+/// Walk PATH; returns the index of the entry gh was found in, and its path.
+fn search(path_var: &str, self_exe: &Path) -> Result<(usize, PathBuf)> {
+    let self_resolved = self_exe
+        .canonicalize()
+        .unwrap_or_else(|_| self_exe.to_path_buf());
+
+    for (index, dir) in path_var.split(':').enumerate() {
         let candidate = PathBuf::from(dir).join("gh");
         if !candidate.exists() {
             continue;
@@ -24,14 +103,39 @@ pub fn find_real_gh() -> Result<PathBuf> {
         use std::os::unix::fs::PermissionsExt;
         if let Ok(meta) = candidate.metadata() {
             if meta.permissions().mode() & 0o111 != 0 {
-                return Ok(candidate);
+                return Ok((index, candidate));
             }
         }
     }
 
-    bail!(
-        "Could not find the real `gh` binary in PATH.\n\
-         Install the GitHub CLI: https://cli.github.com"
+    Err(Error::GhNotFound.into())
+}
+
+/// An empty PATH entry means the current directory.
+fn dir_stamp(dir: &str) -> Option<Stamp> {
+    stamp(Path::new(if dir.is_empty() { "." } else { dir }))
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some(Stamp {
+        dev: meta.dev(),
+        ino: meta.ino(),
+        mtime_ns: meta.mtime() * 1_000_000_000 + meta.mtime_nsec(),
+        ctime_ns: meta.ctime() * 1_000_000_000 + meta.ctime_nsec(),
+    })
+}
+
+/// Whether gh may contact GitHub for `args`. False only for commands that
+/// are known to be local: help, version, completion scripts, aliases and
+/// gh's own config.
+pub fn needs_token(args: &[String]) -> bool {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        return false;
+    }
+    !matches!(
+        args.first().map(String::as_str),
+        None | Some("--version" | "version" | "help" | "completion" | "alias" | "config")
     )
 }
 
@@ -42,10 +146,13 @@ pub fn exec_passthrough(args: &[String], token: Option<&str>) -> Result<()> {
     use std::os::unix::process::CommandExt;
 
     let gh = find_real_gh()?;
+    tracing::debug!(gh = %gh.display(), token = token.is_some(), "exec passthrough");
     let mut cmd = process::Command::new(&gh);
-    cmd.args(args).env("GH_GUARD_ACTIVE", "1");
+    cmd.args(args)
+        .env("GH_GUARD_ACTIVE", "1")
+        .envs(crate::http::child_env());
     if let Some(t) = token {
-        cmd.env("GH_TOKEN", t);
+        cmd.envs(crate::host::token_env(t));
     }
     // exec() only returns on failure.
     let err = cmd.exec();
@@ -56,10 +163,13 @@ pub fn exec_passthrough(args: &[String], token: Option<&str>) -> Result<()> {
 /// Used post-approval so we can capture the code and exit cleanly.
 pub fn run_gh(args: &[String], token: Option<&str>) -> Result<i32> {
     let gh = find_real_gh()?;
+    tracing::debug!(gh = %gh.display(), token = token.is_some(), "spawn gh");
     let mut cmd = process::Command::new(&gh);
-    cmd.args(args).env("GH_GUARD_ACTIVE", "1");
+    cmd.args(args)
+        .env("GH_GUARD_ACTIVE", "1")
+        .envs(crate::http::child_env());
     if let Some(t) = token {
-        cmd.env("GH_TOKEN", t);
+        cmd.envs(crate::host::token_env(t));
     }
     let status = cmd.status()?;
     Ok(status.code().unwrap_or(1))
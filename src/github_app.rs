@@ -0,0 +1,167 @@
+use crate::settings::GitHubApp;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A minted installation token, cached in the secret store until shortly
+/// before it expires.
+#[derive(Debug, Deserialize, Serialize)]
+struct Cached {
+    /// Installation ID, or the account login it was looked up for.
+    key: String,
+    token: String,
+    /// Unix seconds.
+    expires_at: i64,
+}
+
+/// Installation tokens live for an hour; mint a new one when less than this
+/// is left, so a long-running gh command does not outlive its token.
+const MIN_REMAINING_SECS: i64 = 10 * 60;
+
+/// Installation token for the app, minted on demand and reused while valid.
+pub fn token(app: &GitHubApp) -> Result<String> {
+    let owner = crate::profile::target_repo()
+        .and_then(|r| r.split_once('/').map(|(o, _)| o.to_lowercase()));
+    let key = match (app.installation_id, &owner) {
+        (Some(id), _) => id.to_string(),
+        (None, Some(owner)) => owner.clone(),
+        (None, None) => "*".to_string(),
+    };
+
+    if let Some(cached) = crate::config::get_app_token_cache()
+        .and_then(|s| serde_json::from_str::<Cached>(&s).ok())
+        .filter(|c| c.key == key)
+        .filter(|c| c.expires_at - Utc::now().timestamp() > MIN_REMAINING_SECS)
+    {
+        tracing::debug!(expires_at = %cached.expires_at, "reusing installation token");
+        return Ok(cached.token);
+    }
+
+    let jwt = jwt(app, &private_key(app)?)?;
+    let installation = match app.installation_id {
+        Some(id) => id,
+        None => find_installation(&jwt, owner.as_deref())?,
+    };
+    let resp: serde_json::Value = agent()?
+        .post(&crate::host::api_url(&format!(
+            "/app/installations/{installation}/access_tokens"
+        )))
+        .set("Authorization", &format!("Bearer {jwt}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("Minting GitHub App installation token: {e}"))?
+        .into_json()?;
+
+    let cached = Cached {
+        key,
+        token: resp["token"]
+            .as_str()
+            .ok_or_else(|| anyhow!("GitHub returned no installation token"))?
+            .to_string(),
+        expires_at: resp["expires_at"]
+            .as_str()
+            .and_then(|t| t.parse::<DateTime<Utc>>().ok())
+            .map_or(Utc::now().timestamp() + 3600, |t| t.timestamp()),
+    };
+    tracing::debug!(installation, expires_at = %cached.expires_at, "minted installation token");
+    if let Err(e) = crate::config::set_app_token_cache(&serde_json::to_string(&cached)?) {
+        tracing::warn!(error = %e, "cannot cache installation token");
+    }
+    Ok(cached.token)
+}
+
+/// The app's slug, fetched with a freshly signed JWT — proves the key and
+/// app ID belong together.
+pub fn verify(app: &GitHubApp, pem: &str) -> Result<String> {
+    let resp: serde_json::Value = agent()?
+        .get(&crate::host::api_url("/app"))
+        .set("Authorization", &format!("Bearer {}", jwt(app, pem)?))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("GitHub API: {e}"))?
+        .into_json()?;
+    Ok(resp["slug"].as_str().unwrap_or("unknown").to_string())
+}
+
+/// Invalidate an installation token before it expires.
+pub fn revoke(token: &str) -> Result<()> {
+    agent()?
+        .delete(&crate::host::api_url("/installation/token"))
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("GitHub API: {e}"))?;
+    Ok(())
+}
+
+fn private_key(app: &GitHubApp) -> Result<String> {
+    match &app.key_file {
+        Some(f) => {
+            let path = crate::settings::expand_home(f);
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Cannot read GitHub App key {}", path.display()))
+        }
+        None => crate::config::get_app_key(),
+    }
+}
+
+/// RS256 app JWT, backdated a minute for clock skew and valid for nine
+/// (GitHub allows at most ten).
+fn jwt(app: &GitHubApp, pem: &str) -> Result<String> {
+    let key = EncodingKey::from_rsa_pem(pem.as_bytes())
+        .context("GitHub App key is not an RSA private key in PEM format")?;
+    let now = Utc::now().timestamp();
+    let claims = serde_json::json!({
+        "iat": now - 60,
+        "exp": now + 9 * 60,
+        "iss": app.app_id.to_string(),
+    });
+    Ok(jsonwebtoken::encode(
+        &Header::new(Algorithm::RS256),
+        &claims,
+        &key,
+    )?)
+}
+
+/// Installation on `owner`'s account, or the app's only installation.
+fn find_installation(jwt: &str, owner: Option<&str>) -> Result<u64> {
+    let resp: serde_json::Value = agent()?
+        .get(&crate::host::api_url("/app/installations?per_page=100"))
+        .set("Authorization", &format!("Bearer {jwt}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("Listing GitHub App installations: {e}"))?
+        .into_json()?;
+    let installations = resp.as_array().cloned().unwrap_or_default();
+
+    let hit = match owner {
+        Some(owner) => installations.iter().find(|i| {
+            i["account"]["login"]
+                .as_str()
+                .is_some_and(|l| l.eq_ignore_ascii_case(owner))
+        }),
+        None if installations.len() == 1 => installations.first(),
+        None => None,
+    };
+    match (hit.and_then(|i| i["id"].as_u64()), owner) {
+        (Some(id), _) => Ok(id),
+        (None, Some(owner)) => bail!("The GitHub App is not installed on {owner}"),
+        (None, None) => bail!(
+            "The GitHub App has {} installations; set [github_app] installation_id",
+            installations.len()
+        ),
+    }
+}
+
+fn agent() -> Result<ureq::Agent> {
+    Ok(crate::http::builder(&crate::host::api_base())?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(15))
+        .build())
+}
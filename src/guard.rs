@@ -0,0 +1,1205 @@
+use crate::audit::{self, Decision};
+use crate::ci_policy::{self, Verdict};
+use crate::error::{self, Error};
+use crate::i18n::{self, Text};
+use crate::notify::{self, Approval, ApprovalResult, Choice, Document, Link, TgConfig};
+use crate::quiet::{self, Delivery};
+use crate::risk::Severity;
+use crate::settings::{QuietAction, Settings};
+use crate::{
+    allow_all, cleanup, config, gh, lockout, oncall, output, panic, ratelimit, readonly, similar,
+    sms, trust,
+};
+use anyhow::Result;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Which gh command is being guarded — only affects wording.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    PrCreate,
+    Api,
+    RepoFork,
+    ReleaseUpload,
+    RepoDelete,
+}
+
+impl Kind {
+    fn running(self, t: &Text) -> &'static str {
+        match self {
+            Kind::PrCreate => t.creating_pr,
+            Kind::Api => t.running_api,
+            Kind::RepoFork => t.forking,
+            Kind::ReleaseUpload => t.uploading,
+            Kind::RepoDelete => t.deleting,
+        }
+    }
+
+    fn cancelled(self, t: &Text) -> &'static str {
+        match self {
+            Kind::PrCreate => t.pr_not_created,
+            Kind::Api => t.api_cancelled,
+            Kind::RepoFork => t.fork_cancelled,
+            Kind::ReleaseUpload => t.upload_cancelled,
+            Kind::RepoDelete => t.delete_cancelled,
+        }
+    }
+}
+
+/// A mutation that needs approval, described independently of which gh
+/// command produced it. Serialisable so it can wait in the offline queue.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Request {
+    pub kind: Kind,
+    /// Full argv to hand to the real gh once approved.
+    pub gh_args: Vec<String>,
+    pub repo: String,
+    pub severity: Severity,
+    /// One-line description used in notes (e.g. `PATCH /repos/o/r/pulls/1`).
+    pub summary: String,
+    /// Telegram approval card (HTML), sent with Approve / Reject buttons.
+    pub card: String,
+    /// Files sent in reply to the card: text it had to cut, or the diff.
+    #[serde(default)]
+    pub documents: Vec<Document>,
+    /// Buttons to GitHub under the card.
+    #[serde(default)]
+    pub links: Vec<Link>,
+    /// How long to wait for a decision instead of `approval_timeout_secs`.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Approvals from different people needed; one if unset.
+    #[serde(default)]
+    pub approvals: Option<u32>,
+    /// Why a policy lets this run without asking (as in the audit log);
+    /// asked as usual if unset.
+    #[serde(default)]
+    pub auto_approve: Option<String>,
+    /// Asked on the phone even when a policy would let it run: changes to
+    /// who belongs to an organisation or team, and deletions.
+    #[serde(default)]
+    pub always_ask: bool,
+    /// Text the approver must reply with before Approve counts: the name
+    /// of a repository about to be deleted.
+    #[serde(default)]
+    pub confirm: Option<String>,
+    /// Queue ID when the daemon is delivering a queued request.
+    #[serde(skip)]
+    pub queued: Option<String>,
+}
+
+impl Request {
+    /// Why a policy lets this run without asking, unless it must be asked.
+    pub fn auto_approved(&self) -> Option<&str> {
+        self.auto_approve.as_deref().filter(|_| !self.always_ask)
+    }
+}
+
+// ── Collaborators ────────────────────────────────────────────────────────────
+
+/// Where a guarded command's credentials come from.
+pub trait Credentials {
+    /// Token handed to gh once a request is approved.
+    fn pat(&self) -> Result<String>;
+    /// The GitHub login `token` acts as, if it can be told.
+    fn login(&self, token: &str) -> Option<String>;
+    /// The `[accounts]` entry [`pat`](Credentials::pat) was taken from, if
+    /// not the profile's own PAT.
+    fn account(&self) -> Option<String> {
+        None
+    }
+    fn telegram(&self) -> Result<TgConfig>;
+}
+
+/// How requests reach the approver and decisions come back.
+pub trait Notifier {
+    /// Send an approval card, with `choices` and `links` as buttons under
+    /// it and, with `confirm`, a prompt to type that; returns
+    /// `(request_id, message_id)`.
+    fn send_with_approval(
+        &self,
+        tg: &TgConfig,
+        html: &str,
+        links: &[Link],
+        choices: &[Choice],
+        confirm: Option<&str>,
+        silent: bool,
+    ) -> Result<(String, i64)>;
+    /// Send a message without buttons.
+    fn send_note(&self, tg: &TgConfig, html: &str) -> Result<()>;
+    /// Send a file in reply to the card `reply_to`; returns its message ID.
+    fn send_document(&self, tg: &TgConfig, doc: &Document, reply_to: i64) -> Result<i64>;
+    fn poll_for_approval(
+        &self,
+        tg: &TgConfig,
+        request_id: &str,
+        message_id: i64,
+        timeout_secs: u64,
+        approvals: u32,
+    ) -> Result<Approval>;
+}
+
+/// The real gh.
+pub trait GhRunner {
+    /// Run gh as a child process and return its exit code.
+    fn run(&self, args: &[String], token: Option<&str>) -> Result<i32>;
+    /// Hand the process over to gh. Returns only on failure, or from a
+    /// stand-in that does not replace the process.
+    fn exec(&self, args: &[String], token: Option<&str>) -> Result<i32>;
+    /// Whether a request that could not be sent may be queued for the
+    /// daemon to run later. Not when someone else runs the command and
+    /// has already been told no.
+    fn can_queue(&self) -> bool {
+        true
+    }
+}
+
+/// Everything the handlers and the pipeline talk to outside gh-guard's own
+/// state. [`Deps::live`] in production; tests substitute fakes.
+pub struct Deps<'a> {
+    pub credentials: &'a dyn Credentials,
+    pub notifier: &'a dyn Notifier,
+    pub gh: &'a dyn GhRunner,
+}
+
+impl Deps<'static> {
+    /// The secret store, the Telegram Bot API and the gh found on PATH.
+    pub fn live() -> Self {
+        Deps {
+            credentials: &Live,
+            notifier: &Live,
+            gh: &Live,
+        }
+    }
+}
+
+struct Live;
+
+impl Credentials for Live {
+    fn pat(&self) -> Result<String> {
+        config::get_pat()
+    }
+
+    fn login(&self, token: &str) -> Option<String> {
+        crate::identity::login(token)
+    }
+
+    fn account(&self) -> Option<String> {
+        config::account()
+    }
+
+    fn telegram(&self) -> Result<TgConfig> {
+        // Cards go out as text messages with `[sms]`; no bot is needed.
+        let (token, chat_id) = match sms::configured() {
+            Some(_) => Default::default(),
+            None => (
+                config::get_telegram_token()?,
+                config::get_telegram_chat_id()?,
+            ),
+        };
+        Ok(TgConfig {
+            token,
+            chat_id,
+            thread_id: None,
+            members: Vec::new(),
+            quorum: None,
+            delegate: None,
+            commands: false,
+        })
+    }
+}
+
+impl Notifier for Live {
+    fn send_with_approval(
+        &self,
+        tg: &TgConfig,
+        html: &str,
+        links: &[Link],
+        choices: &[Choice],
+        confirm: Option<&str>,
+        silent: bool,
+    ) -> Result<(String, i64)> {
+        if let Some(sms) = sms::configured() {
+            if tg.quorum.is_some() {
+                anyhow::bail!("[voting] needs Telegram: a text message cannot collect votes");
+            }
+            // A text has no message ID worth keeping, nor buttons.
+            return sms::send_with_approval(sms, html, confirm).map(|id| (id, 0));
+        }
+        notify::send_with_approval(tg, html, links, choices, confirm, silent)
+    }
+
+    fn send_note(&self, tg: &TgConfig, html: &str) -> Result<()> {
+        match sms::configured() {
+            Some(sms) => sms::send_note(sms, html),
+            None => notify::send_note(tg, html),
+        }
+    }
+
+    fn send_document(&self, tg: &TgConfig, doc: &Document, reply_to: i64) -> Result<i64> {
+        if sms::configured().is_some() {
+            anyhow::bail!("text messages carry no files");
+        }
+        notify::send_document(tg, doc, reply_to)
+    }
+
+    fn poll_for_approval(
+        &self,
+        tg: &TgConfig,
+        request_id: &str,
+        message_id: i64,
+        timeout_secs: u64,
+        approvals: u32,
+    ) -> Result<Approval> {
+        if let Some(sms) = sms::configured() {
+            if approvals > 1 {
+                anyhow::bail!("A text message cannot collect {approvals} approvals");
+            }
+            return sms::poll_for_approval(sms, request_id, timeout_secs);
+        }
+        notify::poll_for_approval(tg, request_id, message_id, timeout_secs, approvals)
+    }
+}
+
+impl GhRunner for Live {
+    fn run(&self, args: &[String], token: Option<&str>) -> Result<i32> {
+        gh::run_gh(args, token)
+    }
+
+    fn exec(&self, args: &[String], token: Option<&str>) -> Result<i32> {
+        gh::exec_passthrough(args, token).map(|()| 0)
+    }
+}
+
+// ── Pipeline ─────────────────────────────────────────────────────────────────
+
+/// Run the shared approval pipeline: read-only mode, lockout, the CI
+/// policy, quiet hours and rate limits, then the Telegram round trip, then
+/// gh itself. Every outcome is written to the audit log and, with
+/// `--guard-json`, reported as a JSON object.
+/// Returns gh's exit code once approved. Any other outcome is an [`Error`]
+/// decision, already reported by the time it is returned.
+///
+/// `banner` prints the request-specific terminal summary. When Telegram
+/// cannot be reached the request may be queued instead (see [`queue`]).
+///
+/// [`queue`]: crate::queue
+pub fn run(req: Request, deps: &Deps, banner: impl FnOnce()) -> Result<i32> {
+    crate::profile::hint_repo(&req.repo);
+    if dry_running() {
+        return report(&req, &crate::settings::load()?, banner);
+    }
+    let mut entry = Pending::new(&req);
+    let result = guard(req, deps, &mut entry, banner);
+    if let Err(e) = &result {
+        if !error::find(e).is_some_and(Error::is_decision) {
+            entry.reason = Some(format!("{e:#}"));
+        }
+    }
+    result
+}
+
+/// [`run`] past the dry-run check; `entry` is written on every way out.
+fn guard(req: Request, deps: &Deps, entry: &mut Pending, banner: impl FnOnce()) -> Result<i32> {
+    let settings = crate::settings::load()?;
+    let source = crate::agent::source();
+    let t = i18n::text();
+    tracing::debug!(repo = %req.repo, severity = %req.severity, source = %source, "guarding");
+
+    if let Some(since) = readonly::since() {
+        entry.reason = Some("read-only".to_string());
+        finish(entry);
+        eprintln!("{}", (t.readonly_refused)(&since).red().bold());
+        return Err(Error::PolicyRejected("read-only").into());
+    }
+
+    if let Some(lock) = lockout::current() {
+        entry.reason = Some("locked".to_string());
+        finish(entry);
+        eprintln!("{}", lock.refusal(t).red().bold());
+        eprintln!("{}", t.locked_hint);
+        return Err(Error::PolicyRejected("locked").into());
+    }
+
+    if let Some(verdict) = ci_policy::decide(settings.ci.as_ref(), &req.repo)? {
+        return run_in_ci(&req, deps, entry, verdict, banner);
+    }
+
+    // Past the rate limit, what a policy would have run is asked about.
+    let auto = auto_approval(&req, &settings);
+    let limited = ratelimit::exceeded(settings.rate_limit.as_ref(), &req.repo).is_some();
+    if let Some(reason) = auto.clone().filter(|_| !limited) {
+        let headline = outcome(
+            "✅",
+            &(t.outcome_auto_approved)(&reason),
+            req.kind.running(t),
+        )
+        .green()
+        .bold();
+        let decision = Decision::AutoApproved;
+        record_approval(&settings, &req.repo, &source);
+        return run_unasked(&req, deps, entry, headline, decision, reason, banner);
+    }
+    if let Some(reason) = &auto {
+        tracing::debug!(reason, "rate limit reached: asking instead");
+    }
+
+    if let Some(reason) = BYPASS.get() {
+        return run_bypassed(&req, deps, &settings, entry, reason, &source, banner);
+    }
+
+    let delivery = quiet::apply(settings.quiet_hours.as_ref(), req.severity)?;
+    // Whoever runs the command for a hook would not come back for it.
+    if delivery == Delivery::Rejected || (delivery == Delivery::Held && !deps.gh.can_queue()) {
+        entry.reason = Some("quiet hours".to_string());
+        finish(entry);
+        eprintln!(
+            "{}",
+            outcome("❌", t.quiet_rejected, req.kind.cancelled(t))
+                .red()
+                .bold()
+        );
+        return Err(Error::PolicyRejected("quiet hours").into());
+    }
+    if delivery == Delivery::Held {
+        let id = match &req.queued {
+            Some(id) => id.clone(),
+            None => crate::queue::hold(&req, true)?,
+        };
+        entry.id = id.clone();
+        entry.decision = Decision::Queued;
+        entry.reason = Some("quiet hours".to_string());
+        finish(entry);
+        let end = settings.quiet_hours.as_ref().map_or("", |q| q.end.as_str());
+        let held = (t.quiet_holding)(end, &req.severity.to_string(), &id);
+        eprintln!("{}", held.yellow());
+        eprintln!("{}", t.queued_hint);
+        return Err(Error::Queued(id).into());
+    }
+
+    // A text message is one person's answer; it cannot stand in for two.
+    if settings.sms.is_some() && (req.approvals.unwrap_or(1) > 1 || settings.quorum().is_some()) {
+        entry.reason = Some("sms cannot collect several approvals".to_string());
+        finish(entry);
+        eprintln!(
+            "{}",
+            outcome("❌", t.sms_needs_more, req.kind.cancelled(t))
+                .red()
+                .bold()
+        );
+        return Err(Error::PolicyRejected("sms").into());
+    }
+
+    let token = deps.credentials.pat()?;
+    let login = deps.credentials.login(&token);
+    let account = deps.credentials.account();
+    entry.account = account.clone();
+    let acting = login.is_some() || account.is_some();
+    let tg = TgConfig {
+        thread_id: topic(&settings, &req),
+        members: settings.members(),
+        quorum: settings.quorum(),
+        delegate: settings.delegate.clone(),
+        commands: settings.buttons.commands,
+        ..deps.credentials.telegram()?
+    };
+    // During a shift the card goes to whoever is on call, who decides alone.
+    let on_call = oncall::current(&settings.on_call)?;
+    let tg = match on_call {
+        Some(shift) => TgConfig {
+            chat_id: shift.chat_id.clone(),
+            thread_id: None,
+            members: shift.members.clone(),
+            quorum: None,
+            ..tg
+        },
+        None => tg,
+    };
+    if let Err(e) = cleanup::tick(settings.cleanup.as_ref(), &tg) {
+        tracing::warn!(error = %e, "could not clean up settled cards");
+    }
+
+    let count = ratelimit::exceeded(settings.rate_limit.as_ref(), &req.repo);
+    if let Some(count) = count.filter(|_| auto.is_none()) {
+        entry.reason = Some("rate limit".to_string());
+        finish(entry);
+        reject_rate_limited(deps, &tg, &settings, &req.repo, count, &req.summary);
+        return Err(Error::PolicyRejected("rate limit").into());
+    }
+
+    if !output::quiet() {
+        banner();
+        if acting {
+            let identity = (t.acting_as)(login.as_deref(), account.as_deref());
+            eprintln!("  {}", identity.bold());
+        }
+        if delivery == Delivery::Silent {
+            eprintln!("  {}", t.sending_silently.dimmed());
+        }
+        if !output::plain() {
+            eprintln!();
+        }
+        eprintln!("{}", t.sending);
+    }
+
+    let card = if acting {
+        let login = login.as_deref().map(notify::escape_html);
+        let account = account.as_deref().map(notify::escape_html);
+        let identity = (t.acting_as)(login.as_deref(), account.as_deref());
+        format!("{}\n\n👤 {identity}", req.card)
+    } else {
+        req.card.clone()
+    };
+    let choices = choices(&req, &settings);
+    let sent = {
+        let _span = tracing::info_span!("notify", severity = %req.severity).entered();
+        let silent = delivery == Delivery::Silent;
+        let confirm = req.confirm.as_deref();
+        deps.notifier
+            .send_with_approval(&tg, &card, &req.links, &choices, confirm, silent)
+    };
+    let (request_id, message_id) = match sent {
+        Ok(ids) => ids,
+        Err(e)
+            if req.queued.is_none()
+                && deps.gh.can_queue()
+                && matches!(error::find(&e), Some(Error::TelegramUnreachable(_))) =>
+        {
+            let Some(id) = crate::queue::offer(&req, settings.offline_queue.as_ref())? else {
+                return Err(e);
+            };
+            entry.id = id.clone();
+            entry.decision = Decision::Queued;
+            finish(entry);
+            eprintln!("{}", (t.queued)(&id).yellow());
+            eprintln!("{}", t.queued_hint);
+            return Err(Error::Queued(id).into());
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(id) = &req.queued {
+        crate::queue::remove(id);
+    }
+    entry.id = request_id.clone();
+    let sent_at = Instant::now();
+
+    tracing::debug!(request_id = %request_id, message_id, "card sent");
+    let mut sent = vec![message_id];
+    for doc in &req.documents {
+        match deps.notifier.send_document(&tg, doc, message_id) {
+            Ok(id) => sent.push(id),
+            Err(e) => {
+                tracing::warn!(error = %e, name = %doc.name, "could not attach a file to the card");
+            }
+        }
+    }
+
+    let timeout = req
+        .timeout_secs
+        .unwrap_or_else(|| settings.approval_timeout());
+    if !output::quiet() {
+        eprintln!("{}", (t.waiting)(&duration_label(timeout)));
+        if let Some(shift) = on_call {
+            eprintln!("  {}", (t.on_call)(&shift.name).bold());
+        }
+    }
+
+    let approval = {
+        let _span = tracing::info_span!("poll", request_id = %request_id).entered();
+        deps.notifier.poll_for_approval(
+            &tg,
+            &request_id,
+            message_id,
+            timeout,
+            req.approvals.unwrap_or(1),
+        )?
+    };
+    entry.latency_ms = Some(sent_at.elapsed().as_millis() as u64);
+    entry.approver = approval.approver;
+    if let [by, to] = approval.delegation.as_slice() {
+        eprintln!("  {}", (t.delegated_by)(by, to).dimmed());
+    }
+    entry.delegation = approval.delegation;
+    match approval.choice {
+        Some(Choice::TrustRepo) => match trust::trust(&req.repo, entry.approver.as_deref()) {
+            Ok(()) => {
+                entry.reason = Some("repo trusted".to_string());
+                eprintln!("  {}", (t.repo_trusted)(&req.repo).dimmed());
+            }
+            Err(e) => tracing::warn!(error = %e, "could not trust the repository"),
+        },
+        Some(Choice::AllowAll { minutes }) => {
+            // No longer than the config allows, whatever the button said.
+            let policy = settings.allow_all.as_ref();
+            let minutes = policy.map_or(0, |p| p.minutes).min(minutes);
+            match allow_all::start(minutes, entry.approver.as_deref()) {
+                Ok(until) => {
+                    let until = until.format("%H:%M").to_string();
+                    entry.reason = Some(format!("allow all until {until}"));
+                    let severity = policy.map_or(Severity::Low, |p| p.max_severity);
+                    let line = (t.allowing_all)(&severity.to_string(), &until);
+                    eprintln!("  {}", line.yellow());
+                }
+                Err(e) => tracing::warn!(error = %e, "could not allow all"),
+            }
+        }
+        Some(Choice::AllowSimilar { minutes }) => {
+            let policy = settings.allow_similar.as_ref();
+            let minutes = policy.map_or(0, |p| p.minutes).min(minutes);
+            match similar::allow(&req, minutes, entry.approver.as_deref()) {
+                Ok(until) => {
+                    let until = until.format("%H:%M").to_string();
+                    entry.reason = Some(format!("allow similar until {until}"));
+                    let line = (t.allowing_similar)(&similar::class(&req), &req.repo, &until);
+                    eprintln!("  {}", line.yellow());
+                }
+                Err(e) => tracing::warn!(error = %e, "could not allow similar requests"),
+            }
+        }
+        None => {}
+    }
+    let label = match approval.result {
+        ApprovalResult::Approved => t.approved,
+        ApprovalResult::Rejected => t.rejected,
+        ApprovalResult::Timeout => t.expired,
+        ApprovalResult::Panic { .. } => t.panicked,
+        ApprovalResult::Cancelled => t.cancelled,
+    };
+    cleanup::record(settings.cleanup.as_ref(), &tg, sent, label, &req.summary);
+
+    match approval.result {
+        ApprovalResult::Approved => {
+            eprintln!(
+                "{}",
+                outcome("✅", t.outcome_approved, req.kind.running(t))
+                    .green()
+                    .bold()
+            );
+            record_approval(&settings, &req.repo, &source);
+            let code = {
+                let _span = tracing::info_span!("exec").entered();
+                deps.gh.run(&req.gh_args, Some(&token))?
+            };
+            tracing::debug!(code, "gh exited");
+            entry.decision = Decision::Approved;
+            entry.exit_code = Some(code);
+            finish(entry);
+            Ok(code)
+        }
+        ApprovalResult::Rejected => {
+            entry.decision = Decision::Rejected;
+            finish(entry);
+            eprintln!(
+                "{}",
+                outcome("❌", t.outcome_rejected, req.kind.cancelled(t))
+                    .red()
+                    .bold()
+            );
+            record_rejection(deps, &tg, &settings, &source);
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Timeout => {
+            entry.decision = Decision::Timeout;
+            finish(entry);
+            eprintln!(
+                "{}",
+                outcome(
+                    "⏱ ",
+                    &(t.outcome_timed_out)(&duration_label(timeout)),
+                    req.kind.cancelled(t)
+                )
+                .yellow()
+            );
+            Err(Error::ApprovalTimedOut.into())
+        }
+        ApprovalResult::Panic { revoke } => {
+            entry.decision = Decision::Panic;
+            finish(entry);
+            panic::run(revoke)?;
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Cancelled => {
+            entry.decision = Decision::Cancelled;
+            finish(entry);
+            eprintln!(
+                "{}",
+                outcome("🚫", t.outcome_cancelled, req.kind.cancelled(t)).yellow()
+            );
+            Err(Error::Cancelled.into())
+        }
+    }
+}
+
+/// Why `req` may run without asking, if it may. Under `[trust]` only in a
+/// trusted repository: neither an open "Allow all" window nor an earlier
+/// "allow similar" reaches one that is not trusted yet.
+fn auto_approval(req: &Request, settings: &Settings) -> Option<String> {
+    let own = match &settings.trust {
+        Some(policy) => trust::auto_approval(policy, req),
+        None => req.auto_approved().map(str::to_string),
+    };
+    let trusted =
+        settings.trust.is_none() || (trust::applies(&req.repo) && trust::is_trusted(&req.repo));
+    let all = |p| (trusted && allow_all::covers(p, req)).then(|| "allow all".to_string());
+    let alike = |p| (trusted && similar::covers(p, req)).then(|| "similar approved".to_string());
+    own.or_else(|| settings.allow_all.as_ref().and_then(all))
+        .or_else(|| settings.allow_similar.as_ref().and_then(alike))
+}
+
+/// The extra buttons on `req`'s card: "Approve & trust repo" under
+/// `[trust]` while its repository is not trusted, "Approve & allow
+/// similar" with `[allow_similar]`, and "Allow all" with `[allow_all]`.
+fn choices(req: &Request, settings: &Settings) -> Vec<Choice> {
+    let mut choices = Vec::new();
+    let known = trust::applies(&req.repo);
+    if settings.trust.is_some() && known && !trust::is_trusted(&req.repo) {
+        choices.push(Choice::TrustRepo);
+    }
+    if let Some(policy) = settings.allow_similar.as_ref().filter(|_| known) {
+        choices.push(Choice::AllowSimilar {
+            minutes: policy.minutes,
+        });
+    }
+    if let Some(policy) = &settings.allow_all {
+        choices.push(Choice::AllowAll {
+            minutes: policy.minutes,
+        });
+    }
+    choices
+}
+
+/// Reason given with `--guard-bypass`, for every request of this process.
+static BYPASS: OnceLock<String> = OnceLock::new();
+
+/// Skip the approval round trip for `reason` (`--guard-bypass`).
+pub fn bypass(reason: String) {
+    let _ = BYPASS.set(reason);
+}
+
+/// Run `req` without asking: audited as bypassed with `reason`, and the
+/// approver told afterwards. Refused unless `[bypass]` sets a budget;
+/// locked mode still applies, and a repository deletion is refused.
+fn run_bypassed(
+    req: &Request,
+    deps: &Deps,
+    settings: &Settings,
+    entry: &mut Pending,
+    reason: &str,
+    source: &str,
+    banner: impl FnOnce(),
+) -> Result<i32> {
+    if req.confirm.is_some() {
+        return refuse_uncarded(req, entry);
+    }
+    let t = i18n::text();
+    let Some(budget) = settings.bypass.as_ref() else {
+        entry.reason = Some("bypass off".to_string());
+        finish(entry);
+        eprintln!(
+            "{}",
+            outcome("❌", t.bypass_off, req.kind.cancelled(t))
+                .red()
+                .bold()
+        );
+        return Err(Error::PolicyRejected("bypass off").into());
+    };
+    let tg = deps.credentials.telegram().map(|tg| TgConfig {
+        thread_id: topic(settings, req),
+        ..tg
+    });
+    match lockout::record_bypass(budget, source) {
+        Ok(Some(lock)) => {
+            entry.reason = Some("bypass budget".to_string());
+            finish(entry);
+            let why = (t.bypass_budget_spent)(budget.per_day);
+            eprintln!(
+                "{}",
+                outcome("❌", &why, req.kind.cancelled(t)).red().bold()
+            );
+            if let Err(e) = tg.and_then(|tg| deps.notifier.send_note(&tg, &lock.note(t))) {
+                tracing::warn!(error = %e, "could not send lockout note to Telegram");
+            }
+            eprintln!("{}", t.now_locked.red().bold());
+            return Err(Error::PolicyRejected("bypass budget").into());
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = %e, "could not record the bypass"),
+    }
+    let token = deps.credentials.pat()?;
+    if !output::quiet() {
+        banner();
+        if !output::plain() {
+            eprintln!();
+        }
+    }
+    eprintln!(
+        "{}",
+        outcome("⚡", &(t.outcome_bypassed)(reason), req.kind.running(t))
+            .yellow()
+            .bold()
+    );
+    tracing::debug!(reason, "approval bypassed");
+
+    // Only a note: the command does not wait for anyone.
+    let html = (t.bypass_note)(
+        &notify::escape_html(&req.summary),
+        &notify::escape_html(reason),
+        &notify::escape_html(source),
+    );
+    if let Err(e) = tg.and_then(|tg| deps.notifier.send_note(&tg, &html)) {
+        tracing::warn!(error = %e, "could not report the bypass on Telegram");
+    }
+
+    let code = {
+        let _span = tracing::info_span!("exec").entered();
+        deps.gh.run(&req.gh_args, Some(&token))?
+    };
+    entry.decision = Decision::Bypassed;
+    entry.reason = Some(reason.to_string());
+    entry.exit_code = Some(code);
+    finish(entry);
+    Ok(code)
+}
+
+/// Set by `--guard-dry-run`.
+static DRY_RUN: OnceLock<()> = OnceLock::new();
+
+/// Only tell what would happen to guarded and other commands
+/// (`--guard-dry-run`).
+pub fn dry_run() {
+    let _ = DRY_RUN.set(());
+}
+
+/// True with `--guard-dry-run`, or `GH_GUARD_DRY_RUN` set to anything but
+/// "", "0" or "false".
+pub fn dry_running() -> bool {
+    DRY_RUN.get().is_some()
+        || std::env::var("GH_GUARD_DRY_RUN").is_ok_and(|v| !matches!(v.trim(), "" | "0" | "false"))
+}
+
+/// What [`run`] would do with a request.
+enum Plan {
+    /// Send the card; quiet hours may make it silent or hold it.
+    Ask { quiet: Option<QuietAction> },
+    /// Run gh without asking, for this reason.
+    Run(String),
+    /// Refuse, for this reason (as in the audit log).
+    Refuse(&'static str),
+}
+
+/// `--guard-dry-run`: go through the checks of [`run`] in the same order,
+/// only reading, and print the decision and the card. Telegram, gh, the
+/// audit log and the counters are left alone. A refusal is still returned
+/// as one, so the exit code tells.
+fn report(req: &Request, settings: &Settings, banner: impl FnOnce()) -> Result<i32> {
+    let t = i18n::text();
+    let auto = auto_approval(req, settings);
+    let limited = ratelimit::exceeded(settings.rate_limit.as_ref(), &req.repo).is_some();
+    let plan = if readonly::since().is_some() {
+        Plan::Refuse("read-only")
+    } else if lockout::current().is_some() {
+        Plan::Refuse("locked")
+    } else if let Some(verdict) = ci_policy::decide(settings.ci.as_ref(), &req.repo)? {
+        match verdict {
+            Verdict::PassThrough if req.confirm.is_some() => Plan::Refuse("deletion needs a card"),
+            Verdict::PassThrough => Plan::Run("ci passthrough".to_string()),
+            Verdict::Granted(grant) => Plan::Run(format!("ci token {}", grant.id)),
+            Verdict::Refused(refusal) => Plan::Refuse(refusal.reason()),
+        }
+    } else if let Some(reason) = auto.clone().filter(|_| !limited) {
+        Plan::Run(reason)
+    } else if let Some(reason) = BYPASS.get() {
+        match &settings.bypass {
+            _ if req.confirm.is_some() => Plan::Refuse("deletion needs a card"),
+            None => Plan::Refuse("bypass off"),
+            Some(budget) if lockout::bypass_budget_spent(budget) => Plan::Refuse("bypass budget"),
+            Some(_) => Plan::Run(reason.clone()),
+        }
+    } else {
+        match quiet::action(settings.quiet_hours.as_ref(), req.severity)? {
+            Some(QuietAction::Reject) => Plan::Refuse("quiet hours"),
+            _ if limited && auto.is_none() => Plan::Refuse("rate limit"),
+            quiet => Plan::Ask { quiet },
+        }
+    };
+
+    if !output::quiet() {
+        banner();
+        if !output::plain() {
+            eprintln!();
+        }
+    }
+    report_head(&req.gh_args, Some(req));
+    match plan {
+        Plan::Ask { quiet } => {
+            let timeout = req
+                .timeout_secs
+                .unwrap_or_else(|| settings.approval_timeout());
+            let approvals = req.approvals.unwrap_or(1);
+            report_line(
+                t.label_decision,
+                &(t.dry_run_ask)(approvals, &duration_label(timeout)),
+            );
+            match oncall::current(&settings.on_call)? {
+                Some(shift) => report_line(t.label_on_call, &shift.name),
+                None => {
+                    if let Some(topic) = topic(settings, req) {
+                        report_line(t.label_topic, &topic.to_string());
+                    }
+                }
+            }
+            if let Some(action) = quiet {
+                let action = match action {
+                    QuietAction::Notify => "notify",
+                    QuietAction::Silent => "silent",
+                    QuietAction::Queue => "queue",
+                    QuietAction::Reject => "reject",
+                };
+                report_line(t.label_quiet, action);
+            }
+            for doc in &req.documents {
+                report_line(t.label_file, &doc.name);
+            }
+            for link in &req.links {
+                report_line(t.label_button, &format!("{} → {}", link.text, link.url));
+            }
+            eprintln!();
+            eprintln!("{}", req.card);
+            report_json("ask", None, Some(req));
+            Ok(0)
+        }
+        Plan::Run(reason) => {
+            report_line(t.label_decision, &(t.dry_run_run)(&reason));
+            report_json("run", Some(&reason), Some(req));
+            Ok(0)
+        }
+        Plan::Refuse(reason) => Err(report_refusal(Some(req), reason)),
+    }
+}
+
+/// `--guard-dry-run` for a command gh-guard does not guard: it would pass
+/// through, unless read-only mode refuses it. Returns the code to exit
+/// with.
+pub fn report_passthrough(args: &[String], read_only: bool) -> Result<i32> {
+    report_head(args, None);
+    if read_only {
+        return Err(report_refusal(None, "read-only"));
+    }
+    report_line(
+        i18n::text().label_decision,
+        i18n::text().dry_run_passthrough,
+    );
+    report_json("pass-through", None, None);
+    Ok(0)
+}
+
+/// The top of a dry-run report: what was intercepted and, for a guarded
+/// command, what it was taken for.
+fn report_head(args: &[String], req: Option<&Request>) {
+    let t = i18n::text();
+    eprintln!("{}", t.dry_run_heading.cyan().bold());
+    report_line(t.label_command, &format!("gh {}", args.join(" ")));
+    if let Some(req) = req {
+        report_line(t.label_repo, &req.repo);
+        report_line(t.label_severity, &req.severity.to_string());
+    }
+}
+
+/// The rest of a dry-run report when the command would be refused for
+/// `reason`. Returns the error to exit with.
+fn report_refusal(req: Option<&Request>, reason: &'static str) -> anyhow::Error {
+    let t = i18n::text();
+    report_line(t.label_decision, &(t.dry_run_refuse)(reason));
+    report_json("refuse", Some(reason), req);
+    Error::PolicyRejected(reason).into()
+}
+
+/// The dry-run result for `--guard-json`; the card only when it would be
+/// sent.
+fn report_json(would: &str, reason: Option<&str>, req: Option<&Request>) {
+    output::emit(&serde_json::json!({
+        "decision": "dry-run",
+        "would": would,
+        "reason": reason,
+        "repo": req.map(|r| &r.repo),
+        "severity": req.map(|r| r.severity),
+        "card": req.filter(|_| would == "ask").map(|r| &r.card),
+    }));
+}
+
+/// One "label : value" line of a dry-run report.
+fn report_line(label: &str, value: &str) {
+    if output::plain() {
+        eprintln!("{label}: {value}");
+    } else {
+        eprintln!("  {label:<8} : {value}");
+    }
+}
+
+/// Refuse `req`, a repository deletion, for want of a card: its name is
+/// always typed out on the phone, so neither `--guard-bypass` nor CI
+/// passthrough runs it.
+fn refuse_uncarded(req: &Request, entry: &mut Pending) -> Result<i32> {
+    let t = i18n::text();
+    entry.reason = Some("deletion needs a card".to_string());
+    finish(entry);
+    eprintln!(
+        "{}",
+        outcome("❌", t.deletion_needs_card, req.kind.cancelled(t))
+            .red()
+            .bold()
+    );
+    Err(Error::PolicyRejected("deletion needs a card").into())
+}
+
+/// Carry out what the CI policy decided for `req` instead of asking.
+fn run_in_ci(
+    req: &Request,
+    deps: &Deps,
+    entry: &mut Pending,
+    verdict: Verdict,
+    banner: impl FnOnce(),
+) -> Result<i32> {
+    let t = i18n::text();
+    let (headline, decision, reason) = match verdict {
+        Verdict::PassThrough if req.confirm.is_some() => return refuse_uncarded(req, entry),
+        Verdict::PassThrough => (
+            outcome("⚡", t.outcome_ci_passthrough, req.kind.running(t))
+                .yellow()
+                .bold(),
+            Decision::Bypassed,
+            "ci passthrough".to_string(),
+        ),
+        Verdict::Granted(grant) => (
+            outcome("✅", &(t.outcome_ci_token)(&grant.id), req.kind.running(t))
+                .green()
+                .bold(),
+            Decision::Approved,
+            format!("ci token {}", grant.id),
+        ),
+        Verdict::Refused(refusal) => {
+            entry.reason = Some(refusal.reason().to_string());
+            finish(entry);
+            eprintln!(
+                "{}",
+                outcome("❌", refusal.why(t), req.kind.cancelled(t))
+                    .red()
+                    .bold()
+            );
+            return Err(Error::PolicyRejected(refusal.reason()).into());
+        }
+    };
+    run_unasked(req, deps, entry, headline, decision, reason, banner)
+}
+
+/// Run `req` without asking, as a policy decided: `headline` tells the
+/// terminal, and the audit log gets `decision` and `reason`.
+fn run_unasked(
+    req: &Request,
+    deps: &Deps,
+    entry: &mut Pending,
+    headline: colored::ColoredString,
+    decision: Decision,
+    reason: String,
+    banner: impl FnOnce(),
+) -> Result<i32> {
+    let token = deps.credentials.pat()?;
+    if !output::quiet() {
+        banner();
+        if !output::plain() {
+            eprintln!();
+        }
+    }
+    eprintln!("{headline}");
+    tracing::debug!(reason, "decided by policy");
+
+    let code = {
+        let _span = tracing::info_span!("exec").entered();
+        deps.gh.run(&req.gh_args, Some(&token))?
+    };
+    entry.decision = decision;
+    entry.reason = Some(reason);
+    entry.exit_code = Some(code);
+    finish(entry);
+    Ok(code)
+}
+
+/// "Approved! Creating PR…": the outcome and what follows from it, led by
+/// an icon unless output is plain.
+fn outcome(icon: &str, outcome: &str, then: &str) -> String {
+    if output::plain() {
+        format!("{outcome} {then}")
+    } else {
+        format!("{icon}  {outcome} {then}")
+    }
+}
+
+/// "5 min" or "90 s".
+pub fn duration_label(secs: u64) -> String {
+    if secs.is_multiple_of(60) {
+        format!("{} min", secs / 60)
+    } else {
+        format!("{secs} s")
+    }
+}
+
+/// Refuse `req` without asking anyone, for a reason its handler found:
+/// audited as auto-rejected with `reason`, and `why` printed as the
+/// outcome (only reported with `--guard-dry-run`). Returns the error to
+/// exit with.
+pub fn refuse(req: &Request, reason: &'static str, why: &str) -> anyhow::Error {
+    if dry_running() {
+        report_head(&req.gh_args, Some(req));
+        return report_refusal(Some(req), reason);
+    }
+    let t = i18n::text();
+    let mut entry = Pending::new(req);
+    entry.reason = Some(reason.to_string());
+    finish(&mut entry);
+    eprintln!("{}", outcome("❌", why, req.kind.cancelled(t)).red().bold());
+    Error::PolicyRejected(reason).into()
+}
+
+/// Refuse `req` for the `[api] deny` pattern `rule`, which no approval
+/// lifts: audited as auto-rejected, and the approver told afterwards that
+/// something tried. Returns the error to exit with.
+pub fn deny(req: &Request, deps: &Deps, rule: &str) -> anyhow::Error {
+    let t = i18n::text();
+    let error = refuse(req, "deny rule", &(t.denied)(rule));
+    if dry_running() {
+        return error;
+    }
+    let html = (t.denied_note)(
+        &notify::escape_html(&req.summary),
+        &notify::escape_html(rule),
+        &notify::escape_html(&crate::agent::source()),
+    );
+    let sent = deps
+        .credentials
+        .telegram()
+        .and_then(|tg| deps.notifier.send_note(&tg, &html));
+    if let Err(e) = sent {
+        tracing::warn!(error = %e, "could not report the denial on Telegram");
+    }
+    error
+}
+
+/// The forum topic `[topics]` gives `req`, if any.
+fn topic(settings: &Settings, req: &Request) -> Option<i64> {
+    let topics = settings.topics.as_ref()?;
+    topics
+        .for_repo(&req.repo)
+        .or_else(|| topics.severity.get(&req.severity).copied())
+        .or(match req.kind {
+            Kind::PrCreate => topics.pr_create,
+            Kind::Api => topics.api,
+            Kind::RepoFork | Kind::ReleaseUpload | Kind::RepoDelete => None,
+        })
+}
+
+/// The audit entry of a request being guarded. Written once by [`finish`];
+/// should the request end without that, e.g. on an error returned early,
+/// it is written on drop with the decision "error".
+struct Pending {
+    entry: audit::Entry,
+    finished: bool,
+}
+
+impl Pending {
+    fn new(req: &Request) -> Self {
+        let mut entry = audit::Entry::new(&req.gh_args, Decision::AutoRejected);
+        entry.repo = Some(req.repo.clone());
+        entry.severity = Some(req.severity);
+        Pending {
+            entry,
+            finished: false,
+        }
+    }
+}
+
+impl std::ops::Deref for Pending {
+    type Target = audit::Entry;
+
+    fn deref(&self) -> &audit::Entry {
+        &self.entry
+    }
+}
+
+impl std::ops::DerefMut for Pending {
+    fn deref_mut(&mut self) -> &mut audit::Entry {
+        &mut self.entry
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.entry.decision = Decision::Error;
+            self.entry.write();
+        }
+    }
+}
+
+/// Record the outcome in the audit log and, in JSON mode, report it to the
+/// caller.
+fn finish(entry: &mut Pending) {
+    entry.finished = true;
+    entry.write();
+    crate::output::emit(&entry.entry);
+}
+
+/// Tell the approver and the terminal that a request exceeding the per-repo
+/// approval cap was auto-rejected, so a runaway agent is throttled without
+/// further taps.
+fn reject_rate_limited(
+    deps: &Deps,
+    tg: &TgConfig,
+    settings: &Settings,
+    repo: &str,
+    count: usize,
+    summary: &str,
+) {
+    let window = settings.rate_limit.as_ref().map_or(0, |l| l.window_minutes);
+    let t = i18n::text();
+    let html = (t.rate_limit_note)(
+        &notify::escape_html(repo),
+        count,
+        window,
+        &notify::escape_html(summary),
+    );
+    if let Err(e) = deps.notifier.send_note(tg, &html) {
+        tracing::warn!(error = %e, "could not send rate-limit note to Telegram");
+    }
+    eprintln!("{}", (t.rate_limited)(count, repo, window).red().bold());
+}
+
+/// Count an approval towards the rate limit and reset the rejection streak.
+/// Failure only warns — the user already approved, so the command itself
+/// should still run.
+fn record_approval(settings: &Settings, repo: &str, source: &str) {
+    if let Err(e) = ratelimit::record(settings.rate_limit.as_ref(), repo) {
+        tracing::warn!(error = %e, "could not record approval for rate limiting");
+    }
+    if let Err(e) = lockout::record_approval(settings.lockout.as_ref(), source) {
+        tracing::warn!(error = %e, "could not reset rejection streak");
+    }
+}
+
+/// Count a rejection from `source`; on hitting the threshold enter locked
+/// mode and tell the approver.
+fn record_rejection(deps: &Deps, tg: &TgConfig, settings: &Settings, source: &str) {
+    match lockout::record_rejection(settings.lockout.as_ref(), source) {
+        Ok(Some(lock)) => {
+            let t = i18n::text();
+            if let Err(e) = deps.notifier.send_note(tg, &lock.note(t)) {
+                tracing::warn!(error = %e, "could not send lockout note to Telegram");
+            }
+            eprintln!("{}", t.now_locked.red().bold());
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = %e, "could not record rejection"),
+    }
+}
@@ -0,0 +1,288 @@
+use crate::audit::{self, Entry};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+/// `gh-guard log`: the filters, or `search` with them.
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct LogArgs {
+    #[command(subcommand)]
+    pub command: Option<LogCommand>,
+    #[command(flatten)]
+    pub filter: LogFilter,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LogCommand {
+    /// Find entries containing every word of a query
+    ///
+    /// Words and "quoted phrases" may appear anywhere: command line,
+    /// endpoint, PR title, repo, decision, reason, approver, agent or ID.
+    /// The other filters apply as for `gh-guard log`.
+    Search(Box<LogFilter>),
+}
+
+/// Filters accepted by `gh-guard log`.
+#[derive(Debug, Default, Clone, Args)]
+pub struct LogFilter {
+    /// Only entries for this repository (owner/name)
+    #[arg(long)]
+    pub repo: Option<String>,
+    /// approved, rejected, timeout, auto-rejected, panic, cancelled,
+    /// passthrough, queued or bypassed
+    #[arg(long, value_parser = lowercase)]
+    pub decision: Option<String>,
+    /// low, medium, high or critical
+    #[arg(long, value_parser = lowercase)]
+    pub severity: Option<String>,
+    /// YYYY-MM-DD, RFC 3339, or an age such as 30m, 12h, 7d, 2w
+    #[arg(long, value_parser = parse_when)]
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Same formats as --since
+    #[arg(long, value_parser = parse_when)]
+    pub until: Option<DateTime<FixedOffset>>,
+    /// Show at most this many of the most recent matches
+    #[arg(short = 'n', long, default_value_t = 50)]
+    pub limit: usize,
+    /// Print raw JSONL instead of a table
+    #[arg(long)]
+    pub json: bool,
+    /// Case-insensitive text matched against args, repo, reason, etc.
+    #[arg(value_name = "TEXT")]
+    pub text: Vec<String>,
+}
+
+fn lowercase(s: &str) -> Result<String> {
+    Ok(s.to_lowercase())
+}
+
+/// Accept "YYYY-MM-DD", a full RFC 3339 timestamp, or a relative age such
+/// as "30m", "12h", "7d".
+pub fn parse_when(s: &str) -> Result<DateTime<FixedOffset>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t);
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let midnight = d.and_hms_opt(0, 0, 0).unwrap_or_default();
+        if let Some(t) = Local.from_local_datetime(&midnight).earliest() {
+            return Ok(t.fixed_offset());
+        }
+    }
+    let invalid = || anyhow!("Invalid date {s:?} (use YYYY-MM-DD or e.g. 7d, 12h)");
+    let (at, unit) = s.char_indices().last().ok_or_else(invalid)?;
+    let n: i64 = s[..at].parse().map_err(|_| invalid())?;
+    let age = match unit {
+        'm' => Duration::minutes(n),
+        'h' => Duration::hours(n),
+        'd' => Duration::days(n),
+        'w' => Duration::weeks(n),
+        _ => return Err(invalid()),
+    };
+    Ok((Local::now() - age).fixed_offset())
+}
+
+/// Does `entry` pass every filter that was given?
+pub fn matches(f: &LogFilter, entry: &Entry) -> bool {
+    if let Some(repo) = &f.repo {
+        if !entry
+            .repo
+            .as_deref()
+            .is_some_and(|r| r.eq_ignore_ascii_case(repo))
+        {
+            return false;
+        }
+    }
+    if let Some(d) = &f.decision {
+        if entry.decision.as_str() != d {
+            return false;
+        }
+    }
+    if let Some(sev) = &f.severity {
+        if entry.severity.is_none_or(|s| s.to_string() != *sev) {
+            return false;
+        }
+    }
+    if f.since.is_some() || f.until.is_some() {
+        let Ok(ts) = DateTime::parse_from_rfc3339(&entry.ts) else {
+            return false;
+        };
+        if f.since.is_some_and(|since| ts < since) || f.until.is_some_and(|until| ts > until) {
+            return false;
+        }
+    }
+    if !f.text.is_empty() && !haystack(entry).contains(&f.text.join(" ").to_lowercase()) {
+        return false;
+    }
+    true
+}
+
+/// Does `entry` pass the filters of `gh-guard log search`, with its text
+/// taken as a query?
+pub fn found(f: &LogFilter, entry: &Entry) -> bool {
+    let query = LogFilter {
+        text: Vec::new(),
+        ..f.clone()
+    };
+    if !matches(&query, entry) {
+        return false;
+    }
+    let haystack = haystack(entry);
+    terms(&f.text.join(" "))
+        .iter()
+        .all(|term| haystack.contains(term))
+}
+
+/// Everything text search looks at, lowercased.
+fn haystack(entry: &Entry) -> String {
+    [
+        entry.args.join(" "),
+        entry.id.clone(),
+        entry.source.clone(),
+        entry.repo.clone().unwrap_or_default(),
+        entry.decision.as_str().to_string(),
+        entry.reason.clone().unwrap_or_default(),
+        entry.approver.clone().unwrap_or_default(),
+        entry.delegation.join(" "),
+        entry.profile.clone().unwrap_or_default(),
+    ]
+    .join("\n")
+    .to_lowercase()
+}
+
+/// The lowercased words of `query`, a "quoted phrase" counting as one.
+fn terms(query: &str) -> Vec<String> {
+    query
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            // Odd parts were between quotes.
+            if i % 2 == 1 {
+                vec![part.trim()]
+            } else {
+                part.split_whitespace().collect()
+            }
+        })
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `gh-guard log` entry point.
+pub fn run(args: &LogArgs) -> Result<()> {
+    match &args.command {
+        Some(LogCommand::Search(filter)) => list(filter, found),
+        None => list(&args.filter, matches),
+    }
+}
+
+/// Print the most recent entries that `keep` lets through.
+fn list(filter: &LogFilter, keep: fn(&LogFilter, &Entry) -> bool) -> Result<()> {
+    let entries = audit::read_all()?;
+    let mut hits: Vec<&Entry> = entries.iter().filter(|e| keep(filter, e)).collect();
+    // Most recent `limit` entries, still printed oldest-first.
+    let skip = hits.len().saturating_sub(filter.limit);
+    hits.drain(..skip);
+
+    if filter.json {
+        for e in hits {
+            println!("{}", serde_json::to_string(e)?);
+        }
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("{}", "No matching audit entries.".dimmed());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "{:<11}  {:<8}  {:<13}  {:<8}  {:<24}  COMMAND",
+            "TIME", "ID", "DECISION", "SEVERITY", "REPO"
+        )
+        .bold()
+    );
+    for e in hits {
+        print_row(e);
+    }
+    Ok(())
+}
+
+fn print_row(e: &Entry) {
+    let time = DateTime::parse_from_rfc3339(&e.ts)
+        .map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| e.ts.clone());
+    let decision = format!("{:<13}", e.decision.as_str());
+    let decision = match e.decision {
+        audit::Decision::Approved | audit::Decision::AutoApproved => decision.green(),
+        audit::Decision::Rejected
+        | audit::Decision::Panic
+        | audit::Decision::Bypassed
+        | audit::Decision::Error => decision.red(),
+        audit::Decision::Timeout | audit::Decision::AutoRejected | audit::Decision::Cancelled => {
+            decision.yellow()
+        }
+        audit::Decision::Passthrough | audit::Decision::Queued => decision.dimmed(),
+    };
+    let severity = e.severity.map(|s| s.to_string()).unwrap_or_default();
+    let repo = e.repo.as_deref().unwrap_or("");
+    let command = ellipsize(&e.args.join(" "), 60);
+    println!(
+        "{time:<11}  {:<8}  {decision}  {severity:<8}  {:<24}  {command}",
+        e.id,
+        ellipsize(repo, 24),
+    );
+}
+
+fn ellipsize(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let cut: String = s.chars().take(max.saturating_sub(1)).collect();
+        format!("{cut}…")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::Decision;
+
+    fn search(query: &str, entry: &Entry) -> bool {
+        let filter = LogFilter {
+            text: vec![query.to_string()],
+            ..LogFilter::default()
+        };
+        found(&filter, entry)
+    }
+
+    #[test]
+    fn search_wants_every_word_anywhere() {
+        let args: Vec<String> = ["api", "-X", "DELETE", "repos/o/api/labels/x"]
+            .map(str::to_string)
+            .to_vec();
+        let mut entry = Entry::new(&args, Decision::AutoRejected);
+        entry.repo = Some("o/api".to_string());
+        entry.reason = Some("rate limit".to_string());
+
+        assert!(search("LIMIT delete", &entry));
+        assert!(search("\"rate limit\" auto-rejected labels", &entry));
+        assert!(search(&entry.id.clone(), &entry));
+        assert!(!search("\"limit rate\"", &entry));
+        assert!(!search("delete approved", &entry));
+    }
+
+    #[test]
+    fn ages_are_a_number_and_a_unit() {
+        let week = Local::now() - Duration::weeks(2);
+        let since = parse_when("2w").unwrap();
+        assert!((since - week.fixed_offset()).num_seconds().abs() < 5);
+        assert!(parse_when("5ä").is_err());
+        assert!(parse_when("ä").is_err());
+        assert!(parse_when("").is_err());
+        assert!(parse_when("d").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! `gh-guard hook`: approval for the gh commands a coding agent is about
+//! to run through its own shell tool, for agents whose gh is not the
+//! gh-guard shim. Wired into the agent's pre-tool-use hook, it reads the
+//! proposed command as JSON on stdin and answers allow or deny in the
+//! agent's format; the agent then runs (or drops) the command itself.
+//!
+//! Claude Code (`PreToolUse`) and Cursor (`beforeShellExecution`) input is
+//! told apart by its shape. Only guarded commands get an answer, so
+//! everything else goes through the agent's own permission rules.
+
+use crate::guard::{Credentials, Deps, GhRunner};
+use crate::notify::TgConfig;
+use crate::{api, config, delete, fork, pr, release};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::Read;
+use std::path::Path;
+
+/// What a pre-tool-use hook is given.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Input {
+    /// Claude Code: any tool call; only `Bash` runs shell commands.
+    Claude {
+        tool_name: String,
+        tool_input: ClaudeTool,
+    },
+    /// Cursor: a shell command.
+    Cursor { command: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaudeTool {
+    #[serde(default)]
+    command: Option<String>,
+}
+
+impl Input {
+    /// The hook input on stdin.
+    pub fn read() -> Result<Self> {
+        let mut raw = String::new();
+        std::io::stdin()
+            .read_to_string(&mut raw)
+            .context("Cannot read the hook input")?;
+        serde_json::from_str(&raw).context("The hook input is not a tool call gh-guard knows")
+    }
+
+    /// The shell command line the agent wants to run, if it is one.
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            Input::Claude {
+                tool_name,
+                tool_input,
+            } if tool_name == "Bash" => tool_input.command.as_deref(),
+            Input::Claude { .. } => None,
+            Input::Cursor { command } => Some(command),
+        }
+    }
+
+    /// Print the verdict on stdout, in the format the agent expects.
+    /// `reason` is shown to the agent.
+    pub fn answer(&self, allow: bool, reason: &str) {
+        let decision = if allow { "allow" } else { "deny" };
+        let answer = match self {
+            Input::Claude { .. } => json!({
+                "hookSpecificOutput": {
+                    "hookEventName": "PreToolUse",
+                    "permissionDecision": decision,
+                    "permissionDecisionReason": reason,
+                }
+            }),
+            Input::Cursor { .. } => json!({
+                "permission": decision,
+                "userMessage": reason,
+                "agentMessage": reason,
+            }),
+        };
+        println!("{answer}");
+    }
+}
+
+/// Programs that run a command given after their own options.
+const WRAPPERS: &[&str] = &[
+    "env", "command", "exec", "time", "nohup", "nice", "timeout", "sudo", "doas", "xargs",
+];
+
+/// The argv of every gh invocation in `shell`, in order: each simple
+/// command of a list or pipeline that starts with `gh` (or a path to it)
+/// after any `VAR=value` words, or runs it through one of [`WRAPPERS`]
+/// (`env -i gh …`, `sudo -u bot /usr/bin/gh …`, `xargs -n1 gh …`). With a
+/// wrapper the first `gh` word is taken as the command, whatever options
+/// come before it. Commands hidden in `$(…)`, `sh -c` or `eval`, and
+/// arguments `xargs` reads from stdin, are not found.
+pub fn gh_commands(shell: &str) -> Vec<Vec<String>> {
+    split(shell)
+        .into_iter()
+        .filter_map(|words| {
+            let words: Vec<String> = words.into_iter().skip_while(|w| is_assignment(w)).collect();
+            let program = program_name(words.first()?)?;
+            let start = if program == "gh" {
+                0
+            } else if WRAPPERS.contains(&program) {
+                words.iter().position(|w| program_name(w) == Some("gh"))?
+            } else {
+                return None;
+            };
+            Some(words[start + 1..].to_vec())
+        })
+        .collect()
+}
+
+/// The file name of `word` taken as a program path.
+fn program_name(word: &str) -> Option<&str> {
+    Path::new(word).file_name()?.to_str()
+}
+
+/// Whether gh-guard asks before running `args`, as `gh-guard` itself
+/// would route them: `pr create` without `--web`, mutating `gh api`,
+/// `repo fork`, `repo delete` and `release upload`.
+pub fn guarded(args: &[String]) -> bool {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("pr"), Some(verb)) if verb == "create" => !pr::parse_pr_args(&args[2..]).web,
+        (Some("api"), _) => api::parse_api_args(&args[1..]).is_mutating,
+        (Some("repo"), Some(verb)) if verb == "fork" => !fork::parse_fork_args(&args[2..]).help,
+        (Some("repo"), Some(verb)) if verb == "delete" => {
+            !delete::parse_delete_args(&args[2..]).help
+        }
+        (Some("release"), Some(verb)) if verb == "upload" => {
+            !release::parse_upload_args(&args[2..]).help
+        }
+        _ => false,
+    }
+}
+
+/// Stands in for gh and its token: the agent runs the command itself once
+/// the hook allows it, with its own gh login.
+pub struct Agent;
+
+impl Agent {
+    pub fn deps(&self) -> Deps<'_> {
+        Deps {
+            credentials: self,
+            gh: self,
+            ..Deps::live()
+        }
+    }
+}
+
+impl Credentials for Agent {
+    /// Only the PR card's CI lookup uses it, and does without.
+    fn pat(&self) -> Result<String> {
+        Ok(config::get_pat().unwrap_or_default())
+    }
+
+    /// The agent runs the command with its own gh login, not the PAT.
+    fn login(&self, _token: &str) -> Option<String> {
+        None
+    }
+
+    fn telegram(&self) -> Result<TgConfig> {
+        Deps::live().credentials.telegram()
+    }
+}
+
+impl GhRunner for Agent {
+    fn run(&self, _args: &[String], _token: Option<&str>) -> Result<i32> {
+        Ok(0)
+    }
+
+    fn exec(&self, _args: &[String], _token: Option<&str>) -> Result<i32> {
+        Ok(0)
+    }
+
+    /// A queued request would later run with the PAT, after the agent was
+    /// told no.
+    fn can_queue(&self) -> bool {
+        false
+    }
+}
+
+/// `shell` cut into simple commands, each as its words with quotes and
+/// escapes resolved. `;`, `&`, `|`, newlines and parentheses end a
+/// command; redirections are dropped with their targets.
+fn split(shell: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words = Words::default();
+    let mut chars = shell.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                words.started = true;
+                words.word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                words.started = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                            words.word.extend(chars.next());
+                        }
+                        _ => words.word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                words.started = true;
+                words.word.extend(chars.next().filter(|&c| c != '\n'));
+            }
+            '>' | '<' => {
+                // The `2` of `2>` names a descriptor, not an argument.
+                if words.word.chars().all(|c| c.is_ascii_digit()) {
+                    words.word.clear();
+                    words.started = false;
+                }
+                words.end();
+                // `>>`, `>&1`, `<<`, …
+                while chars.next_if(|&c| matches!(c, '>' | '<' | '&')).is_some() {}
+                words.redirect = true;
+            }
+            ';' | '&' | '|' | '\n' | '(' | ')' => {
+                words.end();
+                commands.push(std::mem::take(&mut words.done));
+            }
+            c if c.is_whitespace() => words.end(),
+            c => {
+                words.started = true;
+                words.word.push(c);
+            }
+        }
+    }
+    words.end();
+    commands.push(words.done);
+    commands.retain(|c| !c.is_empty());
+    commands
+}
+
+/// The words of the simple command being split.
+#[derive(Default)]
+struct Words {
+    done: Vec<String>,
+    word: String,
+    /// Whether `word` has begun, so that `''` still counts as one.
+    started: bool,
+    /// Whether the next word is a redirection's target.
+    redirect: bool,
+}
+
+impl Words {
+    fn end(&mut self) {
+        if self.started && !std::mem::take(&mut self.redirect) {
+            self.done.push(std::mem::take(&mut self.word));
+        }
+        self.word.clear();
+        self.started = false;
+    }
+}
+
+/// `NAME=value`, as set before a command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_gh_in_lists_and_pipelines() {
+        let found = gh_commands(
+            "cd repo && GH_REPO=o/r gh api -X DELETE 'repos/o/r/labels/needs review' 2>/dev/null \
+             | jq . ; echo \"gh pr create\"; /usr/bin/gh pr create --title \"fix: \\\"x\\\"\" &",
+        );
+        assert_eq!(
+            found,
+            [
+                vec!["api", "-X", "DELETE", "repos/o/r/labels/needs review"],
+                vec!["pr", "create", "--title", "fix: \"x\""],
+            ]
+        );
+        assert!(gh_commands("ghost api -X DELETE x; git push").is_empty());
+    }
+
+    #[test]
+    fn finds_gh_behind_wrappers() {
+        let delete = ["api", "-X", "DELETE", "repos/o/r"];
+        for shell in [
+            "/usr/bin/env gh api -X DELETE repos/o/r",
+            "env -i PATH=/bin gh api -X DELETE repos/o/r",
+            "sudo -u bot /usr/local/bin/gh api -X DELETE repos/o/r",
+            "echo o/r | xargs -n1 gh api -X DELETE repos/o/r",
+            "nohup time gh api -X DELETE repos/o/r",
+        ] {
+            assert_eq!(gh_commands(shell).last().unwrap(), &delete, "{shell}");
+        }
+        assert!(gh_commands("sudo apt install git").is_empty());
+        assert!(gh_commands("grep gh api.txt").is_empty());
+    }
+
+    #[test]
+    fn only_mutations_are_guarded() {
+        let args = |cmd: &str| gh_commands(cmd).remove(0);
+        assert!(guarded(&args("gh api -X PATCH repos/o/r")));
+        assert!(guarded(&args("gh api repos/o/r/issues -f title=x")));
+        assert!(guarded(&args("gh pr create --fill")));
+        assert!(guarded(&args("gh repo fork o/r --clone")));
+        assert!(!guarded(&args("gh repo fork --help")));
+        assert!(guarded(&args("gh release upload v1 a.zip --clobber")));
+        assert!(!guarded(&args("gh api repos/o/r")));
+        assert!(!guarded(&args("gh pr create --web")));
+        assert!(!guarded(&args("gh issue create -t x")));
+    }
+}
@@ -0,0 +1,60 @@
+/// The GitHub host everything targets: `GH_HOST` (as gh itself reads it),
+/// else `host` from the active profile or the config file, else github.com.
+pub fn name() -> String {
+    std::env::var("GH_HOST")
+        .ok()
+        .or_else(|| crate::settings::load().ok()?.host)
+        .map(|h| normalize(&h))
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "github.com".to_string())
+}
+
+/// "https://ghe.example.com/" → "ghe.example.com".
+pub fn normalize(host: &str) -> String {
+    let host = host.trim();
+    let host = host.split_once("://").map_or(host, |(_, rest)| rest);
+    host.trim_end_matches('/').to_lowercase()
+}
+
+/// GitHub Enterprise Cloud with data residency (`<tenant>.ghe.com`).
+fn is_ghe_cloud(host: &str) -> bool {
+    host.ends_with(".ghe.com")
+}
+
+/// REST API root for the host: `https://api.github.com`,
+/// `https://api.<tenant>.ghe.com`, or `https://<host>/api/v3` for
+/// GitHub Enterprise Server.
+pub fn api_base() -> String {
+    let host = name();
+    if host == "github.com" || is_ghe_cloud(&host) {
+        format!("https://api.{host}")
+    } else {
+        format!("https://{host}/api/v3")
+    }
+}
+
+/// Full REST URL for `path` (e.g. "/user").
+pub fn api_url(path: &str) -> String {
+    format!("{}/{}", api_base(), path.trim_start_matches('/'))
+}
+
+/// Web URL for `path` on the host (e.g. "/settings/tokens").
+pub fn web_url(path: &str) -> String {
+    format!("https://{}/{}", name(), path.trim_start_matches('/'))
+}
+
+/// Environment that hands `token` to gh for the host. gh reads `GH_TOKEN`
+/// for github.com and ghe.com, and `GH_ENTERPRISE_TOKEN` for Enterprise
+/// Server; `GH_HOST` makes commands run outside a clone target the host too.
+pub fn token_env(token: &str) -> Vec<(&'static str, String)> {
+    let host = name();
+    if host == "github.com" {
+        return vec![("GH_TOKEN", token.to_string())];
+    }
+    let var = if is_ghe_cloud(&host) {
+        "GH_TOKEN"
+    } else {
+        "GH_ENTERPRISE_TOKEN"
+    };
+    vec![(var, token.to_string()), ("GH_HOST", host)]
+}
@@ -0,0 +1,317 @@
+use crate::settings::Network;
+use anyhow::{anyhow, bail, Context, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+
+const TELEGRAM_HOST: &str = "api.telegram.org";
+
+/// `[network]` settings, read once per process.
+static NETWORK: OnceLock<Network> = OnceLock::new();
+/// TLS setups built from `[network]`, or why they could not be.
+static TLS: OnceLock<Result<Tls, String>> = OnceLock::new();
+
+/// Client configs per destination; `None` where ureq's defaults suffice.
+#[derive(Default)]
+struct Tls {
+    general: Option<Arc<ClientConfig>>,
+    telegram: Option<Arc<ClientConfig>>,
+}
+
+/// Agent builder for requests to `url` (only its host matters), with the
+/// proxy applied unless the host is excluded, and the extra CA bundle or
+/// Telegram certificate pins in place. Every outbound request — GitHub,
+/// Telegram, Vault, audit sinks — starts here.
+pub fn builder(url: &str) -> Result<ureq::AgentBuilder> {
+    let host = host_of(url);
+    let mut builder = ureq::AgentBuilder::new();
+    if let Some(proxy) = proxy_for(host) {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(tls) = tls_for(host)? {
+        builder = builder.tls_config(tls);
+    }
+    Ok(builder)
+}
+
+/// Async client builder with the same proxy and TLS setup, for the
+/// approval path. Unlike ureq, which never reuses a connection made
+/// through a proxy, it keeps connections alive across requests and speaks
+/// HTTP/2 where the server offers it.
+pub fn async_builder(url: &str) -> Result<reqwest::ClientBuilder> {
+    let host = host_of(url);
+    // The proxy decision is ours (see `proxy_url`), not reqwest's.
+    let mut builder = reqwest::Client::builder().no_proxy();
+    if let Some(url) = proxy_url(host) {
+        let proxy = reqwest::Proxy::all(&url).context("Unusable proxy setting")?;
+        builder = builder.proxy(proxy);
+    }
+    let mut tls = match tls_for(host)? {
+        Some(tls) => (*tls).clone(),
+        None => default_tls()?,
+    };
+    tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(builder.use_preconfigured_tls(tls))
+}
+
+/// Environment that makes gh use the configured proxy too. Empty when the
+/// proxy comes from the environment already.
+pub fn child_env() -> Vec<(&'static str, String)> {
+    let cfg = network();
+    let Some(proxy) = &cfg.proxy else {
+        return Vec::new();
+    };
+    let mut env = vec![("HTTPS_PROXY", proxy.clone())];
+    if !cfg.no_proxy.is_empty() {
+        let inherited = std::env::var("NO_PROXY").unwrap_or_default();
+        let all: Vec<&str> = cfg
+            .no_proxy
+            .iter()
+            .map(String::as_str)
+            .chain(inherited.split(',').filter(|s| !s.trim().is_empty()))
+            .collect();
+        env.push(("NO_PROXY", all.join(",")));
+    }
+    env
+}
+
+/// Base URL of the Bot API: `[network] telegram_api`, else Telegram's own.
+pub fn telegram_api() -> &'static str {
+    network()
+        .telegram_api
+        .as_deref()
+        .map_or("https://api.telegram.org", |u| u.trim_end_matches('/'))
+}
+
+/// `[network]`, from the file alone: profiles do not override it, and
+/// resolving one here could happen before the target repository is known.
+fn network() -> &'static Network {
+    NETWORK.get_or_init(|| {
+        crate::settings::load_file()
+            .map(|s| s.network)
+            .unwrap_or_default()
+    })
+}
+
+fn proxy_for(host: &str) -> Option<ureq::Proxy> {
+    match ureq::Proxy::new(proxy_url(host)?) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            tracing::warn!(error = %e, "ignoring unusable proxy setting");
+            None
+        }
+    }
+}
+
+/// `[network] proxy`, else the usual environment variables. All our
+/// traffic is HTTPS, so `HTTPS_PROXY` wins over `HTTP_PROXY`.
+fn proxy_url(host: &str) -> Option<String> {
+    let cfg = network();
+    let url = cfg.proxy.clone().or_else(|| {
+        [
+            "HTTPS_PROXY",
+            "https_proxy",
+            "ALL_PROXY",
+            "all_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+        ]
+        .iter()
+        .find_map(|v| std::env::var(v).ok().filter(|s| !s.trim().is_empty()))
+    })?;
+
+    let env_no_proxy = std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .unwrap_or_default();
+    let excluded = cfg
+        .no_proxy
+        .iter()
+        .map(String::as_str)
+        .chain(env_no_proxy.split(','))
+        .any(|rule| bypasses(rule.trim(), host));
+    if excluded {
+        tracing::debug!(host, "proxy bypassed");
+        return None;
+    }
+    Some(url)
+}
+
+// ── TLS ──────────────────────────────────────────────────────────────────────
+
+/// Client config for `host`, or `None` to keep the client's defaults.
+fn tls_for(host: &str) -> Result<Option<Arc<ClientConfig>>> {
+    let configs = TLS
+        .get_or_init(|| tls_configs(network()).map_err(|e| format!("{e:#}")))
+        .as_ref()
+        .map_err(|e| anyhow!("{e}"))?;
+    let tls = if host.eq_ignore_ascii_case(TELEGRAM_HOST) {
+        &configs.telegram
+    } else {
+        &configs.general
+    };
+    Ok(tls.clone())
+}
+
+/// Mozilla's roots and nothing else — what ureq uses by default.
+fn default_tls() -> Result<ClientConfig> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    Ok(
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    )
+}
+
+fn tls_configs(cfg: &Network) -> Result<Tls> {
+    if cfg.ca_bundle.is_none() && cfg.telegram_pins.is_empty() {
+        return Ok(Tls::default());
+    }
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    if let Some(bundle) = &cfg.ca_bundle {
+        let path = crate::settings::expand_home(bundle);
+        let certs = CertificateDer::pem_file_iter(&path)
+            .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("Cannot read CA bundle {}", path.display()))?;
+        if certs.is_empty() {
+            bail!("CA bundle {} contains no certificates", path.display());
+        }
+        let (added, _) = roots.add_parsable_certificates(certs);
+        tracing::debug!(added, bundle = %path.display(), "extra CA certificates loaded");
+    }
+    let roots = Arc::new(roots);
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let general = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots.clone())
+        .with_no_client_auth();
+    let general = Arc::new(general);
+    if cfg.telegram_pins.is_empty() {
+        return Ok(Tls {
+            general: Some(general.clone()),
+            telegram: Some(general),
+        });
+    }
+
+    let pins = cfg
+        .telegram_pins
+        .iter()
+        .map(|p| parse_pin(p))
+        .collect::<Result<Vec<_>>>()?;
+    let inner = WebPkiServerVerifier::builder_with_provider(roots, provider.clone()).build()?;
+    let telegram = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(Pinned { inner, pins }))
+        .with_no_client_auth();
+    Ok(Tls {
+        general: Some(general),
+        telegram: Some(Arc::new(telegram)),
+    })
+}
+
+/// "sha256:AB:CD:…" or plain hex → 32 bytes.
+fn parse_pin(pin: &str) -> Result<[u8; 32]> {
+    let hex_digits: String = pin
+        .trim()
+        .trim_start_matches("sha256:")
+        .chars()
+        .filter(|c| *c != ':')
+        .collect();
+    hex::decode(&hex_digits)
+        .ok()
+        .and_then(|b| <[u8; 32]>::try_from(b).ok())
+        .ok_or_else(|| {
+            anyhow!("Invalid telegram_pins entry {pin:?} (expected a SHA-256 fingerprint)")
+        })
+}
+
+/// Normal WebPKI verification, plus: some certificate in the chain must
+/// have one of the pinned SHA-256 fingerprints. Pinning an intermediate
+/// survives the routine renewal of the leaf certificate.
+#[derive(Debug)]
+struct Pinned {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for Pinned {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .any(|c| self.pins.contains(&Sha256::digest(c.as_ref()).into()));
+        if !pinned {
+            return Err(rustls::Error::General(
+                "Telegram certificate does not match [network] telegram_pins".to_string(),
+            ));
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+// ── Proxy ────────────────────────────────────────────────────────────────────
+
+/// curl-style `NO_PROXY` rule: `*`, a host, or a domain suffix with or
+/// without a leading dot. Ports are ignored.
+fn bypasses(rule: &str, host: &str) -> bool {
+    let rule = rule.split(':').next().unwrap_or_default().to_lowercase();
+    let rule = rule.trim_start_matches("*.").trim_start_matches('.');
+    if rule.is_empty() {
+        return false;
+    }
+    let host = host.to_lowercase();
+    rule == "*" || host == rule || host.ends_with(&format!(".{rule}"))
+}
+
+/// "https://api.github.com:443/user" → "api.github.com".
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, r)| r);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    authority.split(':').next().unwrap_or_default()
+}
@@ -0,0 +1,1092 @@
+use crate::notify::escape_html;
+use std::sync::OnceLock;
+
+/// Every sentence of the approval flow, in one language: the terminal
+/// banners and outcomes, and what the approver sees on Telegram. Telegram
+/// texts are HTML; values passed to them are already escaped.
+#[derive(Clone)]
+pub struct Text {
+    // ── Telegram ──
+    pub approve: &'static str,
+    pub reject: &'static str,
+    /// Beside Approve while `[trust]` does not trust the repository yet.
+    pub approve_and_trust: &'static str,
+    /// Beside Approve with `[allow_all]`.
+    pub allow_all: fn(minutes: u64) -> String,
+    /// Beside Approve with `[allow_similar]`.
+    pub allow_similar: &'static str,
+    /// Under the other buttons with `[delegate]`.
+    pub delegate: fn(name: &str) -> String,
+    /// Under the card, for clients that cannot tap buttons.
+    pub text_commands: fn(request_id: &str) -> String,
+    /// Last line of an `[sms]` card: the replies that decide it, the
+    /// approval carrying what a typed confirmation would.
+    pub sms_reply: fn(request_id: &str, confirm: Option<&str>) -> String,
+    /// Link buttons under the card.
+    pub view_compare: &'static str,
+    pub open_repo: &'static str,
+    /// Shown briefly on the phone after a tap.
+    pub approving: &'static str,
+    pub rejecting: &'static str,
+    /// After an approval that still needs more from other people.
+    pub approval_counted: fn(count: u32, needed: u32) -> String,
+    pub already_approved: &'static str,
+    /// Asks for the repository name before a deletion is approved.
+    pub type_to_confirm: fn(expected: &str) -> String,
+    pub confirm_mismatch: fn(expected: &str) -> String,
+    pub confirmed: &'static str,
+    /// A tap on Approve before the name was typed.
+    pub confirm_first: &'static str,
+    /// A tap from someone not in `[group] members`.
+    pub not_a_member: &'static str,
+    /// In reply to the copy of the card a delegate gets.
+    pub handed_over: fn(by: &str) -> String,
+    pub delegating: &'static str,
+    /// A tap in the approval chat once the request was delegated.
+    pub delegated_away: &'static str,
+    pub delegate_failed: &'static str,
+    /// The poll under a card with `[voting]`; its answers are `approve`
+    /// and `reject`.
+    pub vote_question: &'static str,
+    /// Labels that replace the buttons once the request is settled.
+    pub approved: &'static str,
+    pub rejected: &'static str,
+    pub expired: &'static str,
+    pub cancelled: &'static str,
+    pub panicked: &'static str,
+    pub delegated: fn(name: &str) -> String,
+    pub reminder: fn(secs: u64) -> String,
+    /// Leads the card's heading; may be empty.
+    pub pr_card_icon: &'static str,
+    pub pr_card_heading: &'static str,
+    pub draft_badge: &'static str,
+    pub title: &'static str,
+    pub branch: &'static str,
+    /// Also the labels in the terminal banner.
+    pub reviewers: &'static str,
+    pub assignees: &'static str,
+    pub labels: &'static str,
+    pub milestone: &'static str,
+    /// The head branch's CI state.
+    pub ci: &'static str,
+    pub ci_passing: &'static str,
+    pub ci_failing: &'static str,
+    pub ci_pending: &'static str,
+    pub ci_none: &'static str,
+    pub description: &'static str,
+    pub template_unused: fn(path: &str) -> String,
+    pub api_card_icon: &'static str,
+    pub api_card_heading: &'static str,
+    pub fields: &'static str,
+    pub query: &'static str,
+    /// Where a webhook sends its events, right under the endpoint.
+    pub webhook_url: &'static str,
+    /// Under a branch-protection call's endpoint: the branch and what
+    /// happens to its protections.
+    pub protection_branch: &'static str,
+    pub protection_removed: &'static str,
+    pub protection_changed: &'static str,
+    pub protection_all: &'static str,
+    /// Under a collaborator or team call's endpoint: who gets what where.
+    pub access: &'static str,
+    /// `who` is "@login" or "team org/slug"; `permission` is `None` when
+    /// GitHub's default applies.
+    pub access_granted: fn(permission: Option<&str>, who: &str, repo: &str) -> String,
+    pub access_removed: fn(who: &str, repo: &str) -> String,
+    pub team: &'static str,
+    /// Under a membership call's endpoint: who joins or leaves what. `what`
+    /// is an organisation or "team org/slug".
+    pub membership: &'static str,
+    pub member_added: fn(who: &str, what: &str, role: Option<&str>) -> String,
+    pub member_removed: fn(who: &str, what: &str) -> String,
+    pub team_created: fn(what: &str) -> String,
+    pub team_changed: fn(what: &str) -> String,
+    pub team_deleted: fn(what: &str) -> String,
+    pub fork_card_icon: &'static str,
+    pub fork_card_heading: &'static str,
+    /// Also the labels in the terminal banner.
+    pub fork_source: &'static str,
+    pub fork_destination: &'static str,
+    pub private: &'static str,
+    pub public: &'static str,
+    /// Under a card for a private repository forked to another owner.
+    pub fork_leaves_owner: fn(owner: &str) -> String,
+    pub delete_card_icon: &'static str,
+    pub delete_card_heading: &'static str,
+    pub delete_warning: &'static str,
+    pub release_card_icon: &'static str,
+    pub release_card_heading: &'static str,
+    /// Also the labels in the terminal banner.
+    pub release_tag: &'static str,
+    pub release_assets: &'static str,
+    /// Under a `gh release upload --clobber` card, and in the terminal.
+    pub clobber_warning: &'static str,
+    /// Under a workflow file's path when its `content` is not base64 text.
+    pub workflow_unreadable: &'static str,
+    /// Under a card whose full text follows as the file `name`.
+    pub document_note: fn(name: &str) -> String,
+    /// Under a card, and in the terminal, when the branch is not where the
+    /// PR will be made from.
+    pub not_pushed: fn(branch: &str) -> String,
+    pub commits_unpushed: fn(count: u32, upstream: &str) -> String,
+    pub behind_upstream: fn(count: u32, upstream: &str) -> String,
+    pub uncommitted: fn(count: usize) -> String,
+    /// The GitHub account a command runs as, under the card and banner:
+    /// its login and the `[accounts]` entry its PAT came from, either known.
+    pub acting_as: fn(login: Option<&str>, account: Option<&str>) -> String,
+    /// A checkout's `git status` in a few words, for the card's last line.
+    pub git_status: fn(changed: usize, untracked: usize, ahead: usize, behind: usize) -> String,
+    /// Under a card whose PR title misses `title_pattern`.
+    pub title_mismatch_note: fn(pattern: &str) -> String,
+    /// Under a card for a protected base branch.
+    pub approvals_note: fn(needed: u32) -> String,
+    pub diff_note: fn(name: &str) -> String,
+    pub diff_missing: &'static str,
+    /// Also the label in the terminal banner.
+    pub host: &'static str,
+    pub unknown_endpoint: &'static str,
+    pub unlock_card: fn(since: &str, rejections: u32, source: &str) -> String,
+    pub lockout_note: fn(rejections: u32, source: &str) -> String,
+    /// Locked mode entered by `--guard-bypass` beyond `[bypass] per_day`.
+    pub unlock_card_bypasses: fn(since: &str, bypasses: u32, source: &str) -> String,
+    pub bypass_lock_note: fn(bypasses: u32, source: &str) -> String,
+    pub rate_limit_note: fn(repo: &str, count: usize, window: u64, summary: &str) -> String,
+    pub queued_card: fn(when: &str) -> String,
+    /// The same, for a request held during quiet hours.
+    pub quiet_queued_card: fn(when: &str) -> String,
+    /// After the fact: `[api] deny` refused a command.
+    pub denied_note: fn(summary: &str, rule: &str, source: &str) -> String,
+    /// After the fact: `--guard-bypass` skipped the card.
+    pub bypass_note: fn(summary: &str, reason: &str, source: &str) -> String,
+    /// `gh-guard ci-token`: may CI act on `repo` for `hours`?
+    pub ci_token_card: fn(repo: &str, hours: u64, source: &str) -> String,
+    pub readonly_off_card: fn(since: &str, source: &str) -> String,
+
+    // ── Terminal ──
+    pub pr_banner: &'static str,
+    pub api_banner: &'static str,
+    pub fork_banner: &'static str,
+    pub release_banner: &'static str,
+    pub delete_banner: &'static str,
+    /// Banner labels, padded by the caller.
+    pub label_title: &'static str,
+    pub label_branch: &'static str,
+    pub label_mode: &'static str,
+    pub mode_draft: &'static str,
+    pub label_method: &'static str,
+    pub label_endpoint: &'static str,
+    /// One `-f` / `-F` field, when the banner is printed as plain lines.
+    pub label_field: &'static str,
+    pub auto_fill_title: &'static str,
+    /// Prompts when `gh pr create` is run without `--title` or `--fill`.
+    pub prompt_title: &'static str,
+    pub prompt_title_required: &'static str,
+    pub prompt_body: &'static str,
+    pub prompt_base: &'static str,
+    pub prompt_default_base: &'static str,
+    pub web_bypass: &'static str,
+    pub sending: &'static str,
+    pub sending_silently: &'static str,
+    pub waiting: fn(timeout: &str) -> String,
+    /// Under the waiting line during an `[[on_call]]` shift.
+    pub on_call: fn(name: &str) -> String,
+    pub countdown: fn(mins: u64, secs: u64) -> String,
+    pub creating_pr: &'static str,
+    pub running_api: &'static str,
+    pub pr_not_created: &'static str,
+    pub api_cancelled: &'static str,
+    pub forking: &'static str,
+    pub fork_cancelled: &'static str,
+    pub deleting: &'static str,
+    pub delete_cancelled: &'static str,
+    pub uploading: &'static str,
+    pub upload_cancelled: &'static str,
+    pub outcome_approved: &'static str,
+    pub outcome_bypassed: fn(reason: &str) -> String,
+    pub outcome_auto_approved: fn(reason: &str) -> String,
+    pub repo_trusted: fn(repo: &str) -> String,
+    pub allowing_all: fn(severity: &str, until: &str) -> String,
+    pub allowing_similar: fn(class: &str, repo: &str, until: &str) -> String,
+    pub delegated_by: fn(by: &str, to: &str) -> String,
+    pub api_rule_denied: &'static str,
+    /// A command matching `[api] deny`, which no approval lifts.
+    pub denied: fn(rule: &str) -> String,
+    pub outcome_rejected: &'static str,
+    pub outcome_timed_out: fn(timeout: &str) -> String,
+    pub outcome_cancelled: &'static str,
+    pub locked: fn(since: &str, rejections: u32, source: &str) -> String,
+    pub locked_bypasses: fn(since: &str, bypasses: u32, source: &str) -> String,
+    pub bypass_budget_spent: fn(per_day: u32) -> String,
+    pub bypass_off: &'static str,
+    pub locked_hint: &'static str,
+    pub now_locked: &'static str,
+    pub quiet_rejected: &'static str,
+    /// With `[sms]`, for requests that need more than one approver.
+    pub sms_needs_more: &'static str,
+    /// For a repository deletion under `--guard-bypass` or CI passthrough.
+    pub deletion_needs_card: &'static str,
+    pub readonly_refused: fn(since: &str) -> String,
+    /// `--guard-dry-run` report; labels padded by the caller.
+    pub dry_run_heading: &'static str,
+    pub label_command: &'static str,
+    pub label_decision: &'static str,
+    pub label_repo: &'static str,
+    pub label_severity: &'static str,
+    pub label_topic: &'static str,
+    pub label_on_call: &'static str,
+    pub label_quiet: &'static str,
+    pub label_file: &'static str,
+    pub label_button: &'static str,
+    pub dry_run_ask: fn(approvals: u32, timeout: &str) -> String,
+    pub dry_run_run: fn(reason: &str) -> String,
+    pub dry_run_refuse: fn(reason: &str) -> String,
+    pub dry_run_passthrough: &'static str,
+    pub hook_allowed: &'static str,
+    pub hook_denied: fn(command: &str, why: &str) -> String,
+    pub title_mismatch: fn(pattern: &str) -> String,
+    pub ci_failing_rejected: &'static str,
+    /// Refusals under `[ci] policy`.
+    pub ci_rejected: &'static str,
+    pub ci_token_missing: &'static str,
+    pub ci_token_invalid: &'static str,
+    pub ci_token_expired: &'static str,
+    pub ci_token_other_repo: &'static str,
+    pub outcome_ci_passthrough: &'static str,
+    pub outcome_ci_token: fn(id: &str) -> String,
+    pub quiet_holding: fn(end: &str, severity: &str, id: &str) -> String,
+    pub rate_limited: fn(count: usize, repo: &str, window: u64) -> String,
+    pub queued: fn(id: &str) -> String,
+    pub queued_hint: &'static str,
+    pub not_locked: &'static str,
+    pub sending_unlock: &'static str,
+    pub unlocked: &'static str,
+    pub unlock_rejected: &'static str,
+    pub unlock_timed_out: &'static str,
+    pub unlock_cancelled: &'static str,
+    pub sending_ci_token: &'static str,
+    pub ci_token_rejected: &'static str,
+    pub ci_token_timed_out: &'static str,
+    pub ci_token_cancelled: &'static str,
+    pub ci_public_key: fn(key: &str) -> String,
+    pub readonly_on: &'static str,
+    pub readonly_status_on: fn(since: &str) -> String,
+    pub readonly_status_off: &'static str,
+    pub sending_readonly_off: &'static str,
+    pub readonly_off: &'static str,
+    pub readonly_off_rejected: &'static str,
+    pub readonly_off_timed_out: &'static str,
+    pub readonly_off_cancelled: &'static str,
+}
+
+pub static EN: Text = Text {
+    approve: "✅ Approve",
+    reject: "❌ Reject",
+    approve_and_trust: "✅ Approve & trust repo",
+    allow_all: |minutes| format!("🔓 Allow all for {minutes} min"),
+    allow_similar: "✅ Approve & allow similar",
+    delegate: |name| format!("➡️ Delegate to {name}"),
+    text_commands: |id| format!("⌨️ No buttons? /approve_{id} · /reject_{id}"),
+    sms_reply: |id, confirm| match confirm {
+        Some(name) => format!("Reply YES {id} {name} to approve or NO {id} to reject."),
+        None => format!("Reply YES {id} to approve or NO {id} to reject."),
+    },
+    view_compare: "🔍 View compare",
+    open_repo: "📂 Open repo",
+    approving: "✅ Approving…",
+    rejecting: "❌ Rejecting…",
+    approval_counted: |count, needed| {
+        format!("✅ Approval {count} of {needed}; waiting for someone else.")
+    },
+    already_approved: "You have already approved this.",
+    type_to_confirm: |expected| {
+        format!(
+            "✍️ This cannot be undone. To approve, reply with <code>{expected}</code>, \
+             then tap Approve."
+        )
+    },
+    confirm_mismatch: |expected| format!("That is not <code>{expected}</code>. Try again."),
+    confirmed: "Confirmed. Tap Approve to go ahead.",
+    confirm_first: "Reply with the repository name first.",
+    not_a_member: "Only the group's approvers can decide this.",
+    handed_over: |by| format!("➡️ {by} handed this request to you."),
+    delegating: "➡️ Handing over…",
+    delegated_away: "This request was handed to someone else.",
+    delegate_failed: "Could not hand this over; decide it here.",
+    vote_question: "Approve this request?",
+    approved: "✅ Approved",
+    rejected: "❌ Rejected",
+    expired: "⏱ Expired",
+    cancelled: "🚫 Cancelled",
+    panicked: "🚨 Panic",
+    delegated: |name| format!("➡️ Delegated to {name}"),
+    reminder: |secs| format!("⏰ Still waiting — this request expires in {secs} s."),
+    pr_card_icon: "🔀",
+    pr_card_heading: "PR Review Required",
+    draft_badge: "DRAFT",
+    title: "Title",
+    branch: "Branch",
+    reviewers: "Reviewers",
+    assignees: "Assignees",
+    labels: "Labels",
+    milestone: "Milestone",
+    ci: "CI",
+    ci_passing: "✅ passing",
+    ci_failing: "❌ failing",
+    ci_pending: "⏳ pending",
+    ci_none: "no checks",
+    description: "Description",
+    template_unused: |path| {
+        format!("<i>No description, although the repository has a PR template ({path}).</i>")
+    },
+    api_card_icon: "🔧",
+    api_card_heading: "API Mutation · Approval Required",
+    fields: "Fields",
+    query: "Query parameters",
+    webhook_url: "🎯 Delivers to",
+    protection_branch: "🛡 Branch",
+    protection_removed: "➖ Turns off",
+    protection_changed: "✏️ Sets",
+    protection_all: "all protections",
+    access: "👥 Access",
+    access_granted: |permission, who, repo| match permission {
+        Some(permission) => format!("grant {permission} to {who} on {repo}"),
+        None => format!("grant access to {who} on {repo}"),
+    },
+    access_removed: |who, repo| format!("remove {who} from {repo}"),
+    team: "team",
+    membership: "👤 Members",
+    member_added: |who, what, role| match role {
+        Some(role) => format!("add {who} to {what} as {role}"),
+        None => format!("add {who} to {what}"),
+    },
+    member_removed: |who, what| format!("remove {who} from {what}"),
+    team_created: |what| format!("create {what}"),
+    team_changed: |what| format!("change {what}"),
+    team_deleted: |what| format!("delete {what}"),
+    fork_card_icon: "🍴",
+    fork_card_heading: "Fork · Approval Required",
+    fork_source: "Source",
+    fork_destination: "Into",
+    private: "🔒 private",
+    public: "public",
+    fork_leaves_owner: |owner| format!("⚠️ <i>Private code leaves {owner}.</i>"),
+    delete_card_icon: "🗑",
+    delete_card_heading: "Repository Deletion · Approval Required",
+    delete_warning: "Deletes the repository with its issues, PRs, wiki and releases. \
+                     This cannot be undone.",
+    release_card_icon: "📦",
+    release_card_heading: "Release Upload · Approval Required",
+    release_tag: "Tag",
+    release_assets: "Assets",
+    clobber_warning: "--clobber: assets of the same name are replaced.",
+    workflow_unreadable: "Its contents could not be decoded; check the fields.",
+    document_note: |name| format!("📎 <i>Cut short; the full text follows as {name}.</i>"),
+    approvals_note: |needed| format!("👥 <i>Needs {needed} approvals from different people.</i>"),
+    diff_note: |name| format!("📎 <i>The diff follows as {name}.</i>"),
+    diff_missing: "⚠️ <i>The diff could not be worked out, so none is attached.</i>",
+    not_pushed: |branch| format!("Branch {branch} has not been pushed."),
+    commits_unpushed: |count, upstream| match count {
+        1 => format!("1 commit is not pushed to {upstream}."),
+        n => format!("{n} commits are not pushed to {upstream}."),
+    },
+    behind_upstream: |count, upstream| match count {
+        1 => format!("The branch is 1 commit behind {upstream}."),
+        n => format!("The branch is {n} commits behind {upstream}."),
+    },
+    uncommitted: |count| match count {
+        1 => "1 uncommitted file is not included.".to_string(),
+        n => format!("{n} uncommitted files are not included."),
+    },
+    acting_as: |login, account| match (login, account) {
+        (Some(login), Some(account)) => format!("as @{login} (account {account})"),
+        (Some(login), None) => format!("as @{login}"),
+        (None, account) => format!("as account {}", account.unwrap_or("?")),
+    },
+    git_status: |changed, untracked, ahead, behind| {
+        let parts: Vec<String> = [
+            (changed, "changed"),
+            (untracked, "untracked"),
+            (ahead, "ahead"),
+            (behind, "behind"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{n} {what}"))
+        .collect();
+        if parts.is_empty() {
+            "clean".to_string()
+        } else {
+            parts.join(", ")
+        }
+    },
+    title_mismatch_note: |pattern| {
+        format!("⚠️ <b>The title does not match</b> <code>{pattern}</code>")
+    },
+    host: "Host",
+    unknown_endpoint: "(unknown endpoint)",
+    unlock_card: |since, rejections, source| {
+        format!(
+            "🔓 <b>Unlock gh-guard?</b>\n\nLocked since {since} after {rejections} consecutive \
+             rejections from <code>{source}</code>.\n\nApprove only if you have investigated."
+        )
+    },
+    lockout_note: |rejections, source| {
+        format!(
+            "🔒 <b>gh-guard locked</b>\n\n{rejections} consecutive rejections from \
+             <code>{source}</code>. All guarded commands are refused until you run \
+             <code>gh-guard unlock</code> and approve it here."
+        )
+    },
+    unlock_card_bypasses: |since, bypasses, source| {
+        format!(
+            "🔓 <b>Unlock gh-guard?</b>\n\nLocked since {since} after {bypasses} bypasses within \
+             a day from <code>{source}</code>.\n\nApprove only if you have investigated."
+        )
+    },
+    bypass_lock_note: |bypasses, source| {
+        format!(
+            "🔒 <b>gh-guard locked</b>\n\n{bypasses} bypasses within a day from \
+             <code>{source}</code>, more than <code>[bypass] per_day</code> allows. All guarded \
+             commands are refused until you run <code>gh-guard unlock</code> and approve it here."
+        )
+    },
+    rate_limit_note: |repo, count, window, summary| {
+        format!(
+            "🚦 <b>Rate limit reached</b>\n\n<code>{repo}</code> already had {count} approved \
+             mutations in the last {window} min.\n\nAuto-rejected: {summary}"
+        )
+    },
+    queued_card: |when| format!("📥 <i>Queued {when} while Telegram was unreachable</i>"),
+    quiet_queued_card: |when| format!("📥 <i>Held {when} until quiet hours ended</i>"),
+    denied_note: |summary, rule, source| {
+        format!(
+            "⛔ <b>Denied</b>\n\nSomething tried a command you never allow.\n\n{summary}\n\n\
+             <b>Rule</b>  <code>{rule}</code>\n<b>By</b>  <code>{source}</code>"
+        )
+    },
+    bypass_note: |summary, reason, source| {
+        format!(
+            "⚡ <b>Approval bypassed</b>\n\n{summary}\n\n<b>Reason</b>  {reason}\n\
+             <b>By</b>  <code>{source}</code>"
+        )
+    },
+    ci_token_card: |repo, hours, source| {
+        format!(
+            "🎫 <b>Approval token for CI?</b>\n\nGuarded commands under CI may run without \
+             asking for <b>{hours} h</b>.\n\n<b>Repository</b>  <code>{repo}</code>\n\
+             <b>Requested by</b>  <code>{source}</code>"
+        )
+    },
+    readonly_off_card: |since, source| {
+        format!(
+            "🔓 <b>Leave read-only mode?</b>\n\nOn since {since}. Mutating commands will be \
+             guarded again instead of refused.\n\n<b>Requested by</b>  <code>{source}</code>"
+        )
+    },
+
+    pr_banner: "gh-guard · PR Approval Required",
+    api_banner: "gh-guard · API Approval Required",
+    fork_banner: "gh-guard · Fork Approval Required",
+    release_banner: "gh-guard · Release Upload Approval Required",
+    delete_banner: "gh-guard · Repository Deletion Approval Required",
+    label_title: "Title",
+    label_branch: "Branch",
+    label_mode: "Mode",
+    mode_draft: "draft",
+    label_method: "Method",
+    label_endpoint: "Endpoint",
+    label_field: "Field",
+    auto_fill_title: "(auto-fill from commits)",
+    prompt_title: "Title",
+    prompt_title_required: "A title is required.",
+    prompt_body: "Body (end with a line holding only \".\"; leave empty for none):",
+    prompt_base: "Base branch",
+    prompt_default_base: "default branch",
+    web_bypass: "gh-guard: --web flag detected, bypassing approval flow.",
+    sending: "Sending to Telegram…",
+    sending_silently: "(quiet hours — sending silently)",
+    waiting: |timeout| format!("Waiting for approval on Telegram ({timeout} timeout)…"),
+    on_call: |name| format!("{name} is on call and decides this."),
+    countdown: |mins, secs| format!("⏳ {mins}:{secs:02} left"),
+    creating_pr: "Creating PR…",
+    running_api: "Running API call…",
+    pr_not_created: "PR not created.",
+    api_cancelled: "API call cancelled.",
+    forking: "Forking…",
+    fork_cancelled: "Repository not forked.",
+    deleting: "Deleting…",
+    delete_cancelled: "Repository not deleted.",
+    uploading: "Uploading…",
+    upload_cancelled: "Nothing uploaded.",
+    outcome_approved: "Approved!",
+    outcome_bypassed: |reason| format!("Approval bypassed ({reason})."),
+    outcome_auto_approved: |reason| format!("Approved by policy ({reason})."),
+    repo_trusted: |repo| format!("{repo} is trusted from now on."),
+    allowing_all: |severity, until| {
+        format!("Everything up to {severity} severity runs without asking until {until}.")
+    },
+    allowing_similar: |class, repo, until| {
+        format!("More gh {class} on {repo} runs without asking until {until}.")
+    },
+    delegated_by: |by, to| format!("Delegated by {by} to {to}."),
+    api_rule_denied: "Refused by an [[api_rule]] in the config.",
+    denied: |rule| format!("Never allowed from this machine ([api] deny \"{rule}\")."),
+    outcome_rejected: "Rejected.",
+    outcome_timed_out: |timeout| format!("Timed out ({timeout})."),
+    outcome_cancelled: "Cancelled.",
+    locked: |since, rejections, source| {
+        format!(
+            "🔒  gh-guard is locked since {since} after {rejections} consecutive rejections \
+             from {source}."
+        )
+    },
+    locked_bypasses: |since, bypasses, source| {
+        format!(
+            "🔒  gh-guard is locked since {since} after {bypasses} bypasses within a day from \
+             {source}."
+        )
+    },
+    bypass_budget_spent: |per_day| format!("Bypass refused: only {per_day} allowed within a day."),
+    bypass_off: "Bypass refused: the config has no [bypass] section.",
+    locked_hint: "Investigate, then run `gh-guard unlock` and approve on your phone.",
+    now_locked: "🔒  gh-guard is now locked.",
+    quiet_rejected: "Auto-rejected during quiet hours.",
+    sms_needs_more: "Auto-rejected: this needs several approvers, and a text message has one.",
+    deletion_needs_card:
+        "Auto-rejected: deleting a repository always takes an approval on the phone.",
+    readonly_refused: |since| {
+        format!(
+            "🚫  Read-only mode (since {since}): commands that change anything on GitHub are \
+             refused. `gh-guard readonly off` lifts it after approval on the phone."
+        )
+    },
+    dry_run_heading: "Dry run — nothing is sent to Telegram and gh does not run.",
+    label_command: "Command",
+    label_decision: "Decision",
+    label_repo: "Repo",
+    label_severity: "Severity",
+    label_topic: "Topic",
+    label_on_call: "On call",
+    label_quiet: "Quiet",
+    label_file: "File",
+    label_button: "Button",
+    dry_run_ask: |approvals, timeout| {
+        if approvals > 1 {
+            format!("ask for {approvals} approvals on Telegram ({timeout} timeout)")
+        } else {
+            format!("ask for approval on Telegram ({timeout} timeout)")
+        }
+    },
+    dry_run_run: |reason| format!("run gh without asking ({reason})"),
+    dry_run_refuse: |reason| format!("refuse without asking ({reason})"),
+    dry_run_passthrough: "pass through to gh",
+    hook_allowed: "Allowed by gh-guard.",
+    hook_denied: |command, why| format!("gh-guard did not allow `{command}`: {why}."),
+    title_mismatch: |pattern| format!("The PR title does not match title_pattern {pattern}."),
+    ci_failing_rejected: "Auto-rejected: CI is failing on the head branch.",
+    ci_rejected: "Auto-rejected under CI ([ci] policy = \"reject\").",
+    ci_token_missing: "Auto-rejected under CI: GH_GUARD_APPROVAL_TOKEN is not set.",
+    ci_token_invalid:
+        "Auto-rejected under CI: the approval token is not signed by [ci] public_key.",
+    ci_token_expired: "Auto-rejected under CI: the approval token has expired.",
+    ci_token_other_repo: "Auto-rejected under CI: the approval token is for another repository.",
+    outcome_ci_passthrough: "Passed through under CI.",
+    outcome_ci_token: |id| format!("Approved by CI token {id}."),
+    quiet_holding: |end, severity, id| {
+        format!("📥  Quiet hours until {end} — {severity}-severity request queued as {id}.")
+    },
+    rate_limited: |count, repo, window| {
+        format!("❌  Rate limit: {count} approvals for {repo} in the last {window} min.")
+    },
+    queued: |id| format!("📥  Telegram is unreachable — queued as {id}."),
+    queued_hint: "`gh-guard daemon` will send it for approval and run gh once approved.",
+    not_locked: "gh-guard is not locked.",
+    sending_unlock: "Sending unlock request to Telegram…",
+    unlocked: "✅  Unlocked.",
+    unlock_rejected: "❌  Unlock rejected. gh-guard stays locked.",
+    unlock_timed_out: "⏱   Unlock timed out. gh-guard stays locked.",
+    unlock_cancelled: "🚫  Unlock cancelled. gh-guard stays locked.",
+    sending_ci_token: "Sending token request to Telegram…",
+    ci_token_rejected: "❌  Token request rejected.",
+    ci_token_timed_out: "⏱   Token request timed out.",
+    ci_token_cancelled: "🚫  Token request cancelled.",
+    ci_public_key: |key| {
+        format!("Tokens are checked against this key; set it in CI as [ci] public_key:\n  {key}")
+    },
+    readonly_on:
+        "🚫  Read-only mode on. Mutating commands are refused until `gh-guard readonly off` \
+                  is approved on your phone.",
+    readonly_status_on: |since| format!("Read-only mode is on (since {since})."),
+    readonly_status_off: "Read-only mode is off.",
+    sending_readonly_off: "Sending request to leave read-only mode to Telegram…",
+    readonly_off: "✅  Read-only mode off.",
+    readonly_off_rejected: "❌  Rejected. Read-only mode stays on.",
+    readonly_off_timed_out: "⏱   Timed out. Read-only mode stays on.",
+    readonly_off_cancelled: "🚫  Cancelled. Read-only mode stays on.",
+};
+
+pub static DE: Text = Text {
+    approve: "✅ Genehmigen",
+    reject: "❌ Ablehnen",
+    approve_and_trust: "✅ Genehmigen & Repo vertrauen",
+    allow_all: |minutes| format!("🔓 {minutes} min alles erlauben"),
+    allow_similar: "✅ Genehmigen & Ähnliches erlauben",
+    delegate: |name| format!("➡️ An {name} abgeben"),
+    text_commands: |id| format!("⌨️ Keine Knöpfe? /approve_{id} · /reject_{id}"),
+    sms_reply: |id, confirm| match confirm {
+        Some(name) => format!("Antworte YES {id} {name} zum Genehmigen oder NO {id} zum Ablehnen."),
+        None => format!("Antworte YES {id} zum Genehmigen oder NO {id} zum Ablehnen."),
+    },
+    view_compare: "🔍 Vergleich ansehen",
+    open_repo: "📂 Repository öffnen",
+    approving: "✅ Wird genehmigt…",
+    rejecting: "❌ Wird abgelehnt…",
+    approval_counted: |count, needed| {
+        format!("✅ Genehmigung {count} von {needed}; jemand anderes muss noch zustimmen.")
+    },
+    already_approved: "Du hast bereits genehmigt.",
+    type_to_confirm: |expected| {
+        format!(
+            "✍️ Das lässt sich nicht rückgängig machen. Zum Genehmigen mit \
+             <code>{expected}</code> antworten, dann auf Genehmigen tippen."
+        )
+    },
+    confirm_mismatch: |expected| {
+        format!("Das ist nicht <code>{expected}</code>. Bitte noch einmal.")
+    },
+    confirmed: "Bestätigt. Zum Fortfahren auf Genehmigen tippen.",
+    confirm_first: "Zuerst mit dem Namen des Repositorys antworten.",
+    not_a_member: "Nur die Genehmiger der Gruppe können das entscheiden.",
+    handed_over: |by| format!("➡️ {by} hat dir diese Anfrage übergeben."),
+    delegating: "➡️ Wird abgegeben…",
+    delegated_away: "Diese Anfrage wurde an jemand anderen abgegeben.",
+    delegate_failed: "Abgeben ging nicht; bitte hier entscheiden.",
+    vote_question: "Diese Anfrage genehmigen?",
+    approved: "✅ Genehmigt",
+    rejected: "❌ Abgelehnt",
+    expired: "⏱ Abgelaufen",
+    cancelled: "🚫 Zurückgezogen",
+    panicked: "🚨 Notfall",
+    delegated: |name| format!("➡️ An {name} abgegeben"),
+    reminder: |secs| format!("⏰ Noch offen — diese Anfrage läuft in {secs} s ab."),
+    pr_card_icon: "🔀",
+    pr_card_heading: "PR-Prüfung erforderlich",
+    draft_badge: "ENTWURF",
+    title: "Titel",
+    branch: "Branch",
+    reviewers: "Reviewer",
+    assignees: "Zugewiesen",
+    labels: "Labels",
+    milestone: "Meilenstein",
+    ci: "CI",
+    ci_passing: "✅ erfolgreich",
+    ci_failing: "❌ fehlgeschlagen",
+    ci_pending: "⏳ läuft",
+    ci_none: "keine Checks",
+    description: "Beschreibung",
+    template_unused: |path| {
+        format!("<i>Keine Beschreibung, obwohl das Repository eine PR-Vorlage hat ({path}).</i>")
+    },
+    api_card_icon: "🔧",
+    api_card_heading: "API-Änderung · Genehmigung erforderlich",
+    fields: "Felder",
+    query: "Query-Parameter",
+    webhook_url: "🎯 Liefert an",
+    protection_branch: "🛡 Branch",
+    protection_removed: "➖ Schaltet ab",
+    protection_changed: "✏️ Setzt",
+    protection_all: "alle Schutzregeln",
+    access: "👥 Zugriff",
+    access_granted: |permission, who, repo| match permission {
+        Some(permission) => format!("{who} erhält {permission} auf {repo}"),
+        None => format!("{who} erhält Zugriff auf {repo}"),
+    },
+    access_removed: |who, repo| format!("{who} verliert den Zugriff auf {repo}"),
+    team: "Team",
+    membership: "👤 Mitglieder",
+    member_added: |who, what, role| match role {
+        Some(role) => format!("{who} tritt {what} bei, als {role}"),
+        None => format!("{who} tritt {what} bei"),
+    },
+    member_removed: |who, what| format!("{who} verlässt {what}"),
+    team_created: |what| format!("{what} anlegen"),
+    team_changed: |what| format!("{what} ändern"),
+    team_deleted: |what| format!("{what} löschen"),
+    fork_card_icon: "🍴",
+    fork_card_heading: "Fork · Genehmigung erforderlich",
+    fork_source: "Quelle",
+    fork_destination: "Nach",
+    private: "🔒 privat",
+    public: "öffentlich",
+    fork_leaves_owner: |owner| format!("⚠️ <i>Privater Code verlässt {owner}.</i>"),
+    delete_card_icon: "🗑",
+    delete_card_heading: "Repository löschen · Genehmigung erforderlich",
+    delete_warning: "Löscht das Repository samt Issues, PRs, Wiki und Releases. \
+                     Das lässt sich nicht rückgängig machen.",
+    release_card_icon: "📦",
+    release_card_heading: "Release-Upload · Genehmigung erforderlich",
+    release_tag: "Tag",
+    release_assets: "Dateien",
+    clobber_warning: "--clobber: Dateien gleichen Namens werden ersetzt.",
+    workflow_unreadable: "Der Inhalt ließ sich nicht dekodieren; siehe die Felder.",
+    document_note: |name| format!("📎 <i>Gekürzt; der vollständige Text folgt als {name}.</i>"),
+    approvals_note: |needed| {
+        format!("👥 <i>Braucht {needed} Genehmigungen von verschiedenen Personen.</i>")
+    },
+    diff_note: |name| format!("📎 <i>Der Diff folgt als {name}.</i>"),
+    diff_missing: "⚠️ <i>Der Diff konnte nicht ermittelt werden und fehlt daher.</i>",
+    not_pushed: |branch| format!("Branch {branch} wurde nicht gepusht."),
+    commits_unpushed: |count, upstream| match count {
+        1 => format!("1 Commit fehlt noch auf {upstream}."),
+        n => format!("{n} Commits fehlen noch auf {upstream}."),
+    },
+    behind_upstream: |count, upstream| match count {
+        1 => format!("Der Branch liegt 1 Commit hinter {upstream}."),
+        n => format!("Der Branch liegt {n} Commits hinter {upstream}."),
+    },
+    uncommitted: |count| match count {
+        1 => "1 nicht committete Datei ist nicht enthalten.".to_string(),
+        n => format!("{n} nicht committete Dateien sind nicht enthalten."),
+    },
+    acting_as: |login, account| match (login, account) {
+        (Some(login), Some(account)) => format!("als @{login} (Konto {account})"),
+        (Some(login), None) => format!("als @{login}"),
+        (None, account) => format!("als Konto {}", account.unwrap_or("?")),
+    },
+    git_status: |changed, untracked, ahead, behind| {
+        let parts: Vec<String> = [
+            (changed, "geändert"),
+            (untracked, "unversioniert"),
+            (ahead, "voraus"),
+            (behind, "zurück"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{n} {what}"))
+        .collect();
+        if parts.is_empty() {
+            "sauber".to_string()
+        } else {
+            parts.join(", ")
+        }
+    },
+    title_mismatch_note: |pattern| {
+        format!("⚠️ <b>Der Titel passt nicht zu</b> <code>{pattern}</code>")
+    },
+    host: "Host",
+    unknown_endpoint: "(unbekannter Endpunkt)",
+    unlock_card: |since, rejections, source| {
+        format!(
+            "🔓 <b>gh-guard entsperren?</b>\n\nGesperrt seit {since} nach {rejections} \
+             Ablehnungen in Folge von <code>{source}</code>.\n\nNur genehmigen, wenn du der \
+             Sache nachgegangen bist."
+        )
+    },
+    lockout_note: |rejections, source| {
+        format!(
+            "🔒 <b>gh-guard gesperrt</b>\n\n{rejections} Ablehnungen in Folge von \
+             <code>{source}</code>. Alle geschützten Befehle werden verweigert, bis du \
+             <code>gh-guard unlock</code> ausführst und hier genehmigst."
+        )
+    },
+    unlock_card_bypasses: |since, bypasses, source| {
+        format!(
+            "🔓 <b>gh-guard entsperren?</b>\n\nGesperrt seit {since} nach {bypasses} \
+             Umgehungen an einem Tag von <code>{source}</code>.\n\nNur genehmigen, wenn du der \
+             Sache nachgegangen bist."
+        )
+    },
+    bypass_lock_note: |bypasses, source| {
+        format!(
+            "🔒 <b>gh-guard gesperrt</b>\n\n{bypasses} Umgehungen an einem Tag von \
+             <code>{source}</code>, mehr als <code>[bypass] per_day</code> erlaubt. Alle \
+             geschützten Befehle werden verweigert, bis du <code>gh-guard unlock</code> \
+             ausführst und hier genehmigst."
+        )
+    },
+    rate_limit_note: |repo, count, window, summary| {
+        format!(
+            "🚦 <b>Limit erreicht</b>\n\n<code>{repo}</code> hatte in den letzten {window} min \
+             bereits {count} genehmigte Änderungen.\n\nAutomatisch abgelehnt: {summary}"
+        )
+    },
+    queued_card: |when| format!("📥 <i>Eingereiht {when}, als Telegram nicht erreichbar war</i>"),
+    quiet_queued_card: |when| format!("📥 <i>Zurückgehalten {when} bis zum Ende der Ruhezeit</i>"),
+    denied_note: |summary, rule, source| {
+        format!(
+            "⛔ <b>Verweigert</b>\n\nEtwas hat einen Befehl versucht, den du nie erlaubst.\n\n\
+             {summary}\n\n<b>Regel</b>  <code>{rule}</code>\n<b>Von</b>  <code>{source}</code>"
+        )
+    },
+    bypass_note: |summary, reason, source| {
+        format!(
+            "⚡ <b>Genehmigung übergangen</b>\n\n{summary}\n\n<b>Grund</b>  {reason}\n\
+             <b>Von</b>  <code>{source}</code>"
+        )
+    },
+    ci_token_card: |repo, hours, source| {
+        format!(
+            "🎫 <b>Genehmigungstoken für CI?</b>\n\nGeschützte Befehle dürfen unter CI \
+             <b>{hours} h</b> lang ohne Nachfrage laufen.\n\n<b>Repository</b>  \
+             <code>{repo}</code>\n<b>Angefragt von</b>  <code>{source}</code>"
+        )
+    },
+    readonly_off_card: |since, source| {
+        format!(
+            "🔓 <b>Nur-Lese-Modus verlassen?</b>\n\nAktiv seit {since}. Ändernde Befehle werden \
+             wieder zur Genehmigung vorgelegt statt verweigert.\n\n<b>Angefragt von</b>  \
+             <code>{source}</code>"
+        )
+    },
+
+    pr_banner: "gh-guard · PR-Genehmigung erforderlich",
+    api_banner: "gh-guard · API-Genehmigung erforderlich",
+    fork_banner: "gh-guard · Fork-Genehmigung erforderlich",
+    release_banner: "gh-guard · Release-Upload-Genehmigung erforderlich",
+    delete_banner: "gh-guard · Genehmigung zum Löschen erforderlich",
+    label_title: "Titel",
+    label_branch: "Branch",
+    label_mode: "Modus",
+    mode_draft: "Entwurf",
+    label_method: "Methode",
+    label_endpoint: "Endpunkt",
+    label_field: "Feld",
+    auto_fill_title: "(aus den Commits übernommen)",
+    prompt_title: "Titel",
+    prompt_title_required: "Ein Titel ist erforderlich.",
+    prompt_body: "Beschreibung (mit einer Zeile nur aus \".\" beenden; leer lassen für keine):",
+    prompt_base: "Zielbranch",
+    prompt_default_base: "Standardbranch",
+    web_bypass: "gh-guard: --web erkannt, Genehmigung wird übersprungen.",
+    sending: "Wird an Telegram gesendet…",
+    sending_silently: "(Ruhezeit — wird lautlos gesendet)",
+    waiting: |timeout| format!("Warte auf Genehmigung über Telegram (Zeitlimit {timeout})…"),
+    on_call: |name| format!("{name} hat Bereitschaft und entscheidet."),
+    countdown: |mins, secs| format!("⏳ noch {mins}:{secs:02}"),
+    creating_pr: "PR wird erstellt…",
+    running_api: "API-Aufruf läuft…",
+    pr_not_created: "PR wurde nicht erstellt.",
+    api_cancelled: "API-Aufruf abgebrochen.",
+    forking: "Fork wird angelegt…",
+    fork_cancelled: "Repository wurde nicht geforkt.",
+    deleting: "Wird gelöscht…",
+    delete_cancelled: "Repository wurde nicht gelöscht.",
+    uploading: "Wird hochgeladen…",
+    upload_cancelled: "Nichts hochgeladen.",
+    outcome_approved: "Genehmigt!",
+    outcome_bypassed: |reason| format!("Genehmigung übergangen ({reason})."),
+    outcome_auto_approved: |reason| format!("Per Richtlinie genehmigt ({reason})."),
+    repo_trusted: |repo| format!("{repo} gilt ab jetzt als vertrauenswürdig."),
+    allowing_all: |severity, until| {
+        format!("Alles bis Schweregrad {severity} läuft bis {until} ohne Nachfrage.")
+    },
+    allowing_similar: |class, repo, until| {
+        format!("Weitere gh {class} auf {repo} laufen bis {until} ohne Nachfrage.")
+    },
+    delegated_by: |by, to| format!("Von {by} an {to} abgegeben."),
+    api_rule_denied: "Von einer [[api_rule]] in der Konfiguration abgelehnt.",
+    denied: |rule| format!("Von diesem Rechner nie erlaubt ([api] deny \"{rule}\")."),
+    outcome_rejected: "Abgelehnt.",
+    outcome_timed_out: |timeout| format!("Zeitlimit überschritten ({timeout})."),
+    outcome_cancelled: "Zurückgezogen.",
+    locked: |since, rejections, source| {
+        format!(
+            "🔒  gh-guard ist seit {since} gesperrt, nach {rejections} Ablehnungen in Folge \
+             von {source}."
+        )
+    },
+    locked_bypasses: |since, bypasses, source| {
+        format!(
+            "🔒  gh-guard ist seit {since} gesperrt, nach {bypasses} Umgehungen an einem Tag \
+             von {source}."
+        )
+    },
+    bypass_budget_spent: |per_day| {
+        format!("Umgehung verweigert: nur {per_day} innerhalb eines Tages erlaubt.")
+    },
+    bypass_off: "Umgehung verweigert: Die Konfiguration hat keinen Abschnitt [bypass].",
+    locked_hint: "Ursache prüfen, dann `gh-guard unlock` ausführen und auf dem Handy genehmigen.",
+    now_locked: "🔒  gh-guard ist jetzt gesperrt.",
+    quiet_rejected: "Während der Ruhezeit automatisch abgelehnt.",
+    sms_needs_more:
+        "Automatisch abgelehnt: Das braucht mehrere Genehmigende, eine SMS hat nur einen.",
+    deletion_needs_card:
+        "Automatisch abgelehnt: Ein Repository zu löschen braucht immer eine Genehmigung auf dem Handy.",
+    readonly_refused: |since| {
+        format!(
+            "🚫  Nur-Lese-Modus (seit {since}): Befehle, die auf GitHub etwas ändern, werden \
+             verweigert. `gh-guard readonly off` hebt ihn nach Genehmigung auf dem Handy auf."
+        )
+    },
+    dry_run_heading: "Probelauf — nichts geht an Telegram, gh läuft nicht.",
+    label_command: "Befehl",
+    label_decision: "Ergebnis",
+    label_repo: "Repo",
+    label_severity: "Schweregrad",
+    label_topic: "Thema",
+    label_on_call: "Dienst",
+    label_quiet: "Ruhezeit",
+    label_file: "Datei",
+    label_button: "Knopf",
+    dry_run_ask: |approvals, timeout| {
+        if approvals > 1 {
+            format!("{approvals} Genehmigungen über Telegram anfragen (Zeitlimit {timeout})")
+        } else {
+            format!("Genehmigung über Telegram anfragen (Zeitlimit {timeout})")
+        }
+    },
+    dry_run_run: |reason| format!("gh ohne Nachfrage ausführen ({reason})"),
+    dry_run_refuse: |reason| format!("ohne Nachfrage verweigern ({reason})"),
+    dry_run_passthrough: "an gh durchreichen",
+    hook_allowed: "Von gh-guard erlaubt.",
+    hook_denied: |command, why| format!("gh-guard hat `{command}` nicht erlaubt: {why}."),
+    title_mismatch: |pattern| format!("Der PR-Titel passt nicht zu title_pattern {pattern}."),
+    ci_failing_rejected: "Automatisch abgelehnt: CI schlägt auf dem Head-Branch fehl.",
+    ci_rejected: "Unter CI automatisch abgelehnt ([ci] policy = \"reject\").",
+    ci_token_missing: "Unter CI automatisch abgelehnt: GH_GUARD_APPROVAL_TOKEN ist nicht gesetzt.",
+    ci_token_invalid:
+        "Unter CI automatisch abgelehnt: Das Token ist nicht mit [ci] public_key signiert.",
+    ci_token_expired: "Unter CI automatisch abgelehnt: Das Token ist abgelaufen.",
+    ci_token_other_repo:
+        "Unter CI automatisch abgelehnt: Das Token gilt für ein anderes Repository.",
+    outcome_ci_passthrough: "Unter CI durchgelassen.",
+    outcome_ci_token: |id| format!("Durch CI-Token {id} genehmigt."),
+    quiet_holding: |end, severity, id| {
+        format!(
+            "📥  Ruhezeit bis {end} — Anfrage mit Schweregrad {severity} eingereiht als {id}."
+        )
+    },
+    rate_limited: |count, repo, window| {
+        format!("❌  Limit: {count} Genehmigungen für {repo} in den letzten {window} min.")
+    },
+    queued: |id| format!("📥  Telegram ist nicht erreichbar — eingereiht als {id}."),
+    queued_hint: "`gh-guard daemon` sendet sie zur Genehmigung und führt gh danach aus.",
+    not_locked: "gh-guard ist nicht gesperrt.",
+    sending_unlock: "Entsperranfrage wird an Telegram gesendet…",
+    unlocked: "✅  Entsperrt.",
+    unlock_rejected: "❌  Entsperren abgelehnt. gh-guard bleibt gesperrt.",
+    unlock_timed_out: "⏱   Entsperranfrage abgelaufen. gh-guard bleibt gesperrt.",
+    unlock_cancelled: "🚫  Entsperren abgebrochen. gh-guard bleibt gesperrt.",
+    sending_ci_token: "Tokenanfrage wird an Telegram gesendet…",
+    ci_token_rejected: "❌  Tokenanfrage abgelehnt.",
+    ci_token_timed_out: "⏱   Tokenanfrage abgelaufen.",
+    ci_token_cancelled: "🚫  Tokenanfrage abgebrochen.",
+    ci_public_key: |key| {
+        format!(
+            "Tokens werden gegen diesen Schlüssel geprüft; in CI als [ci] public_key \
+             setzen:\n  {key}"
+        )
+    },
+    readonly_on: "🚫  Nur-Lese-Modus an. Ändernde Befehle werden verweigert, bis \
+                  `gh-guard readonly off` auf dem Handy genehmigt ist.",
+    readonly_status_on: |since| format!("Nur-Lese-Modus ist an (seit {since})."),
+    readonly_status_off: "Nur-Lese-Modus ist aus.",
+    sending_readonly_off: "Anfrage zum Verlassen des Nur-Lese-Modus wird an Telegram gesendet…",
+    readonly_off: "✅  Nur-Lese-Modus aus.",
+    readonly_off_rejected: "❌  Abgelehnt. Nur-Lese-Modus bleibt an.",
+    readonly_off_timed_out: "⏱   Abgelaufen. Nur-Lese-Modus bleibt an.",
+    readonly_off_cancelled: "🚫  Abgebrochen. Nur-Lese-Modus bleibt an.",
+};
+
+/// The language in effect: `language` from the config file, else the
+/// locale (`LC_ALL`, `LC_MESSAGES`, `LANG`), else English, with the
+/// file's `[buttons]` applied. Resolved once.
+pub fn text() -> &'static Text {
+    static TEXT: OnceLock<&'static Text> = OnceLock::new();
+    TEXT.get_or_init(|| {
+        // The file alone: resolving the profile here would happen before the
+        // guarded command's repository is known.
+        let settings = crate::settings::load_file().ok();
+        let language = settings.as_ref().and_then(|s| s.language.as_deref());
+        let base = match language {
+            Some(lang) => lookup(lang).unwrap_or_else(|| {
+                tracing::warn!(language = %lang, "unsupported language, using English");
+                &EN
+            }),
+            None => locale().as_deref().and_then(lookup).unwrap_or(&EN),
+        };
+        match settings {
+            Some(s) if !s.buttons.is_empty() => Box::leak(Box::new(customized(base, s.buttons))),
+            _ => base,
+        }
+    })
+}
+
+/// `base` with the labels and icons `[buttons]` replaces.
+fn customized(base: &Text, buttons: crate::settings::Buttons) -> Text {
+    let keep = |own: Option<String>, built_in: &'static str| -> &'static str {
+        own.map_or(built_in, |s| String::leak(s))
+    };
+    Text {
+        approve: keep(buttons.approve, base.approve),
+        reject: keep(buttons.reject, base.reject),
+        approved: keep(buttons.approved, base.approved),
+        rejected: keep(buttons.rejected, base.rejected),
+        // Button labels are plain text; the icons go into card HTML.
+        pr_card_icon: keep(
+            buttons.pr_icon.as_deref().map(escape_html),
+            base.pr_card_icon,
+        ),
+        api_card_icon: keep(
+            buttons.api_icon.as_deref().map(escape_html),
+            base.api_card_icon,
+        ),
+        ..base.clone()
+    }
+}
+
+/// The first locale variable set, as POSIX does. Unit tests ignore it, as
+/// they do the config file.
+fn locale() -> Option<String> {
+    if cfg!(test) {
+        return None;
+    }
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.is_empty())
+}
+
+/// The table for a language tag or locale name: `de`, `de-AT`,
+/// `de_DE.UTF-8`, …
+pub fn lookup(tag: &str) -> Option<&'static Text> {
+    let lang = tag.split(['_', '-', '.', '@']).next()?;
+    match lang.to_ascii_lowercase().as_str() {
+        "en" | "c" | "posix" => Some(&EN),
+        "de" => Some(&DE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_names_and_tags() {
+        for tag in ["de", "DE", "de-AT", "de_DE.UTF-8", "de_CH@euro"] {
+            assert!(std::ptr::eq(lookup(tag).unwrap(), &DE), "{tag}");
+        }
+        for tag in ["en_US.UTF-8", "C", "POSIX", "C.UTF-8"] {
+            assert!(std::ptr::eq(lookup(tag).unwrap(), &EN), "{tag}");
+        }
+        assert!(lookup("fr_FR").is_none());
+        assert!(lookup("").is_none());
+    }
+
+    #[test]
+    fn buttons_replace_labels_and_icons() {
+        let buttons = crate::settings::Buttons {
+            approve: Some("OK-7342".to_string()),
+            pr_icon: Some(String::new()),
+            api_icon: Some("<!>".to_string()),
+            ..Default::default()
+        };
+        let t = customized(&DE, buttons);
+        assert_eq!(t.approve, "OK-7342");
+        assert_eq!(t.reject, DE.reject);
+        assert_eq!(t.pr_card_icon, "");
+        assert_eq!(t.api_card_icon, "&lt;!&gt;");
+        assert_eq!(t.pr_card_heading, DE.pr_card_heading);
+    }
+}
@@ -0,0 +1,58 @@
+//! The GitHub account a token acts as, asked of `GET /user` once per token
+//! and remembered, so the approver sees who a PR or API call will come from
+//! before a PAT of the wrong account does any harm.
+
+use crate::state;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const STATE_FILE: &str = "identities.json";
+
+/// Logins by token fingerprint, as in `pat_expiry.json`.
+type Known = BTreeMap<String, String>;
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+/// `token`'s login, from the cache or else from GitHub. `None` when GitHub
+/// cannot be asked or does not say, as for GitHub App installation tokens.
+pub fn login(token: &str) -> Option<String> {
+    let fingerprint = crate::expiry::fingerprint(token);
+    if let Some(login) = state::load::<Known>(STATE_FILE).remove(&fingerprint) {
+        return Some(login);
+    }
+    let login = match query(token) {
+        Ok(login) => login,
+        Err(e) => {
+            tracing::debug!(error = %e, "cannot tell which account the token belongs to");
+            return None;
+        }
+    };
+    let remembered = state::update(STATE_FILE, |known: &mut Known| {
+        known.insert(fingerprint, login.clone());
+    });
+    if let Err(e) = remembered {
+        tracing::debug!(error = %e, "cannot remember the token's account");
+    }
+    Some(login)
+}
+
+fn query(token: &str) -> Result<String> {
+    let url = crate::host::api_url("/user");
+    let user: User = crate::http::builder(&url)?
+        .timeout_connect(Duration::from_secs(3))
+        .timeout(Duration::from_secs(5))
+        .build()
+        .get(&url)
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .call()
+        .map_err(|e| anyhow!("GitHub API: {e}"))?
+        .into_json()?;
+    Ok(user.login)
+}
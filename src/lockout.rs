@@ -0,0 +1,182 @@
+use crate::i18n::Text;
+use crate::notify::escape_html;
+use crate::settings::{Bypass, Lockout};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const STATE_FILE: &str = "lockout.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LockState {
+    /// Consecutive rejections per "host/agent" source.
+    streaks: HashMap<String, u32>,
+    /// Set once a streak hits the threshold; cleared by `gh-guard unlock`.
+    locked: Option<Lock>,
+    /// Unix seconds of each `--guard-bypass` within the last day.
+    #[serde(default)]
+    bypasses: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Lock {
+    pub source: String,
+    pub since: String,
+    /// Rejections in a row, or for [`LockCause::Bypasses`] bypasses within
+    /// a day, that led to the lock.
+    pub rejections: u32,
+    #[serde(default)]
+    pub cause: LockCause,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockCause {
+    #[default]
+    Rejections,
+    /// `--guard-bypass` used more often than `[bypass] per_day` allows.
+    Bypasses,
+}
+
+impl Lock {
+    fn new(source: &str, count: u32, cause: LockCause) -> Lock {
+        Lock {
+            source: source.to_string(),
+            since: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            rejections: count,
+            cause,
+        }
+    }
+
+    /// Why guarded commands are refused, for the terminal.
+    pub fn refusal(&self, t: &Text) -> String {
+        let locked = match self.cause {
+            LockCause::Rejections => t.locked,
+            LockCause::Bypasses => t.locked_bypasses,
+        };
+        locked(&self.since, self.rejections, &self.source)
+    }
+
+    /// The approver's notice that gh-guard just locked (Telegram HTML).
+    pub fn note(&self, t: &Text) -> String {
+        let note = match self.cause {
+            LockCause::Rejections => t.lockout_note,
+            LockCause::Bypasses => t.bypass_lock_note,
+        };
+        note(self.rejections, &escape_html(&self.source))
+    }
+
+    /// The card asking to lift the lock (Telegram HTML).
+    pub fn unlock_card(&self, t: &Text) -> String {
+        let card = match self.cause {
+            LockCause::Rejections => t.unlock_card,
+            LockCause::Bypasses => t.unlock_card_bypasses,
+        };
+        card(
+            &escape_html(&self.since),
+            self.rejections,
+            &escape_html(&self.source),
+        )
+    }
+}
+
+/// The active lock, if gh-guard is in locked mode.
+pub fn current() -> Option<Lock> {
+    crate::state::load::<LockState>(STATE_FILE).locked
+}
+
+/// Count a rejection from `source`. Returns the new lock when this rejection
+/// pushed the streak over the configured threshold.
+pub fn record_rejection(cfg: Option<&Lockout>, source: &str) -> Result<Option<Lock>> {
+    let Some(cfg) = cfg else {
+        return Ok(None);
+    };
+    crate::state::update(STATE_FILE, |st: &mut LockState| {
+        let streak = st.streaks.entry(source.to_string()).or_default();
+        *streak += 1;
+        let rejections = *streak;
+        if rejections < cfg.max_consecutive_rejections || st.locked.is_some() {
+            return None;
+        }
+        let lock = Lock::new(source, rejections, LockCause::Rejections);
+        st.locked = Some(lock.clone());
+        Some(lock)
+    })
+}
+
+/// An approval breaks the streak for `source`.
+pub fn record_approval(cfg: Option<&Lockout>, source: &str) -> Result<()> {
+    if cfg.is_none() {
+        return Ok(());
+    }
+    crate::state::update(STATE_FILE, |st: &mut LockState| {
+        st.streaks.remove(source);
+    })
+}
+
+/// Count a `--guard-bypass` from `source`. Returns the new lock, and the
+/// bypass must not go ahead, when it is one more than `[bypass] per_day`
+/// within the last 24 hours.
+pub fn record_bypass(cfg: &Bypass, source: &str) -> Result<Option<Lock>> {
+    let now = crate::mailbox::now();
+    crate::state::update(STATE_FILE, |st: &mut LockState| {
+        st.bypasses.retain(|at| at + 24 * 60 * 60 > now);
+        st.bypasses.push(now);
+        let count = st.bypasses.len() as u32;
+        if count <= cfg.per_day || st.locked.is_some() {
+            return None;
+        }
+        let lock = Lock::new(source, count, LockCause::Bypasses);
+        st.locked = Some(lock.clone());
+        Some(lock)
+    })
+}
+
+/// Whether one more bypass now would go over `[bypass] per_day`, without
+/// counting it.
+pub fn bypass_budget_spent(cfg: &Bypass) -> bool {
+    let now = crate::mailbox::now();
+    let st: LockState = crate::state::load(STATE_FILE);
+    let recent = st.bypasses.iter().filter(|at| *at + 24 * 60 * 60 > now);
+    recent.count() as u32 >= cfg.per_day
+}
+
+/// Leave locked mode and reset all streaks.
+pub fn clear() -> Result<()> {
+    crate::state::update(STATE_FILE, |st: &mut LockState| {
+        *st = LockState::default();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approvals_alongside_a_bypass_keep_its_lock() {
+        let agents: Vec<_> = (0..8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let lockout = Lockout {
+                        max_consecutive_rejections: u32::MAX,
+                    };
+                    let source = format!("host/agent-{i}");
+                    for _ in 0..25 {
+                        record_rejection(Some(&lockout), &source).unwrap();
+                        record_approval(Some(&lockout), &source).unwrap();
+                    }
+                })
+            })
+            .collect();
+        let lock = record_bypass(&Bypass { per_day: 0 }, "host/bypasser").unwrap();
+        for agent in agents {
+            agent.join().unwrap();
+        }
+
+        assert!(lock.is_some());
+        let locked = current().expect("still locked");
+        assert_eq!(locked.cause, LockCause::Bypasses);
+        clear().unwrap();
+        assert!(current().is_none());
+    }
+}
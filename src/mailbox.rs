@@ -0,0 +1,161 @@
+//! Telegram updates one gh-guard process received for another. Of the
+//! processes waiting for a decision, only the one holding the poll lock
+//! calls `getUpdates` (two polls would cut each other off with 409
+//! Conflict); a tap on another process's card is left here for it, and
+//! `/panic` for all of them.
+
+use crate::state;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const STATE_FILE: &str = "mailbox.json";
+
+/// Updates no one collected (their process is gone) are dropped after this.
+const MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// How often a waiting process looks for updates left for it.
+const CHECK_EVERY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Mailbox {
+    held: Vec<Held>,
+    /// `getUpdates` offset the last poller reached, so the next one does
+    /// not see the same updates again.
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Held {
+    /// Request the update belongs to; `None` for every waiting process.
+    to: Option<String>,
+    /// Unix seconds when it was received.
+    at: u64,
+    update: Value,
+}
+
+impl Held {
+    fn is_for(&self, request_id: &str, since: u64) -> bool {
+        match &self.to {
+            Some(to) => to == request_id,
+            None => self.at >= since,
+        }
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Held by the one process that polls Telegram for everyone; dropping it
+/// lets another take over.
+pub struct Poller {
+    _lock: std::fs::File,
+}
+
+/// The poll lock, unless another process holds it.
+pub fn try_poll() -> Option<Poller> {
+    let dir = state::dir();
+    std::fs::create_dir_all(&dir).ok()?;
+    let lock = std::fs::File::create(dir.join("poll.lock")).ok()?;
+    lock.try_lock().ok()?;
+    Some(Poller { _lock: lock })
+}
+
+/// The `getUpdates` offset the last poller reached.
+pub fn offset() -> Option<i64> {
+    state::load::<Mailbox>(STATE_FILE).offset
+}
+
+/// Record the `getUpdates` offset reached; best effort.
+pub fn save_offset(offset: i64) {
+    let saved = state::update(STATE_FILE, |mailbox: &mut Mailbox| {
+        mailbox.offset = Some(mailbox.offset.map_or(offset, |o| o.max(offset)));
+    });
+    if let Err(e) = saved {
+        tracing::warn!(error = %e, "could not record the Telegram update offset");
+    }
+}
+
+/// Leave `update` for the process waiting on `to`, or with `None` for
+/// every process waiting now.
+pub fn post(to: Option<&str>, update: &Value) -> Result<()> {
+    let now = now();
+    state::update(STATE_FILE, |mailbox: &mut Mailbox| {
+        mailbox.held.retain(|h| h.at + MAX_AGE_SECS > now);
+        mailbox.held.push(Held {
+            to: to.map(str::to_string),
+            at: now,
+            update: update.clone(),
+        });
+    })
+}
+
+/// Updates left for `request_id`, and those for everyone since `since`
+/// (unix seconds). Its own are removed; the others stay for the rest.
+pub fn take(request_id: &str, since: u64) -> Vec<Value> {
+    // Looked at every second by every waiting process: lock only for news.
+    let mailbox: Mailbox = state::load(STATE_FILE);
+    if !mailbox.held.iter().any(|h| h.is_for(request_id, since)) {
+        return Vec::new();
+    }
+    let taken = state::update(STATE_FILE, |mailbox: &mut Mailbox| {
+        let mut taken = Vec::new();
+        mailbox.held.retain(|h| {
+            if h.is_for(request_id, since) {
+                taken.push(h.update.clone());
+            }
+            h.to.as_deref() != Some(request_id)
+        });
+        taken
+    });
+    taken.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "could not read relayed Telegram updates");
+        Vec::new()
+    })
+}
+
+/// Wait until something is left for `request_id`; see [`take`].
+pub async fn wait(request_id: &str, since: u64) -> Vec<Value> {
+    loop {
+        let held = take(request_id, since);
+        if !held.is_empty() {
+            return held;
+        }
+        tokio::time::sleep(CHECK_EVERY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn one_poller_at_a_time() {
+        let poller = try_poll().expect("lock is free");
+        assert!(try_poll().is_none());
+        drop(poller);
+        assert!(try_poll().is_some());
+    }
+
+    #[test]
+    fn updates_reach_their_request_once_and_broadcasts_everyone() {
+        let since = now();
+        post(Some("mb-a"), &json!({"update_id": 1})).unwrap();
+        post(None, &json!({"update_id": 2})).unwrap();
+
+        assert_eq!(
+            take("mb-a", since),
+            [json!({"update_id": 1}), json!({"update_id": 2})]
+        );
+        assert_eq!(take("mb-a", since), [json!({"update_id": 2})]);
+        assert_eq!(take("mb-b", since), [json!({"update_id": 2})]);
+        assert!(take("mb-c", since + 60).is_empty());
+    }
+}
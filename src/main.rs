@@ -1,193 +1,1290 @@
+mod agent;
+mod allow_all;
 mod api;
+mod audit;
+mod audit_sync;
+mod ci;
+mod ci_policy;
+mod cleanup;
+mod cli;
 mod config;
+mod daemon;
+mod delete;
+mod digest;
+mod error;
+mod exit;
+mod expiry;
+mod flags;
+mod fork;
 mod gh;
+mod github_app;
+mod guard;
+mod history;
+mod hook;
+mod host;
+mod http;
+mod i18n;
+mod identity;
+mod lockout;
+mod mailbox;
+mod markdown;
+mod metrics;
 mod notify;
+mod oauth;
+mod oncall;
+mod output;
+mod panic;
 mod pr;
+mod profile;
+mod queue;
+mod quiet;
+mod ratelimit;
+mod readonly;
+mod release;
+mod risk;
+mod secret_cache;
+mod secret_file;
+mod secret_pass;
+mod secret_vault;
+mod settings;
 mod setup;
+mod shim;
+mod similar;
+mod sms;
+mod state;
+mod stats;
+mod trust;
 
-use anyhow::{bail, Result};
+use anyhow::Result;
+use cli::{Cli, Command};
 use colored::Colorize;
+use error::Error;
 use notify::ApprovalResult;
 use std::process;
+use std::sync::OnceLock;
 
 fn main() {
+    init_tracing();
     if let Err(e) = run() {
-        eprintln!("{} {}", "gh-guard error:".red().bold(), e);
-        process::exit(1);
+        let err = error::find(&e);
+        // Decisions were reported where they were made.
+        if !err.is_some_and(Error::is_decision) {
+            eprintln!("{} {}", "gh-guard error:".red().bold(), e);
+            output::emit(&serde_json::json!({
+                "decision": "error",
+                "kind": err.map_or("other", Error::kind),
+                "error": format!("{e:#}"),
+            }));
+        }
+        process::exit(err.map_or(exit::ERROR, Error::exit_code));
     }
 }
 
 fn run() -> Result<()> {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let flags = cli::take_guard_flags(&mut args)?;
+    output::set_json_flag(flags.guard_json);
+    output::init_terminal(flags.guard_no_color);
+    output::set_verbosity(flags.verbosity());
+    if flags.guard_verbose {
+        log_verbose();
+    }
+    if let Some(name) = &flags.guard_profile {
+        profile::select(name);
+    }
+    if let Some(reason) = flags.guard_bypass {
+        guard::bypass(reason);
+    }
+    if flags.guard_dry_run {
+        guard::dry_run();
+    }
+    let deps = guard::Deps::live();
 
     // ── Infinite-loop guard ──────────────────────────────────────────────────
     // If gh-guard is installed as 'gh' (symlink / PATH shadow) and we call
     // the real gh after approval, we set GH_GUARD_ACTIVE so a re-entered
     // instance skips interception and goes straight to passthrough.
     if std::env::var("GH_GUARD_ACTIVE").is_ok() {
-        let token = config::get_pat().ok();
-        let code = gh::run_gh(&args, token.as_deref())?;
+        // The gh that started us has passed its token on already.
+        let inherited = host::token_env("")
+            .iter()
+            .all(|(var, _)| std::env::var_os(var).is_some());
+        let token = if inherited {
+            None
+        } else {
+            passthrough_token(&args, &deps)
+        };
+        let code = deps.gh.run(&args, token.as_deref())?;
         process::exit(code);
     }
 
+    if flags.guard_help {
+        Cli::print_help();
+        return Ok(());
+    }
+
+    // gh-guard's own subcommands
+    if args.first().is_some_and(|a| Cli::owns(a)) {
+        let cli = Cli::parse_own(&args);
+        output::init_terminal(cli.guard.guard_no_color);
+        if cli.guard.guard_verbose {
+            log_verbose();
+        }
+        if let Some(name) = &cli.profile {
+            profile::select(name);
+        }
+        return match cli.command {
+            Command::Setup { step } => setup::run(step),
+            Command::Daemon => daemon::run(),
+            Command::Log(filter) => history::run(&filter),
+            Command::Stats(opts) => stats::run(&opts),
+            Command::Panic { revoke } => panic::run(revoke),
+            Command::Unlock => handle_unlock(),
+            Command::CiToken(args) => ci_policy::run(&args),
+            Command::Readonly { action } => readonly::run(action),
+            Command::Queue { action } => queue::run(action),
+            Command::Hook => handle_hook(),
+            Command::Explain { args } => {
+                guard::dry_run();
+                process::exit(intercept(&args, &deps)?)
+            }
+        };
+    }
+
+    let code = intercept(&args, &deps)?;
+    process::exit(code);
+}
+
+/// Send a gh command line down the guarded path it belongs to. Returns the
+/// code to exit with.
+fn intercept(args: &[String], deps: &guard::Deps) -> Result<i32> {
     match args.first().map(String::as_str) {
         // No args: hand off to gh (shows gh's own help)
-        None => passthrough(&args),
-
-        // Built-in setup wizard
-        Some("setup") => setup::run(args.get(1).map(String::as_str)),
+        None => passthrough(args, deps),
 
         // PR creation with phone approval
         Some("pr") if args.get(1).map(String::as_str) == Some("create") => {
             let pr_flags: &[String] = if args.len() > 2 { &args[2..] } else { &[] };
-            handle_pr_create(pr_flags)
+            handle_pr_create(pr_flags, deps)
         }
 
-        // gh api mutations (PATCH, POST, PUT, DELETE) with phone approval
+        // gh api mutations (anything but GET and HEAD) with phone approval
         Some("api") => {
             let api_flags: &[String] = if args.len() > 1 { &args[1..] } else { &[] };
-            handle_api(api_flags)
+            handle_api(api_flags, deps)
+        }
+
+        // Forks, which can take a repository's code to another owner
+        Some("repo") if args.get(1).map(String::as_str) == Some("fork") => {
+            handle_repo_fork(&args[2..], deps)
+        }
+
+        // Deleting a repository, which cannot be undone
+        Some("repo") if args.get(1).map(String::as_str) == Some("delete") => {
+            handle_repo_delete(&args[2..], deps)
+        }
+
+        // Release assets, which --clobber replaces under everyone's feet
+        Some("release") if args.get(1).map(String::as_str) == Some("upload") => {
+            handle_release_upload(&args[2..], deps)
         }
 
         // Everything else: transparent passthrough
-        _ => passthrough(&args),
+        _ => passthrough(args, deps),
     }
 }
 
-fn handle_pr_create(raw_flags: &[String]) -> Result<()> {
-    let parsed = pr::parse_pr_args(raw_flags);
+/// Swaps the log filter once `--guard-verbose` has been seen.
+type Reload = Box<dyn Fn(tracing_subscriber::EnvFilter) + Send + Sync>;
+
+static RELOAD_FILTER: OnceLock<Reload> = OnceLock::new();
+
+/// Diagnostics go to stderr, filtered by `GH_GUARD_LOG` (same syntax as
+/// `RUST_LOG`, e.g. `GH_GUARD_LOG=debug`). Closing spans report their
+/// duration, which times each Telegram round trip. Default: warnings only.
+fn init_tracing() {
+    use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+    let filter = EnvFilter::try_from_env("GH_GUARD_LOG").unwrap_or_else(|_| EnvFilter::new("warn"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(output::colour())
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter_reloading();
+    let handle = builder.reload_handle();
+    let _ = RELOAD_FILTER.set(Box::new(move |filter| {
+        let _ = handle.reload(filter);
+    }));
+    builder.init();
+}
+
+/// `--guard-verbose`: gh-guard's own debug events and span timings, unless
+/// `GH_GUARD_LOG` already says what to show.
+fn log_verbose() {
+    if std::env::var_os("GH_GUARD_LOG").is_some() {
+        return;
+    }
+    if let Some(reload) = RELOAD_FILTER.get() {
+        reload(tracing_subscriber::EnvFilter::new("warn,gh_guard=debug"));
+    }
+}
+
+/// `gh pr create`: approval for anything but `--web`. Returns the code to
+/// exit with.
+fn handle_pr_create(raw_flags: &[String], deps: &guard::Deps) -> Result<i32> {
+    let t = i18n::text();
+    let mut parsed =
+        tracing::info_span!("parse", cmd = "pr create").in_scope(|| pr::parse_pr_args(raw_flags));
+    let mut full_args = vec!["pr".to_string(), "create".to_string()];
+    full_args.extend_from_slice(raw_flags);
 
     // --web opens a browser form; no meaningful interception possible.
     if parsed.web {
-        eprintln!("{}", "gh-guard: --web flag detected, bypassing approval flow.".yellow());
-        let mut full = vec!["pr".to_string(), "create".to_string()];
-        full.extend_from_slice(raw_flags);
-        return passthrough(&full);
+        eprintln!("{}", t.web_bypass.yellow());
+        return passthrough(&full_args, deps);
     }
 
-    // Require --title or --fill so the notification has useful content.
-    // Without them gh would open an interactive TUI we cannot intercept.
+    // Without --title or --fill gh would open its form, which we cannot
+    // intercept: ask the same questions here and pass the answers as flags.
     if !parsed.has_title && !parsed.fill {
-        bail!(
-            "gh-guard cannot intercept interactive PR creation.\n\
-             Add --title \"...\" (and optionally --body \"...\") to your command,\n\
-             or use --fill to auto-fill from commit messages."
-        );
+        if !pr::can_prompt() {
+            return Err(Error::NotInterceptable.into());
+        }
+        pr::prompt(t, &mut parsed, &mut full_args)?;
     }
 
-    let token = config::get_pat()?;
-    let tg = notify::TgConfig {
-        token: config::get_telegram_token()?,
-        chat_id: config::get_telegram_chat_id()?,
+    // With --fill, show what gh will fill in rather than that it will.
+    let filled = parsed.fill.then(|| pr::fill_preview(&parsed)).flatten();
+    let mut body_text = match &filled {
+        Some((_, body)) if parsed.body.is_none() && parsed.body_file.is_none() => body.clone(),
+        _ => pr::resolve_body(&parsed),
     };
 
-    let body_text = pr::resolve_body(&parsed);
-    let pr_title = parsed
+    // gh leaves a PR template alone once it is not prompting; fill it in
+    // when asked to, and otherwise tell the approver it went unused.
+    let settings = settings::load_file()?;
+    let mut template_note = None;
+    if body_text.trim().is_empty() {
+        if let Some((path, template)) = pr::template() {
+            if settings.pr_create.fill_template {
+                full_args.extend(["--body".to_string(), template.clone()]);
+                body_text = template;
+            } else {
+                template_note = Some((t.template_unused)(&notify::escape_html(&path)));
+            }
+        }
+    }
+    let real_title = parsed
         .title
         .as_deref()
-        .unwrap_or("(auto-fill from commits)");
-    let branch_info = pr::branch_info(parsed.base.as_deref());
-
-    eprintln!("{}", "══════════════════════════════════".cyan());
-    eprintln!("{}", " gh-guard · PR Approval Required  ".cyan().bold());
-    eprintln!("{}", "══════════════════════════════════".cyan());
-    eprintln!("  Title  : {}", pr_title.bold());
-    eprintln!("  Branch : {}", branch_info);
-    if parsed.draft {
-        eprintln!("  Mode   : {}", "draft".yellow());
-    }
-    eprintln!();
-    eprintln!("Sending to Telegram…");
-
-    let (request_id, message_id) = notify::send_approval_request(
-        &tg,
-        pr_title,
-        &body_text,
-        &branch_info,
-        parsed.draft,
-    )?;
-
-    eprintln!("Waiting for approval on Telegram (5-min timeout)…");
-
-    match notify::poll_for_approval(&tg, &request_id, message_id, 300)? {
-        ApprovalResult::Approved => {
-            eprintln!("{}", "✅  Approved! Creating PR…".green().bold());
-            let mut full_args = vec!["pr".to_string(), "create".to_string()];
-            full_args.extend_from_slice(raw_flags);
-            let code = gh::run_gh(&full_args, Some(&token))?;
-            process::exit(code);
+        .or(filled.as_ref().map(|(title, _)| title.as_str()));
+    let pr_title = real_title.unwrap_or(t.auto_fill_title);
+    // A title gh has yet to work out cannot be checked.
+    let title_pattern = settings.pr_create.title_pattern.as_deref();
+    let title_mismatch = match (title_pattern, real_title) {
+        (Some(pattern), Some(title)) if !pr::title_matches(pattern, title)? => Some(pattern),
+        _ => None,
+    };
+    let named_repo = pr::named_repo(parsed.repo.as_deref());
+    let branch_info = pr::branch_info(&parsed, named_repo.as_deref());
+    // The checkout's default branch says nothing about another repository's.
+    let default_branch = named_repo.is_none().then(pr::default_branch).flatten();
+    let target = named_repo.or_else(pr::current_repo);
+    // Before the links and the CI lookup read settings or the PAT, so both
+    // come from the target's profile.
+    if let Some(target) = &target {
+        profile::hint_repo(target);
+    }
+    let head = parsed.head.clone().or_else(pr::current_branch);
+    let mut links = Vec::new();
+    if let Some(target) = &target {
+        if let Some(head) = &head {
+            links.push(notify::Link {
+                text: t.view_compare.to_string(),
+                url: pr::compare_url(target, parsed.base.as_deref(), head),
+            });
         }
-        ApprovalResult::Rejected => {
-            eprintln!("{}", "❌  Rejected. PR not created.".red().bold());
-            process::exit(1);
+        links.push(notify::Link {
+            text: t.open_repo.to_string(),
+            url: host::web_url(target),
+        });
+    }
+    // With --head the checkout is not what the PR is made from.
+    let checkout = parsed.head.is_none();
+    let mut checkout_warnings = match checkout.then(pr::push_state).flatten() {
+        Some(pr::PushState::NotPushed { branch }) => vec![(t.not_pushed)(&branch)],
+        Some(pr::PushState::Pushed {
+            upstream,
+            ahead,
+            behind,
+        }) => {
+            let mut warnings = Vec::new();
+            if ahead > 0 {
+                warnings.push((t.commits_unpushed)(ahead, &upstream));
+            }
+            if behind > 0 {
+                warnings.push((t.behind_upstream)(behind, &upstream));
+            }
+            warnings
         }
-        ApprovalResult::Timeout => {
-            eprintln!("{}", "⏱   Timed out (5 min). PR not created.".yellow());
-            process::exit(1);
+        None => Vec::new(),
+    };
+    let uncommitted = checkout.then(pr::uncommitted_files).flatten();
+    if let Some(count) = uncommitted.filter(|n| *n > 0) {
+        checkout_warnings.push((t.uncommitted)(count));
+    }
+    let ci = match (&target, &head) {
+        (Some(target), Some(head))
+            if settings.pr_create.ci_status || settings.pr_create.reject_failing_ci =>
+        {
+            ci_state(deps, target, head)
+        }
+        _ => None,
+    };
+    let repo = target.unwrap_or_else(|| "(unknown repo)".to_string());
+    let severity = risk::classify_pr(&parsed);
+
+    let mut meta = parsed.meta(t);
+    if let Some(ci) = ci {
+        let state = match ci {
+            ci::CiState::Passing => t.ci_passing,
+            ci::CiState::Failing => t.ci_failing,
+            ci::CiState::Pending => t.ci_pending,
+            ci::CiState::None => t.ci_none,
+        };
+        meta.push((t.ci, state.to_string()));
+    }
+
+    let mut vars = notify::CardVars::new(pr_title, &repo, severity).body(&body_text);
+    vars.branch = Some(&branch_info);
+    vars.draft = parsed.draft;
+    vars.reviewers = &parsed.reviewers;
+    vars.assignees = &parsed.assignees;
+    vars.labels = &parsed.labels;
+    vars.milestone = parsed.milestone.as_deref();
+    vars.ci = ci.map(ci::CiState::as_str);
+    let mut card = notify::card_or(settings.templates.pr_create.as_deref(), vars, || {
+        notify::pr_card(t, pr_title, &body_text, &branch_info, parsed.draft, &meta)
+    });
+    // Notes under the card, and the files sent in reply to it.
+    let mut notes: Vec<String> = checkout_warnings
+        .iter()
+        .map(|warning| format!("⚠️ <i>{}</i>", notify::escape_html(warning)))
+        .collect();
+    notes.extend(template_note);
+    if let Some(pattern) = title_mismatch {
+        notes.push((t.title_mismatch_note)(&notify::escape_html(pattern)));
+    }
+    let mut documents = Vec::new();
+    if let Some(doc) = notify::body_document(&body_text) {
+        notes.push((t.document_note)(&doc.name));
+        documents.push(doc);
+    }
+    let policy = pr::branch_policy(
+        &settings.branch_policy,
+        parsed.base.as_deref(),
+        default_branch.as_deref(),
+    );
+    if let Some(policy) = policy {
+        tracing::debug!(base = ?parsed.base, ?policy, "branch policy applies");
+        if let Some(needed) = policy.approvals.filter(|n| *n > 1) {
+            notes.push((t.approvals_note)(needed));
+        }
+        if policy.attach_diff {
+            match pr::diff(&parsed).filter(|d| !d.is_empty()) {
+                Some(text) => {
+                    let doc = notify::Document {
+                        name: "diff.patch".to_string(),
+                        text,
+                    };
+                    notes.push((t.diff_note)(&doc.name));
+                    documents.push(doc);
+                }
+                None => notes.push(t.diff_missing.to_string()),
+            }
+        }
+    }
+    for note in notes {
+        card.push_str("\n\n");
+        card.push_str(&note);
+    }
+
+    let req = guard::Request {
+        kind: guard::Kind::PrCreate,
+        gh_args: full_args,
+        repo,
+        severity,
+        summary: format!("PR “{pr_title}”"),
+        card,
+        documents,
+        links,
+        timeout_secs: policy.and_then(|p| p.approval_timeout_secs),
+        approvals: policy.and_then(|p| p.approvals),
+        queued: None,
+        auto_approve: None,
+        always_ask: false,
+        confirm: None,
+    };
+    deny_as_api(&req, deps, "POST", "/pulls")?;
+    if let Some(pattern) = title_mismatch {
+        let why = (t.title_mismatch)(pattern);
+        if settings.pr_create.title_check == settings::TitleCheck::Block {
+            return Err(guard::refuse(&req, "title pattern", &why));
+        }
+        eprintln!("{}", why.yellow());
+    }
+    if ci == Some(ci::CiState::Failing) && settings.pr_create.reject_failing_ci {
+        return Err(guard::refuse(&req, "ci failing", t.ci_failing_rejected));
+    }
+    for warning in &checkout_warnings {
+        eprintln!("{}", warning.yellow());
+    }
+
+    let banner = || {
+        let width = meta
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .fold(6, usize::max);
+        heading(t.pr_banner);
+        banner_line(t.label_title, width, pr_title.bold());
+        banner_line(t.label_branch, width, &branch_info);
+        for (label, value) in &meta {
+            banner_line(label, width, value);
+        }
+        if parsed.draft {
+            banner_line(t.label_mode, width, t.mode_draft.yellow());
+        }
+    };
+
+    guard::run(req, deps, banner)
+}
+
+/// The head branch's CI state for the card, or `None` (logged) when GitHub
+/// cannot say, e.g. for a branch that is not pushed yet. Call once `repo`
+/// is hinted: the PAT is looked up, and the profile settled, here.
+fn ci_state(deps: &guard::Deps, repo: &str, head: &str) -> Option<ci::CiState> {
+    let state = deps
+        .credentials
+        .pat()
+        .and_then(|token| ci::state(&token, repo, head));
+    match state {
+        Ok(state) => Some(state),
+        Err(e) => {
+            tracing::warn!(error = %e, repo, head, "could not look up CI state");
+            None
         }
     }
 }
 
-fn handle_api(api_flags: &[String]) -> Result<()> {
-    let parsed = api::parse_api_args(api_flags);
+/// `gh api`: approval for mutating methods, passthrough otherwise. Returns
+/// the code to exit with.
+fn handle_api(api_flags: &[String], deps: &guard::Deps) -> Result<i32> {
+    let t = i18n::text();
+    let mut parsed =
+        tracing::info_span!("parse", cmd = "api").in_scope(|| api::parse_api_args(api_flags));
+    let mut full_args = vec!["api".to_string()];
+    full_args.extend_from_slice(api_flags);
+    // Before anything loads settings, so reads too get that host's profile.
+    if let Some(host) = &parsed.hostname {
+        profile::hint_host(host);
+    }
 
     // GET / HEAD are read-only — pass straight through.
     if !parsed.is_mutating {
-        let mut full = vec!["api".to_string()];
-        full.extend_from_slice(api_flags);
-        return passthrough(&full);
+        return passthrough(&full_args, deps);
     }
 
-    let token = config::get_pat()?;
-    let tg = notify::TgConfig {
-        token: config::get_telegram_token()?,
-        chat_id: config::get_telegram_chat_id()?,
+    // Show (and check policy against) what gh will call, not its template,
+    // spelled as GitHub reads it.
+    if let Some(endpoint) = &mut parsed.endpoint {
+        *endpoint = api::normalize_endpoint(&api::expand_placeholders(endpoint));
+    }
+    api::read_field_files(&mut parsed);
+    let endpoint_display = parsed.endpoint.as_deref().unwrap_or("(unknown)");
+    let target = api::repo_from_endpoint(endpoint_display).or_else(|| pr::target_repo(None));
+    if let Some(target) = &target {
+        profile::hint_repo(target);
+    }
+    let links = target
+        .iter()
+        .map(|repo| notify::Link {
+            text: t.open_repo.to_string(),
+            url: match &parsed.hostname {
+                Some(host) => format!("https://{host}/{repo}"),
+                None => host::web_url(repo),
+            },
+        })
+        .collect();
+    let repo = target.unwrap_or_else(|| "(unknown repo)".to_string());
+    let severity = risk::classify_api(&parsed);
+    let summary = format!("{} {endpoint_display}", parsed.method);
+    let webhook = api::is_webhook(&api::endpoint_segments(endpoint_display));
+    let protection = api::protection_change(&parsed);
+    let meta = api_meta(t, &parsed, webhook, protection.as_ref());
+
+    let fields = parsed.shown_fields();
+    let settings = settings::load_file()?;
+    let approvals = if protection.is_some() {
+        Some(settings.strictest_approvals())
+    } else {
+        webhook.then_some(settings.api.webhook_approvals)
+    };
+    let mut vars = notify::CardVars::new(&summary, &repo, severity).fields(&fields);
+    vars.host = parsed.hostname.as_deref();
+    let mut card = notify::card_or(settings.templates.api.as_deref(), vars, || {
+        notify::api_card(
+            t,
+            &parsed.method,
+            parsed.endpoint.as_deref(),
+            parsed.hostname.as_deref(),
+            &fields,
+            &meta,
+        )
+    });
+    if let Some(write) = api::workflow_write(&parsed) {
+        card.push_str("\n\n");
+        card.push_str(&notify::workflow_snippet(
+            t,
+            &write.path,
+            write.content.as_deref(),
+        ));
+    }
+    let document = notify::fields_document(&fields);
+    if let Some(doc) = &document {
+        card.push_str("\n\n");
+        card.push_str(&(t.document_note)(&doc.name));
+    }
+    if let Some(needed) = approvals.filter(|n| *n > 1) {
+        card.push_str("\n\n");
+        card.push_str(&(t.approvals_note)(needed));
+    }
+
+    // Typed out by the approver first, as GitHub asks of whoever deletes.
+    let deleted = api::deleted_repo(&parsed);
+    let mut req = guard::Request {
+        kind: guard::Kind::Api,
+        gh_args: full_args,
+        repo,
+        severity,
+        summary,
+        card,
+        documents: document.into_iter().collect(),
+        links,
+        timeout_secs: None,
+        approvals,
+        queued: None,
+        auto_approve: None,
+        always_ask: api::is_membership(&api::endpoint_segments(endpoint_display))
+            || deleted.is_some(),
+        confirm: deleted,
+    };
+    if let Some(endpoint) = &parsed.endpoint {
+        if let Some(rule) = api::denied(&settings.api.deny, &parsed.method, endpoint) {
+            return Err(guard::deny(&req, deps, rule));
+        }
+    }
+    let rule = match &parsed.endpoint {
+        Some(endpoint) => api::matching_rule(&settings.api_rule, &parsed.method, endpoint)?,
+        None => None,
     };
+    match rule.map(|r| r.action) {
+        Some(settings::ApiAction::Deny) => {
+            return Err(guard::refuse(&req, "api rule", t.api_rule_denied));
+        }
+        Some(settings::ApiAction::AutoApprove) => req.auto_approve = Some("api rule".to_string()),
+        Some(settings::ApiAction::Require) => req.always_ask = true,
+        None => {}
+    }
 
-    let endpoint_display = parsed.endpoint.as_deref().unwrap_or("(unknown)");
+    let banner = || {
+        heading(t.api_banner);
+        banner_line(t.label_method, 8, parsed.method.yellow().bold());
+        banner_line(t.label_endpoint, 8, endpoint_display);
+        for (label, value) in &meta {
+            banner_line(label, 8, value.bold());
+        }
+        if let Some(host) = &parsed.hostname {
+            banner_line(t.host, 8, host);
+        }
+        // A file's contents are for the card; here its name and size do.
+        for f in fields.iter().filter_map(|f| f.lines().next()) {
+            if output::plain() {
+                eprintln!("{}: {f}", t.label_field);
+            } else {
+                eprintln!("    {}", f.dimmed());
+            }
+        }
+    };
 
-    eprintln!("{}", "══════════════════════════════════".cyan());
-    eprintln!("{}", " gh-guard · API Approval Required ".cyan().bold());
-    eprintln!("{}", "══════════════════════════════════".cyan());
-    eprintln!("  Method   : {}", parsed.method.yellow().bold());
-    eprintln!("  Endpoint : {}", endpoint_display);
-    for f in &parsed.fields {
-        eprintln!("    {}", f.dimmed());
+    guard::run(req, deps, banner)
+}
+
+/// `gh repo fork`: approval unless the fork stays with the source's owner
+/// and `[repo_fork] auto_approve_same_org` says so. Returns the code to
+/// exit with.
+fn handle_repo_fork(fork_flags: &[String], deps: &guard::Deps) -> Result<i32> {
+    let t = i18n::text();
+    let parsed = tracing::info_span!("parse", cmd = "repo fork")
+        .in_scope(|| fork::parse_fork_args(fork_flags));
+    let mut full_args = vec!["repo".to_string(), "fork".to_string()];
+    full_args.extend_from_slice(fork_flags);
+    if parsed.help {
+        return passthrough(&full_args, deps);
     }
-    eprintln!();
-    eprintln!("Sending to Telegram…");
 
-    let (request_id, message_id) = notify::send_api_approval_request(
-        &tg,
-        &parsed.method,
-        parsed.endpoint.as_deref(),
-        &parsed.fields,
-    )?;
+    let target = pr::target_repo(parsed.repo.as_deref());
+    // Before the PAT is looked up, so it is the source's profile's.
+    if let Some(target) = &target {
+        profile::hint_repo(target);
+    }
+    let source = target.unwrap_or_else(|| "(unknown repo)".to_string());
+    // gh forks to the authenticated user unless told an organisation.
+    let token = deps.credentials.pat().ok();
+    let owner = parsed.org.clone().or_else(|| {
+        token
+            .as_deref()
+            .and_then(|token| deps.credentials.login(token))
+    });
+    // "@me" when the login cannot be looked up, as gh itself says.
+    let destination = parsed.destination(&source, owner.as_deref().unwrap_or("@me"));
+    let same_owner = owner
+        .as_deref()
+        .is_some_and(|owner| fork::same_owner(&source, owner));
+    // Only a fork leaving its owner needs to know what it takes along.
+    let private = match token.as_deref().filter(|_| !same_owner) {
+        Some(token) => match fork::is_private(token, &source) {
+            Ok(private) => Some(private),
+            Err(e) => {
+                tracing::warn!(error = %e, repo = source, "could not look up visibility");
+                None
+            }
+        },
+        None => None,
+    };
+    let severity = risk::classify_fork(same_owner, private);
+    let summary = format!("fork {source} → {destination}");
+
+    let settings = settings::load_file()?;
+    let vars = notify::CardVars::new(&summary, &source, severity);
+    let mut card = notify::card_or(None, vars, || {
+        notify::fork_card(t, &source, &destination, private, parsed.clone)
+    });
+    if private == Some(true) {
+        if let Some((owner, _)) = source.split_once('/') {
+            card.push_str("\n\n");
+            card.push_str(&(t.fork_leaves_owner)(&notify::escape_html(owner)));
+        }
+    }
+
+    let req = guard::Request {
+        kind: guard::Kind::RepoFork,
+        gh_args: full_args,
+        links: vec![notify::Link {
+            text: t.open_repo.to_string(),
+            url: host::web_url(&source),
+        }],
+        repo: source.clone(),
+        severity,
+        summary,
+        card,
+        documents: Vec::new(),
+        timeout_secs: None,
+        approvals: None,
+        queued: None,
+        auto_approve: (same_owner && settings.repo_fork.auto_approve_same_org)
+            .then(|| "same owner".to_string()),
+        always_ask: false,
+        confirm: None,
+    };
+    deny_as_api(&req, deps, "POST", "/forks")?;
 
-    eprintln!("Waiting for approval on Telegram (5-min timeout)…");
+    let banner = || {
+        heading(t.fork_banner);
+        let visibility = match private {
+            Some(true) => format!(" ({})", t.private).red().bold().to_string(),
+            Some(false) => format!(" ({})", t.public),
+            None => String::new(),
+        };
+        banner_line(t.fork_source, 6, format!("{source}{visibility}"));
+        banner_line(t.fork_destination, 6, destination.bold());
+    };
 
-    match notify::poll_for_approval(&tg, &request_id, message_id, 300)? {
+    guard::run(req, deps, banner)
+}
+
+/// `gh repo delete`: approval, always, once the approver has typed the
+/// repository's name. Returns the code to exit with.
+fn handle_repo_delete(delete_flags: &[String], deps: &guard::Deps) -> Result<i32> {
+    let t = i18n::text();
+    let parsed = tracing::info_span!("parse", cmd = "repo delete")
+        .in_scope(|| delete::parse_delete_args(delete_flags));
+    let mut full_args = vec!["repo".to_string(), "delete".to_string()];
+    full_args.extend_from_slice(delete_flags);
+    if parsed.help {
+        return passthrough(&full_args, deps);
+    }
+
+    let target = pr::target_repo(parsed.repo.as_deref());
+    if let Some(target) = &target {
+        profile::hint_repo(target);
+    }
+    let repo = target
+        .clone()
+        .unwrap_or_else(|| "(unknown repo)".to_string());
+    let severity = risk::classify_repo_delete();
+    let summary = format!("delete {repo}");
+    let vars = notify::CardVars::new(&summary, &repo, severity);
+    let card = notify::card_or(None, vars, || notify::delete_card(t, &repo));
+
+    let req = guard::Request {
+        kind: guard::Kind::RepoDelete,
+        gh_args: full_args,
+        links: vec![notify::Link {
+            text: t.open_repo.to_string(),
+            url: host::web_url(&repo),
+        }],
+        repo: repo.clone(),
+        severity,
+        summary,
+        card,
+        documents: Vec::new(),
+        timeout_secs: None,
+        approvals: None,
+        queued: None,
+        auto_approve: None,
+        always_ask: true,
+        confirm: target,
+    };
+    deny_as_api(&req, deps, "DELETE", "")?;
+
+    let banner = || {
+        heading(t.delete_banner);
+        banner_line(t.label_repo, 4, repo.red().bold());
+    };
+
+    guard::run(req, deps, banner)
+}
+
+/// `gh release upload`: approval, with `--clobber` called out. Returns the
+/// code to exit with.
+fn handle_release_upload(upload_flags: &[String], deps: &guard::Deps) -> Result<i32> {
+    let t = i18n::text();
+    let parsed = tracing::info_span!("parse", cmd = "release upload")
+        .in_scope(|| release::parse_upload_args(upload_flags));
+    let mut full_args = vec!["release".to_string(), "upload".to_string()];
+    full_args.extend_from_slice(upload_flags);
+    if parsed.help {
+        return passthrough(&full_args, deps);
+    }
+
+    let target = pr::target_repo(parsed.repo.as_deref());
+    if let Some(target) = &target {
+        profile::hint_repo(target);
+    }
+    let repo = target.unwrap_or_else(|| "(unknown repo)".to_string());
+    let tag = parsed.tag.as_deref().unwrap_or("(no tag)");
+    let assets = parsed.assets();
+    let severity = risk::classify_release_upload(parsed.clobber);
+    let summary = format!("release {tag}: {}", assets.join(", "));
+
+    let vars = notify::CardVars::new(&summary, &repo, severity);
+    let card = notify::card_or(None, vars, || {
+        notify::release_card(t, tag, &assets, parsed.clobber)
+    });
+
+    let req = guard::Request {
+        kind: guard::Kind::ReleaseUpload,
+        gh_args: full_args,
+        links: vec![notify::Link {
+            text: t.open_repo.to_string(),
+            url: host::web_url(&repo),
+        }],
+        repo,
+        severity,
+        summary,
+        card,
+        documents: Vec::new(),
+        timeout_secs: None,
+        approvals: None,
+        queued: None,
+        auto_approve: None,
+        always_ask: false,
+        confirm: None,
+    };
+    // The tag stands in for the release ID the real endpoint has.
+    deny_as_api(&req, deps, "POST", &format!("/releases/{tag}/assets"))?;
+    if parsed.clobber {
+        eprintln!("{}", format!("⚠️  {}", t.clobber_warning).yellow().bold());
+    }
+
+    let banner = || {
+        heading(t.release_banner);
+        banner_line(t.release_tag, 6, tag.bold());
+        for asset in &assets {
+            banner_line(t.release_assets, 6, asset);
+        }
+    };
+
+    guard::run(req, deps, banner)
+}
+
+/// Refuse `req` when `[api] deny` covers the API call its gh command makes,
+/// `method` on `/repos/{repo}{path}`, so `gh repo delete my-org/x` is
+/// denied by `DELETE /repos/my-org/*` as its `gh api` form would be.
+fn deny_as_api(req: &guard::Request, deps: &guard::Deps, method: &str, path: &str) -> Result<()> {
+    // "(unknown repo)": there is no call to match.
+    if !req.repo.contains('/') {
+        return Ok(());
+    }
+    let settings = settings::load_file()?;
+    let endpoint = format!("/repos/{}{path}", req.repo);
+    match api::denied(&settings.api.deny, method, &endpoint) {
+        Some(rule) => Err(guard::deny(req, deps, rule)),
+        None => Ok(()),
+    }
+}
+
+/// Lines that say what a `gh api` call does where the endpoint alone does
+/// not: where a webhook delivers, which protections a branch loses, who
+/// gets access to a repository or joins an organisation.
+fn api_meta<'t>(
+    t: &'t i18n::Text,
+    parsed: &api::ApiArgs,
+    webhook: bool,
+    protection: Option<&api::ProtectionChange>,
+) -> Vec<(&'t str, String)> {
+    let mut meta = Vec::new();
+    if let Some(change) = api::membership_change(parsed) {
+        meta.push((t.membership, membership_line(t, &change)));
+    }
+    if let Some(change) = api::access_change(parsed) {
+        let who = if change.team {
+            format!("{} {}", t.team, change.grantee)
+        } else {
+            format!("@{}", change.grantee)
+        };
+        let line = if change.removed {
+            (t.access_removed)(&who, &change.repo)
+        } else {
+            (t.access_granted)(change.permission.as_deref(), &who, &change.repo)
+        };
+        meta.push((t.access, line));
+    }
+    if let Some(url) = parsed.body_value(&["config", "url"]).filter(|_| webhook) {
+        meta.push((t.webhook_url, url));
+    }
+    if let Some(change) = protection {
+        meta.push((t.protection_branch, change.branch.clone()));
+        if change.all_removed {
+            meta.push((t.protection_removed, t.protection_all.to_string()));
+        }
+        if !change.removed.is_empty() {
+            meta.push((t.protection_removed, change.removed.join(", ")));
+        }
+        if !change.changed.is_empty() {
+            meta.push((t.protection_changed, change.changed.join(", ")));
+        }
+    }
+    meta
+}
+
+/// "add @eve to team acme/devs as maintainer", and the like.
+fn membership_line(t: &i18n::Text, change: &api::MembershipChange) -> String {
+    let what = if change.team {
+        format!("{} {}", t.team, change.group)
+    } else {
+        change.group.clone()
+    };
+    match &change.user {
+        Some(user) if change.removed => (t.member_removed)(&format!("@{user}"), &what),
+        Some(user) => (t.member_added)(&format!("@{user}"), &what, change.role.as_deref()),
+        None if change.removed => (t.team_deleted)(&what),
+        None if change.created => (t.team_created)(&what),
+        None => (t.team_changed)(&what),
+    }
+}
+
+/// The boxed title on top of an approval banner.
+fn heading(title: &str) {
+    if output::plain() {
+        eprintln!("{title}");
+        return;
+    }
+    let rule = "═".repeat(34.max(title.chars().count() + 2));
+    eprintln!("{}", rule.cyan());
+    eprintln!("{}", format!(" {title:<33}").cyan().bold());
+    eprintln!("{}", rule.cyan());
+}
+
+/// One "label : value" line of a banner, the label padded to `width`.
+fn banner_line(label: &str, width: usize, value: impl std::fmt::Display) {
+    if output::plain() {
+        eprintln!("{label}: {value}");
+    } else {
+        eprintln!("  {label:<width$} : {value}");
+    }
+}
+
+fn handle_unlock() -> Result<()> {
+    let t = i18n::text();
+    let Some(lock) = lockout::current() else {
+        eprintln!("{}", t.not_locked);
+        return Ok(());
+    };
+    let settings = settings::load()?;
+    let deps = guard::Deps::live();
+    let tg = notify::TgConfig {
+        members: settings.members(),
+        quorum: settings.quorum(),
+        commands: settings.buttons.commands,
+        ..deps.credentials.telegram()?
+    };
+
+    eprintln!("{}", t.sending_unlock);
+    let card = lock.unlock_card(t);
+    let (request_id, message_id) =
+        deps.notifier
+            .send_with_approval(&tg, &card, &[], &[], None, false)?;
+    let timeout = settings.approval_timeout();
+    eprintln!("{}", (t.waiting)(&guard::duration_label(timeout)));
+
+    let approval = deps
+        .notifier
+        .poll_for_approval(&tg, &request_id, message_id, timeout, 1)?;
+    match approval.result {
         ApprovalResult::Approved => {
-            eprintln!("{}", "✅  Approved! Running API call…".green().bold());
-            let mut full = vec!["api".to_string()];
-            full.extend_from_slice(api_flags);
-            let code = gh::run_gh(&full, Some(&token))?;
-            process::exit(code);
+            lockout::clear()?;
+            eprintln!("{}", t.unlocked.green().bold());
+            Ok(())
         }
         ApprovalResult::Rejected => {
-            eprintln!("{}", "❌  Rejected. API call cancelled.".red().bold());
-            process::exit(1);
+            eprintln!("{}", t.unlock_rejected.red().bold());
+            Err(Error::ApprovalRejected.into())
         }
         ApprovalResult::Timeout => {
-            eprintln!("{}", "⏱   Timed out (5 min). API call cancelled.".yellow());
-            process::exit(1);
+            eprintln!("{}", t.unlock_timed_out.yellow());
+            Err(Error::ApprovalTimedOut.into())
+        }
+        ApprovalResult::Panic { revoke } => {
+            panic::run(revoke)?;
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Cancelled => {
+            eprintln!("{}", t.unlock_cancelled.yellow());
+            Err(Error::Cancelled.into())
         }
     }
 }
 
-/// Replace the current process with `gh <args>`, injecting GH_TOKEN.
+/// `gh-guard hook`: put the guarded gh commands in an agent's shell
+/// command through approval, and tell the agent whether it may run it. No
+/// answer leaves the command to the agent's own rules.
+fn handle_hook() -> Result<()> {
+    let t = i18n::text();
+    // The answer must be all the agent finds on stdout.
+    std::env::remove_var("GH_GUARD_OUTPUT");
+    let input = hook::Input::read()?;
+    let Some(shell) = input.command() else {
+        return Ok(());
+    };
+    let agent = hook::Agent;
+    let deps = agent.deps();
+    let mut asked = false;
+    for args in hook::gh_commands(shell) {
+        let verdict = if let Some(since) = readonly::since().filter(|_| !readonly::allows(&args)) {
+            if guard::dry_running() {
+                guard::report_passthrough(&args, true)
+            } else {
+                Err(readonly::refuse(&args, &since))
+            }
+        } else if hook::guarded(&args) {
+            intercept(&args, &deps)
+        } else {
+            continue;
+        };
+        // A dry run reports; the agent decides as if gh-guard were not there.
+        if guard::dry_running() {
+            continue;
+        }
+        if let Err(e) = verdict {
+            let command = format!("gh {}", args.join(" "));
+            input.answer(false, &(t.hook_denied)(&command, &e.to_string()));
+            return Ok(());
+        }
+        asked = true;
+    }
+    if asked {
+        input.answer(true, t.hook_allowed);
+    }
+    Ok(())
+}
+
+/// Replace the current process with `gh <args>`, injecting the PAT.
 /// Uses exec() on Unix so TTY ownership and signal handling are correct.
-fn passthrough(args: &[String]) -> Result<()> {
-    let token = config::get_pat().ok();
-    gh::exec_passthrough(args, token.as_deref())
+fn passthrough(args: &[String], deps: &guard::Deps) -> Result<i32> {
+    let read_only = readonly::since().filter(|_| !readonly::allows(args));
+    if guard::dry_running() {
+        return guard::report_passthrough(args, read_only.is_some());
+    }
+    if let Some(since) = read_only {
+        return Err(readonly::refuse(args, &since));
+    }
+    audit::Entry::new(args, audit::Decision::Passthrough).write();
+    let token = passthrough_token(args, deps);
+    deps.gh.exec(args, token.as_deref())
+}
+
+/// The PAT for a passthrough command, looked up only if gh will talk to
+/// GitHub — `gh --version` or `gh config list` should not cost a keychain
+/// prompt.
+fn passthrough_token(args: &[String], deps: &guard::Deps) -> Option<String> {
+    if !gh::needs_token(args) {
+        tracing::debug!("local gh command, no token looked up");
+        return None;
+    }
+    deps.credentials.pat().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use guard::{Credentials, Deps, GhRunner, Notifier};
+    use notify::{Approval, Choice, Document, Link, TgConfig};
+    use std::cell::RefCell;
+
+    /// gh's exit code in these tests, to tell it apart from gh-guard's own.
+    const GH_EXIT: i32 = 7;
+
+    type Call = (Vec<String>, Option<String>);
+
+    /// The secret store, Telegram and gh in one, recording what reaches
+    /// them. `answer: None` makes Telegram unreachable.
+    struct Fake {
+        answer: Option<ApprovalResult>,
+        cards: RefCell<Vec<String>>,
+        ran: RefCell<Vec<Call>>,
+        execs: RefCell<Vec<Call>>,
+    }
+
+    impl Fake {
+        fn new(answer: Option<ApprovalResult>) -> Fake {
+            Fake {
+                answer,
+                cards: RefCell::default(),
+                ran: RefCell::default(),
+                execs: RefCell::default(),
+            }
+        }
+
+        fn deps(&self) -> Deps<'_> {
+            Deps {
+                credentials: self,
+                notifier: self,
+                gh: self,
+            }
+        }
+    }
+
+    impl Credentials for Fake {
+        fn pat(&self) -> Result<String> {
+            Ok("ghp_fake".to_string())
+        }
+
+        fn login(&self, _token: &str) -> Option<String> {
+            Some("fake-bot".to_string())
+        }
+
+        fn telegram(&self) -> Result<TgConfig> {
+            Ok(TgConfig {
+                token: "1:fake".to_string(),
+                chat_id: "1".to_string(),
+                thread_id: None,
+                members: Vec::new(),
+                quorum: None,
+                delegate: None,
+                commands: false,
+            })
+        }
+    }
+
+    impl Notifier for Fake {
+        fn send_with_approval(
+            &self,
+            _tg: &TgConfig,
+            html: &str,
+            _links: &[Link],
+            _choices: &[Choice],
+            _confirm: Option<&str>,
+            _silent: bool,
+        ) -> Result<(String, i64)> {
+            self.answer
+                .ok_or_else(|| Error::TelegramUnreachable("down".to_string()))?;
+            self.cards.borrow_mut().push(html.to_string());
+            Ok(("req1".to_string(), 1))
+        }
+
+        fn send_note(&self, _tg: &TgConfig, _html: &str) -> Result<()> {
+            Ok(())
+        }
+
+        fn send_document(&self, _tg: &TgConfig, _doc: &Document, _reply_to: i64) -> Result<i64> {
+            Ok(8)
+        }
+
+        fn poll_for_approval(
+            &self,
+            _tg: &TgConfig,
+            _request_id: &str,
+            _message_id: i64,
+            _timeout_secs: u64,
+            _approvals: u32,
+        ) -> Result<Approval> {
+            Ok(Approval {
+                result: self.answer.expect("card was sent"),
+                approver: Some("@me".to_string()),
+                choice: None,
+                delegation: Vec::new(),
+            })
+        }
+    }
+
+    impl GhRunner for Fake {
+        fn run(&self, args: &[String], token: Option<&str>) -> Result<i32> {
+            self.ran
+                .borrow_mut()
+                .push((args.to_vec(), token.map(str::to_string)));
+            Ok(GH_EXIT)
+        }
+
+        fn exec(&self, args: &[String], token: Option<&str>) -> Result<i32> {
+            self.execs
+                .borrow_mut()
+                .push((args.to_vec(), token.map(str::to_string)));
+            Ok(0)
+        }
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn call(list: &[&str], token: Option<&str>) -> Call {
+        (args(list), token.map(str::to_string))
+    }
+
+    #[test]
+    fn api_read_passes_through_with_token() {
+        let fake = Fake::new(None);
+
+        let code = handle_api(&args(&["repos/o/r/pulls"]), &fake.deps()).unwrap();
+
+        assert_eq!(code, 0);
+        assert!(fake.cards.borrow().is_empty());
+        assert!(fake.ran.borrow().is_empty());
+        assert_eq!(
+            *fake.execs.borrow(),
+            [call(&["api", "repos/o/r/pulls"], Some("ghp_fake"))]
+        );
+    }
+
+    #[test]
+    fn api_help_gets_no_token() {
+        let fake = Fake::new(None);
+
+        handle_api(&args(&["--help"]), &fake.deps()).unwrap();
+
+        assert_eq!(*fake.execs.borrow(), [call(&["api", "--help"], None)]);
+    }
+
+    #[test]
+    fn approved_mutation_runs_gh_with_token() {
+        let fake = Fake::new(Some(ApprovalResult::Approved));
+        let raw = ["-XDELETE", "repos/o/r/labels/bug"];
+
+        let code = handle_api(&args(&raw), &fake.deps()).unwrap();
+
+        assert_eq!(code, GH_EXIT);
+        let cards = fake.cards.borrow();
+        assert_eq!(cards.len(), 1);
+        assert!(cards[0].contains("DELETE repos/o/r/labels/bug"));
+        let expected = ["api", "-XDELETE", "repos/o/r/labels/bug"];
+        assert_eq!(*fake.ran.borrow(), [call(&expected, Some("ghp_fake"))]);
+        assert!(fake.execs.borrow().is_empty());
+    }
+
+    #[test]
+    fn undecided_mutations_never_run_gh() {
+        for (answer, expected) in [
+            (ApprovalResult::Rejected, exit::REJECTED),
+            (ApprovalResult::Timeout, exit::TIMEOUT),
+            (ApprovalResult::Cancelled, exit::CANCELLED),
+        ] {
+            let fake = Fake::new(Some(answer));
+
+            let err =
+                handle_api(&args(&["-f", "name=x", "orgs/o/repos"]), &fake.deps()).unwrap_err();
+
+            assert_eq!(error::find(&err).map(Error::exit_code), Some(expected));
+            assert_eq!(fake.cards.borrow().len(), 1);
+            assert!(fake.ran.borrow().is_empty());
+            assert!(fake.execs.borrow().is_empty());
+        }
+    }
+
+    #[test]
+    fn unreachable_telegram_never_runs_gh() {
+        let fake = Fake::new(None);
+
+        let err = handle_api(&args(&["-X", "PUT", "repos/o/r/topics"]), &fake.deps()).unwrap_err();
+
+        assert_eq!(
+            error::find(&err).map(Error::kind),
+            Some("telegram-unreachable")
+        );
+        assert!(fake.ran.borrow().is_empty());
+        assert!(fake.execs.borrow().is_empty());
+    }
+
+    #[test]
+    fn pr_create_needs_title_or_fill() {
+        let fake = Fake::new(Some(ApprovalResult::Approved));
+
+        let err =
+            handle_pr_create(&args(&["--draft", "--base", "main"]), &fake.deps()).unwrap_err();
+
+        assert!(matches!(error::find(&err), Some(Error::NotInterceptable)));
+        assert!(fake.cards.borrow().is_empty());
+        assert!(fake.ran.borrow().is_empty());
+    }
+
+    #[test]
+    fn pr_create_web_passes_through() {
+        let fake = Fake::new(None);
+
+        let code = handle_pr_create(&args(&["--web"]), &fake.deps()).unwrap();
+
+        assert_eq!(code, 0);
+        assert_eq!(
+            *fake.execs.borrow(),
+            [call(&["pr", "create", "--web"], Some("ghp_fake"))]
+        );
+    }
+
+    #[test]
+    fn approved_pr_is_created_as_asked() {
+        let fake = Fake::new(Some(ApprovalResult::Approved));
+        let raw = ["-t", "Fix <login>", "-b", "Closes #1", "-B", "main", "-d"];
+
+        let code = handle_pr_create(&args(&raw), &fake.deps()).unwrap();
+
+        assert_eq!(code, GH_EXIT);
+        let card = &fake.cards.borrow()[0];
+        assert!(card.contains("Fix &lt;login&gt;"), "{card}");
+        assert!(card.contains("Closes #1"), "{card}");
+        assert!(card.contains("DRAFT"), "{card}");
+        let mut expected = vec!["pr", "create"];
+        expected.extend(raw);
+        assert_eq!(*fake.ran.borrow(), [call(&expected, Some("ghp_fake"))]);
+    }
 }
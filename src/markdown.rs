@@ -0,0 +1,315 @@
+//! GitHub-flavoured Markdown, as PR descriptions are written, turned into
+//! the small HTML subset Telegram renders: `<b>`, `<i>`, `<s>`, `<code>`,
+//! `<pre>`, `<a>` and `<blockquote>`. Anything else stays as escaped text.
+
+use crate::notify::escape_html;
+use std::borrow::Cow;
+
+/// Render `md` for a Telegram message. Headings become bold lines, list
+/// bullets "•", task boxes ☐ / ☑, and HTML comments (PR template hints)
+/// are dropped. Every tag opened is closed, however `md` ends.
+pub fn to_telegram_html(md: &str) -> String {
+    let md = strip_comments(md);
+    let mut out: Vec<String> = Vec::new();
+    let mut quote: Vec<String> = Vec::new();
+    let mut lines = md.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if let Some(text) = trimmed.strip_prefix('>') {
+            quote.push(inline(text.strip_prefix(' ').unwrap_or(text)));
+            continue;
+        }
+        if !quote.is_empty() {
+            out.push(format!("<blockquote>{}</blockquote>", quote.join("\n")));
+            quote.clear();
+        }
+
+        if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+            let lang = trimmed[fence.len()..].trim();
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| !l.trim_start().starts_with(fence))
+                .collect();
+            let code = escape_html(&code.join("\n"));
+            out.push(if is_language(lang) {
+                format!("<pre><code class=\"language-{lang}\">{code}</code></pre>")
+            } else {
+                format!("<pre>{code}</pre>")
+            });
+        } else if let Some(text) = heading(trimmed) {
+            out.push(format!("<b>{}</b>", inline(text)));
+        } else if is_rule(trimmed) {
+            out.push("──────────".to_string());
+        } else {
+            let indent = &line[..line.len() - trimmed.len()];
+            out.push(format!("{indent}{}", list_item(trimmed)));
+        }
+    }
+    if !quote.is_empty() {
+        out.push(format!("<blockquote>{}</blockquote>", quote.join("\n")));
+    }
+
+    // Blank runs (often left where comments were) count as one.
+    let mut html = String::new();
+    let mut blank = false;
+    for line in out {
+        if line.trim().is_empty() {
+            blank = !html.is_empty();
+            continue;
+        }
+        if blank {
+            html.push('\n');
+            blank = false;
+        }
+        if !html.is_empty() {
+            html.push('\n');
+        }
+        html.push_str(&line);
+    }
+    html
+}
+
+/// `md` without `<!-- … -->`; an unterminated comment runs to the end.
+fn strip_comments(md: &str) -> Cow<'_, str> {
+    if !md.contains("<!--") {
+        return Cow::Borrowed(md);
+    }
+    let mut out = String::new();
+    let mut rest = md;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// The text of an ATX heading ("## Why" → "Why").
+fn heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    if !(1..=6).contains(&level) || !(text.is_empty() || text.starts_with(' ')) {
+        return None;
+    }
+    Some(text.trim().trim_end_matches('#').trim_end())
+}
+
+/// `---`, `***` or `___`, spaces allowed.
+fn is_rule(line: &str) -> bool {
+    let marks: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|m| marks.chars().all(|c| c.to_string() == *m))
+}
+
+/// A fence's info string, if it is safe to put in a class name.
+fn is_language(lang: &str) -> bool {
+    !lang.is_empty()
+        && lang
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-#_.".contains(c))
+}
+
+/// One line of running text, with a bullet or task box for list items.
+fn list_item(line: &str) -> String {
+    let Some(item) = ["- ", "* ", "+ "]
+        .into_iter()
+        .find_map(|b| line.strip_prefix(b))
+    else {
+        return inline(line);
+    };
+    for (box_, mark) in [("[ ] ", "☐"), ("[x] ", "☑"), ("[X] ", "☑")] {
+        if let Some(task) = item.strip_prefix(box_) {
+            return format!("{mark} {}", inline(task));
+        }
+    }
+    format!("• {}", inline(item))
+}
+
+/// Inline code, emphasis, strike-through and links within one line.
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        let (html, used) = match c {
+            '\\' => match after.chars().next().filter(char::is_ascii_punctuation) {
+                Some(p) => (Some(escape_html(&p.to_string())), 1 + p.len_utf8()),
+                None => (None, 0),
+            },
+            '`' => match after.find('`') {
+                Some(end) => (
+                    Some(format!("<code>{}</code>", escape_html(&after[..end]))),
+                    end + 2,
+                ),
+                None => (None, 0),
+            },
+            '[' => link(rest),
+            '<' => autolink(rest),
+            '*' | '_' | '~' => emphasis(rest, prev),
+            _ => (None, 0),
+        };
+        match html {
+            Some(html) => {
+                out.push_str(&html);
+                prev = rest[..used].chars().last();
+                rest = &rest[used..];
+            }
+            None => {
+                out.push_str(&escape_html(&c.to_string()));
+                prev = Some(c);
+                rest = after;
+            }
+        }
+    }
+    out
+}
+
+/// `[label](url)` at the start of `text`, and the bytes it spans.
+fn link(text: &str) -> (Option<String>, usize) {
+    let Some(close) = text.find("](") else {
+        return (None, 0);
+    };
+    let Some(end) = text[close + 2..].find(')') else {
+        return (None, 0);
+    };
+    let label = &text[1..close];
+    let url = text[close + 2..close + 2 + end].trim();
+    if label.contains('[') || !is_web_url(url) {
+        return (None, 0);
+    }
+    let html = format!("<a href=\"{}\">{}</a>", escape_attr(url), inline(label));
+    (Some(html), close + 3 + end)
+}
+
+/// `<https://…>` at the start of `text`.
+fn autolink(text: &str) -> (Option<String>, usize) {
+    let Some(end) = text.find('>') else {
+        return (None, 0);
+    };
+    let url = &text[1..end];
+    if !is_web_url(url) || url.contains(char::is_whitespace) {
+        return (None, 0);
+    }
+    let html = format!("<a href=\"{}\">{}</a>", escape_attr(url), escape_html(url));
+    (Some(html), end + 1)
+}
+
+/// `**b**`, `__b__`, `*i*`, `_i_` or `~~s~~` at the start of `text`.
+/// Intra-word underscores (`snake_case`) are left alone, as GitHub does.
+fn emphasis(text: &str, prev: Option<char>) -> (Option<String>, usize) {
+    let (delim, tag) = [
+        ("**", "b"),
+        ("__", "b"),
+        ("~~", "s"),
+        ("*", "i"),
+        ("_", "i"),
+    ]
+    .into_iter()
+    .find(|(d, _)| text.starts_with(d))
+    .unwrap_or(("", ""));
+    if delim.is_empty() || (delim.starts_with('_') && prev.is_some_and(char::is_alphanumeric)) {
+        return (None, 0);
+    }
+    let body = &text[delim.len()..];
+    if body.starts_with(char::is_whitespace) {
+        return (None, 0);
+    }
+    let mut from = 0;
+    while let Some(i) = body[from..].find(delim) {
+        let end = from + i;
+        let next = body[end + delim.len()..].chars().next();
+        let inner = &body[..end];
+        let after_text =
+            !inner.is_empty() && !inner.ends_with(char::is_whitespace) && !inner.ends_with('\\');
+        let in_word = delim.starts_with('_') && next.is_some_and(char::is_alphanumeric);
+        // `**` inside `*…*` belongs to a nested bold.
+        let nested = delim == "*" && next == Some('*');
+        if after_text && !in_word && !nested {
+            let html = format!("<{tag}>{}</{tag}>", inline(inner));
+            return (Some(html), delim.len() * 2 + end);
+        }
+        from = end + delim.len();
+    }
+    (None, 0)
+}
+
+fn is_web_url(url: &str) -> bool {
+    ["https://", "http://", "mailto:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+fn escape_attr(s: &str) -> String {
+    escape_html(s).replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_sections_and_lists() {
+        let md = "<!-- Describe your change -->\n## Summary\n\nFixes the **login** flow.\n\n\
+                  ### Checklist\n- [x] Tests\n- [ ] Docs\n* one\n  1. nested";
+        assert_eq!(
+            to_telegram_html(md),
+            "<b>Summary</b>\n\nFixes the <b>login</b> flow.\n\n<b>Checklist</b>\n☑ Tests\n\
+             ☐ Docs\n• one\n  1. nested"
+        );
+    }
+
+    #[test]
+    fn code_is_escaped_verbatim() {
+        let md = "Run `a<b>` then:\n```rust\nlet x = *y*;\n```\n~~~\n<tag>";
+        assert_eq!(
+            to_telegram_html(md),
+            "Run <code>a&lt;b&gt;</code> then:\n\
+             <pre><code class=\"language-rust\">let x = *y*;</code></pre>\n<pre>&lt;tag&gt;</pre>"
+        );
+    }
+
+    #[test]
+    fn links_only_to_the_web() {
+        assert_eq!(
+            to_telegram_html("See [the *docs*](https://x.dev/a?b=1&c=\"2\") or <https://y.dev>."),
+            "See <a href=\"https://x.dev/a?b=1&amp;c=&quot;2&quot;\">the <i>docs</i></a> or \
+             <a href=\"https://y.dev\">https://y.dev</a>."
+        );
+        assert_eq!(
+            to_telegram_html("[x](javascript:alert(1)) <b>"),
+            "[x](javascript:alert(1)) &lt;b&gt;"
+        );
+    }
+
+    #[test]
+    fn emphasis_needs_a_closing_marker() {
+        assert_eq!(
+            to_telegram_html("a_b_c, 2 * 3 * 4, _it_, ~~old~~, **open, \\*lit\\*"),
+            "a_b_c, 2 * 3 * 4, <i>it</i>, <s>old</s>, **open, *lit*"
+        );
+    }
+
+    #[test]
+    fn quotes_and_rules() {
+        assert_eq!(
+            to_telegram_html("> Note\n> more\n\n---\nend"),
+            "<blockquote>Note\nmore</blockquote>\n\n──────────\nend"
+        );
+    }
+
+    #[test]
+    fn cut_off_input_stays_balanced() {
+        assert_eq!(
+            to_telegram_html("```\ncode <!-- never closed"),
+            "<pre>code </pre>"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Prometheus metrics served by `gh-guard daemon` on a localhost port
+//! (`[metrics]`). They are worked out at each scrape from what gh-guard
+//! keeps on disk anyway — open cards, the offline queue, the audit log —
+//! plus a few counters the short-lived gh-guard processes add to.
+
+use crate::audit::{self, Decision, Entry};
+use crate::settings::Metrics;
+use crate::state;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+
+const STATE_FILE: &str = "metrics.json";
+
+/// Upper bounds of the Telegram call duration buckets, in seconds.
+const BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// A scraper that connects and says nothing must not stall the others.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What gh-guard processes count as they go.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Counters {
+    /// Failed or refused `getUpdates` polls.
+    poll_errors: u64,
+    /// Bot API calls that got an answer, by how long they took.
+    telegram: Histogram,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Histogram {
+    /// Per bucket of [`BUCKETS`], not cumulative; the last counts the rest.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        self.buckets.resize(BUCKETS.len() + 1, 0);
+        let bucket = BUCKETS
+            .iter()
+            .position(|le| secs <= *le)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Count a Bot API call answered after `took`; best effort.
+pub fn telegram_call(took: Duration) {
+    record(|c| c.telegram.observe(took.as_secs_f64()));
+}
+
+/// Count a `getUpdates` poll that failed; best effort.
+pub fn poll_error() {
+    record(|c| c.poll_errors += 1);
+}
+
+fn record(f: impl FnOnce(&mut Counters)) {
+    if let Err(e) = state::update(STATE_FILE, f) {
+        tracing::debug!(error = %e, "could not update metrics");
+    }
+}
+
+/// Serve `/metrics` on `127.0.0.1:<port>` from a background thread.
+pub fn serve(cfg: &Metrics) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, cfg.port))
+        .with_context(|| format!("Cannot serve metrics on 127.0.0.1:{}", cfg.port))?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            if let Err(e) = handle(stream) {
+                tracing::debug!(error = %e, "metrics request failed");
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Headers are of no interest, but must be read before answering.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match (request.split_whitespace().next(), path) {
+        (Some("GET"), "/metrics") => ("200 OK", render(&snapshot()?)),
+        (Some("GET"), _) => ("404 Not Found", "Not found; try /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    write!(
+        &stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
+
+/// Everything one scrape reports.
+struct Snapshot {
+    pending: usize,
+    queued: usize,
+    audit: Vec<Entry>,
+    counters: Counters,
+}
+
+fn snapshot() -> Result<Snapshot> {
+    Ok(Snapshot {
+        pending: crate::notify::open_cards(),
+        queued: crate::queue::len(),
+        audit: audit::read_all()?,
+        counters: state::load(STATE_FILE),
+    })
+}
+
+/// The Prometheus text format of `s`.
+fn render(s: &Snapshot) -> String {
+    let mut out = String::new();
+    family(
+        &mut out,
+        "pending_requests",
+        "gauge",
+        "Approval cards waiting for a decision.",
+    );
+    let _ = writeln!(out, "gh_guard_pending_requests {}", s.pending);
+    family(
+        &mut out,
+        "queued_requests",
+        "gauge",
+        "Requests held until Telegram can be reached.",
+    );
+    let _ = writeln!(out, "gh_guard_queued_requests {}", s.queued);
+
+    // Guarded requests only, as `gh-guard stats` counts them.
+    let guarded = s
+        .audit
+        .iter()
+        .filter(|e| !matches!(e.decision, Decision::Passthrough | Decision::Queued));
+    let mut requests: BTreeMap<(&str, String), u64> = BTreeMap::new();
+    let (mut decided_ms, mut decided) = (0, 0);
+    for e in guarded {
+        let severity = e.severity.map_or("none".to_string(), |s| s.to_string());
+        *requests.entry((e.decision.as_str(), severity)).or_default() += 1;
+        if let Some(ms) = e.latency_ms {
+            decided_ms += ms;
+            decided += 1;
+        }
+    }
+    family(
+        &mut out,
+        "requests_total",
+        "counter",
+        "Guarded requests by decision and severity.",
+    );
+    for ((decision, severity), n) in &requests {
+        let _ = writeln!(
+            out,
+            "gh_guard_requests_total{{decision=\"{decision}\",severity=\"{severity}\"}} {n}"
+        );
+    }
+    family(
+        &mut out,
+        "decision_seconds",
+        "summary",
+        "Time from card to decision.",
+    );
+    let _ = writeln!(
+        out,
+        "gh_guard_decision_seconds_sum {}",
+        decided_ms as f64 / 1000.0
+    );
+    let _ = writeln!(out, "gh_guard_decision_seconds_count {decided}");
+
+    let hist = &s.counters.telegram;
+    family(
+        &mut out,
+        "telegram_request_duration_seconds",
+        "histogram",
+        "Bot API calls that got an answer, retries included.",
+    );
+    let mut cumulative = 0;
+    for (i, le) in BUCKETS.iter().enumerate() {
+        cumulative += hist.buckets.get(i).copied().unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "gh_guard_telegram_request_duration_seconds_bucket{{le=\"{le}\"}} {cumulative}"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "gh_guard_telegram_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        hist.count
+    );
+    let _ = writeln!(
+        out,
+        "gh_guard_telegram_request_duration_seconds_sum {}",
+        hist.sum
+    );
+    let _ = writeln!(
+        out,
+        "gh_guard_telegram_request_duration_seconds_count {}",
+        hist.count
+    );
+    family(
+        &mut out,
+        "telegram_poll_errors_total",
+        "counter",
+        "Failed or refused Telegram polls.",
+    );
+    let _ = writeln!(
+        out,
+        "gh_guard_telegram_poll_errors_total {}",
+        s.counters.poll_errors
+    );
+    out
+}
+
+/// The `# HELP` and `# TYPE` lines of a metric.
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(
+        out,
+        "# HELP gh_guard_{name} {help}\n# TYPE gh_guard_{name} {kind}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::Severity;
+
+    fn entry(decision: Decision, severity: Option<Severity>, latency_ms: Option<u64>) -> Entry {
+        let mut e = Entry::new(&["api".to_string()], decision);
+        e.severity = severity;
+        e.latency_ms = latency_ms;
+        e
+    }
+
+    #[test]
+    fn renders_guarded_requests_and_counters() {
+        let mut counters = Counters {
+            poll_errors: 2,
+            ..Counters::default()
+        };
+        counters.telegram.observe(0.2);
+        counters.telegram.observe(40.0);
+        let snapshot = Snapshot {
+            pending: 1,
+            queued: 0,
+            audit: vec![
+                entry(Decision::Approved, Some(Severity::High), Some(1_500)),
+                entry(Decision::Approved, Some(Severity::High), Some(500)),
+                entry(Decision::AutoRejected, Some(Severity::Low), None),
+                entry(Decision::Passthrough, None, None),
+            ],
+            counters,
+        };
+
+        let text = render(&snapshot);
+
+        for line in [
+            "gh_guard_pending_requests 1",
+            "gh_guard_requests_total{decision=\"approved\",severity=\"high\"} 2",
+            "gh_guard_requests_total{decision=\"auto-rejected\",severity=\"low\"} 1",
+            "gh_guard_decision_seconds_sum 2",
+            "gh_guard_decision_seconds_count 2",
+            "gh_guard_telegram_request_duration_seconds_bucket{le=\"0.1\"} 0",
+            "gh_guard_telegram_request_duration_seconds_bucket{le=\"0.25\"} 1",
+            "gh_guard_telegram_request_duration_seconds_bucket{le=\"30\"} 1",
+            "gh_guard_telegram_request_duration_seconds_bucket{le=\"+Inf\"} 2",
+            "gh_guard_telegram_poll_errors_total 2",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line}\n{text}");
+        }
+        assert!(!text.contains("passthrough"), "{text}");
+    }
+}
@@ -1,269 +1,2095 @@
+use crate::agent;
+use crate::error::Error;
+use crate::i18n::{self, Text};
+use crate::mailbox;
+use crate::markdown;
+use crate::output::{self, Verbosity};
+use crate::risk::Severity;
+use crate::settings::Delegate;
 use anyhow::{anyhow, Context, Result};
-use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant as TokioInstant;
+use tracing::Instrument;
 use uuid::Uuid;
 
+#[derive(Clone, Copy)]
 pub enum ApprovalResult {
     Approved,
     Rejected,
     Timeout,
+    /// The approver sent `/panic` (or `/panic revoke`) instead of deciding.
+    Panic { revoke: bool },
+    /// Ctrl+C on this machine withdrew the request.
+    Cancelled,
 }
 
+/// A decision together with who made it.
+pub struct Approval {
+    pub result: ApprovalResult,
+    /// Telegram @username (or first name) of whoever decided.
+    pub approver: Option<String>,
+    /// The extra button approved with, if it was not plain Approve.
+    pub choice: Option<Choice>,
+    /// Who tapped "➡️ Delegate", then who the request was handed to;
+    /// empty unless it was.
+    pub delegation: Vec<String>,
+}
+
+/// A button beside Approve that approves and asks for something more.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Choice {
+    /// Approve, and trust the repository from now on.
+    TrustRepo,
+    /// Approve, and let everything run without asking for `minutes`.
+    AllowAll { minutes: u64 },
+    /// Approve, and let the same kind of request in the same repository
+    /// run without asking for `minutes`.
+    AllowSimilar { minutes: u64 },
+}
+
+impl Choice {
+    /// The word before the request ID in the button's callback data.
+    fn action(self) -> String {
+        match self {
+            Choice::TrustRepo => "trust".to_string(),
+            Choice::AllowAll { minutes } => format!("allow{minutes}"),
+            Choice::AllowSimilar { minutes } => format!("similar{minutes}"),
+        }
+    }
+
+    /// The choice whose callback data starts with `action`, if any.
+    fn parse(action: &str) -> Option<Choice> {
+        if action == "trust" {
+            return Some(Choice::TrustRepo);
+        }
+        if let Some(minutes) = action.strip_prefix("similar") {
+            let minutes = minutes.parse().ok()?;
+            return Some(Choice::AllowSimilar { minutes });
+        }
+        let minutes = action.strip_prefix("allow")?.parse().ok()?;
+        Some(Choice::AllowAll { minutes })
+    }
+
+    fn label(self, t: &i18n::Text) -> String {
+        match self {
+            Choice::TrustRepo => t.approve_and_trust.to_string(),
+            Choice::AllowAll { minutes } => (t.allow_all)(minutes),
+            Choice::AllowSimilar { .. } => t.allow_similar.to_string(),
+        }
+    }
+
+    /// The choice `data` taps on `request_id`'s card, if any.
+    fn tapped(data: &str, request_id: &str) -> Option<Choice> {
+        let (action, id) = data.split_once(':')?;
+        Choice::parse(action).filter(|_| id == request_id)
+    }
+}
+
+#[derive(Clone)]
 pub struct TgConfig {
     pub token: String,
     pub chat_id: String,
+    /// Forum topic new messages go to, in a group with Topics enabled.
+    pub thread_id: Option<i64>,
+    /// Telegram user IDs whose taps and `/panic` count; anyone's when empty.
+    pub members: Vec<i64>,
+    /// Votes that decide a request, asked for with a poll instead of
+    /// buttons.
+    pub quorum: Option<u32>,
+    /// Who "➡️ Delegate" hands a request to, if cards have the button.
+    pub delegate: Option<Delegate>,
+    /// Whether cards end with the `/approve_<id>` and `/reject_<id>`
+    /// commands, which are accepted either way.
+    pub commands: bool,
 }
 
 impl TgConfig {
+    /// Whether `user`, a Telegram `User` object, may decide.
+    fn may_decide(&self, user: &serde_json::Value) -> bool {
+        self.members.is_empty()
+            || user["id"]
+                .as_i64()
+                .is_some_and(|id| self.members.contains(&id))
+    }
+
+    /// Whether `chat`, a Telegram `Chat` object, is this chat.
+    fn is_chat(&self, chat: &serde_json::Value) -> bool {
+        chat["id"].as_i64().map(|id| id.to_string()).as_deref() == Some(self.chat_id.as_str())
+    }
+
+    /// `label`, and in a group with `[group]` members, who decided.
+    fn settled_label(&self, label: &str, by: Option<&str>) -> String {
+        match by {
+            Some(names) if !self.members.is_empty() => format!("{label} · {names}"),
+            _ => label.to_string(),
+        }
+    }
+
     fn api(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{}", self.token, method)
+        format!(
+            "{}/bot{}/{}",
+            crate::http::telegram_api(),
+            self.token,
+            method
+        )
     }
 }
 
-fn agent(timeout_secs: u64) -> ureq::Agent {
-    ureq::AgentBuilder::new()
-        .timeout_connect(Duration::from_secs(10))
-        .timeout(Duration::from_secs(timeout_secs))
-        .build()
+/// Per-request timeout for everything but the long poll.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Allowance on top of the long-poll window before a `getUpdates` request
+/// is given up as lost.
+const POLL_MARGIN: Duration = Duration::from_secs(10);
+
+/// The runtime the Telegram calls run on and the one client they share, so
+/// the card, every long poll and the final edit reuse a single pooled
+/// connection instead of each negotiating TCP, proxy and TLS anew.
+struct Transport {
+    runtime: tokio::runtime::Runtime,
+    client: reqwest::Client,
+}
+
+fn transport() -> Result<&'static Transport> {
+    static TRANSPORT: OnceLock<Result<Transport, String>> = OnceLock::new();
+    TRANSPORT
+        .get_or_init(|| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?;
+            let client = crate::http::async_builder(crate::http::telegram_api())
+                .and_then(|b| {
+                    Ok(b.connect_timeout(Duration::from_secs(10))
+                        .pool_idle_timeout(Duration::from_secs(90))
+                        .tcp_keepalive(Duration::from_secs(30))
+                        .build()?)
+                })
+                .map_err(|e| format!("{e:#}"))?;
+            Ok(Transport { runtime, client })
+        })
+        .as_ref()
+        .map_err(|e| anyhow!("{e}"))
+}
+
+/// Drive a Telegram future to completion from synchronous code.
+fn block_on<T>(f: impl Future<Output = Result<T>>) -> Result<T> {
+    transport()?.runtime.block_on(f)
+}
+
+/// A button under a card that opens `url`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Link {
+    pub text: String,
+    pub url: String,
 }
 
-/// Core sender: posts any pre-formatted HTML text with Approve / Reject buttons.
+/// Core sender: posts any pre-formatted HTML text with Approve / Reject buttons,
+/// `choices` in rows below them, then "➡️ Delegate" with `tg.delegate`,
+/// and `links` in another; with `tg.commands`, `/approve_<id>` and
+/// `/reject_<id>` end the text. With `tg.quorum` a
+/// poll follows the card instead of the buttons that decide. With `confirm`
+/// the approver is asked to reply with that text, and approvals only count
+/// once they have.
+/// `silent` delivers without sound (used during quiet hours).
 /// Returns `(request_id, message_id)` — both needed for the polling phase.
-fn send_with_approval(tg: &TgConfig, html: &str) -> Result<(String, i64)> {
+pub fn send_with_approval(
+    tg: &TgConfig,
+    html: &str,
+    links: &[Link],
+    choices: &[Choice],
+    confirm: Option<&str>,
+    silent: bool,
+) -> Result<(String, i64)> {
     let uid = Uuid::new_v4().to_string();
     let request_id = uid[..8].to_string();
+    let t = i18n::text();
 
+    let mut keyboard = Vec::new();
+    let mut text = html.to_string();
+    if tg.quorum.is_none() {
+        if tg.commands {
+            text.push_str(&format!("\n\n{}", (t.text_commands)(&request_id)));
+        }
+        keyboard.push(decision_row(t, &request_id));
+        // A row each: their labels are too long to share one.
+        for c in choices {
+            keyboard.push(serde_json::json!([{
+                "text": c.label(t),
+                "callback_data": format!("{}:{request_id}", c.action()),
+            }]));
+        }
+        if let Some(to) = &tg.delegate {
+            keyboard.push(serde_json::json!([{
+                "text": (t.delegate)(&to.name),
+                "callback_data": format!("delegate:{request_id}"),
+            }]));
+        }
+    }
+    if !links.is_empty() {
+        keyboard.push(serde_json::json!(links
+            .iter()
+            .map(|l| serde_json::json!({"text": l.text, "url": l.url}))
+            .collect::<Vec<_>>()));
+    }
+    let mut payload = serde_json::json!({
+        "chat_id": tg.chat_id,
+        "text": text,
+        "parse_mode": "HTML",
+        "disable_notification": silent,
+    });
+    if !keyboard.is_empty() {
+        payload["reply_markup"] = serde_json::json!({ "inline_keyboard": keyboard });
+    }
+
+    let message_id = block_on(async {
+        expire_abandoned(tg).await;
+        let message_id = send_message(tg, &payload).await?;
+        if tg.quorum.is_some() {
+            open_vote(tg, &request_id, message_id, silent).await?;
+        }
+        if let Some(expected) = confirm {
+            ask_to_type(tg, &request_id, message_id, expected, silent).await?;
+        }
+        Ok(message_id)
+    })?;
+    Ok((request_id, message_id))
+}
+
+/// The Approve / Reject buttons of `request_id`'s card.
+fn decision_row(t: &Text, request_id: &str) -> serde_json::Value {
+    serde_json::json!([
+        {"text": t.approve, "callback_data": format!("approve:{request_id}")},
+        {"text": t.reject,  "callback_data": format!("reject:{request_id}")}
+    ])
+}
+
+/// Send an informational message with no buttons (e.g. an auto-rejection notice).
+pub fn send_note(tg: &TgConfig, html: &str) -> Result<()> {
     let payload = serde_json::json!({
         "chat_id": tg.chat_id,
         "text": html,
         "parse_mode": "HTML",
-        "reply_markup": {
-            "inline_keyboard": [[
-                {"text": "✅ Approve", "callback_data": format!("approve:{request_id}")},
-                {"text": "❌ Reject",  "callback_data": format!("reject:{request_id}")}
-            ]]
-        }
     });
+    block_on(send_message(tg, &payload))?;
+    Ok(())
+}
 
-    let resp: serde_json::Value = agent(15)
-        .post(&tg.api("sendMessage"))
-        .set("Content-Type", "application/json")
-        .send_json(&payload)
-        .context("Failed to reach Telegram API")?
-        .into_json()
-        .context("Invalid Telegram response")?;
+/// Full text that did not fit on a card, sent as a file in reply to it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Document {
+    /// File name shown in the chat, e.g. "description.md".
+    pub name: String,
+    pub text: String,
+}
 
+/// Send `doc` as a file in reply to the card `reply_to`, without sound: the
+/// card itself has already buzzed. Returns the new message's ID.
+pub fn send_document(tg: &TgConfig, doc: &Document, reply_to: i64) -> Result<i64> {
+    let boundary = format!("gh-guard-{}", Uuid::new_v4().simple());
+    let reply = serde_json::json!({ "message_id": reply_to }).to_string();
+    let thread_id = tg.thread_id.map(|id| id.to_string());
+    let mut form = String::new();
+    let parts = [
+        ("chat_id", Some(tg.chat_id.as_str())),
+        ("message_thread_id", thread_id.as_deref()),
+        ("reply_parameters", Some(&reply)),
+        ("disable_notification", Some("true")),
+    ];
+    for (name, value) in parts.iter().filter_map(|(n, v)| Some((n, (*v)?))) {
+        form.push_str(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ));
+    }
+    form.push_str(&format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"document\"; filename=\"{}\"\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n--{boundary}--\r\n",
+        doc.name, doc.text
+    ));
+
+    let content_type = format!("multipart/form-data; boundary={boundary}");
+    let resp = block_on(send(tg, "sendDocument", |r| {
+        r.header(reqwest::header::CONTENT_TYPE, &content_type)
+            .body(form.clone())
+    }))?;
     if !resp["ok"].as_bool().unwrap_or(false) {
-        return Err(anyhow!(
+        return Err(Error::TelegramApi(format!(
+            "Telegram sendDocument failed: {}",
+            resp["description"].as_str().unwrap_or("unknown error")
+        ))
+        .into());
+    }
+    resp["result"]["message_id"]
+        .as_i64()
+        .ok_or_else(|| anyhow!("Missing message_id in Telegram response"))
+}
+
+/// Delete one of gh-guard's messages. Telegram allows this for 48 hours
+/// after it was sent.
+pub fn delete_message(tg: &TgConfig, message_id: i64) -> Result<()> {
+    let payload = serde_json::json!({"chat_id": tg.chat_id, "message_id": message_id});
+    expect_ok(tg, "deleteMessage", &payload)
+}
+
+/// Replace a settled card's text with `html`, dropping its buttons.
+pub fn collapse_card(tg: &TgConfig, message_id: i64, html: &str) -> Result<()> {
+    let payload = serde_json::json!({
+        "chat_id": tg.chat_id,
+        "message_id": message_id,
+        "text": html,
+        "parse_mode": "HTML",
+    });
+    expect_ok(tg, "editMessageText", &payload)
+}
+
+fn expect_ok(tg: &TgConfig, method: &str, payload: &serde_json::Value) -> Result<()> {
+    let resp = block_on(call(tg, method, payload))?;
+    if !resp["ok"].as_bool().unwrap_or(false) {
+        return Err(Error::TelegramApi(format!(
+            "Telegram {method} failed: {}",
+            resp["description"].as_str().unwrap_or("unknown error")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// The description as a file, when the card cuts it.
+pub fn body_document(body: &str) -> Option<Document> {
+    let body = body.trim();
+    (body.chars().count() > BODY_CHARS).then(|| Document {
+        name: "description.md".to_string(),
+        text: body.to_string(),
+    })
+}
+
+/// Every field in full as a file, when the card cuts any of them.
+pub fn fields_document(fields: &[String]) -> Option<Document> {
+    let full = fields_text(fields, usize::MAX);
+    let shown = truncate(&fields_text(fields, FIELD_CHARS), FIELDS_CHARS).into_owned();
+    (shown != full).then(|| Document {
+        name: "fields.txt".to_string(),
+        text: full,
+    })
+}
+
+/// Post a `sendMessage` payload and return the new message's ID.
+async fn send_message(tg: &TgConfig, payload: &serde_json::Value) -> Result<i64> {
+    let mut payload = payload.clone();
+    if let Some(thread_id) = tg.thread_id {
+        payload["message_thread_id"] = thread_id.into();
+    }
+    let resp = call(tg, "sendMessage", &payload).await?;
+
+    if !resp["ok"].as_bool().unwrap_or(false) {
+        return Err(Error::TelegramApi(format!(
             "Telegram sendMessage failed: {}",
             resp["description"].as_str().unwrap_or("unknown error")
-        ));
+        ))
+        .into());
     }
 
-    let message_id = resp["result"]["message_id"]
+    resp["result"]["message_id"]
         .as_i64()
-        .ok_or_else(|| anyhow!("Missing message_id in Telegram response"))?;
-
-    Ok((request_id, message_id))
+        .ok_or_else(|| anyhow!("Missing message_id in Telegram response"))
 }
 
-/// Format and send a PR approval notification.
-pub fn send_approval_request(
-    tg: &TgConfig,
+/// Most of a PR description shown on a card.
+const BODY_CHARS: usize = 3000;
+/// Most of one `gh api` field value shown on a card.
+const FIELD_CHARS: usize = 300;
+/// Most of all the fields together.
+const FIELDS_CHARS: usize = 3000;
+/// Most of a workflow file shown under a card.
+const WORKFLOW_CHARS: usize = 1500;
+
+/// Format a PR approval notification.
+/// `meta` holds further labelled lines (reviewers, labels, …), shown under
+/// the branch.
+pub fn pr_card(
+    t: &Text,
     title: &str,
     body: &str,
     branch_info: &str,
     draft: bool,
-) -> Result<(String, i64)> {
-    let draft_badge = if draft { " · <b>DRAFT</b>" } else { "" };
+    meta: &[(&str, String)],
+) -> String {
+    let draft_badge = if draft {
+        format!(" · <b>{}</b>", t.draft_badge)
+    } else {
+        String::new()
+    };
     let body_section = {
         let trimmed = body.trim();
         if trimmed.is_empty() {
             String::new()
         } else {
             format!(
-                "\n\n<b>Description</b>\n<pre>{}</pre>",
-                escape_html(truncate(trimmed, 3000))
+                "\n\n<b>{}</b>\n{}",
+                t.description,
+                markdown::to_telegram_html(&truncate(trimmed, BODY_CHARS))
             )
         }
     };
-    let html = format!(
-        "🔀 <b>PR Review Required</b>{}\n\n<b>Title</b>   {}\n<b>Branch</b>  {}{}",
+    let meta_lines: String = meta
+        .iter()
+        .map(|(label, value)| format!("\n<b>{label}</b>  {}", escape_html(value)))
+        .collect();
+    format!(
+        "{}{}\n\n<b>{}</b>   {}\n<b>{}</b>  {}{}{}",
+        heading(t.pr_card_icon, t.pr_card_heading),
         draft_badge,
+        t.title,
         escape_html(title),
+        t.branch,
         escape_html(branch_info),
+        meta_lines,
         body_section,
-    );
-    send_with_approval(tg, &html)
+    )
 }
 
-/// Format and send a `gh api` mutation approval notification.
-pub fn send_api_approval_request(
-    tg: &TgConfig,
+/// Format a `gh api` mutation approval notification.
+/// `host` is the one named with `--hostname`, if any. `meta` holds
+/// labelled lines that matter most for the endpoint (where a webhook
+/// delivers, …), shown right under it.
+pub fn api_card(
+    t: &Text,
     method: &str,
     endpoint: Option<&str>,
+    host: Option<&str>,
     fields: &[String],
-) -> Result<(String, i64)> {
-    let endpoint_str = endpoint.unwrap_or("(unknown endpoint)");
+    meta: &[(&str, String)],
+) -> String {
+    let endpoint_str = endpoint.unwrap_or(t.unknown_endpoint);
     let mut html = format!(
-        "🔧 <b>API Mutation · Approval Required</b>\n\n<code>{} {}</code>",
+        "{}\n\n<code>{} {}</code>",
+        heading(t.api_card_icon, t.api_card_heading),
         escape_html(method),
         escape_html(endpoint_str),
     );
+    for (label, value) in meta {
+        html.push_str(&format!(
+            "\n<b>{label}</b>  <code>{}</code>",
+            escape_html(value)
+        ));
+    }
+    if let Some(host) = host {
+        html.push_str(&format!("\n<b>{}</b>  {}", t.host, escape_html(host)));
+    }
     if !fields.is_empty() {
-        let formatted = fields
+        let formatted = fields_text(fields, FIELD_CHARS);
+        // gh sends a GET's fields as query parameters.
+        let heading = if method == "GET" { t.query } else { t.fields };
+        html.push_str(&format!(
+            "\n\n<b>{}</b>\n<pre>{}</pre>",
+            heading,
+            escape_html(&truncate(&formatted, FIELDS_CHARS))
+        ));
+    }
+    html
+}
+
+/// The start of a workflow file written through the contents API, decoded,
+/// for under the card; `content` is `None` when it could not be read.
+pub fn workflow_snippet(t: &Text, path: &str, content: Option<&str>) -> String {
+    let heading = format!("📜 <b>{}</b>", escape_html(path));
+    match content {
+        Some(text) => format!(
+            "{heading}\n<pre>{}</pre>",
+            escape_html(&truncate(text.trim_end(), WORKFLOW_CHARS))
+        ),
+        None => format!("{heading}\n<i>{}</i>", t.workflow_unreadable),
+    }
+}
+
+/// Format a `gh repo fork` approval notification. `private` is `None`
+/// when GitHub could not be asked.
+pub fn fork_card(
+    t: &Text,
+    source: &str,
+    destination: &str,
+    private: Option<bool>,
+    clone: bool,
+) -> String {
+    let visibility = match private {
+        Some(true) => format!(" · <b>{}</b>", t.private),
+        Some(false) => format!(" · {}", t.public),
+        None => String::new(),
+    };
+    let clone_line = if clone { "\n<code>--clone</code>" } else { "" };
+    format!(
+        "{}\n\n<b>{}</b>  <code>{}</code>{}\n<b>{}</b>  <code>{}</code>{}",
+        heading(t.fork_card_icon, t.fork_card_heading),
+        t.fork_source,
+        escape_html(source),
+        visibility,
+        t.fork_destination,
+        escape_html(destination),
+        clone_line,
+    )
+}
+
+/// Format a `gh repo delete` approval notification.
+pub fn delete_card(t: &Text, repo: &str) -> String {
+    format!(
+        "{}\n\n<b>{}</b>  <code>{}</code>\n\n⚠️ <b>{}</b>",
+        heading(t.delete_card_icon, t.delete_card_heading),
+        t.label_repo,
+        escape_html(repo),
+        escape_html(t.delete_warning),
+    )
+}
+
+/// Format a `gh release upload` approval notification, one asset per line.
+pub fn release_card(t: &Text, tag: &str, assets: &[String], clobber: bool) -> String {
+    let assets: String = assets
+        .iter()
+        .map(|a| format!("\n• <code>{}</code>", escape_html(a)))
+        .collect();
+    let clobber_line = if clobber {
+        format!("\n\n⚠️ <b>{}</b>", escape_html(t.clobber_warning))
+    } else {
+        String::new()
+    };
+    format!(
+        "{}\n\n<b>{}</b>  <code>{}</code>\n<b>{}</b>{}{}",
+        heading(t.release_card_icon, t.release_card_heading),
+        t.release_tag,
+        escape_html(tag),
+        t.release_assets,
+        assets,
+        clobber_line,
+    )
+}
+
+/// A card's first line: `icon`, if any, and the bold heading.
+fn heading(icon: &str, text: &str) -> String {
+    if icon.is_empty() {
+        format!("<b>{text}</b>")
+    } else {
+        format!("{icon} <b>{text}</b>")
+    }
+}
+
+/// One "key = value" line per field, each value cut to `value_chars`.
+fn fields_text(fields: &[String], value_chars: usize) -> String {
+    fields
+        .iter()
+        .map(|f| {
+            if let Some((k, v)) = f.split_once('=') {
+                format!("{} = {}", k, truncate(v, value_chars))
+            } else {
+                f.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What a `[templates]` card can refer to. Text is cut to the same lengths
+/// as on the built-in cards and escaped when printed.
+#[derive(Serialize)]
+pub struct CardVars<'a> {
+    /// PR title, or "METHOD endpoint" for `gh api`.
+    pub title: &'a str,
+    pub repo: &'a str,
+    /// "head → base"; PRs only.
+    pub branch: Option<&'a str>,
+    /// PR description; empty for `gh api`.
+    pub body: Cow<'a, str>,
+    /// The description's Markdown rendered as Telegram HTML, printed as is.
+    pub body_html: minijinja::Value,
+    pub draft: bool,
+    pub reviewers: &'a [String],
+    pub assignees: &'a [String],
+    pub labels: &'a [String],
+    pub milestone: Option<&'a str>,
+    /// "passing", "failing", "pending" or "none", with `ci_status` on.
+    pub ci: Option<&'static str>,
+    /// `--hostname` of a `gh api` call.
+    pub host: Option<&'a str>,
+    /// `-f` / `-F` fields of a `gh api` call.
+    pub fields: Vec<CardField<'a>>,
+    pub severity: String,
+    pub machine: String,
+    pub agent: String,
+    /// The working directory, `~/…` under the home directory.
+    pub cwd: String,
+    /// In a git checkout: its branch, and `git status` in a few words.
+    pub git_branch: Option<String>,
+    pub git_status: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CardField<'a> {
+    pub name: &'a str,
+    pub value: Cow<'a, str>,
+}
+
+impl<'a> CardVars<'a> {
+    pub fn new(title: &'a str, repo: &'a str, severity: Severity) -> Self {
+        let git = crate::pr::git_state();
+        let t = i18n::text();
+        CardVars {
+            title,
+            repo,
+            branch: None,
+            body: Cow::Borrowed(""),
+            body_html: minijinja::Value::from_safe_string(String::new()),
+            draft: false,
+            reviewers: &[],
+            assignees: &[],
+            labels: &[],
+            milestone: None,
+            ci: None,
+            host: None,
+            fields: Vec::new(),
+            severity: severity.to_string(),
+            machine: agent::hostname(),
+            agent: agent::detect(),
+            cwd: agent::workdir(),
+            git_status: git
+                .as_ref()
+                .map(|g| (t.git_status)(g.changed, g.untracked, g.ahead, g.behind)),
+            git_branch: git.map(|g| g.branch),
+        }
+    }
+
+    pub fn body(mut self, body: &'a str) -> Self {
+        self.body = truncate(body.trim(), BODY_CHARS);
+        self.body_html = minijinja::Value::from_safe_string(markdown::to_telegram_html(&self.body));
+        self
+    }
+
+    pub fn fields(mut self, fields: &'a [String]) -> Self {
+        self.fields = fields
             .iter()
             .map(|f| {
-                if let Some((k, v)) = f.split_once('=') {
-                    format!("{} = {}", k, truncate(v, 300))
-                } else {
-                    f.clone()
+                let (name, value) = f.split_once('=').unwrap_or((f, ""));
+                CardField {
+                    name,
+                    value: truncate(value, FIELD_CHARS),
                 }
             })
-            .collect::<Vec<_>>()
-            .join("\n");
-        html.push_str(&format!(
-            "\n\n<b>Fields</b>\n<pre>{}</pre>",
-            escape_html(&formatted)
-        ));
+            .collect();
+        self
+    }
+}
+
+/// Render a user template for a card. Values are HTML-escaped unless marked
+/// `|safe`, and an unknown variable is an error rather than empty text.
+pub fn render_card(template: &str, vars: &CardVars) -> Result<String> {
+    let mut env = minijinja::Environment::new();
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+    env.set_formatter(|out, _state, value| {
+        if value.is_none() {
+            return Ok(());
+        }
+        let text = value.to_string();
+        if value.is_safe() {
+            out.write_str(&text)?;
+        } else {
+            out.write_str(&escape_html(&text))?;
+        }
+        Ok(())
+    });
+    let html = env.render_str(template, vars)?;
+    if html.trim().is_empty() {
+        return Err(anyhow!("template rendered nothing"));
     }
-    send_with_approval(tg, &html)
+    Ok(html)
 }
 
-/// Long-poll `getUpdates` until the user taps Approve or Reject, or we time out.
+/// The card from `template` when one is configured and renders, else the
+/// built-in one, closed by the checkout line. A broken template must not
+/// stop the request from going out.
+pub fn card_or(
+    template: Option<&str>,
+    vars: CardVars,
+    built_in: impl FnOnce() -> String,
+) -> String {
+    let built_in = || format!("{}\n\n{}", built_in(), checkout_line(&vars));
+    let Some(template) = template else {
+        return built_in();
+    };
+    match render_card(template, &vars) {
+        Ok(html) => html,
+        Err(e) => {
+            tracing::warn!(error = %e, "card template failed, using the built-in card");
+            built_in()
+        }
+    }
+}
+
+/// "📂 ~/src/app · main · 2 changed": which checkout the command runs in.
+fn checkout_line(vars: &CardVars) -> String {
+    let mut line = format!("📂 <code>{}</code>", escape_html(&vars.cwd));
+    if let Some(branch) = &vars.git_branch {
+        line.push_str(&format!(" · <b>{}</b>", escape_html(branch)));
+    }
+    if let Some(status) = &vars.git_status {
+        line.push_str(" · ");
+        line.push_str(status);
+    }
+    line
+}
+
+/// Wait until the user taps Approve or Reject, or the time runs out.
 ///
 /// - Uses Telegram's server-side long-polling (up to 30 s per request) so we
 ///   get notified within ~1 s of the user tapping, with no busy-loop.
-/// - After a decision the inline buttons are replaced with a status label so
-///   the user can't accidentally double-tap.
+/// - Meanwhile a countdown ticks on the terminal, a reminder is posted a
+///   minute before the deadline, and Ctrl+C withdraws the request
+///   (`ApprovalResult::Cancelled`).
+/// - However the wait ends, the inline buttons are replaced with a status
+///   label so the user can't accidentally double-tap.
+/// - A `/panic` message from the approval chat aborts the wait with
+///   `ApprovalResult::Panic`.
+/// - With `tg.members` set, taps and `/panic` from anyone else are turned
+///   away, and the final label names who decided.
+/// - "➡️ Delegate" copies the card to `tg.delegate`'s chat; from then on
+///   only taps on that copy decide.
+/// - `/approve_<id>` and `/reject_<id>` messages count as taps on the
+///   buttons, for clients that cannot tap them.
+///
+/// All calls go over the connection the card was sent on, kept alive
+/// between polls.
 pub fn poll_for_approval(
     tg: &TgConfig,
     request_id: &str,
     message_id: i64,
     timeout_secs: u64,
-) -> Result<ApprovalResult> {
-    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
-    // HTTP timeout must exceed the Telegram long-poll window (30 s) plus overhead.
-    let a = agent(45);
+    approvals: u32,
+) -> Result<Approval> {
+    track_card(tg, message_id, timeout_secs);
+    let approval = block_on(wait_for_approval(
+        tg,
+        request_id,
+        message_id,
+        timeout_secs,
+        approvals,
+    ))?;
+    // On an error the card stays live; a later run marks it expired.
+    untrack_card(tg, message_id);
+    if let Err(e) = block_on(close_vote(tg, request_id)) {
+        tracing::warn!(error = %e, "could not close the poll");
+    }
+    close_prompt(request_id);
+    Ok(approval)
+}
+
+/// Post a reminder this long before an unanswered request expires.
+const REMIND_BEFORE_SECS: u64 = 60;
+
+async fn wait_for_approval(
+    tg: &TgConfig,
+    request_id: &str,
+    message_id: i64,
+    timeout_secs: u64,
+    approvals: u32,
+) -> Result<Approval> {
+    let deadline = TokioInstant::now() + Duration::from_secs(timeout_secs);
+    let t = i18n::text();
+    let tg = Arc::new(tg.clone());
+    // Debug lines would tear through it, and quiet means quiet.
+    let countdown = !output::plain() && output::verbosity() == Verbosity::Normal;
+    let clear_countdown = || {
+        if countdown {
+            eprint!("\r\x1b[2K");
+        }
+    };
+
+    let handover = RefCell::new(None);
+    let updates = poll_updates(&tg, request_id, message_id, deadline, approvals, &handover);
+    tokio::pin!(updates);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let mut reminded = timeout_secs <= 2 * REMIND_BEFORE_SECS;
+
+    let (result, label) = loop {
+        tokio::select! {
+            approval = &mut updates => {
+                clear_countdown();
+                let delegation = handover.take().map(|h: Handover| h.chain).unwrap_or_default();
+                return approval.map(|a| Approval { delegation, ..a });
+            }
+            _ = &mut ctrl_c => break (ApprovalResult::Cancelled, t.cancelled),
+            _ = tokio::time::sleep_until(deadline) => break (ApprovalResult::Timeout, t.expired),
+            _ = tick.tick() => {
+                let left = deadline.saturating_duration_since(TokioInstant::now()).as_secs();
+                if countdown {
+                    eprint!("\r  {} ", (t.countdown)(left / 60, left % 60));
+                }
+                if !reminded && left <= REMIND_BEFORE_SECS {
+                    reminded = true;
+                    let (tg, message_id) = match &*handover.borrow() {
+                        Some(h) => (Arc::new(h.tg.clone()), h.message_id),
+                        None => (tg.clone(), message_id),
+                    };
+                    tokio::spawn(async move {
+                        if let Err(e) = send_reminder(&tg, message_id, left).await {
+                            tracing::warn!(error = %e, "could not send Telegram reminder");
+                        }
+                    });
+                }
+            }
+        }
+    };
+    clear_countdown();
+    let handover = handover.take();
+    let (tg, message_id) = match &handover {
+        Some(h) => (&h.tg, h.message_id),
+        None => (&*tg, message_id),
+    };
+    settle(tg, message_id, label, None).await;
+    Ok(Approval {
+        result,
+        approver: None,
+        choice: None,
+        delegation: handover.map(|h| h.chain).unwrap_or_default(),
+    })
+}
+
+/// A request handed on with "➡️ Delegate".
+struct Handover {
+    /// The delegate's chat, which decides from now on.
+    tg: TgConfig,
+    /// Their copy of the card.
+    message_id: i64,
+    /// Who delegated, then who to.
+    chain: Vec<String>,
+}
+
+/// What one round of waiting brought.
+enum Polled {
+    Telegram(reqwest::Result<reqwest::Response>),
+    /// Left for this request by another gh-guard process.
+    Relayed(Vec<serde_json::Value>),
+}
+
+/// The `getUpdates` loop: returns once `approvals` different people have
+/// tapped Approve, anyone has tapped Reject or `/panic` arrives, and never
+/// on its own otherwise. Taps on other requests' cards, and `/panic`, are
+/// left in the [`mailbox`] for the processes waiting on them. A delegation
+/// is left in `handover`.
+async fn poll_updates(
+    home: &TgConfig,
+    request_id: &str,
+    card_id: i64,
+    deadline: TokioInstant,
+    approvals: u32,
+    handover: &RefCell<Option<Handover>>,
+) -> Result<Approval> {
+    let client = &transport()?.client;
+    let t = i18n::text();
     let mut offset: Option<i64> = None;
+    let mut cycle = 0u32;
+    let started = mailbox::now();
+    let mut poller = None;
+    // Telegram user ID and name of each approval so far.
+    let mut approved_by: Vec<(i64, Option<String>)> = Vec::new();
+    let mut chosen = None;
+    let vote = home.quorum.and_then(|_| poll_of(request_id));
+    let mut ballots = Ballots::default();
+    let mut prompt = prompt_of(request_id);
+    // Approvals only count once the prompt, if any, has been answered.
+    let mut confirmed = prompt.is_none();
 
     loop {
-        let remaining_secs = if Instant::now() < deadline {
-            (deadline - Instant::now()).as_secs()
-        } else {
-            0
-        };
-        if remaining_secs == 0 {
-            break;
+        cycle += 1;
+        if poller.is_none() {
+            poller = mailbox::try_poll();
+            if poller.is_some() {
+                tracing::debug!("polling Telegram for every waiting process");
+                offset = offset.max(mailbox::offset());
+            }
         }
-
         // Ask Telegram to hold the connection for up to 30 s (or remaining time).
-        let poll_timeout = remaining_secs.min(30);
+        let remaining_secs = deadline
+            .saturating_duration_since(TokioInstant::now())
+            .as_secs();
+        let poll_timeout = remaining_secs.clamp(1, 30);
 
         let mut req = serde_json::json!({
             "timeout": poll_timeout,
-            "allowed_updates": ["callback_query"]
+            "allowed_updates": ["callback_query", "message", "poll_answer"]
         });
         if let Some(off) = offset {
             req["offset"] = serde_json::json!(off);
         }
 
-        match a
-            .post(&tg.api("getUpdates"))
-            .set("Content-Type", "application/json")
-            .send_json(&req)
-        {
-            Ok(resp) => {
-                let data: serde_json::Value = resp
-                    .into_json()
+        let polled = if poller.is_some() {
+            // The request must outlive the long-poll window it asks for.
+            let poll = client
+                .post(home.api("getUpdates"))
+                .json(&req)
+                .timeout(Duration::from_secs(poll_timeout) + POLL_MARGIN)
+                .send()
+                .instrument(tracing::info_span!(
+                    "telegram",
+                    method = "getUpdates",
+                    cycle,
+                    poll_timeout
+                ));
+            // Dropping an unanswered poll loses nothing: Telegram only
+            // forgets updates once a later poll's offset has passed them.
+            tokio::select! {
+                result = poll => Polled::Telegram(result),
+                held = mailbox::wait(request_id, started) => Polled::Relayed(held),
+            }
+        } else {
+            // Another process polls; take over should it finish first.
+            tokio::select! {
+                held = mailbox::wait(request_id, started) => Polled::Relayed(held),
+                _ = tokio::time::sleep(POLLER_RETRY) => Polled::Relayed(Vec::new()),
+            }
+        };
+        let (updates, relayed) = match polled {
+            Polled::Telegram(Ok(resp)) => {
+                let data = resp
+                    .json::<serde_json::Value>()
+                    .await
                     .unwrap_or(serde_json::json!({"ok": false, "result": []}));
+                let Some(updates) = data["result"].as_array() else {
+                    // 409: something else polls with this bot token.
+                    let error = &data["description"];
+                    tracing::warn!(%error, "Telegram poll refused, retrying in 2 s");
+                    crate::metrics::poll_error();
+                    tokio::time::sleep(POLLER_RETRY).await;
+                    continue;
+                };
+                (updates.clone(), false)
+            }
+            Polled::Telegram(Err(e)) => {
+                tracing::warn!(error = %describe(e), "Telegram poll error, retrying in 5 s");
+                crate::metrics::poll_error();
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            Polled::Relayed(held) => (held, true),
+        };
+        tracing::debug!(count = updates.len(), relayed, "updates received");
+        // Before this request's own tap can end the loop.
+        if !relayed {
+            for update in &updates {
+                // Advance the offset so Telegram marks this update as seen.
+                let update_id = update["update_id"].as_i64().unwrap_or(0);
+                let next = update_id + 1;
+                offset = Some(offset.map_or(next, |prev| prev.max(next)));
+                if let Some(other) = other_request(update, request_id) {
+                    relay(Some(&other), update);
+                }
+            }
+            if let Some(offset) = offset {
+                mailbox::save_offset(offset);
+            }
+        }
+        for update in &updates {
+            // Once delegated, the delegate's chat and copy of the card decide.
+            let handed = handover
+                .borrow()
+                .as_ref()
+                .map(|h| (h.tg.clone(), h.message_id));
+            let (tg, message_id) = handed
+                .as_ref()
+                .map_or((home, card_id), |(tg, id)| (tg, *id));
+            if let Some(msg) = update.get("message") {
+                let panicked = panic_command(home, msg).or_else(|| panic_command(tg, msg));
+                if let Some(revoke) = panicked {
+                    if !relayed {
+                        relay(None, update);
+                    }
+                    let approver = sender_name(&msg["from"]);
+                    let label = tg.settled_label(t.panicked, approver.as_deref());
+                    settle(tg, message_id, &label, None).await;
+                    return Ok(Approval {
+                        result: ApprovalResult::Panic { revoke },
+                        approver,
+                        choice: None,
+                        delegation: Vec::new(),
+                    });
+                }
+                // A command in reply to the prompt counts as a command.
+                let answer = prompt
+                    .as_ref()
+                    .filter(|p| p.answered_by(tg, msg) && text_command(msg).is_none());
+                if let Some(prompt) = answer {
+                    if msg["text"].as_str().map(str::trim) != Some(prompt.expected.as_str()) {
+                        let mismatch = (t.confirm_mismatch)(&escape_html(&prompt.expected));
+                        let _ = reply(tg, msg, &mismatch).await;
+                        continue;
+                    }
+                    tracing::debug!("typed confirmation received");
+                    confirmed = true;
+                    let needed = tg.quorum.unwrap_or(1).max(approvals);
+                    let Some((true, names)) = vote.as_ref().and(ballots.decided(needed)) else {
+                        let _ = reply(tg, msg, t.confirmed).await;
+                        continue;
+                    };
+                    let approver = (!names.is_empty()).then(|| names.join(", "));
+                    let label = tg.settled_label(t.approved, approver.as_deref());
+                    settle(tg, message_id, &label, None).await;
+                    return Ok(Approval {
+                        result: ApprovalResult::Approved,
+                        approver,
+                        choice: None,
+                        delegation: Vec::new(),
+                    });
+                }
+            }
 
-                if let Some(updates) = data["result"].as_array() {
-                    for update in updates {
-                        // Advance the offset so Telegram marks this update as seen.
-                        let update_id = update["update_id"].as_i64().unwrap_or(0);
-                        let next = update_id + 1;
-                        offset = Some(offset.map_or(next, |prev| prev.max(next)));
-
-                        let Some(cq) = update.get("callback_query") else {
-                            continue;
-                        };
+            if let Some(answer) = update.get("poll_answer") {
+                if vote.is_none() || answer["poll_id"].as_str() != vote.as_deref() {
+                    continue;
+                }
+                if !tg.may_decide(&answer["user"]) {
+                    tracing::debug!(user = %answer["user"]["id"], "vote from a non-member ignored");
+                    continue;
+                }
+                ballots.cast(answer);
+                let needed = tg.quorum.unwrap_or(1).max(approvals);
+                let Some((approved, names)) = ballots.decided(needed) else {
+                    tracing::debug!(needed, "vote counted");
+                    continue;
+                };
+                if approved && !confirmed {
+                    tracing::debug!("approved by vote, waiting for the typed confirmation");
+                    continue;
+                }
+                let (result, label) = if approved {
+                    (ApprovalResult::Approved, t.approved)
+                } else {
+                    (ApprovalResult::Rejected, t.rejected)
+                };
+                let approver = (!names.is_empty()).then(|| names.join(", "));
+                let label = tg.settled_label(label, approver.as_deref());
+                settle(tg, message_id, &label, None).await;
+                return Ok(Approval {
+                    result,
+                    approver,
+                    choice: None,
+                    delegation: Vec::new(),
+                });
+            }
 
-                        let cb_data = cq["data"].as_str().unwrap_or("");
+            let (data, via) = if let Some(cq) = update.get("callback_query") {
+                let data = cq["data"].as_str().unwrap_or("").to_string();
+                (data, Via::Button(cq))
+            } else if let Some(data) = update.get("message").and_then(text_command) {
+                (data, Via::Command(&update["message"]))
+            } else {
+                continue;
+            };
+            // Commands count in the chat that decides, or are turned away
+            // from the one that delegated.
+            let chat = via.chat();
+            if matches!(via, Via::Command(_)) && !tg.is_chat(chat) && !home.is_chat(chat) {
+                continue;
+            }
+            let cb_data = data.as_str();
+            let approve = format!("approve:{request_id}");
+            let reject = format!("reject:{request_id}");
+            let delegate = format!("delegate:{request_id}");
+            let choice = Choice::tapped(cb_data, request_id);
+            let ours =
+                cb_data == approve || cb_data == reject || cb_data == delegate || choice.is_some();
+            if ours && handed.is_some() && !tg.is_chat(chat) {
+                let _ = via.answer(tg, t.delegated_away).await;
+                continue;
+            }
+            let from = via.sender();
+            if ours && !tg.may_decide(from) {
+                tracing::debug!(user = %from["id"], "tap from a non-member ignored");
+                let _ = via.answer(tg, t.not_a_member).await;
+                continue;
+            }
 
-                        if cb_data == format!("approve:{request_id}") {
-                            let _ = answer_callback(tg, cq, "✅ Approving…", &a);
-                            let _ = replace_buttons(tg, message_id, "✅ Approved", &a);
-                            return Ok(ApprovalResult::Approved);
-                        }
-                        if cb_data == format!("reject:{request_id}") {
-                            let _ = answer_callback(tg, cq, "❌ Rejecting…", &a);
-                            let _ = replace_buttons(tg, message_id, "❌ Rejected", &a);
-                            return Ok(ApprovalResult::Rejected);
-                        }
-                        // Stale callback from a previous request — ack and discard.
-                        let _ = answer_callback(tg, cq, "", &a);
+            if let Some(to) = tg.delegate.as_ref().filter(|_| cb_data == delegate) {
+                let by = sender_name(from).unwrap_or_else(|| from["id"].to_string());
+                let handed = hand_over(tg, to, request_id, message_id, &by).await;
+                let (decider, copy_id) = match handed {
+                    Ok(handed) => handed,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "could not delegate the request");
+                        let _ = via.answer(tg, t.delegate_failed).await;
+                        continue;
+                    }
+                };
+                tracing::debug!(to = %to.name, "request delegated");
+                let label = tg.settled_label(&(t.delegated)(&to.name), Some(&by));
+                settle(tg, message_id, &label, via.callback(t.delegating)).await;
+                // Approvals so far were the approval chat's; the delegate
+                // starts afresh, typing the confirmation too.
+                approved_by.clear();
+                chosen = None;
+                let expected = prompt
+                    .as_ref()
+                    .filter(|_| !confirmed)
+                    .map(|p| p.expected.clone());
+                if let Some(expected) = expected {
+                    close_prompt(request_id);
+                    let asked = ask_to_type(&decider, request_id, copy_id, &expected, false).await;
+                    if let Err(e) = asked {
+                        tracing::warn!(error = %e, "could not ask the delegate to type");
                     }
+                    prompt = prompt_of(request_id);
                 }
+                *handover.borrow_mut() = Some(Handover {
+                    tg: decider,
+                    message_id: copy_id,
+                    chain: vec![by, to.name.clone()],
+                });
+                continue;
             }
-            Err(e) => {
-                eprintln!("  (Telegram poll error: {e} — retrying in 5 s…)");
-                std::thread::sleep(Duration::from_secs(5));
+
+            if (cb_data == approve || choice.is_some()) && !confirmed {
+                let _ = via.answer(tg, t.confirm_first).await;
+                continue;
+            }
+            if cb_data == approve || choice.is_some() {
+                let user = from["id"].as_i64().unwrap_or_default();
+                if approved_by.iter().any(|(id, _)| *id == user) {
+                    let _ = via.answer(tg, t.already_approved).await;
+                    continue;
+                }
+                approved_by.push((user, sender_name(from)));
+                chosen = chosen.or(choice);
+                let count = approved_by.len() as u32;
+                if count < approvals {
+                    tracing::debug!(count, approvals, "approval counted");
+                    let counted = (t.approval_counted)(count, approvals);
+                    let _ = via.answer(tg, &counted).await;
+                    continue;
+                }
+                let names: Vec<String> = approved_by.into_iter().filter_map(|(_, n)| n).collect();
+                let approver = (!names.is_empty()).then(|| names.join(", "));
+                let label = tg.settled_label(t.approved, approver.as_deref());
+                settle(tg, message_id, &label, via.callback(t.approving)).await;
+                return Ok(Approval {
+                    result: ApprovalResult::Approved,
+                    approver,
+                    choice: chosen,
+                    delegation: Vec::new(),
+                });
             }
+            if cb_data == reject {
+                let approver = sender_name(from);
+                let label = tg.settled_label(t.rejected, approver.as_deref());
+                settle(tg, message_id, &label, via.callback(t.rejecting)).await;
+                return Ok(Approval {
+                    result: ApprovalResult::Rejected,
+                    approver,
+                    choice: None,
+                    delegation: Vec::new(),
+                });
+            }
+            // Another process's card, relayed above: it answers the tap.
+            if other_request(update, request_id).is_some() {
+                continue;
+            }
+            // A settled card's label, or something unknown — ack and discard.
+            let _ = via.answer(tg, "").await;
         }
     }
+}
 
-    Ok(ApprovalResult::Timeout)
+/// How a decision arrived, so that it is answered the same way.
+#[derive(Clone, Copy)]
+enum Via<'a> {
+    /// A tap on a button: the callback query.
+    Button(&'a serde_json::Value),
+    /// `/approve_<id>` or `/reject_<id>`: the message.
+    Command(&'a serde_json::Value),
 }
 
-// ── Private helpers ───────────────────────────────────────────────────────────
+impl<'a> Via<'a> {
+    /// The Telegram `User` who decided.
+    fn sender(self) -> &'a serde_json::Value {
+        match self {
+            Via::Button(v) | Via::Command(v) => &v["from"],
+        }
+    }
 
-/// Acknowledge a callback query, removing the loading spinner on the phone.
-fn answer_callback(
+    /// The chat the tap or command came from.
+    fn chat(self) -> &'a serde_json::Value {
+        match self {
+            Via::Button(cq) => &cq["message"]["chat"],
+            Via::Command(msg) => &msg["chat"],
+        }
+    }
+
+    /// Tell the approver `text`: as a notice on the phone for a tap, in a
+    /// reply for a command (none when empty).
+    async fn answer(self, tg: &TgConfig, text: &str) -> Result<()> {
+        match self {
+            Via::Button(cq) => answer_callback(tg, cq, text).await,
+            Via::Command(_) if text.is_empty() => Ok(()),
+            Via::Command(msg) => reply(tg, msg, &escape_html(text)).await,
+        }
+    }
+
+    /// The tap to acknowledge with `text` as the card is settled; a command
+    /// has none.
+    fn callback(self, text: &'a str) -> Option<(&'a serde_json::Value, &'a str)> {
+        match self {
+            Via::Button(cq) => Some((cq, text)),
+            Via::Command(_) => None,
+        }
+    }
+}
+
+/// `/approve_<id>` or `/reject_<id>` in `msg`, for clients that cannot tap
+/// buttons (watches, notification previews), as the callback data of the
+/// button it stands for.
+fn text_command(msg: &serde_json::Value) -> Option<String> {
+    let text = msg["text"].as_str()?.trim();
+    // Commands in groups may carry the bot name: /approve_ab12cd34@my_bot
+    let cmd = text.split_whitespace().next()?.split('@').next()?;
+    let (action, id) = cmd.strip_prefix('/')?.split_once('_')?;
+    ["approve", "reject"]
+        .contains(&action)
+        .then(|| format!("{action}:{id}"))
+}
+
+/// Copy the card `message_id` into `to`'s chat with Approve / Reject under
+/// it, and tell them who handed it over. Returns the chat that decides from
+/// now on and the copy's message ID.
+async fn hand_over(
     tg: &TgConfig,
-    cq: &serde_json::Value,
-    text: &str,
-    a: &ureq::Agent,
-) -> Result<()> {
-    let id = cq["id"].as_str().unwrap_or("");
-    a.post(&tg.api("answerCallbackQuery"))
-        .set("Content-Type", "application/json")
-        .send_json(&serde_json::json!({"callback_query_id": id, "text": text}))?;
-    Ok(())
+    to: &Delegate,
+    request_id: &str,
+    message_id: i64,
+    by: &str,
+) -> Result<(TgConfig, i64)> {
+    let t = i18n::text();
+    let decider = TgConfig {
+        chat_id: to.chat_id.clone(),
+        thread_id: None,
+        members: to.members.clone(),
+        quorum: None,
+        delegate: None,
+        ..tg.clone()
+    };
+    let payload = serde_json::json!({
+        "chat_id": decider.chat_id,
+        "from_chat_id": tg.chat_id,
+        "message_id": message_id,
+        "reply_markup": {"inline_keyboard": [decision_row(t, request_id)]},
+    });
+    let resp = call(tg, "copyMessage", &payload).await?;
+    let Some(copy_id) = resp["result"]["message_id"].as_i64() else {
+        return Err(Error::TelegramApi(format!(
+            "Telegram copyMessage failed: {}",
+            resp["description"].as_str().unwrap_or("unknown error")
+        ))
+        .into());
+    };
+    let note = serde_json::json!({
+        "chat_id": decider.chat_id,
+        "text": (t.handed_over)(&escape_html(by)),
+        "parse_mode": "HTML",
+        "reply_parameters": {"message_id": copy_id},
+    });
+    send_message(&decider, &note).await?;
+    Ok((decider, copy_id))
 }
 
-/// Swap the Approve/Reject buttons for a single non-actionable status label.
-fn replace_buttons(
+/// The request whose card was tapped, whose poll was answered or whose
+/// prompt was replied to, if that is not `request_id`.
+fn other_request(update: &serde_json::Value, request_id: &str) -> Option<String> {
+    let id = match update["poll_answer"]["poll_id"].as_str() {
+        Some(poll_id) => request_of(poll_id)?,
+        None if update.get("message").is_some() => match text_command(&update["message"]) {
+            Some(data) => data.split_once(':')?.1.to_string(),
+            None => prompted_by(&update["message"])?,
+        },
+        None => {
+            let data = update["callback_query"]["data"].as_str()?;
+            let (action, id) = data.split_once(':')?;
+            let known = ["approve", "reject", "delegate"].contains(&action);
+            if !known && Choice::parse(action).is_none() {
+                return None;
+            }
+            id.to_string()
+        }
+    };
+    (id != request_id).then_some(id)
+}
+
+/// Leave `update` in the mailbox; a failure is logged, as the update would
+/// have been lost anyway.
+fn relay(to: Option<&str>, update: &serde_json::Value) {
+    tracing::debug!(?to, "relaying update to another process");
+    if let Err(e) = mailbox::post(to, update) {
+        tracing::warn!(error = %e, "could not relay Telegram update");
+    }
+}
+
+/// Acknowledge the tap, if any, and swap the buttons for `label`. Best
+/// effort: the decision stands even if Telegram misses these.
+async fn settle(
     tg: &TgConfig,
     message_id: i64,
     label: &str,
-    a: &ureq::Agent,
+    callback: Option<(&serde_json::Value, &str)>,
+) {
+    if let Some((cq, text)) = callback {
+        let _ = answer_callback(tg, cq, text).await;
+    }
+    let _ = replace_buttons(tg, message_id, label).await;
+}
+
+// ── Open cards ────────────────────────────────────────────────────────────────
+
+/// Cards waiting for a decision, so that one left live by a process that
+/// was killed can be marked expired by the next.
+const OPEN_CARDS: &str = "open_cards.json";
+
+/// Time past a card's deadline before another process takes it as
+/// abandoned.
+const ABANDONED_AFTER_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenCard {
+    chat_id: String,
+    message_id: i64,
+    /// Unix seconds.
+    expires_at: u64,
+}
+
+fn track_card(tg: &TgConfig, message_id: i64, timeout_secs: u64) {
+    let card = OpenCard {
+        chat_id: tg.chat_id.clone(),
+        message_id,
+        expires_at: mailbox::now() + timeout_secs,
+    };
+    let tracked = crate::state::update(OPEN_CARDS, |cards: &mut Vec<OpenCard>| cards.push(card));
+    if let Err(e) = tracked {
+        tracing::warn!(error = %e, "could not record the open card");
+    }
+}
+
+/// Cards still waiting for a decision, across chats.
+pub fn open_cards() -> usize {
+    let now = mailbox::now();
+    crate::state::load::<Vec<OpenCard>>(OPEN_CARDS)
+        .iter()
+        .filter(|c| c.expires_at > now)
+        .count()
+}
+
+fn untrack_card(tg: &TgConfig, message_id: i64) {
+    let _ = crate::state::update(OPEN_CARDS, |cards: &mut Vec<OpenCard>| {
+        cards.retain(|c| c.chat_id != tg.chat_id || c.message_id != message_id);
+    });
+}
+
+/// Swap the buttons of this chat's abandoned cards for "⏱ Expired". Best
+/// effort: a card that cannot be edited is forgotten all the same.
+async fn expire_abandoned(tg: &TgConfig) {
+    let cutoff = mailbox::now().saturating_sub(ABANDONED_AFTER_SECS);
+    let open: Vec<OpenCard> = crate::state::load(OPEN_CARDS);
+    if !open
+        .iter()
+        .any(|c| c.chat_id == tg.chat_id && c.expires_at < cutoff)
+    {
+        return;
+    }
+    let abandoned = crate::state::update(OPEN_CARDS, |cards: &mut Vec<OpenCard>| {
+        let (abandoned, open) = std::mem::take(cards)
+            .into_iter()
+            .partition(|c| c.chat_id == tg.chat_id && c.expires_at < cutoff);
+        *cards = open;
+        abandoned
+    });
+    for card in abandoned.unwrap_or_default() {
+        tracing::debug!(
+            message_id = card.message_id,
+            "marking an abandoned card expired"
+        );
+        let _ = replace_buttons(tg, card.message_id, i18n::text().expired).await;
+    }
+}
+
+/// How often a process that does not poll Telegram checks whether the one
+/// that does has finished; also the pause after a refused poll.
+const POLLER_RETRY: Duration = Duration::from_secs(2);
+
+// ── Votes ─────────────────────────────────────────────────────────────────────
+
+/// Polls open under cards, so that the process polling Telegram knows
+/// whose an answer is.
+const OPEN_VOTES: &str = "open_votes.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenVote {
+    poll_id: String,
+    request_id: String,
+    chat_id: String,
+    /// The poll's own message.
+    message_id: i64,
+    /// Unix seconds when it was sent; polls of processes long gone are
+    /// forgotten a day later.
+    at: u64,
+}
+
+/// Post the poll that decides `request_id` in reply to its card.
+async fn open_vote(tg: &TgConfig, request_id: &str, card_id: i64, silent: bool) -> Result<()> {
+    let t = i18n::text();
+    let mut payload = serde_json::json!({
+        "chat_id": tg.chat_id,
+        "question": t.vote_question,
+        "options": [{"text": t.approve}, {"text": t.reject}],
+        "is_anonymous": false,
+        "disable_notification": silent,
+        "reply_parameters": {"message_id": card_id},
+    });
+    if let Some(thread_id) = tg.thread_id {
+        payload["message_thread_id"] = thread_id.into();
+    }
+    let resp = call(tg, "sendPoll", &payload).await?;
+    let (Some(poll_id), Some(message_id)) = (
+        resp["result"]["poll"]["id"].as_str(),
+        resp["result"]["message_id"].as_i64(),
+    ) else {
+        return Err(Error::TelegramApi(format!(
+            "Telegram sendPoll failed: {}",
+            resp["description"].as_str().unwrap_or("unknown error")
+        ))
+        .into());
+    };
+    let vote = OpenVote {
+        poll_id: poll_id.to_string(),
+        request_id: request_id.to_string(),
+        chat_id: tg.chat_id.clone(),
+        message_id,
+        at: mailbox::now(),
+    };
+    crate::state::update(OPEN_VOTES, |votes: &mut Vec<OpenVote>| {
+        votes.retain(|v| v.at + 24 * 60 * 60 > vote.at);
+        votes.push(vote);
+    })
+}
+
+/// The poll deciding `request_id`, if it has one.
+fn poll_of(request_id: &str) -> Option<String> {
+    let votes: Vec<OpenVote> = crate::state::load(OPEN_VOTES);
+    votes
+        .into_iter()
+        .find(|v| v.request_id == request_id)
+        .map(|v| v.poll_id)
+}
+
+/// The request the poll `poll_id` decides.
+fn request_of(poll_id: &str) -> Option<String> {
+    let votes: Vec<OpenVote> = crate::state::load(OPEN_VOTES);
+    votes
+        .into_iter()
+        .find(|v| v.poll_id == poll_id)
+        .map(|v| v.request_id)
+}
+
+/// Stop `request_id`'s poll, if any, so no one votes on a settled request.
+async fn close_vote(tg: &TgConfig, request_id: &str) -> Result<()> {
+    let closed = crate::state::update(OPEN_VOTES, |votes: &mut Vec<OpenVote>| {
+        let i = votes.iter().position(|v| v.request_id == request_id)?;
+        Some(votes.remove(i))
+    })?;
+    let Some(vote) = closed else {
+        return Ok(());
+    };
+    let payload = serde_json::json!({"chat_id": vote.chat_id, "message_id": vote.message_id});
+    call(tg, "stopPoll", &payload).await?;
+    Ok(())
+}
+
+// ── Typed confirmations ──────────────────────────────────────────────────────
+
+/// Prompts asking for a request's confirmation to be typed, so that the
+/// process polling Telegram knows whose a reply is.
+const OPEN_PROMPTS: &str = "open_prompts.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenPrompt {
+    request_id: String,
+    chat_id: String,
+    /// The prompt's own message, which the answer replies to.
+    message_id: i64,
+    /// What the answer must say, exactly.
+    expected: String,
+    /// Unix seconds when it was sent; prompts of processes long gone are
+    /// forgotten a day later.
+    at: u64,
+}
+
+impl OpenPrompt {
+    /// Whether `msg` replies to this prompt, from someone who may decide.
+    fn answered_by(&self, tg: &TgConfig, msg: &serde_json::Value) -> bool {
+        self.replied_to_by(msg) && tg.may_decide(&msg["from"])
+    }
+
+    fn replied_to_by(&self, msg: &serde_json::Value) -> bool {
+        msg["chat"]["id"].as_i64().map(|id| id.to_string()) == Some(self.chat_id.clone())
+            && msg["reply_to_message"]["message_id"].as_i64() == Some(self.message_id)
+    }
+}
+
+/// Ask, in reply to the card, for `expected` to be typed. The prompt opens
+/// the keyboard with a reply to it already started (ForceReply).
+async fn ask_to_type(
+    tg: &TgConfig,
+    request_id: &str,
+    card_id: i64,
+    expected: &str,
+    silent: bool,
 ) -> Result<()> {
-    a.post(&tg.api("editMessageReplyMarkup"))
-        .set("Content-Type", "application/json")
-        .send_json(&serde_json::json!({
+    let payload = serde_json::json!({
+        "chat_id": tg.chat_id,
+        "text": (i18n::text().type_to_confirm)(&escape_html(expected)),
+        "parse_mode": "HTML",
+        "disable_notification": silent,
+        "reply_parameters": {"message_id": card_id},
+        "reply_markup": {"force_reply": true, "input_field_placeholder": expected},
+    });
+    let message_id = send_message(tg, &payload).await?;
+    let prompt = OpenPrompt {
+        request_id: request_id.to_string(),
+        chat_id: tg.chat_id.clone(),
+        message_id,
+        expected: expected.to_string(),
+        at: mailbox::now(),
+    };
+    crate::state::update(OPEN_PROMPTS, |prompts: &mut Vec<OpenPrompt>| {
+        prompts.retain(|p| p.at + 24 * 60 * 60 > prompt.at);
+        prompts.push(prompt);
+    })
+}
+
+/// The prompt `request_id` waits on, if it has one.
+fn prompt_of(request_id: &str) -> Option<OpenPrompt> {
+    let prompts: Vec<OpenPrompt> = crate::state::load(OPEN_PROMPTS);
+    prompts.into_iter().find(|p| p.request_id == request_id)
+}
+
+/// The request whose prompt `msg` replies to, if any.
+fn prompted_by(msg: &serde_json::Value) -> Option<String> {
+    let prompts: Vec<OpenPrompt> = crate::state::load(OPEN_PROMPTS);
+    prompts
+        .into_iter()
+        .find(|p| p.replied_to_by(msg))
+        .map(|p| p.request_id)
+}
+
+/// Forget `request_id`'s prompt once it is settled.
+fn close_prompt(request_id: &str) {
+    let _ = crate::state::update(OPEN_PROMPTS, |prompts: &mut Vec<OpenPrompt>| {
+        prompts.retain(|p| p.request_id != request_id);
+    });
+}
+
+/// Answer `msg` with `html`, in reply to it.
+async fn reply(tg: &TgConfig, msg: &serde_json::Value, html: &str) -> Result<()> {
+    let payload = serde_json::json!({
+        "chat_id": tg.chat_id,
+        "text": html,
+        "parse_mode": "HTML",
+        "reply_parameters": {"message_id": msg["message_id"]},
+    });
+    send_message(tg, &payload).await.map(|_| ())
+}
+
+/// Each voter's current answer; a retracted vote is dropped.
+#[derive(Default)]
+struct Ballots(Vec<(i64, Option<String>, bool)>);
+
+impl Ballots {
+    fn cast(&mut self, answer: &serde_json::Value) {
+        let user = answer["user"]["id"].as_i64().unwrap_or_default();
+        self.0.retain(|(id, ..)| *id != user);
+        if let Some(option) = answer["option_ids"][0].as_i64() {
+            let name = sender_name(&answer["user"]);
+            self.0.push((user, name, option == 0));
+        }
+    }
+
+    /// Whether `needed` votes approve or reject, and who cast them.
+    fn decided(&self, needed: u32) -> Option<(bool, Vec<String>)> {
+        [true, false].into_iter().find_map(|approve| {
+            let votes = self.0.iter().filter(|(.., a)| *a == approve);
+            if votes.clone().count() < needed as usize {
+                return None;
+            }
+            let names = votes.filter_map(|(_, name, _)| name.clone()).collect();
+            Some((approve, names))
+        })
+    }
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────────
+
+/// "@username", falling back to the first name, of a Telegram `User` object.
+fn sender_name(user: &serde_json::Value) -> Option<String> {
+    user["username"]
+        .as_str()
+        .map(|u| format!("@{u}"))
+        .or_else(|| user["first_name"].as_str().map(str::to_string))
+}
+
+/// Recognise `/panic` or `/panic revoke` sent from the approval chat.
+/// Returns whether revocation was requested.
+fn panic_command(tg: &TgConfig, msg: &serde_json::Value) -> Option<bool> {
+    let chat = msg["chat"]["id"].as_i64()?.to_string();
+    if chat != tg.chat_id || !tg.may_decide(&msg["from"]) {
+        return None;
+    }
+    let text = msg["text"].as_str()?.trim();
+    let mut words = text.split_whitespace();
+    // Commands in groups may carry the bot name: /panic@my_bot
+    let cmd = words.next()?.split('@').next()?;
+    if cmd != "/panic" {
+        return None;
+    }
+    Some(words.next() == Some("revoke"))
+}
+
+/// Reply to the approval card that it is about to expire.
+async fn send_reminder(tg: &TgConfig, message_id: i64, left_secs: u64) -> Result<()> {
+    call(
+        tg,
+        "sendMessage",
+        &serde_json::json!({
+            "chat_id": tg.chat_id,
+            "text": (i18n::text().reminder)(left_secs),
+            "reply_parameters": {"message_id": message_id},
+        }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Acknowledge a callback query, removing the loading spinner on the phone.
+async fn answer_callback(tg: &TgConfig, cq: &serde_json::Value, text: &str) -> Result<()> {
+    let id = cq["id"].as_str().unwrap_or("");
+    call(
+        tg,
+        "answerCallbackQuery",
+        &serde_json::json!({"callback_query_id": id, "text": text}),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Swap the Approve/Reject buttons for a single non-actionable status label.
+async fn replace_buttons(tg: &TgConfig, message_id: i64, label: &str) -> Result<()> {
+    call(
+        tg,
+        "editMessageReplyMarkup",
+        &serde_json::json!({
             "chat_id": tg.chat_id,
             "message_id": message_id,
             "reply_markup": {
                 "inline_keyboard": [[{"text": label, "callback_data": "noop"}]]
             }
-        }))?;
+        }),
+    )
+    .await?;
     Ok(())
 }
 
-fn escape_html(s: &str) -> String {
+// ── Retry ─────────────────────────────────────────────────────────────────────
+
+/// Tries per Bot API call before the error is passed on.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Backoff before the second try; doubled for each one after that.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// A 429 asking us to wait longer than this is reported instead of waited out.
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// POST `payload` to a Bot API method and return the decoded response.
+async fn call(
+    tg: &TgConfig,
+    method: &str,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    send(tg, method, |r| r.json(payload)).await
+}
+
+/// POST to a Bot API method, with `body` adding the payload to each try,
+/// and return the decoded response.
+///
+/// Connection errors and 5xx answers are retried with jittered exponential
+/// backoff; a 429 is retried after the `retry_after` Telegram asks for.
+/// Other errors (bad token, malformed request) fail at once.
+async fn send(
+    tg: &TgConfig,
+    method: &str,
+    body: impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+) -> Result<serde_json::Value> {
+    let client = &transport()?.client;
+    let span = tracing::info_span!("telegram", method);
+    let started = std::time::Instant::now();
+    let mut attempt = 1;
+    loop {
+        let sent = body(client.post(tg.api(method)))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .instrument(span.clone())
+            .await;
+        let (err, wait) = match sent {
+            Ok(resp) if resp.status().is_success() => {
+                crate::metrics::telegram_call(started.elapsed());
+                return resp.json().await.context("Invalid Telegram response");
+            }
+            Ok(resp) => {
+                let code = resp.status().as_u16();
+                let body: serde_json::Value = resp.json().await.unwrap_or_default();
+                let err = anyhow::Error::new(Error::TelegramApi(format!(
+                    "Telegram {method} failed: {}",
+                    body["description"].as_str().unwrap_or("unknown error")
+                )));
+                match code {
+                    429 => {
+                        let secs = body["parameters"]["retry_after"].as_u64().unwrap_or(1);
+                        if secs > MAX_RETRY_AFTER_SECS {
+                            return Err(err);
+                        }
+                        (err, Duration::from_secs(secs))
+                    }
+                    500..=599 => (err, backoff(attempt)),
+                    _ => return Err(err),
+                }
+            }
+            Err(e) => (
+                anyhow::Error::new(Error::TelegramUnreachable(describe(e))),
+                backoff(attempt),
+            ),
+        };
+        if attempt >= MAX_ATTEMPTS {
+            return Err(err);
+        }
+        tracing::warn!(
+            error = %err,
+            attempt,
+            wait_ms = wait.as_millis() as u64,
+            "retrying Telegram call"
+        );
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// A request error and its causes, without the URL — which carries the bot
+/// token.
+fn describe(e: reqwest::Error) -> String {
+    let e = e.without_url();
+    let mut text = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        text.push_str(": ");
+        text.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    text
+}
+
+/// Exponential delay for the given attempt, randomised to between half and
+/// all of it so that several gh-guard processes do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let full = BASE_DELAY * 2u32.pow(attempt - 1);
+    let jitter = (Uuid::new_v4().as_u128() % 1000) as u32;
+    full / 2 + full / 2 * jitter / 1000
+}
+
+pub fn escape_html(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
 }
 
-fn truncate(s: &str, max_chars: usize) -> &str {
-    if s.len() <= max_chars {
-        s
-    } else {
-        let mut b = max_chars;
-        while b > 0 && !s.is_char_boundary(b) {
-            b -= 1;
+/// `s` cut to at most `max_chars` characters, with "…" marking the cut.
+pub fn truncate(s: &str, max_chars: usize) -> Cow<'_, str> {
+    match s.char_indices().nth(max_chars) {
+        None => Cow::Borrowed(s),
+        Some((end, _)) => Cow::Owned(format!("{}…", &s[..end])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{DE, EN};
+
+    fn fields(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn pr_card_plain() {
+        insta::assert_snapshot!(pr_card(
+            &EN,
+            "Fix login redirect",
+            "Closes #12.",
+            "fix/login → main",
+            false,
+            &[]
+        ));
+    }
+
+    #[test]
+    fn pr_card_draft_without_body() {
+        insta::assert_snapshot!(pr_card(&EN, "WIP", "  \n ", "wip → main", true, &[]));
+    }
+
+    #[test]
+    fn pr_card_escapes_html() {
+        insta::assert_snapshot!(pr_card(
+            &EN,
+            "<b>bold</b> & <script>alert(1)</script>",
+            "Use `Vec<T>` & `Option<&str>`; a -> b, x < y > z.\n</pre><a href=\"x\">",
+            "feat/<x>&y → main",
+            false,
+            &[]
+        ));
+    }
+
+    #[test]
+    fn pr_card_emoji() {
+        insta::assert_snapshot!(pr_card(
+            &EN,
+            "🚀 Ship it 👩‍💻",
+            "✅ tests\n🇫🇷 locale\n日本語の説明",
+            "ü/ñ → main",
+            false,
+            &[]
+        ));
+    }
+
+    #[test]
+    fn pr_card_long_body_is_cut_with_ellipsis() {
+        let body = "abcdefghij".repeat(301);
+        let card = pr_card(&EN, "Long", &body, "a → main", false, &[]);
+        assert!(card.ends_with(&format!("{}…", "abcdefghij".repeat(300))));
+        insta::assert_snapshot!(card.replace(&"abcdefghij".repeat(299), "[2990 chars]"));
+    }
+
+    #[test]
+    fn pr_card_renders_markdown() {
+        let body = "<!-- What does this change? -->\n## Why\n\nLogin **loops** on \
+                    [expiry](https://x.dev/1).\n\n- [x] `cargo test`\n- ~~docs~~\n\n\
+                    ```sh\nmake <all>\n```";
+        insta::assert_snapshot!(pr_card(&EN, "Fix", body, "fix → main", false, &[]));
+    }
+
+    #[test]
+    fn pr_card_cuts_multibyte_text_on_characters() {
+        // 3-byte and 4-byte characters straddling the 3000th character.
+        let body = format!("{}€😀😀", "é".repeat(2998));
+        let card = pr_card(&EN, "Multibyte", &body, "a → main", false, &[]);
+        insta::assert_snapshot!(card.replace(&"é".repeat(2990), "[2990 × é]"));
+    }
+
+    #[test]
+    fn pr_card_escapes_after_cutting() {
+        // Cutting must never split an entity such as &amp;.
+        let body = format!("{}&&&", "x".repeat(2999));
+        let card = pr_card(&EN, "Amp", &body, "a → main", false, &[]);
+        insta::assert_snapshot!(card.replace(&"x".repeat(2990), "[2990 × x]"));
+    }
+
+    #[test]
+    fn api_card_with_fields() {
+        insta::assert_snapshot!(api_card(
+            &EN,
+            "PATCH",
+            Some("repos/o/r/pulls/7"),
+            None,
+            &fields(&["title=<new> & improved 🎉", "state=closed", "flag"]),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn api_card_without_endpoint_or_fields() {
+        insta::assert_snapshot!(api_card(&EN, "DELETE", None, None, &[], &[]));
+    }
+
+    #[test]
+    fn api_card_shows_get_fields_as_query() {
+        insta::assert_snapshot!(api_card(
+            &EN,
+            "GET",
+            Some("search/issues"),
+            None,
+            &fields(&["q=is:open bug"]),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn api_card_escapes_method_and_endpoint() {
+        insta::assert_snapshot!(api_card(
+            &EN,
+            "<POST>",
+            Some("repos/o/r/issues?q=a&b=<c>"),
+            None,
+            &[],
+            &[]
+        ));
+    }
+
+    #[test]
+    fn api_card_cuts_long_values() {
+        let value = format!("{}😀😀", "ß".repeat(299));
+        let card = api_card(
+            &EN,
+            "POST",
+            Some("graphql"),
+            None,
+            &fields(&[&format!("query={value}"), "empty="]),
+            &[],
+        );
+        insta::assert_snapshot!(card.replace(&"ß".repeat(290), "[290 × ß]"));
+    }
+
+    #[test]
+    fn api_card_on_another_host() {
+        insta::assert_snapshot!(api_card(
+            &EN,
+            "POST",
+            Some("repos/o/r/issues"),
+            Some("ghe.corp"),
+            &[],
+            &[]
+        ));
+    }
+
+    #[test]
+    fn api_card_leads_with_the_webhook_url() {
+        insta::assert_snapshot!(api_card(
+            &EN,
+            "POST",
+            Some("repos/o/r/hooks"),
+            None,
+            &fields(&["config[url]=https://x.test/?a=1&b=<2>", "events[]=push"]),
+            &[(EN.webhook_url, "https://x.test/?a=1&b=<2>".to_string())]
+        ));
+    }
+
+    #[test]
+    fn cut_text_goes_into_a_document() {
+        assert!(body_document(&"x".repeat(3000)).is_none());
+        let doc = body_document(&format!(" {} ", "x".repeat(3001))).unwrap();
+        assert_eq!(doc.text, "x".repeat(3001));
+
+        assert!(fields_document(&fields(&["a=1", "flag"])).is_none());
+        let long = format!("query={}", "q".repeat(301));
+        let doc = fields_document(&fields(&["a=1", &long])).unwrap();
+        assert_eq!(doc.text, format!("a = 1\nquery = {}", "q".repeat(301)));
+        let many: Vec<String> = (0..20)
+            .map(|i| format!("f{i}={}", "v".repeat(200)))
+            .collect();
+        assert!(fields_document(&many).is_some());
+    }
+
+    #[test]
+    fn pr_card_with_people_and_labels() {
+        let meta = [
+            (EN.reviewers, "alice, org/team".to_string()),
+            (EN.labels, "bug, <good first issue>".to_string()),
+            (EN.milestone, "v1.0".to_string()),
+        ];
+        insta::assert_snapshot!(pr_card(&EN, "Fix it", "", "fix → main", false, &meta));
+    }
+
+    #[test]
+    fn pr_card_german() {
+        insta::assert_snapshot!(pr_card(
+            &DE,
+            "Ärger beheben",
+            "Behebt #3.",
+            "fix → main",
+            true,
+            &[]
+        ));
+    }
+
+    #[test]
+    fn api_card_german() {
+        insta::assert_snapshot!(api_card(
+            &DE,
+            "DELETE",
+            None,
+            None,
+            &fields(&["grund=alt"]),
+            &[]
+        ));
+    }
+
+    /// Fixed machine and agent, so the output does not depend on the host.
+    fn vars<'a>(title: &'a str, list: &'a [String]) -> CardVars<'a> {
+        CardVars {
+            machine: "laptop".into(),
+            agent: "claude-code".into(),
+            cwd: "~/src/app".into(),
+            git_branch: Some("main".into()),
+            git_status: Some("clean".into()),
+            ..CardVars::new(title, "o/r", Severity::High).fields(list)
         }
-        &s[..b]
+    }
+
+    #[test]
+    fn template_card_escapes_values() {
+        let list = fields(&["title=<script>", "body=a & b"]);
+        let template = "🔧 <b>{{ title }}</b> on {{ repo }} [{{ severity }}]\n\
+                        {% for f in fields %}{{ f.name }}: {{ f.value }}\n{% endfor %}\
+                        <i>{{ agent }}@{{ machine }}</i>";
+        insta::assert_snapshot!(
+            render_card(template, &vars("POST repos/o/r/issues", &list)).unwrap()
+        );
+    }
+
+    #[test]
+    fn template_card_body_html_is_not_escaped_again() {
+        let vars = vars("T", &[]).body("**a** & <b>");
+        assert_eq!(
+            render_card("{{ body_html }} | {{ body }}", &vars).unwrap(),
+            "<b>a</b> &amp; &lt;b&gt; | **a** &amp; &lt;b&gt;"
+        );
+    }
+
+    #[test]
+    fn template_card_rejects_unknown_variables_and_empty_output() {
+        let err = render_card("{{ titel }}", &vars("x", &[])).unwrap_err();
+        assert!(err.to_string().contains("undefined"), "{err}");
+        assert!(render_card("{% if branch %}x{% endif %}", &vars("x", &[])).is_err());
+        assert_eq!(render_card("{{ branch }}·", &vars("x", &[])).unwrap(), "·");
+    }
+
+    #[test]
+    fn broken_template_falls_back_to_the_built_in_card() {
+        let built_in = || "built-in".to_string();
+        let with_checkout = "built-in\n\n📂 <code>~/src/app</code> · <b>main</b> · clean";
+        assert_eq!(card_or(None, vars("x", &[]), built_in), with_checkout);
+        assert_eq!(
+            card_or(Some("{{ oops"), vars("x", &[]), built_in),
+            with_checkout
+        );
+        assert_eq!(card_or(Some("{{ title }}"), vars("x", &[]), built_in), "x");
+    }
+
+    #[test]
+    fn truncate_counts_characters() {
+        assert_eq!(truncate("héllo", 5), "héllo");
+        assert_eq!(truncate("héllo", 2), "hé…");
+        assert_eq!(truncate("😀😀", 1), "😀…");
+        assert_eq!(truncate("", 0), "");
     }
 }
@@ -0,0 +1,167 @@
+use crate::settings::OAuth;
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Refresh data for an expiring user token (GitHub Apps issue these; classic
+/// OAuth app tokens do not expire and have none).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Refresh {
+    pub client_id: String,
+    pub refresh_token: String,
+    /// Unix seconds when the access token expires.
+    pub expires_at: i64,
+    /// Unix seconds when the refresh token itself expires.
+    pub refresh_expires_at: Option<i64>,
+}
+
+/// Result of a successful authorization.
+pub struct Grant {
+    pub access_token: String,
+    pub refresh: Option<Refresh>,
+}
+
+/// Refresh this long before the access token expires.
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// Run GitHub's device authorization flow: show a one-time code, wait for
+/// the user to approve it in the browser, return the token.
+pub fn login(cfg: &OAuth) -> Result<Grant> {
+    let scope = cfg.scopes.join(" ");
+    let resp = post(
+        "/login/device/code",
+        &[("client_id", &cfg.client_id), ("scope", &scope)],
+    )?;
+    let device_code = field(&resp, "device_code")?;
+    let user_code = field(&resp, "user_code")?;
+    let uri = field(&resp, "verification_uri")?;
+    let mut interval = resp["interval"].as_u64().unwrap_or(5);
+    let deadline = Instant::now() + Duration::from_secs(resp["expires_in"].as_u64().unwrap_or(900));
+
+    println!("Open {} and enter the code:", uri.cyan());
+    println!();
+    println!("    {}", user_code.bold());
+    println!();
+    println!("Waiting for approval…");
+
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_secs(interval));
+        let resp = post(
+            "/login/oauth/access_token",
+            &[
+                ("client_id", &cfg.client_id),
+                ("device_code", &device_code),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ],
+        )?;
+        match resp["error"].as_str() {
+            None => return grant(&cfg.client_id, &resp),
+            Some("authorization_pending") => {}
+            // GitHub asks us to back off by five seconds each time.
+            Some("slow_down") => {
+                interval = resp["interval"].as_u64().unwrap_or(interval + 5);
+            }
+            Some("expired_token") => break,
+            Some("access_denied") => bail!("Authorization was denied on GitHub."),
+            Some(e) => bail!(
+                "GitHub device flow: {}",
+                resp["error_description"].as_str().unwrap_or(e)
+            ),
+        }
+    }
+    bail!("The code expired before it was approved. Run the setup step again.")
+}
+
+/// `token` as is while it has time left, else a refreshed one (which is
+/// stored, along with its new refresh token).
+pub fn fresh(token: String, refresh: Refresh) -> Result<String> {
+    let now = Utc::now().timestamp();
+    if refresh.expires_at - now > REFRESH_MARGIN_SECS {
+        return Ok(token);
+    }
+    if refresh.refresh_expires_at.is_some_and(|t| t <= now) {
+        bail!("GitHub login expired. Run `gh-guard setup login` again.");
+    }
+
+    tracing::debug!(
+        expires_at = refresh.expires_at,
+        "refreshing GitHub user token"
+    );
+    let renewed = post(
+        "/login/oauth/access_token",
+        &[
+            ("client_id", &refresh.client_id),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh.refresh_token),
+        ],
+    )
+    .and_then(|resp| match resp["error"].as_str() {
+        None => grant(&refresh.client_id, &resp),
+        Some(e) => Err(anyhow!(
+            "{}",
+            resp["error_description"].as_str().unwrap_or(e)
+        )),
+    });
+
+    match renewed {
+        Ok(g) => {
+            crate::config::set_pat(&g.access_token)?;
+            match &g.refresh {
+                Some(r) => crate::config::set_oauth_refresh(&serde_json::to_string(r)?)?,
+                None => crate::config::delete_oauth_refresh()?,
+            }
+            Ok(g.access_token)
+        }
+        // Still usable for a few minutes: carry on and retry next time.
+        Err(e) if refresh.expires_at > now => {
+            tracing::warn!(error = %e, "cannot refresh GitHub token yet");
+            Ok(token)
+        }
+        Err(e) => Err(e.context(
+            "GitHub token expired and could not be refreshed. Run `gh-guard setup login` again.",
+        )),
+    }
+}
+
+fn grant(client_id: &str, resp: &serde_json::Value) -> Result<Grant> {
+    let now = Utc::now().timestamp();
+    let refresh = match (resp["refresh_token"].as_str(), resp["expires_in"].as_i64()) {
+        (Some(rt), Some(exp)) => Some(Refresh {
+            client_id: client_id.to_string(),
+            refresh_token: rt.to_string(),
+            expires_at: now + exp,
+            refresh_expires_at: resp["refresh_token_expires_in"].as_i64().map(|s| now + s),
+        }),
+        _ => None,
+    };
+    Ok(Grant {
+        access_token: field(resp, "access_token")?,
+        refresh,
+    })
+}
+
+fn field(resp: &serde_json::Value, name: &str) -> Result<String> {
+    resp[name]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("GitHub response is missing {name}"))
+}
+
+/// POST a form to the host's web endpoints. Device-flow errors come back as
+/// `{"error": …}` with status 200, so the body is returned for the caller.
+fn post(path: &str, form: &[(&str, &str)]) -> Result<serde_json::Value> {
+    let url = crate::host::web_url(path);
+    crate::http::builder(&url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .post(&url)
+        .set("Accept", "application/json")
+        .set("User-Agent", "gh-guard/0.1")
+        .send_form(form)
+        .map_err(|e| anyhow!("GitHub: {e}"))?
+        .into_json()
+        .map_err(Into::into)
+}
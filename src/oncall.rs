@@ -0,0 +1,87 @@
+//! `[[on_call]]`: a rotation of approvers. A request sent during someone's
+//! shift goes to their chat instead of the configured one, so hours you
+//! are away are covered by whoever is on call.
+
+use crate::settings::OnCall;
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Weekday};
+
+/// Whose shift it is right now, if anyone's: the first that covers it.
+pub fn current(shifts: &[OnCall]) -> Result<Option<&OnCall>> {
+    let now = Local::now().naive_local();
+    for shift in shifts {
+        if covers(shift, now)? {
+            return Ok(Some(shift));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether `shift` covers `now`. A shift that ends no later than it starts
+/// runs past midnight and belongs to the day it started on.
+fn covers(shift: &OnCall, now: NaiveDateTime) -> Result<bool> {
+    let start = parse_hhmm(&shift.start)?;
+    let end = parse_hhmm(&shift.end)?;
+    let days = shift
+        .days
+        .iter()
+        .map(|d| {
+            d.parse::<Weekday>()
+                .map_err(|_| anyhow!("Invalid on_call day {d:?} (expected mon … sun)"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let on = |day: Weekday| days.is_empty() || days.contains(&day);
+    let (today, time) = (now.weekday(), now.time());
+    Ok(if start < end {
+        on(today) && start <= time && time < end
+    } else {
+        (on(today) && time >= start) || (on(today.pred()) && time < end)
+    })
+}
+
+fn parse_hhmm(s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| anyhow!("Invalid on_call time {s:?} (expected HH:MM)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shift(days: &[&str], start: &str, end: &str) -> OnCall {
+        OnCall {
+            name: "sam".to_string(),
+            chat_id: "1".to_string(),
+            days: days.iter().map(|d| d.to_string()).collect(),
+            start: start.to_string(),
+            end: end.to_string(),
+            members: Vec::new(),
+        }
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        // 2026-10-16 is a Friday.
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn shifts_cover_their_days_and_run_past_midnight() {
+        let evenings = shift(&["fri"], "18:00", "09:00");
+        assert!(!covers(&evenings, at("2026-10-16 17:59")).unwrap());
+        assert!(covers(&evenings, at("2026-10-16 18:00")).unwrap());
+        assert!(covers(&evenings, at("2026-10-17 08:59")).unwrap());
+        assert!(!covers(&evenings, at("2026-10-17 18:30")).unwrap());
+
+        let weekend = shift(&["Sat", "sunday"], "00:00", "00:00");
+        assert!(covers(&weekend, at("2026-10-18 23:59")).unwrap());
+        assert!(!covers(&weekend, at("2026-10-19 00:00")).unwrap());
+
+        let lunch = shift(&[], "12:00", "13:00");
+        assert!(covers(&lunch, at("2026-10-20 12:30")).unwrap());
+        assert!(covers(
+            &shift(&["someday"], "00:00", "00:00"),
+            at("2026-10-20 12:30")
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+
+static JSON_FLAG: OnceLock<bool> = OnceLock::new();
+
+/// Record whether `--guard-json` was given. Call once, before any output.
+pub fn set_json_flag(on: bool) {
+    let _ = JSON_FLAG.set(on);
+}
+
+/// True when a machine-readable result was requested, via `--guard-json`
+/// or `GH_GUARD_OUTPUT=json`.
+pub fn json() -> bool {
+    *JSON_FLAG.get().unwrap_or(&false)
+        || std::env::var("GH_GUARD_OUTPUT").is_ok_and(|v| v.eq_ignore_ascii_case("json"))
+}
+
+/// Write one JSON object as a single line to the result channel:
+/// the file descriptor named by `GH_GUARD_OUTPUT_FD` if set, else stdout.
+/// No-op unless JSON output is enabled.
+pub fn emit<T: serde::Serialize>(value: &T) {
+    if !json() {
+        return;
+    }
+    let line = match serde_json::to_string(value) {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not serialise JSON result");
+            return;
+        }
+    };
+    let result = match std::env::var("GH_GUARD_OUTPUT_FD") {
+        Ok(fd) => std::fs::OpenOptions::new()
+            .write(true)
+            .open(format!("/dev/fd/{}", fd.trim()))
+            .and_then(|mut f| writeln!(f, "{line}")),
+        Err(_) => writeln!(std::io::stdout().lock(), "{line}"),
+    };
+    if let Err(e) = result {
+        tracing::warn!(error = %e, "could not write JSON result");
+    }
+}
+
+// ── Terminal ─────────────────────────────────────────────────────────────────
+
+static PLAIN: OnceLock<bool> = OnceLock::new();
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+/// How much of the approval flow is told on stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// `--guard-quiet`: the outcome, warnings and errors.
+    Quiet,
+    Normal,
+    /// `--guard-verbose`: everything, plus debug events (request IDs, each
+    /// Telegram poll, timings).
+    Verbose,
+}
+
+/// Record `--guard-quiet` / `--guard-verbose`. Call once, before any output.
+pub fn set_verbosity(v: Verbosity) {
+    let _ = VERBOSITY.set(v);
+}
+
+pub fn verbosity() -> Verbosity {
+    *VERBOSITY.get().unwrap_or(&Verbosity::Normal)
+}
+
+/// True with `--guard-quiet`: banners and progress are left out.
+pub fn quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// Settle how stderr looks, before anything is printed. Colour goes with
+/// `--guard-no-color`, a non-empty `NO_COLOR`, or a stderr that is not a
+/// terminal.
+pub fn init_terminal(no_color: bool) {
+    if no_color || !colour() {
+        colored::control::set_override(false);
+    }
+}
+
+/// Whether the environment allows colour on stderr: a terminal, and no
+/// non-empty `NO_COLOR`.
+pub fn colour() -> bool {
+    !plain() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// True when stderr is not a terminal — usually an agent capturing it.
+/// Messages are then plain `label: value` lines: no box-drawing banner,
+/// no icons, no countdown.
+pub fn plain() -> bool {
+    *PLAIN.get_or_init(|| !std::io::stderr().is_terminal())
+}
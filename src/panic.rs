@@ -0,0 +1,113 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::time::Duration;
+
+/// Emergency stop: optionally revoke the stored PAT on GitHub, then delete it
+/// from the OS keyring so this machine loses write access immediately.
+/// Telegram credentials are kept so the approver can still be told.
+/// In GitHub App mode the installation token is revoked instead and the
+/// app key is deleted as well. The PATs of `[accounts]` go the same way.
+pub fn run(revoke: bool) -> Result<()> {
+    eprintln!("{}", "🚨  gh-guard panic".red().bold());
+    let settings = crate::settings::load().unwrap_or_default();
+    let app = settings.github_app.is_some();
+    let pat = crate::config::get_pat().ok();
+
+    let mut revoke_error = None;
+    if revoke {
+        match pat.as_deref() {
+            Some(token) if app => match crate::github_app::revoke(token) {
+                Ok(()) => eprintln!("  {} installation token revoked", "✓".green()),
+                Err(e) => {
+                    eprintln!("  {} could not revoke installation token: {e}", "✗".red());
+                    revoke_error = Some(e);
+                }
+            },
+            Some(token) => match revoke_token(token) {
+                Ok(()) => eprintln!("  {} PAT revoked on GitHub", "✓".green()),
+                Err(e) => {
+                    eprintln!("  {} could not revoke PAT: {e}", "✗".red());
+                    revoke_error = Some(e);
+                }
+            },
+            None => eprintln!("  {} no PAT stored — nothing to revoke", "·".dimmed()),
+        }
+    }
+
+    crate::config::delete_pat()?;
+    crate::secret_cache::clear();
+    eprintln!(
+        "  {} PAT removed from {}",
+        "✓".green(),
+        crate::config::store_name()
+    );
+    for name in settings.accounts.keys() {
+        let Ok(token) = crate::config::get_account_pat(name) else {
+            continue;
+        };
+        // The command's own account was revoked above already.
+        if revoke && pat.as_deref() != Some(&token) {
+            match revoke_token(&token) {
+                Ok(()) => eprintln!("  {} PAT of account {name} revoked on GitHub", "✓".green()),
+                Err(e) => {
+                    eprintln!(
+                        "  {} could not revoke PAT of account {name}: {e}",
+                        "✗".red()
+                    );
+                    revoke_error.get_or_insert(e);
+                }
+            }
+        }
+        crate::config::delete_account_pat(name)?;
+        eprintln!("  {} PAT of account {name} removed", "✓".green());
+    }
+    if app {
+        crate::config::delete_app_credentials()?;
+        eprintln!(
+            "  {} GitHub App key removed from {}",
+            "✓".green(),
+            crate::config::store_name()
+        );
+    }
+    if crate::config::active_env_overrides().contains(&"GH_GUARD_PAT") {
+        eprintln!(
+            "  {} GH_GUARD_PAT is set in this environment — unset it too",
+            "!".yellow().bold()
+        );
+    }
+
+    let deps = crate::guard::Deps::live();
+    if let Ok(tg) = deps.credentials.telegram() {
+        let html = format!(
+            "🚨 <b>Panic executed</b> on <code>{}</code>\n\nPAT deleted from the keychain{}.",
+            crate::notify::escape_html(&crate::agent::hostname()),
+            match (revoke, &revoke_error) {
+                (false, _) => "",
+                (true, None) => " and revoked on GitHub",
+                (true, Some(_)) => " — <b>revocation failed</b>, revoke it manually",
+            }
+        );
+        let _ = deps.notifier.send_note(&tg, &html);
+    }
+
+    eprintln!("Run {} to restore access.", "gh-guard setup pat".cyan());
+    match revoke_error {
+        Some(e) => Err(e.context("PAT deleted locally but is still valid on GitHub")),
+        None => Ok(()),
+    }
+}
+
+/// Report the token through GitHub's credential revocation API, which
+/// revokes it without needing any other authentication.
+fn revoke_token(token: &str) -> Result<()> {
+    let url = crate::host::api_url("/credentials/revoke");
+    crate::http::builder(&url)?
+        .timeout(Duration::from_secs(15))
+        .build()
+        .post(&url)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", "gh-guard/0.1")
+        .send_json(serde_json::json!({ "credentials": [token] }))
+        .map_err(|e| anyhow!("GitHub API: {e}"))?;
+    Ok(())
+}
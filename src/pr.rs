@@ -1,3 +1,12 @@
+use crate::error::Error;
+use crate::flags::{self, flag, Arg, Flag};
+use crate::i18n::Text;
+use crate::profile::wildcard_matches;
+use crate::settings::BranchPolicy;
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
 /// Parsed metadata extracted from raw `gh pr create` flags.
 /// Unknown flags are ignored here — the original slice is always passed
 /// through to `gh` unchanged so nothing is lost.
@@ -7,83 +16,115 @@ pub struct PrArgs {
     pub body: Option<String>,
     pub body_file: Option<String>,
     pub base: Option<String>,
+    /// `--head`: the branch (or `owner:branch`) to open the PR from.
+    pub head: Option<String>,
+    /// `-R` / `--repo`, as given.
+    pub repo: Option<String>,
+    pub reviewers: Vec<String>,
+    pub assignees: Vec<String>,
+    pub labels: Vec<String>,
+    pub milestone: Option<String>,
     pub draft: bool,
+    /// Any of `--fill`, `--fill-first`, `--fill-verbose`.
     pub fill: bool,
+    pub fill_first: bool,
+    pub fill_verbose: bool,
     pub web: bool,
     /// True only when --title was explicitly supplied.
     pub has_title: bool,
 }
 
+/// `gh pr create` flags, so values are not mistaken for anything else.
+const FLAGS: &[Flag] = &[
+    flag("title", Some('t'), true),
+    flag("body", Some('b'), true),
+    flag("body-file", Some('F'), true),
+    flag("base", Some('B'), true),
+    flag("head", Some('H'), true),
+    flag("assignee", Some('a'), true),
+    flag("label", Some('l'), true),
+    flag("milestone", Some('m'), true),
+    flag("project", Some('p'), true),
+    flag("reviewer", Some('r'), true),
+    flag("template", Some('T'), true),
+    flag("recover", None, true),
+    flag("repo", Some('R'), true),
+    flag("draft", Some('d'), false),
+    flag("fill", Some('f'), false),
+    flag("fill-first", None, false),
+    flag("fill-verbose", None, false),
+    flag("web", Some('w'), false),
+    flag("editor", Some('e'), false),
+    flag("dry-run", None, false),
+    flag("no-maintainer-edit", None, false),
+];
+
 /// Scan the raw flag slice for known `gh pr create` options.
 pub fn parse_pr_args(args: &[String]) -> PrArgs {
     let mut out = PrArgs::default();
-    let mut i = 0;
-
-    while i < args.len() {
-        let arg = args[i].as_str();
 
-        // Helper: peek at the next token as a value.
-        let next = || args.get(i + 1).map(String::as_str);
-
-        match arg {
-            "--title" | "-t" => {
-                if let Some(v) = next() {
-                    out.title = Some(v.to_string());
-                    out.has_title = true;
-                    i += 2;
-                    continue;
-                }
-            }
-            "--body" | "-b" => {
-                if let Some(v) = next() {
-                    out.body = Some(v.to_string());
-                    i += 2;
-                    continue;
-                }
-            }
-            "--body-file" | "-F" => {
-                if let Some(v) = next() {
-                    out.body_file = Some(v.to_string());
-                    i += 2;
-                    continue;
-                }
-            }
-            "--base" | "-B" => {
-                if let Some(v) = next() {
-                    out.base = Some(v.to_string());
-                    i += 2;
-                    continue;
-                }
-            }
-            "--draft" | "-d" => {
-                out.draft = true;
+    for arg in flags::split(args, FLAGS) {
+        let Arg::Flag { name, value } = arg else {
+            continue;
+        };
+        match (name, value) {
+            ("title", Some(v)) => {
+                out.title = Some(v.to_string());
+                out.has_title = true;
             }
-            "--fill" | "--fill-verbose" => {
-                out.fill = true;
-            }
-            "--web" | "-w" => {
-                out.web = true;
-            }
-            _ => {
-                // Handle --flag=value forms.
-                if let Some(v) = arg.strip_prefix("--title=") {
-                    out.title = Some(v.to_string());
-                    out.has_title = true;
-                } else if let Some(v) = arg.strip_prefix("--body=") {
-                    out.body = Some(v.to_string());
-                } else if let Some(v) = arg.strip_prefix("--base=") {
-                    out.base = Some(v.to_string());
-                } else if let Some(v) = arg.strip_prefix("--body-file=") {
-                    out.body_file = Some(v.to_string());
-                }
+            ("body", Some(v)) => out.body = Some(v.to_string()),
+            ("body-file", Some(v)) => out.body_file = Some(v.to_string()),
+            ("base", Some(v)) => out.base = Some(v.to_string()),
+            ("head", Some(v)) => out.head = Some(v.to_string()),
+            ("repo", Some(v)) => out.repo = Some(v.to_string()),
+            ("reviewer", Some(v)) => out.reviewers.extend(list(v)),
+            ("assignee", Some(v)) => out.assignees.extend(list(v)),
+            ("label", Some(v)) => out.labels.extend(list(v)),
+            ("milestone", Some(v)) => out.milestone = Some(v.to_string()),
+            ("draft", v) => out.draft = flags::enabled(v),
+            ("fill" | "fill-first" | "fill-verbose", v) => {
+                out.fill = flags::enabled(v);
+                out.fill_first = out.fill && name == "fill-first";
+                out.fill_verbose = out.fill && name == "fill-verbose";
             }
+            ("web", v) => out.web = flags::enabled(v),
+            _ => {}
         }
-        i += 1;
     }
     out
 }
 
 /// Return body text: inline --body takes priority, then --body-file.
+/// Values of a flag gh takes repeatedly or comma-separated.
+fn list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+impl PrArgs {
+    /// Reviewers, assignees, labels and milestone, labelled, for the card
+    /// and the banner. Empty ones are left out.
+    pub fn meta(&self, t: &Text) -> Vec<(&'static str, String)> {
+        let mut meta = Vec::new();
+        for (label, values) in [
+            (t.reviewers, &self.reviewers),
+            (t.assignees, &self.assignees),
+            (t.labels, &self.labels),
+        ] {
+            if !values.is_empty() {
+                meta.push((label, values.join(", ")));
+            }
+        }
+        if let Some(m) = &self.milestone {
+            meta.push((t.milestone, m.clone()));
+        }
+        meta
+    }
+}
+
 pub fn resolve_body(pr: &PrArgs) -> String {
     if let Some(ref body) = pr.body {
         return body.clone();
@@ -95,19 +136,811 @@ pub fn resolve_body(pr: &PrArgs) -> String {
 }
 
 /// Human-readable "source → base" branch string shown in the notification.
-pub fn branch_info(base: Option<&str>) -> String {
-    let current = std::process::Command::new("git")
+/// One commit on the PR branch.
+#[derive(Debug)]
+struct Commit {
+    subject: String,
+    body: String,
+}
+
+/// The title and body `--fill` will give the PR, worked out from git the
+/// way gh does: one commit (or `--fill-first`) lends its message, several
+/// make a list of subjects under the branch name. `None` outside a checkout
+/// or when the branch has no commits of its own.
+pub fn fill_preview(pr: &PrArgs) -> Option<(String, String)> {
+    let base = base_ref(pr)?;
+    let log = git(&["log", "--format=%s%x1f%b%x1e", &format!("{base}..HEAD")])?;
+    let commits: Vec<Commit> = log
+        .split('\x1e')
+        .filter_map(|c| {
+            let (subject, body) = c.trim_start_matches('\n').split_once('\x1f')?;
+            Some(Commit {
+                subject: subject.to_string(),
+                body: body.trim_end().to_string(),
+            })
+        })
+        .collect();
+    let head = current_branch().unwrap_or_default();
+    fill_from(&commits, &head, pr.fill_first, pr.fill_verbose)
+}
+
+/// `commits` newest first, as `git log` lists them.
+fn fill_from(
+    commits: &[Commit],
+    head: &str,
+    first: bool,
+    verbose: bool,
+) -> Option<(String, String)> {
+    let oldest = commits.last()?;
+    if commits.len() == 1 || first {
+        return Some((oldest.subject.clone(), oldest.body.clone()));
+    }
+    let title = head.replace(['-', '_'], " ");
+    let mut body = String::new();
+    for (i, c) in commits.iter().enumerate().rev() {
+        body.push_str(&format!("- **{}**\n", c.subject));
+        if verbose && !c.body.is_empty() {
+            for line in c.body.lines() {
+                body.push_str(&format!("  {line}\n"));
+            }
+            if i > 0 {
+                body.push('\n');
+            }
+        }
+    }
+    Some((title, body.trim_end().to_string()))
+}
+
+/// The repository's PR template as gh finds it — `.github/`, the root, then
+/// `docs/`, in any case — with its path relative to the root. A directory of
+/// templates needs a choice gh-guard cannot make for the user, so it is
+/// not looked at.
+pub fn template() -> Option<(String, String)> {
+    template_in(Path::new(&git(&["rev-parse", "--show-toplevel"])?))
+}
+
+fn template_in(root: &Path) -> Option<(String, String)> {
+    for dir in [".github", "", "docs"] {
+        let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.eq_ignore_ascii_case("pull_request_template.md") {
+                continue;
+            }
+            let Ok(body) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let path = Path::new(dir).join(&name);
+            return Some((path.display().to_string(), body));
+        }
+    }
+    None
+}
+
+/// `origin/<branch>` if that remote-tracking branch exists.
+/// What to compare the checkout with: the base branch, as `origin/<base>`
+/// when that exists, else the default branch of `origin`.
+fn base_ref(pr: &PrArgs) -> Option<String> {
+    match &pr.base {
+        Some(b) => Some(remote_ref(b).unwrap_or_else(|| b.clone())),
+        None => git(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"]),
+    }
+}
+
+/// Name of the default branch of the checkout's `origin`, if git knows it.
+pub fn default_branch() -> Option<String> {
+    let head = git(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])?;
+    Some(head.strip_prefix("origin/").unwrap_or(&head).to_string())
+}
+
+/// Where the checked-out branch stands against the branch it is pushed to.
+#[derive(Debug, PartialEq)]
+pub enum PushState {
+    /// Neither an upstream nor a branch of the same name on `origin`.
+    NotPushed { branch: String },
+    Pushed {
+        upstream: String,
+        /// Local commits the upstream lacks.
+        ahead: u32,
+        /// Upstream commits the local branch lacks.
+        behind: u32,
+    },
+}
+
+/// The checked-out branch against its upstream, else `origin/<branch>`,
+/// as far as the last fetch knows. `None` outside a checkout or on a
+/// detached HEAD.
+pub fn push_state() -> Option<PushState> {
+    let branch = current_branch()?;
+    let same_name = format!("origin/{branch}");
+    let upstream = git(&["rev-parse", "--abbrev-ref", "@{upstream}"]).or_else(|| {
+        git(&["rev-parse", "--verify", "--quiet", &same_name])?;
+        Some(same_name)
+    });
+    let Some(upstream) = upstream else {
+        return Some(PushState::NotPushed { branch });
+    };
+    let range = format!("HEAD...{upstream}");
+    let counts = git(&["rev-list", "--left-right", "--count", &range])?;
+    let (ahead, behind) = counts.split_once('\t')?;
+    Some(PushState::Pushed {
+        upstream,
+        ahead: ahead.parse().ok()?,
+        behind: behind.parse().ok()?,
+    })
+}
+
+/// How many files `git status` shows as changed or untracked; `None`
+/// outside a checkout.
+pub fn uncommitted_files() -> Option<usize> {
+    let status = git(&["status", "--porcelain"])?;
+    Some(status.lines().count())
+}
+
+/// A checkout's branch and what `git status` shows of it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GitState {
+    /// The branch, or the short commit when detached.
+    pub branch: String,
+    /// Staged, modified, deleted, renamed or conflicted files.
+    pub changed: usize,
+    pub untracked: usize,
+    /// Commits ahead of and behind the upstream, if there is one.
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// The state of the current checkout; `None` outside one.
+pub fn git_state() -> Option<GitState> {
+    git(&["status", "--porcelain=v2", "--branch"]).map(|status| parse_status(&status))
+}
+
+/// `git status --porcelain=v2 --branch` output, read.
+fn parse_status(status: &str) -> GitState {
+    let mut state = GitState::default();
+    let mut oid = "";
+    for line in status.lines() {
+        match line.split_once(' ') {
+            Some(("#", header)) => match header.split_once(' ') {
+                Some(("branch.oid", value)) => oid = value,
+                Some(("branch.head", value)) => state.branch = value.to_string(),
+                Some(("branch.ab", value)) => {
+                    for count in value.split_whitespace() {
+                        if let Some(n) = count.strip_prefix('+') {
+                            state.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = count.strip_prefix('-') {
+                            state.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Some(("1" | "2" | "u", _)) => state.changed += 1,
+            Some(("?", _)) => state.untracked += 1,
+            _ => {}
+        }
+    }
+    if state.branch == "(detached)" {
+        state.branch = oid.chars().take(7).collect();
+    }
+    state
+}
+
+/// Most of a diff sent to the approver.
+const DIFF_BYTES: usize = 1 << 20;
+
+/// What the PR will contain, as `git diff base...HEAD` prints it, cut to
+/// 1 MiB. `None` outside a checkout or when the base is unknown.
+pub fn diff(pr: &PrArgs) -> Option<String> {
+    let base = base_ref(pr)?;
+    let mut diff = git(&[
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        &format!("{base}...HEAD"),
+    ])?;
+    if diff.len() > DIFF_BYTES {
+        let mut end = DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+        diff.push_str("\n… (cut at 1 MiB)\n");
+    }
+    Some(diff)
+}
+
+/// Whether `title` matches the configured `title_pattern`.
+pub fn title_matches(pattern: &str, title: &str) -> Result<bool> {
+    let re = regex::Regex::new(pattern)
+        .with_context(|| format!("Invalid [pr_create] title_pattern {pattern:?}"))?;
+    Ok(re.is_match(title))
+}
+
+/// The first policy that covers a PR into `base` (`None`: the default
+/// branch), given the name of the repository's default branch if known.
+pub fn branch_policy<'a>(
+    policies: &'a [BranchPolicy],
+    base: Option<&str>,
+    default: Option<&str>,
+) -> Option<&'a BranchPolicy> {
+    let is_default = base.is_none() || base == default;
+    let name = base.or(default);
+    policies.iter().find(|p| {
+        (p.default_branch && is_default)
+            || name.is_some_and(|n| p.branches.iter().any(|pat| wildcard_matches(pat, n)))
+    })
+}
+
+fn remote_ref(branch: &str) -> Option<String> {
+    let name = format!("origin/{branch}");
+    git(&["rev-parse", "--verify", "--quiet", &name]).map(|_| name)
+}
+
+/// Trimmed stdout of a successful `git <args>`.
+fn git(args: &[&str]) -> Option<String> {
+    std::process::Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+}
+
+/// "head → base". The head is `--head` if given, else the checked-out
+/// branch. `repo` is the target repository when one was named (`-R`,
+/// `GH_REPO`) rather than taken from the checkout; the base is then shown
+/// as `owner/repo:base`.
+pub fn branch_info(pr: &PrArgs, repo: Option<&str>) -> String {
+    let curr = pr
+        .head
+        .clone()
+        .or_else(current_branch)
+        .unwrap_or_else(|| "(current)".to_string());
+    let base = pr.base.as_deref();
+    match (repo, base) {
+        (None, Some(b)) => format!("{curr} → {b}"),
+        (None, None) => format!("{curr} → (default branch)"),
+        (Some(r), Some(b)) => format!("{curr} → {r}:{b}"),
+        (Some(r), None) => format!("{curr} → {r} (default branch)"),
+    }
+}
+
+/// GitHub's compare page for the PR's branches, on `repo`. Without a base,
+/// GitHub compares against the default branch.
+pub fn compare_url(repo: &str, base: Option<&str>, head: &str) -> String {
+    let range = match base {
+        Some(base) => format!("{}...{}", url_escape(base), url_escape(head)),
+        None => url_escape(head),
+    };
+    crate::host::web_url(&format!("/{repo}/compare/{range}"))
+}
+
+/// A branch name as a URL path: `/` and `:` (fork heads) stay, characters
+/// that would end the path or be misread are percent-encoded.
+pub fn url_escape(branch: &str) -> String {
+    let mut out = String::new();
+    for b in branch.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/:".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// The repository named by `-R` (`flag`) or else `GH_REPO`, as `owner/repo`,
+/// the way gh picks it before falling back to the checkout.
+pub fn named_repo(flag: Option<&str>) -> Option<String> {
+    let named = match flag {
+        Some(f) => f.to_string(),
+        None => std::env::var("GH_REPO")
+            .ok()
+            .filter(|v| !v.trim().is_empty())?,
+    };
+    normalize_repo(named.trim())
+}
+
+/// The repository a command acts on: the one named, or the checkout's.
+pub fn target_repo(flag: Option<&str>) -> Option<String> {
+    named_repo(flag).or_else(current_repo)
+}
+
+/// `owner/repo` from `OWNER/REPO`, `HOST/OWNER/REPO` or a clone URL.
+fn normalize_repo(s: &str) -> Option<String> {
+    if s.contains("://") || s.starts_with("git@") {
+        return repo_from_remote(s);
+    }
+    let parts: Vec<&str> = s.trim_end_matches('/').split('/').collect();
+    match parts.as_slice() {
+        [.., owner, repo] if !owner.is_empty() && !repo.is_empty() => {
+            Some(format!("{owner}/{}", repo.trim_end_matches(".git")))
+        }
+        _ => None,
+    }
+}
+
+/// Name of the checked-out branch; `None` on a detached HEAD or outside git.
+pub fn current_branch() -> Option<String> {
+    std::process::Command::new("git")
         .args(["rev-parse", "--abbrev-ref", "HEAD"])
         .output()
         .ok()
         .and_then(|o| String::from_utf8(o.stdout).ok())
         .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty() && s != "HEAD");
+        .filter(|s| !s.is_empty() && s != "HEAD")
+}
+
+/// `owner/repo` of the current checkout's `origin` remote, if any.
+pub fn current_repo() -> Option<String> {
+    repo_from_remote(&origin_url()?)
+}
+
+/// URL of the current checkout's `origin` remote, if any.
+pub fn origin_url() -> Option<String> {
+    let url = std::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())?;
+    Some(url.trim().to_string())
+}
+
+/// Extract `owner/repo` from an SSH or HTTPS remote URL.
+fn repo_from_remote(url: &str) -> Option<String> {
+    // git@host:owner/repo.git | ssh://git@host/owner/repo.git | https://host/owner/repo
+    let path = if let Some((_, rest)) = url.split_once("://") {
+        rest.split_once('/')?.1
+    } else {
+        url.split_once(':')?.1
+    };
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    let mut parts = path.rsplitn(3, '/');
+    let repo = parts.next().filter(|s| !s.is_empty())?;
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!("{owner}/{repo}"))
+}
+
+// ── Interactive ──────────────────────────────────────────────────────────────
+
+/// Whether gh-guard can ask for a title itself: both ends of a terminal.
+pub fn can_prompt() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Ask on the terminal for what gh's own form would (title, body, base
+/// branch), fill in `pr` and append the answers to `gh_args` as flags, so
+/// the approver sees them and gh runs without prompting.
+pub fn prompt(t: &Text, pr: &mut PrArgs, gh_args: &mut Vec<String>) -> Result<()> {
+    let answers = ask(
+        t,
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        pr,
+        last_commit_subject().as_deref(),
+    )?;
+    gh_args.extend(["--title".to_string(), answers.title.clone()]);
+    pr.title = Some(answers.title);
+    pr.has_title = true;
+    if let Some(body) = answers.body {
+        gh_args.extend(["--body".to_string(), body.clone()]);
+        pr.body = Some(body);
+    }
+    if let Some(base) = answers.base {
+        gh_args.extend(["--base".to_string(), base.clone()]);
+        pr.base = Some(base);
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct Answers {
+    title: String,
+    /// `None` when the command already had a body.
+    body: Option<String>,
+    /// `None` for the repository's default branch, or when `--base` was given.
+    base: Option<String>,
+}
+
+fn ask(
+    t: &Text,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+    pr: &PrArgs,
+    default_title: Option<&str>,
+) -> Result<Answers> {
+    let title = loop {
+        match default_title {
+            Some(d) => write!(out, "{} [{d}]: ", t.prompt_title)?,
+            None => write!(out, "{}: ", t.prompt_title)?,
+        }
+        out.flush()?;
+        let line = read_line(input)?.ok_or(Error::Cancelled)?;
+        match (line.trim(), default_title) {
+            ("", Some(d)) => break d.to_string(),
+            ("", None) => writeln!(out, "{}", t.prompt_title_required)?,
+            (title, _) => break title.to_string(),
+        }
+    };
+
+    // gh would still prompt for a body it was not given.
+    let body = if pr.body.is_none() && pr.body_file.is_none() {
+        writeln!(out, "{}", t.prompt_body)?;
+        let mut lines = Vec::new();
+        while let Some(line) = read_line(input)? {
+            if line == "." {
+                break;
+            }
+            lines.push(line);
+        }
+        Some(lines.join("\n").trim().to_string())
+    } else {
+        None
+    };
+
+    let base = if pr.base.is_none() {
+        write!(out, "{} [{}]: ", t.prompt_base, t.prompt_default_base)?;
+        out.flush()?;
+        read_line(input)?
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+    } else {
+        None
+    };
+
+    Ok(Answers { title, body, base })
+}
+
+/// One line without its line ending, or `None` at end of input.
+fn read_line(input: &mut impl BufRead) -> Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+}
+
+/// Subject of the commit at HEAD, the usual title for a one-commit PR.
+fn last_commit_subject() -> Option<String> {
+    git(&["log", "-1", "--format=%s"]).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::EN;
+    use proptest::prelude::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn clusters_and_attached_values() {
+        let parsed = parse_pr_args(&args(&["-dtFix it", "-BRelease", "-f"]));
+        assert!(parsed.draft && parsed.fill && parsed.has_title);
+        assert_eq!(parsed.title.as_deref(), Some("Fix it"));
+        assert_eq!(parsed.base.as_deref(), Some("Release"));
+    }
+
+    #[test]
+    fn repeated_flags_last_wins() {
+        let parsed = parse_pr_args(&args(&[
+            "-t",
+            "one",
+            "--title=two",
+            "--draft",
+            "--draft=false",
+        ]));
+        assert_eq!(parsed.title.as_deref(), Some("two"));
+        assert!(!parsed.draft);
+    }
+
+    #[test]
+    fn values_of_other_flags_are_not_titles() {
+        let parsed = parse_pr_args(&args(&["--reviewer", "-t", "--label", "--title"]));
+        assert!(!parsed.has_title);
+        assert_eq!(parsed.title, None);
+    }
+
+    /// Arguments that cannot set the title, each kept whole.
+    fn answer(input: &str, pr: &PrArgs, default_title: Option<&str>) -> Answers {
+        let mut shown = Vec::new();
+        ask(&EN, &mut input.as_bytes(), &mut shown, pr, default_title).unwrap()
+    }
+
+    #[test]
+    fn prompts_for_what_gh_would() {
+        let answers = answer(
+            "Fix it\nFirst line.\n\nSecond.\n.\nrelease\n",
+            &PrArgs::default(),
+            None,
+        );
+        assert_eq!(
+            answers,
+            Answers {
+                title: "Fix it".to_string(),
+                body: Some("First line.\n\nSecond.".to_string()),
+                base: Some("release".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn empty_answers_take_the_defaults() {
+        let answers = answer("\n.\n\n", &PrArgs::default(), Some("Last commit"));
+        assert_eq!(answers.title, "Last commit");
+        assert_eq!(answers.body.as_deref(), Some(""));
+        assert_eq!(answers.base, None);
+    }
+
+    #[test]
+    fn title_is_asked_again_until_given() {
+        let mut shown = Vec::new();
+        let pr = parse_pr_args(&args(&["--body", "x", "--base", "main"]));
+        let answers = ask(&EN, &mut "\n \nFinally\n".as_bytes(), &mut shown, &pr, None).unwrap();
+        assert_eq!(
+            (answers.title.as_str(), answers.body, answers.base),
+            ("Finally", None, None)
+        );
+        let shown = String::from_utf8(shown).unwrap();
+        assert_eq!(
+            shown.matches(EN.prompt_title_required).count(),
+            2,
+            "{shown}"
+        );
+    }
+
+    #[test]
+    fn end_of_input_cancels() {
+        let mut shown = Vec::new();
+        let err = ask(
+            &EN,
+            &mut "".as_bytes(),
+            &mut shown,
+            &PrArgs::default(),
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(crate::error::find(&err), Some(Error::Cancelled)));
+    }
+
+    #[test]
+    fn branch_policies_match_by_name_or_default() {
+        let policies = [
+            BranchPolicy {
+                branches: vec!["release/*".to_string()],
+                approvals: Some(2),
+                ..Default::default()
+            },
+            BranchPolicy {
+                default_branch: true,
+                attach_diff: true,
+                ..Default::default()
+            },
+        ];
+        let approvals =
+            |base, default| branch_policy(&policies, base, default).map(|p| p.approvals);
+        assert_eq!(approvals(Some("release/1.2"), Some("main")), Some(Some(2)));
+        assert_eq!(approvals(Some("main"), Some("main")), Some(None));
+        assert_eq!(approvals(None, None), Some(None));
+        assert_eq!(approvals(Some("feature/x"), Some("main")), None);
+        assert_eq!(approvals(Some("main"), None), None);
+    }
+
+    #[test]
+    fn titles_are_checked_against_the_pattern() {
+        let conventional = r"^(feat|fix|chore)(\([\w-]+\))?!?: ";
+        assert!(title_matches(conventional, "fix(auth): token refresh").unwrap());
+        assert!(!title_matches(conventional, "Fixed the token refresh").unwrap());
+        assert!(title_matches(r"\b[A-Z]+-\d+\b", "PROJ-42 Retry uploads").unwrap());
+        assert!(title_matches("(", "x").is_err());
+    }
+
+    #[test]
+    fn template_is_found_where_gh_looks() {
+        let root = tempfile::tempdir().unwrap();
+        let write = |path: &str, body: &str| std::fs::write(root.path().join(path), body).unwrap();
+        let found = || template_in(root.path()).map(|(path, body)| format!("{path}: {body}"));
+        assert_eq!(found(), None);
+
+        std::fs::create_dir_all(root.path().join("docs")).unwrap();
+        std::fs::create_dir_all(root.path().join(".github/PULL_REQUEST_TEMPLATE")).unwrap();
+        write("docs/pull_request_template.md", "docs");
+        assert_eq!(
+            found().as_deref(),
+            Some("docs/pull_request_template.md: docs")
+        );
+
+        write(".github/PULL_REQUEST_TEMPLATE.md", "## What");
+        assert_eq!(
+            found().as_deref(),
+            Some(".github/PULL_REQUEST_TEMPLATE.md: ## What")
+        );
+    }
+
+    fn commits(list: &[(&str, &str)]) -> Vec<Commit> {
+        list.iter()
+            .map(|(subject, body)| Commit {
+                subject: subject.to_string(),
+                body: body.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fill_from_a_single_commit_takes_its_message() {
+        let one = commits(&[("Fix login", "Closes #12.")]);
+        let (title, body) = fill_from(&one, "fix-login", false, false).unwrap();
+        assert_eq!(
+            (title.as_str(), body.as_str()),
+            ("Fix login", "Closes #12.")
+        );
+        assert!(fill_from(&[], "x", false, false).is_none());
+    }
+
+    #[test]
+    fn fill_from_several_commits_lists_them_oldest_first() {
+        let log = commits(&[("Second", "More\ndetail"), ("First", "")]);
+
+        let (title, body) = fill_from(&log, "fix_login-flow", false, false).unwrap();
+        assert_eq!(title, "fix login flow");
+        assert_eq!(body, "- **First**\n- **Second**");
+
+        let (_, body) = fill_from(&log, "b", false, true).unwrap();
+        assert_eq!(body, "- **First**\n- **Second**\n  More\n  detail");
+
+        let (title, body) = fill_from(&log, "b", true, false).unwrap();
+        assert_eq!((title.as_str(), body.as_str()), ("First", ""));
+    }
 
-    match (current, base) {
-        (Some(curr), Some(b)) => format!("{curr} → {b}"),
-        (Some(curr), None) => format!("{curr} → (default branch)"),
-        (None, Some(b)) => format!("(current) → {b}"),
-        (None, None) => "(current) → (default branch)".to_string(),
+    #[test]
+    fn repo_names_reduce_to_owner_and_repo() {
+        for named in [
+            "acme/api",
+            "github.acme.com/acme/api",
+            "https://github.com/acme/api.git",
+            "git@github.com:acme/api.git",
+        ] {
+            assert_eq!(
+                normalize_repo(named).as_deref(),
+                Some("acme/api"),
+                "{named}"
+            );
+        }
+        assert_eq!(normalize_repo("api"), None);
+        assert_eq!(
+            parse_pr_args(&args(&["-Racme/api"])).repo.as_deref(),
+            Some("acme/api")
+        );
+    }
+
+    #[test]
+    fn head_wins_over_the_checkout() {
+        let parsed = parse_pr_args(&args(&["-H", "me:feature/x", "-B", "main"]));
+        assert_eq!(branch_info(&parsed, None), "me:feature/x → main");
+        assert_eq!(
+            branch_info(&parsed, Some("acme/api")),
+            "me:feature/x → acme/api:main"
+        );
+    }
+
+    #[test]
+    fn people_and_labels_accumulate() {
+        let parsed = parse_pr_args(&args(&[
+            "-r",
+            "alice,org/team",
+            "--reviewer=bob",
+            "-l",
+            "bug, ",
+            "-a@me",
+            "--milestone",
+            "v1",
+        ]));
+        assert_eq!(parsed.reviewers, ["alice", "org/team", "bob"]);
+        assert_eq!(parsed.labels, ["bug"]);
+        assert_eq!(parsed.assignees, ["@me"]);
+        let meta = parsed.meta(&EN);
+        assert_eq!(
+            meta,
+            [
+                ("Reviewers", "alice, org/team, bob".to_string()),
+                ("Assignees", "@me".to_string()),
+                ("Labels", "bug".to_string()),
+                ("Milestone", "v1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fill_variants() {
+        let parsed = parse_pr_args(&args(&["--fill-first"]));
+        assert!(parsed.fill && parsed.fill_first && !parsed.fill_verbose);
+        let parsed = parse_pr_args(&args(&["--fill-verbose=false"]));
+        assert!(!parsed.fill && !parsed.fill_verbose);
+    }
+
+    #[test]
+    fn git_status_is_counted() {
+        let status = parse_status(
+            "# branch.oid 4f2a9c1d0e\n# branch.head feat/x\n# branch.upstream origin/feat/x\n\
+             # branch.ab +2 -1\n1 .M N... 100644 100644 100644 a b src/main.rs\n\
+             2 R. N... 100644 100644 100644 a b R100 new.rs\told.rs\n? notes.txt",
+        );
+        assert_eq!(
+            status,
+            GitState {
+                branch: "feat/x".to_string(),
+                changed: 2,
+                untracked: 1,
+                ahead: 2,
+                behind: 1,
+            }
+        );
+        let detached = parse_status("# branch.oid 4f2a9c1d0e\n# branch.head (detached)");
+        assert_eq!(detached.branch, "4f2a9c1");
+        assert_eq!(detached.changed + detached.ahead, 0);
+    }
+
+    fn unit() -> impl Strategy<Value = Vec<String>> {
+        let value_flag = prop_oneof![
+            Just("-b"),
+            Just("--body"),
+            Just("-B"),
+            Just("-H"),
+            Just("--head"),
+            Just("-r"),
+            Just("--reviewer"),
+            Just("-l"),
+            Just("-a"),
+            Just("-R"),
+        ];
+        prop_oneof![
+            (value_flag, ".*").prop_map(|(f, v)| vec![f.to_string(), v]),
+            prop_oneof![
+                Just("-d"),
+                Just("--draft"),
+                Just("-f"),
+                Just("-w"),
+                Just("-dw")
+            ]
+            .prop_map(|f| vec![f.to_string()]),
+            "--zz[a-z]{0,4}".prop_map(|f| vec![f]),
+            "[a-z]{1,8}".prop_map(|p| vec![p]),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(raw in proptest::collection::vec(".*|-{1,2}[a-zA-Z=]{0,5}", 0..12)) {
+            let parsed = parse_pr_args(&raw);
+            prop_assert_eq!(parsed.has_title, parsed.title.is_some());
+        }
+
+        #[test]
+        fn title_in_any_spelling(
+            units in proptest::collection::vec(unit(), 0..8),
+            at in any::<prop::sample::Index>(),
+            title in "[^\\x00]{0,20}",
+            form in 0usize..5,
+        ) {
+            let spelled = match form {
+                0 => vec!["-t".to_string(), title.clone()],
+                1 => vec!["--title".to_string(), title.clone()],
+                2 => vec![format!("--title={title}")],
+                3 => vec!["-dt".to_string(), title.clone()],
+                _ => vec!["-t".to_string(), title.clone(), "--draft".to_string()],
+            };
+            let mut units = units;
+            units.insert(at.index(units.len() + 1), spelled);
+            let parsed = parse_pr_args(&units.concat());
+            prop_assert!(parsed.has_title);
+            prop_assert_eq!(parsed.title, Some(title));
+        }
     }
 }
@@ -0,0 +1,130 @@
+use std::sync::OnceLock;
+
+/// Profile named on the command line (`--profile` / `--guard-profile`).
+static FLAG: OnceLock<String> = OnceLock::new();
+/// Target repository of the guarded command, when known before settings load.
+static REPO_HINT: OnceLock<String> = OnceLock::new();
+/// GitHub host the command names itself (`gh api --hostname`).
+static HOST_HINT: OnceLock<String> = OnceLock::new();
+static ACTIVE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Record the profile given on the command line. Call before anything reads
+/// settings or secrets.
+pub fn select(name: &str) {
+    let _ = FLAG.set(name.to_string());
+}
+
+/// Record the repository a command targets (e.g. from a `gh api` endpoint),
+/// used instead of the current git remote for `repos` matching.
+pub fn hint_repo(repo: &str) {
+    let _ = REPO_HINT.set(repo.to_string());
+}
+
+/// Record the GitHub host a command names, used to pick the profile whose
+/// `host` it is.
+pub fn hint_host(host: &str) {
+    let _ = HOST_HINT.set(host.to_string());
+}
+
+/// The active profile, or `None` for the default one. Resolved once per
+/// process: `--profile` flag, then `GH_GUARD_PROFILE`, then the first
+/// `[profiles.*]` whose `host` is the one the command names, or whose
+/// `repos` match the target repository or whose
+/// `remotes` match the `origin` URL, then `default_profile` from the
+/// config file.
+pub fn active() -> Option<&'static str> {
+    ACTIVE.get_or_init(resolve).as_deref()
+}
+
+fn resolve() -> Option<String> {
+    let explicit = FLAG
+        .get()
+        .cloned()
+        .or_else(|| std::env::var("GH_GUARD_PROFILE").ok())
+        .filter(|p| !p.trim().is_empty());
+    if let Some(p) = explicit {
+        return named(p.trim());
+    }
+
+    let settings = crate::settings::load_file().ok()?;
+    if let Some(host) = HOST_HINT.get() {
+        let hit = settings.profiles.iter().find(|(_, p)| {
+            p.host
+                .as_deref()
+                .is_some_and(|h| crate::host::normalize(h) == *host)
+        });
+        if let Some((name, _)) = hit {
+            tracing::debug!(profile = %name, host = %host, "profile selected by host");
+            return named(name);
+        }
+    }
+    if settings.profiles.values().any(|p| !p.repos.is_empty()) {
+        if let Some(repo) = target_repo() {
+            let hit = settings
+                .profiles
+                .iter()
+                .find(|(_, p)| p.repos.iter().any(|pat| repo_matches(pat, &repo)));
+            if let Some((name, _)) = hit {
+                tracing::debug!(profile = %name, repo = %repo, "profile selected by repo");
+                return named(name);
+            }
+        }
+    }
+    if settings.profiles.values().any(|p| !p.remotes.is_empty()) {
+        if let Some(url) = crate::pr::origin_url() {
+            let hit = settings
+                .profiles
+                .iter()
+                .find(|(_, p)| p.remotes.iter().any(|pat| wildcard_matches(pat, &url)));
+            if let Some((name, _)) = hit {
+                tracing::debug!(profile = %name, remote = %url, "profile selected by remote");
+                return named(name);
+            }
+        }
+    }
+    settings.default_profile.and_then(|p| named(&p))
+}
+
+/// Repository the current command targets: the hint, else `GH_REPO` or the
+/// git remote.
+pub fn target_repo() -> Option<String> {
+    REPO_HINT
+        .get()
+        .cloned()
+        .or_else(|| crate::pr::target_repo(None))
+}
+
+/// "default" is the unnamed profile.
+fn named(name: &str) -> Option<String> {
+    (name != "default").then(|| name.to_string())
+}
+
+/// `*` matches any run of characters; case-insensitive.
+pub fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.to_lowercase(), text.to_lowercase());
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty(); // no `*` at all
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// `owner/repo`, `owner/*` or `*`, case-insensitive.
+fn repo_matches(pattern: &str, repo: &str) -> bool {
+    let (pattern, repo) = (pattern.to_lowercase(), repo.to_lowercase());
+    match pattern.strip_suffix('*') {
+        Some(prefix) => repo.starts_with(prefix),
+        None => pattern == repo,
+    }
+}
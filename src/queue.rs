@@ -0,0 +1,274 @@
+use crate::audit::{self, Decision};
+use crate::error::Error;
+use crate::guard::{self, Kind, Request};
+use crate::quiet;
+use crate::settings::{OfflineQueue, QuietAction};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration, Local};
+use clap::Subcommand;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+const STATE_FILE: &str = "queue.json";
+
+/// `gh-guard queue` steps; with none given the queue is listed.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Action {
+    /// List queued requests
+    List,
+    /// Remove a queued request without sending it
+    Drop { id: String },
+    /// Deliver one queued request now (run by the daemon)
+    #[command(hide = true)]
+    Run { id: String },
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Queue {
+    requests: Vec<Queued>,
+}
+
+/// A request held back because Telegram could not be reached, or until
+/// quiet hours end.
+#[derive(Debug, Deserialize, Serialize)]
+struct Queued {
+    id: String,
+    /// RFC 3339.
+    queued_at: String,
+    /// Profile in effect, so delivery uses the same identity and policies.
+    profile: Option<String>,
+    /// Directory the command was run in; `gh pr create` depends on it.
+    cwd: PathBuf,
+    /// Agent that issued the command, for audit attribution and lockout.
+    agent: String,
+    /// Deliveries tried and failed so far.
+    #[serde(default)]
+    attempts: u32,
+    /// Held by a quiet-hours `queue` action rather than for Telegram.
+    #[serde(default)]
+    quiet_hours: bool,
+    request: Request,
+}
+
+pub fn run(action: Option<Action>) -> Result<()> {
+    match action.unwrap_or(Action::List) {
+        Action::List => list(),
+        Action::Drop { id } => {
+            let q = take(&id)?;
+            log_dropped(&q, "dropped from queue");
+            println!("Dropped {id}.");
+            Ok(())
+        }
+        Action::Run { id } => deliver(&id),
+    }
+}
+
+/// Decide whether to queue a request that could not be sent: ask on a
+/// terminal, otherwise queue only when `[offline_queue]` is configured.
+/// Returns the queue ID when queued.
+pub fn offer(req: &Request, cfg: Option<&OfflineQueue>) -> Result<Option<String>> {
+    let wanted = if io::stdin().is_terminal() && io::stderr().is_terminal() {
+        eprint!("Telegram is unreachable. Queue this request until it is back? [y/N] ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        answer.trim().eq_ignore_ascii_case("y")
+    } else {
+        cfg.is_some()
+    };
+    if !wanted {
+        return Ok(None);
+    }
+    hold(req, false).map(Some)
+}
+
+/// Put `req` in the queue for the daemon to deliver, with `quiet_hours`
+/// when it waits for the window to end; returns the queue ID.
+pub fn hold(req: &Request, quiet_hours: bool) -> Result<String> {
+    let mut request = req.clone();
+    // Without --head, gh would use whatever is checked out when the request
+    // finally runs.
+    if matches!(request.kind, Kind::PrCreate) {
+        let head = crate::pr::parse_pr_args(&request.gh_args[2..]).head;
+        if let (None, Some(branch)) = (head, crate::pr::current_branch()) {
+            request.gh_args.extend(["--head".to_string(), branch]);
+        }
+    }
+
+    let uid = uuid::Uuid::new_v4().to_string();
+    let queued = Queued {
+        id: uid[..8].to_string(),
+        queued_at: Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        profile: crate::profile::active().map(str::to_string),
+        cwd: std::env::current_dir().context("Cannot determine the working directory")?,
+        agent: crate::agent::detect(),
+        attempts: 0,
+        quiet_hours,
+        request,
+    };
+    let id = queued.id.clone();
+    crate::state::update(STATE_FILE, |queue: &mut Queue| queue.requests.push(queued))?;
+    Ok(id)
+}
+
+/// Requests waiting to be delivered.
+pub fn len() -> usize {
+    crate::state::load::<Queue>(STATE_FILE).requests.len()
+}
+
+/// Forget a request once its approval card has been delivered.
+pub fn remove(id: &str) {
+    if let Err(e) = take(id) {
+        tracing::warn!(error = %e, id, "cannot remove delivered request from queue");
+    }
+}
+
+/// Called once per daemon tick: drop requests older than `max_age_hours`,
+/// then deliver the rest oldest first. Each one runs as `gh-guard queue run`
+/// in its original directory and profile, which sends the card, waits for
+/// the decision and runs gh — so a tick blocks while a request awaits
+/// approval. Stops at the first request that still cannot be sent;
+/// requests still in quiet hours are passed over.
+pub fn tick(cfg: Option<&OfflineQueue>, now: DateTime<Local>) -> Result<()> {
+    let max_age = cfg.map_or(OfflineQueue::default_max_age(), |q| q.max_age_hours);
+    let queue: Queue = crate::state::load(STATE_FILE);
+
+    for q in queue.requests {
+        let expired = DateTime::parse_from_rfc3339(&q.queued_at)
+            .ok()
+            .is_none_or(|t| now.fixed_offset() - t > Duration::hours(max_age as i64));
+        if expired {
+            let q = take(&q.id)?;
+            log_dropped(&q, "queue expired");
+            eprintln!("Dropped queued request {} (older than {max_age} h).", q.id);
+            continue;
+        }
+
+        let status = std::process::Command::new(std::env::current_exe()?)
+            .args(["--guard-profile", q.profile.as_deref().unwrap_or("default")])
+            .args(["queue", "run", &q.id])
+            .current_dir(&q.cwd)
+            .env("GH_GUARD_AGENT", &q.agent)
+            .env_remove("GH_GUARD_ACTIVE")
+            .status();
+        let still_queued = || {
+            crate::state::load::<Queue>(STATE_FILE)
+                .requests
+                .iter()
+                .any(|r| r.id == q.id)
+        };
+        match status {
+            // Locked, quiet hours or rate limit: refused and audited for good.
+            Ok(s) if s.code() == Some(crate::exit::POLICY) => {
+                let _ = take(&q.id);
+                eprintln!("Queued request {} refused by policy.", q.id);
+            }
+            // Quiet hours are not over for it yet.
+            Ok(s) if s.code() == Some(crate::exit::QUEUED) => {
+                tracing::debug!(id = %q.id, "queued request held for quiet hours");
+            }
+            Ok(s) if !still_queued() => {
+                eprintln!(
+                    "Delivered queued request {} ({}), exit code {}.",
+                    q.id,
+                    q.request.summary,
+                    s.code().unwrap_or(-1)
+                );
+            }
+            result => {
+                crate::state::update(STATE_FILE, |queue: &mut Queue| {
+                    if let Some(r) = queue.requests.iter_mut().find(|r| r.id == q.id) {
+                        r.attempts += 1;
+                    }
+                })?;
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, id = %q.id, "cannot start delivery");
+                    continue;
+                }
+                tracing::debug!(id = %q.id, "queued request not delivered yet");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Send a queued request through the normal approval pipeline.
+fn deliver(id: &str) -> Result<()> {
+    let queue: Queue = crate::state::load(STATE_FILE);
+    let q = queue
+        .requests
+        .into_iter()
+        .find(|q| q.id == id)
+        .ok_or_else(|| anyhow!("No queued request {id}"))?;
+    let settings = crate::settings::load()?;
+    let quiet = settings.quiet_hours.as_ref();
+    if quiet::action(quiet, q.request.severity)? == Some(QuietAction::Queue) {
+        return Err(Error::Queued(q.id).into());
+    }
+    let when = DateTime::parse_from_rfc3339(&q.queued_at)
+        .map(|t| t.with_timezone(&Local).format("%a %H:%M").to_string())
+        .unwrap_or(q.queued_at);
+
+    let t = crate::i18n::text();
+    let held = if q.quiet_hours {
+        t.quiet_queued_card
+    } else {
+        t.queued_card
+    };
+    let mut req = q.request;
+    req.card = format!("{}\n\n{}", held(&when), req.card);
+    req.queued = Some(q.id);
+    let code = guard::run(req, &guard::Deps::live(), || {
+        eprintln!("Delivering queued request {id}…")
+    })?;
+    std::process::exit(code);
+}
+
+fn list() -> Result<()> {
+    let queue: Queue = crate::state::load(STATE_FILE);
+    if queue.requests.is_empty() {
+        println!("No queued requests.");
+        return Ok(());
+    }
+    for q in &queue.requests {
+        let when = DateTime::parse_from_rfc3339(&q.queued_at)
+            .map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| q.queued_at.clone());
+        let tries = match q.attempts {
+            0 => String::new(),
+            n => format!("  ({n} tries)").dimmed().to_string(),
+        };
+        println!(
+            "{}  {when}  {:<8}  {:<24}  {}{tries}",
+            q.id.bold(),
+            q.request.severity.to_string(),
+            q.request.repo,
+            q.request.summary,
+        );
+    }
+    Ok(())
+}
+
+/// Remove and return a queued request.
+fn take(id: &str) -> Result<Queued> {
+    crate::state::update(STATE_FILE, |queue: &mut Queue| {
+        let pos = queue.requests.iter().position(|q| q.id == id)?;
+        Some(queue.requests.remove(pos))
+    })?
+    .ok_or_else(|| anyhow!("No queued request {id} (see `gh-guard queue`)"))
+}
+
+/// Audit a queued request that will never be sent.
+fn log_dropped(q: &Queued, reason: &str) {
+    let mut entry = audit::Entry::new(&q.request.gh_args, Decision::AutoRejected);
+    entry.id = q.id.clone();
+    entry.source = format!("{}/{}", crate::agent::hostname(), q.agent);
+    entry.repo = Some(q.request.repo.clone());
+    entry.severity = Some(q.request.severity);
+    entry.reason = Some(reason.to_string());
+    entry.profile = q.profile.clone();
+    entry.write();
+}
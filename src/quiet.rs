@@ -0,0 +1,59 @@
+use crate::risk::Severity;
+use crate::settings::{QuietAction, QuietHours};
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveTime};
+
+/// How a request should be delivered once quiet hours have been applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Normal,
+    Silent,
+    Rejected,
+    /// Put in the offline queue, for the daemon to send once the window
+    /// ends.
+    Held,
+}
+
+/// Decide how to deliver a request of `severity` right now.
+pub fn apply(quiet: Option<&QuietHours>, severity: Severity) -> Result<Delivery> {
+    Ok(match action(quiet, severity)? {
+        None | Some(QuietAction::Notify) => Delivery::Normal,
+        Some(QuietAction::Silent) => Delivery::Silent,
+        Some(QuietAction::Reject) => Delivery::Rejected,
+        Some(QuietAction::Queue) => Delivery::Held,
+    })
+}
+
+/// What quiet hours do with a request of `severity` right now; `None`
+/// outside the window.
+pub fn action(quiet: Option<&QuietHours>, severity: Severity) -> Result<Option<QuietAction>> {
+    let Some(q) = quiet else {
+        return Ok(None);
+    };
+    let start = parse_hhmm(&q.start)?;
+    let end = parse_hhmm(&q.end)?;
+    if !in_window(Local::now().time(), start, end) {
+        return Ok(None);
+    }
+    Ok(Some(match severity {
+        Severity::Low => q.low,
+        Severity::Medium => q.medium,
+        Severity::High => q.high,
+        Severity::Critical => q.critical,
+    }))
+}
+
+fn parse_hhmm(s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| anyhow!("Invalid quiet_hours time {s:?} (expected HH:MM)"))
+}
+
+/// True when `now` falls inside [start, end), handling windows that wrap
+/// past midnight (e.g. 23:00–07:00).
+fn in_window(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
@@ -0,0 +1,69 @@
+use crate::settings::RateLimit;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const STATE_FILE: &str = "approvals.json";
+
+/// Approval timestamps (unix seconds) per repository.
+type Approvals = HashMap<String, Vec<u64>>;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Return `Some(count)` when `repo` has already used up its approval budget
+/// for the current window, `None` when another approval is allowed.
+pub fn exceeded(limit: Option<&RateLimit>, repo: &str) -> Option<usize> {
+    let limit = limit?;
+    let cutoff = now().saturating_sub(limit.window_minutes * 60);
+    let approvals: Approvals = crate::state::load(STATE_FILE);
+    let count = approvals
+        .get(repo)
+        .map_or(0, |ts| ts.iter().filter(|&&t| t > cutoff).count());
+    (count >= limit.max_approvals).then_some(count)
+}
+
+/// Record an approved mutation against `repo`, pruning entries older than
+/// the window so the state file stays small.
+pub fn record(limit: Option<&RateLimit>, repo: &str) -> Result<()> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+    let now = now();
+    let cutoff = now.saturating_sub(limit.window_minutes * 60);
+    crate::state::update(STATE_FILE, |approvals: &mut Approvals| {
+        for ts in approvals.values_mut() {
+            ts.retain(|&t| t > cutoff);
+        }
+        approvals.retain(|_, ts| !ts.is_empty());
+        approvals.entry(repo.to_string()).or_default().push(now);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approvals_recorded_side_by_side_all_count() {
+        let limit = RateLimit {
+            max_approvals: 1000,
+            window_minutes: 60,
+        };
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..25 {
+                        record(Some(&limit), "race/count").unwrap();
+                    }
+                });
+            }
+        });
+        let approvals: Approvals = crate::state::load(STATE_FILE);
+        assert_eq!(approvals["race/count"].len(), 200);
+    }
+}
@@ -0,0 +1,213 @@
+//! Read-only mode: while it is on, every command that could change
+//! something on GitHub is refused on the spot, without a card. For handing
+//! the machine to an agent that should only look around.
+//!
+//! Turning it on takes no approval; turning it off does, since the agent
+//! it is meant to hold back could otherwise do it.
+
+use crate::audit::{self, Decision};
+use crate::error::Error;
+use crate::notify::{self, ApprovalResult, TgConfig};
+use crate::{guard, i18n, output, panic, state};
+use anyhow::Result;
+use clap::Subcommand;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "readonly.json";
+
+/// `gh-guard readonly` steps; with none given the current state is shown.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Action {
+    /// Refuse every mutating command until turned off
+    On,
+    /// Allow mutations again (requires approval on your phone)
+    Off,
+    /// Show whether read-only mode is on
+    Status,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ReadOnly {
+    /// Local time it was turned on, "YYYY-MM-DD HH:MM".
+    since: Option<String>,
+}
+
+/// When read-only mode was turned on, if it is on.
+pub fn since() -> Option<String> {
+    state::load::<ReadOnly>(STATE_FILE).since
+}
+
+/// Whether `args`, on their way to gh unguarded, only read. Anything not
+/// known to be a read is refused in read-only mode: `gh api` (whose
+/// mutations are guarded anyway), local commands, searches, and the
+/// `list` / `view` / … verbs of the other commands.
+pub fn allows(args: &[String]) -> bool {
+    if asks_for_help(args) {
+        return true;
+    }
+    let verb = args.get(1).map(String::as_str);
+    match args.first().map(String::as_str) {
+        None => true,
+        Some(
+            "--version" | "version" | "help" | "completion" | "config" | "api" | "browse"
+            | "search" | "status",
+        ) => true,
+        Some("auth") => verb == Some("status"),
+        Some(_) => matches!(
+            verb,
+            Some(
+                "list"
+                    | "ls"
+                    | "view"
+                    | "status"
+                    | "diff"
+                    | "checks"
+                    | "checkout"
+                    | "clone"
+                    | "download"
+                    | "watch"
+                    | "verify"
+            )
+        ),
+    }
+}
+
+/// Whether `args` end in `--help` / `-h` after nothing but the command's
+/// words (`pr merge --help`). After another flag it may be that flag's
+/// value: `issue create -t --help` creates an issue titled "--help".
+fn asks_for_help(args: &[String]) -> bool {
+    let first_flag = args.iter().position(|a| a.starts_with('-'));
+    first_flag.is_some_and(|i| i + 1 == args.len() && matches!(args[i].as_str(), "--help" | "-h"))
+}
+
+/// Refuse `args` for read-only mode, on since `since`: audited as
+/// auto-rejected. Returns the error to exit with.
+pub fn refuse(args: &[String], since: &str) -> anyhow::Error {
+    let t = i18n::text();
+    let mut entry = audit::Entry::new(args, Decision::AutoRejected);
+    entry.reason = Some("read-only".to_string());
+    entry.write();
+    output::emit(&entry);
+    eprintln!("{}", (t.readonly_refused)(since).red().bold());
+    Error::PolicyRejected("read-only").into()
+}
+
+/// `gh-guard readonly` entry point.
+pub fn run(action: Option<Action>) -> Result<()> {
+    let t = i18n::text();
+    match action.unwrap_or(Action::Status) {
+        Action::On => {
+            let since = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+            state::update(STATE_FILE, |st: &mut ReadOnly| {
+                st.since.get_or_insert(since);
+            })?;
+            eprintln!("{}", t.readonly_on.yellow().bold());
+            Ok(())
+        }
+        Action::Off => turn_off(),
+        Action::Status => {
+            match since() {
+                Some(since) => println!("{}", (t.readonly_status_on)(&since)),
+                None => println!("{}", t.readonly_status_off),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Leave read-only mode once the approver agrees.
+fn turn_off() -> Result<()> {
+    let t = i18n::text();
+    let Some(since) = since() else {
+        println!("{}", t.readonly_status_off);
+        return Ok(());
+    };
+    let settings = crate::settings::load()?;
+    let deps = guard::Deps::live();
+    let tg = TgConfig {
+        members: settings.members(),
+        ..deps.credentials.telegram()?
+    };
+
+    eprintln!("{}", t.sending_readonly_off);
+    let html = (t.readonly_off_card)(
+        &notify::escape_html(&since),
+        &notify::escape_html(&crate::agent::source()),
+    );
+    let (request_id, message_id) =
+        deps.notifier
+            .send_with_approval(&tg, &html, &[], &[], None, false)?;
+    let timeout = settings.approval_timeout();
+    eprintln!("{}", (t.waiting)(&guard::duration_label(timeout)));
+
+    let approval = deps
+        .notifier
+        .poll_for_approval(&tg, &request_id, message_id, timeout, 1)?;
+    match approval.result {
+        ApprovalResult::Approved => {
+            state::save(STATE_FILE, &ReadOnly::default())?;
+            eprintln!("{}", t.readonly_off.green().bold());
+            Ok(())
+        }
+        ApprovalResult::Rejected => {
+            eprintln!("{}", t.readonly_off_rejected.red().bold());
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Timeout => {
+            eprintln!("{}", t.readonly_off_timed_out.yellow());
+            Err(Error::ApprovalTimedOut.into())
+        }
+        ApprovalResult::Panic { revoke } => {
+            panic::run(revoke)?;
+            Err(Error::ApprovalRejected.into())
+        }
+        ApprovalResult::Cancelled => {
+            eprintln!("{}", t.readonly_off_cancelled.yellow());
+            Err(Error::Cancelled.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(cmd: &str) -> bool {
+        let args: Vec<String> = cmd.split_whitespace().map(str::to_string).collect();
+        allows(&args)
+    }
+
+    #[test]
+    fn only_known_reads_are_allowed() {
+        for read in [
+            "",
+            "--version",
+            "issue list -R o/r",
+            "pr view 12 --comments",
+            "pr checks",
+            "run download 42",
+            "api repos/o/r",
+            "search prs bug",
+            "auth status",
+            "pr merge --help",
+            "issue create -h",
+        ] {
+            assert!(allowed(read), "{read}");
+        }
+        for write in [
+            "issue create -t x",
+            "pr merge 12",
+            "repo delete o/r",
+            "release upload v1 a.tgz",
+            "auth logout",
+            "extension exec foo",
+            "workflow run ci.yml",
+            "issue create -t --help",
+            "issue create --help -t x",
+            "pr merge 12 --squash -h",
+        ] {
+            assert!(!allowed(write), "{write}");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! `gh release upload`: which files go onto which release. With `--clobber`
+//! an asset of the same name is replaced, which changes what everyone who
+//! downloads it gets.
+
+use crate::flags::{self, flag, Arg, Flag};
+use std::path::Path;
+
+/// Parsed metadata from `gh release upload` flags (everything after
+/// "upload").
+#[derive(Debug, Default)]
+pub struct UploadArgs {
+    pub tag: Option<String>,
+    /// The files as given, each possibly with a `#display label`.
+    pub files: Vec<String>,
+    pub clobber: bool,
+    /// `-R` / `--repo`, as given.
+    pub repo: Option<String>,
+    pub help: bool,
+}
+
+/// `gh release upload` flags, so values are not mistaken for files.
+const FLAGS: &[Flag] = &[
+    flag("clobber", None, false),
+    flag("repo", Some('R'), true),
+    flag("help", Some('h'), false),
+];
+
+/// Scan raw `gh release upload` flags: the tag, then the files.
+pub fn parse_upload_args(args: &[String]) -> UploadArgs {
+    let mut out = UploadArgs::default();
+    for arg in flags::split(args, FLAGS) {
+        match arg {
+            Arg::Flag {
+                name: "clobber",
+                value,
+            } => out.clobber = flags::enabled(value),
+            Arg::Flag {
+                name: "repo",
+                value: Some(v),
+            } => out.repo = Some(v.to_string()),
+            Arg::Flag {
+                name: "help",
+                value,
+            } => out.help = flags::enabled(value),
+            Arg::Positional(p) if out.tag.is_none() => out.tag = Some(p.to_string()),
+            Arg::Positional(p) => out.files.push(p.to_string()),
+            _ => {}
+        }
+    }
+    out
+}
+
+impl UploadArgs {
+    /// The asset names gh gives the files: each file's name, followed by
+    /// its label when one is set with `file#label`.
+    pub fn assets(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .map(|file| {
+                let (path, label) = match file.split_once('#') {
+                    Some((path, label)) => (path, Some(label)),
+                    None => (file.as_str(), None),
+                };
+                let name = Path::new(path)
+                    .file_name()
+                    .map_or(path.into(), |n| n.to_string_lossy());
+                match label.filter(|l| !l.is_empty()) {
+                    Some(label) => format!("{name} ({label})"),
+                    None => name.into_owned(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> UploadArgs {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_upload_args(&args)
+    }
+
+    #[test]
+    fn tag_files_and_clobber() {
+        let upload = parse(&[
+            "v1.2.0",
+            "dist/app.tar.gz",
+            "-R",
+            "acme/app",
+            "dist/app.zip#Windows build",
+            "--clobber",
+        ]);
+        assert_eq!(upload.tag.as_deref(), Some("v1.2.0"));
+        assert_eq!(upload.repo.as_deref(), Some("acme/app"));
+        assert!(upload.clobber);
+        assert_eq!(upload.assets(), ["app.tar.gz", "app.zip (Windows build)"]);
+
+        let upload = parse(&["v1", "a.txt"]);
+        assert!(!upload.clobber);
+        assert_eq!(upload.assets(), ["a.txt"]);
+    }
+}
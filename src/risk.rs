@@ -0,0 +1,175 @@
+use crate::api::{self, ApiArgs};
+use crate::pr::PrArgs;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How much damage an intercepted command could do if it were malicious.
+/// Ordered, so policies can say "high and above".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+/// Draft PRs can't be merged without another step, so they rank lower.
+pub fn classify_pr(pr: &PrArgs) -> Severity {
+    if pr.draft {
+        Severity::Low
+    } else {
+        Severity::Medium
+    }
+}
+
+/// A fork that stays with its owner copies nothing anywhere new; a private
+/// repository forked to another owner takes its code along.
+pub fn classify_fork(same_owner: bool, private: Option<bool>) -> Severity {
+    match (same_owner, private) {
+        (true, _) | (false, Some(false)) => Severity::Low,
+        (false, None) => Severity::Medium,
+        (false, Some(true)) => Severity::High,
+    }
+}
+
+/// New assets only add to a release; replacing one changes what people
+/// who download it get.
+pub fn classify_release_upload(clobber: bool) -> Severity {
+    if clobber {
+        Severity::High
+    } else {
+        Severity::Medium
+    }
+}
+
+/// A deleted repository is gone, with everything in it.
+pub fn classify_repo_delete() -> Severity {
+    Severity::Critical
+}
+
+/// Rank a `gh api` mutation by method and endpoint shape.
+pub fn classify_api(api: &ApiArgs) -> Severity {
+    let segments = api::endpoint_segments(api.endpoint.as_deref().unwrap_or(""));
+
+    // A webhook sends events, secrets and code included, wherever it says.
+    if api::is_webhook(&segments) {
+        return Severity::Critical;
+    }
+    // Any change to a branch's protection can open it to force pushes.
+    if api::protection_change(api).is_some() {
+        return Severity::Critical;
+    }
+    // A workflow runs whatever it says in CI, with the repository's secrets.
+    if api::workflow_write(api).is_some() {
+        return Severity::Critical;
+    }
+    // Collaborators and teams: people and bots getting write access.
+    if api::access_change(api).is_some() {
+        return Severity::Critical;
+    }
+    // So does joining an organisation or a team.
+    if api::membership_change(api).is_some_and(|m| m.user.is_some() && !m.removed) {
+        return Severity::Critical;
+    }
+    // DELETE /repos/{owner}/{repo} removes the whole repository.
+    if api.method == "DELETE" && segments.len() == 3 && segments[0] == "repos" {
+        return Severity::Critical;
+    }
+    // Labels and reactions are everyday chatter, even when removed.
+    if is_label_or_reaction(&api.method, &segments) {
+        return Severity::Low;
+    }
+    if api.method == "DELETE" {
+        return Severity::High;
+    }
+    if is_comment(&api.method, &segments) {
+        return Severity::Low;
+    }
+    Severity::Medium
+}
+
+/// `method` on `segments` is one of GitHub's label or reaction calls. Only
+/// their own endpoints count, with the methods they take: a branch or file
+/// that happens to be called "labels" is not a label.
+fn is_label_or_reaction(method: &str, segments: &[&str]) -> bool {
+    let subject = match (method, segments) {
+        ("POST", ["repos", _, _, "labels"])
+        | ("PATCH" | "DELETE", ["repos", _, _, "labels", _])
+        | ("POST" | "PUT" | "DELETE", ["repos", _, _, "issues", _, "labels"])
+        | ("DELETE", ["repos", _, _, "issues", _, "labels", _]) => return true,
+        ("POST", [subject @ .., "reactions"]) | ("DELETE", [subject @ .., "reactions", _]) => {
+            subject
+        }
+        _ => return false,
+    };
+    matches!(
+        subject,
+        ["repos", _, _, "issues" | "comments" | "releases", _]
+            | ["repos", _, _, "issues" | "pulls", "comments", _]
+    )
+}
+
+/// `method` on `segments` writes or edits an issue, pull request or commit
+/// comment.
+fn is_comment(method: &str, segments: &[&str]) -> bool {
+    match method {
+        "POST" => matches!(
+            segments,
+            ["repos", _, _, "issues" | "pulls" | "commits", _, "comments"]
+                | ["repos", _, _, "pulls", _, "comments", _, "replies"]
+        ),
+        "PATCH" => matches!(
+            segments,
+            ["repos", _, _, "issues" | "pulls", "comments", _] | ["repos", _, _, "comments", _]
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(method: &str, endpoint: &str) -> Severity {
+        let args = ["-X", method, endpoint].map(str::to_string);
+        classify_api(&api::parse_api_args(&args))
+    }
+
+    #[test]
+    fn labels_reactions_and_comments_are_low() {
+        assert_eq!(api("POST", "repos/o/r/issues/7/labels"), Severity::Low);
+        assert_eq!(api("DELETE", "repos/o/r/labels/bug"), Severity::Low);
+        assert_eq!(api("POST", "repos/o/r/issues/7/reactions"), Severity::Low);
+        assert_eq!(
+            api("DELETE", "repos/o/r/pulls/comments/9/reactions/3"),
+            Severity::Low
+        );
+        assert_eq!(api("POST", "repos/o/r/issues/7/comments"), Severity::Low);
+        assert_eq!(api("DELETE", "repos/o/r/issues/comments/9"), Severity::High);
+    }
+
+    #[test]
+    fn paths_merely_named_like_labels_are_not_low() {
+        let branch = api("DELETE", "repos/o/r/git/refs/heads/labels");
+        assert_eq!(branch, Severity::High);
+        assert_eq!(api("PUT", "repos/o/r/contents/labels/x"), Severity::Medium);
+        assert_eq!(api("PUT", "repos/o/r/contents/reactions"), Severity::Medium);
+        assert_eq!(
+            api("PUT", "repos/o/r/contents/comments/x"),
+            Severity::Medium
+        );
+        assert_eq!(api("PUT", "repos/o/r/labels/bug"), Severity::Medium);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::settings::SecretBackend;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a client waits for the daemon before going to the store itself.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the daemon waits for a secret it did not have to be unlocked
+/// and handed over: long enough to type a passphrase.
+const UNLOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+type Cache = Arc<Mutex<HashMap<String, (String, Instant)>>>;
+
+/// One request per connection, as a JSON line. Replies are a JSON line
+/// too: `{"value": …}` for `get`, `{}` otherwise. A `put` is only taken
+/// on the connection of a `get` that missed, for the key it asked for:
+/// the client that went to the store for it hands it over.
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum Request {
+    Get { key: String },
+    Put { key: String, value: String },
+    Forget { key: String },
+    Clear,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Reply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+/// Socket in a directory only we can enter; macOS ignores the permissions
+/// of the socket file itself.
+fn socket_path() -> PathBuf {
+    crate::state::dir().join("agent").join("secrets.sock")
+}
+
+/// Serve `[secrets] cache_minutes` from the daemon: secrets a command has
+/// unlocked are handed over and kept in memory, so the next commands skip
+/// the keychain prompt, passphrase or `pass`/Vault round trip until `ttl`
+/// runs out. Nothing is written to disk.
+pub fn serve(ttl: Duration) -> Result<()> {
+    let path = socket_path();
+    let dir = path.parent().expect("socket path has a parent");
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+        .with_context(|| format!("Cannot create {}", dir.display()))?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    // A socket left behind by a daemon that did not exit cleanly.
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("Cannot listen on {}", path.display()))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+    std::thread::spawn(move || {
+        let cache = Cache::default();
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let cache = Arc::clone(&cache);
+            // Its own thread: a miss waits for the client to unlock the store.
+            std::thread::spawn(move || {
+                if let Err(e) = handle(stream, &cache, ttl) {
+                    tracing::debug!(error = %e, "secret cache request failed");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(stream: UnixStream, cache: &Cache, ttl: Duration) -> Result<()> {
+    check_peer(&stream)?;
+    // A client that connects and says nothing must not stall the others.
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let lock = || {
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (_, expires)| *expires > Instant::now());
+        cache
+    };
+    let (reply, missed) = match serde_json::from_str(&line)? {
+        Request::Get { key } => {
+            let value = lock().get(&key).map(|(v, _)| v.clone());
+            let missed = value.is_none().then_some(key);
+            (Reply { value }, missed)
+        }
+        Request::Put { .. } => bail!("secret handed over without a lookup"),
+        Request::Forget { key } => {
+            lock().remove(&key);
+            (Reply::default(), None)
+        }
+        Request::Clear => {
+            lock().clear();
+            (Reply::default(), None)
+        }
+    };
+    let mut out = serde_json::to_vec(&reply)?;
+    out.push(b'\n');
+    (&stream).write_all(&out)?;
+
+    let Some(missed) = missed else { return Ok(()) };
+    stream.set_read_timeout(Some(UNLOCK_TIMEOUT))?;
+    line.clear();
+    // Closed without a word: the store did not have it either.
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(());
+    }
+    match serde_json::from_str(&line)? {
+        Request::Put { key, value } if key == missed => {
+            lock().insert(key, (value, Instant::now() + ttl));
+            Ok(())
+        }
+        _ => bail!("unexpected request after a miss on {missed}"),
+    }
+}
+
+/// Whether the daemon may serve `backend`'s secrets. Only Linux lets it
+/// check that a client is gh-guard itself (see [`check_peer`]); elsewhere,
+/// serving the keychain's secrets to any program of the user's would undo
+/// the keychain's own per-application prompt.
+pub fn can_serve(backend: SecretBackend) -> bool {
+    cfg!(any(target_os = "linux", target_os = "android")) || backend != SecretBackend::Keyring
+}
+
+/// Refuse a client that does not run as us or, where the OS says, that is
+/// not this gh-guard binary: the socket's directory keeps other users out,
+/// this keeps out the user's other programs.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn check_peer(stream: &UnixStream) -> Result<()> {
+    use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+    let cred = getsockopt(stream.as_raw_fd(), PeerCredentials)?;
+    if cred.uid() != nix::unistd::geteuid().as_raw() {
+        bail!("connection from uid {}", cred.uid());
+    }
+    // An upgrade replaces the binary under a running daemon.
+    let exe = |pid: &str| -> Result<PathBuf> {
+        let path = fs::read_link(format!("/proc/{pid}/exe"))?;
+        let path = path.to_string_lossy();
+        Ok(PathBuf::from(path.trim_end_matches(" (deleted)")))
+    };
+    let peer = exe(&cred.pid().to_string())?;
+    if peer != exe("self")? {
+        bail!("connection from {}, not gh-guard", peer.display());
+    }
+    Ok(())
+}
+
+/// Refuse a client that does not run as us: the socket's directory keeps
+/// other users out, this keeps out whatever gets past it.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn check_peer(stream: &UnixStream) -> Result<()> {
+    let (uid, _) = nix::unistd::getpeereid(stream.as_raw_fd())?;
+    if uid != nix::unistd::geteuid() {
+        bail!("connection from uid {uid}");
+    }
+    Ok(())
+}
+
+/// What the daemon had for a secret.
+pub enum Cached {
+    Hit(String),
+    /// Not cached (or no daemon): unlock it from the store and hand it
+    /// over with [`Miss::put`], on the connection that asked.
+    Miss(Miss),
+}
+
+pub struct Miss(Option<(UnixStream, String)>);
+
+impl Miss {
+    /// Hand the secret the store unlocked to the daemon.
+    pub fn put(self, value: &str) {
+        let Some((stream, key)) = self.0 else { return };
+        let put = Request::Put {
+            key,
+            value: value.to_string(),
+        };
+        if let Ok(mut line) = serde_json::to_vec(&put) {
+            line.push(b'\n');
+            let _ = (&stream).write_all(&line);
+        }
+    }
+}
+
+/// A cached secret, or a [`Miss`] when caching is off, the daemon is not
+/// running or the secret has not been handed over yet.
+pub fn get(key: &str) -> Cached {
+    let get = Request::Get {
+        key: key.to_string(),
+    };
+    match connect().and_then(|stream| Some((exchange(&stream, &get)?, stream))) {
+        Some((Reply { value: Some(v) }, _)) => Cached::Hit(v),
+        Some((_, stream)) => Cached::Miss(Miss(Some((stream, key.to_string())))),
+        None => Cached::Miss(Miss(None)),
+    }
+}
+
+/// Drop a secret that was changed or deleted.
+pub fn forget(key: &str) {
+    ask(&Request::Forget {
+        key: key.to_string(),
+    });
+}
+
+/// Drop everything (`gh-guard panic`).
+pub fn clear() {
+    ask(&Request::Clear);
+}
+
+/// Best effort: any failure means "no cache" and the caller falls back to
+/// the store.
+fn ask(req: &Request) -> Option<Reply> {
+    exchange(&connect()?, req)
+}
+
+fn connect() -> Option<UnixStream> {
+    crate::settings::load().ok()?.secrets.cache_minutes?;
+    let stream = UnixStream::connect(socket_path()).ok()?;
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+    Some(stream)
+}
+
+fn exchange(stream: &UnixStream, req: &Request) -> Option<Reply> {
+    let mut line = serde_json::to_vec(req).ok()?;
+    line.push(b'\n');
+    (&*stream).write_all(&line).ok()?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).ok()?;
+    serde_json::from_str(&reply).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `req` then `then` (if any) on a fresh connection served from `cache`;
+    /// the reply, or `None` when the daemon hung up without one.
+    fn send(cache: &Cache, req: &Request, then: Option<&Request>) -> Option<Reply> {
+        let (client, server) = UnixStream::pair().unwrap();
+        let cache = Arc::clone(cache);
+        let served = std::thread::spawn(move || handle(server, &cache, Duration::from_secs(60)));
+        let reply = exchange(&client, req);
+        if let Some(then) = then {
+            let mut line = serde_json::to_vec(then).unwrap();
+            line.push(b'\n');
+            (&client).write_all(&line).unwrap();
+        }
+        drop(client);
+        let _ = served.join().unwrap();
+        reply
+    }
+
+    fn get(key: &str) -> Request {
+        Request::Get {
+            key: key.to_string(),
+        }
+    }
+
+    fn put(key: &str, value: &str) -> Request {
+        Request::Put {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn secrets_are_only_taken_after_a_miss_on_that_key() {
+        let cache = Cache::default();
+        assert!(send(&cache, &put("pat", "forged"), None).is_none());
+        assert!(cache.lock().unwrap().is_empty());
+
+        let reply = send(&cache, &get("pat"), Some(&put("tg", "forged"))).unwrap();
+        assert_eq!(reply.value, None);
+        assert!(cache.lock().unwrap().is_empty());
+
+        send(&cache, &get("pat"), Some(&put("pat", "ghp_x"))).unwrap();
+        let reply = send(&cache, &get("pat"), Some(&put("pat", "forged"))).unwrap();
+        assert_eq!(reply.value.as_deref(), Some("ghp_x"));
+        assert_eq!(cache.lock().unwrap()["pat"].0, "ghp_x");
+    }
+}
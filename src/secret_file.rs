@@ -0,0 +1,210 @@
+use crate::config::SecretStore;
+use crate::settings::Secrets;
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::XChaCha20Poly1305;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Passphrase entered once per process, so several lookups prompt only once.
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+/// On-disk envelope. The plaintext is a JSON map of secret name → value.
+#[derive(Debug, Deserialize, Serialize)]
+struct Envelope {
+    version: u32,
+    /// "argon2id" (passphrase) or "sha256" (key file).
+    kdf: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets kept in one local file, encrypted with XChaCha20-Poly1305 under
+/// a key from a key file or a passphrase.
+pub struct FileStore {
+    path: PathBuf,
+    key_file: Option<PathBuf>,
+}
+
+impl FileStore {
+    /// `[secrets] file` / `key_file`; the file defaults to `secrets.enc`
+    /// next to the config file.
+    pub fn new(cfg: &Secrets) -> Self {
+        let path = match &cfg.file {
+            Some(p) => crate::settings::expand_home(p),
+            None => crate::settings::path()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+                .join("secrets.enc"),
+        };
+        FileStore {
+            path,
+            key_file: cfg.key_file.as_deref().map(crate::settings::expand_home),
+        }
+    }
+}
+
+impl SecretStore for FileStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.load()?.remove(name))
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        let mut secrets = self.load()?;
+        secrets.insert(name.to_string(), value.to_string());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        let mut secrets = self.load()?;
+        if secrets.remove(name).is_some() {
+            self.save(&secrets)?;
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("encrypted file {}", self.path.display())
+    }
+}
+
+impl FileStore {
+    fn load(&self) -> Result<BTreeMap<String, String>> {
+        let path = &self.path;
+        let text = match std::fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+        };
+        let env: Envelope = serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a gh-guard secrets file", path.display()))?;
+        if env.version != 1 {
+            bail!(
+                "Unsupported secrets file version {} in {}",
+                env.version,
+                path.display()
+            );
+        }
+
+        let salt = hex::decode(&env.salt)?;
+        let nonce = hex::decode(&env.nonce)?;
+        let ciphertext = hex::decode(&env.ciphertext)?;
+        if nonce.len() != 24 {
+            bail!("Corrupt nonce in {}", path.display());
+        }
+        let key = self.derive_key(&env.kdf, &salt, false)?;
+        let plaintext = XChaCha20Poly1305::new(&key.into())
+            .decrypt(nonce.as_slice().into(), ciphertext.as_slice())
+            .map_err(|_| {
+                anyhow!(
+                    "Cannot decrypt {} — wrong passphrase or key file?",
+                    path.display()
+                )
+            })?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Re-encrypt the whole map under a fresh salt and nonce and write it
+    /// atomically with mode 0600.
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let path = &self.path;
+        let creating = !path.exists();
+        let kdf = if self.key_file.is_some() {
+            "sha256"
+        } else {
+            "argon2id"
+        };
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let key = self.derive_key(kdf, &salt, creating)?;
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(secrets)?;
+        let ciphertext = XChaCha20Poly1305::new(&key.into())
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| anyhow!("Encryption failed"))?;
+
+        let env = Envelope {
+            version: 1,
+            kdf: kdf.to_string(),
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)
+            .and_then(|mut f| f.write_all(serde_json::to_string_pretty(&env)?.as_bytes()))
+            .with_context(|| format!("Cannot write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    /// 256-bit key from the key file (SHA-256 of its contents) or from the
+    /// passphrase (Argon2id with the file's salt).
+    fn derive_key(&self, kdf: &str, salt: &[u8], creating: bool) -> Result<[u8; 32]> {
+        match kdf {
+            "sha256" => {
+                let key_file = self.key_file.as_ref().ok_or_else(|| {
+                    anyhow!("Secrets file was written with a key file; set [secrets] key_file")
+                })?;
+                let bytes = std::fs::read(key_file)
+                    .with_context(|| format!("Cannot read key file {}", key_file.display()))?;
+                if bytes.len() < 32 {
+                    bail!(
+                        "Key file {} is too short (need at least 32 bytes)",
+                        key_file.display()
+                    );
+                }
+                Ok(Sha256::digest(&bytes).into())
+            }
+            "argon2id" => {
+                let passphrase = passphrase(creating)?;
+                let mut key = [0u8; 32];
+                argon2::Argon2::default()
+                    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                    .map_err(|e| anyhow!("Key derivation failed: {e}"))?;
+                Ok(key)
+            }
+            other => bail!("Unknown key derivation {other:?} in secrets file"),
+        }
+    }
+}
+
+/// `GH_GUARD_PASSPHRASE`, else a hidden prompt (confirmed when creating the
+/// file, so a typo cannot lock the secrets away).
+fn passphrase(creating: bool) -> Result<&'static str> {
+    if let Some(p) = PASSPHRASE.get() {
+        return Ok(p);
+    }
+    let p = match std::env::var("GH_GUARD_PASSPHRASE") {
+        Ok(p) => p,
+        Err(_) => {
+            let p = rpassword::prompt_password("gh-guard secrets passphrase: ")
+                .context("No passphrase: set GH_GUARD_PASSPHRASE or run in a terminal")?;
+            if creating && rpassword::prompt_password("Repeat passphrase: ")? != p {
+                bail!("Passphrases do not match.");
+            }
+            p
+        }
+    };
+    if p.is_empty() {
+        bail!("Passphrase cannot be empty.");
+    }
+    Ok(PASSPHRASE.get_or_init(|| p))
+}
@@ -0,0 +1,83 @@
+use crate::config::SecretStore;
+use crate::settings::PassItems;
+use anyhow::{anyhow, bail, Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The standard Unix password store, driven through the `pass` CLI.
+pub struct PassStore {
+    items: PassItems,
+}
+
+impl PassStore {
+    pub fn new(items: PassItems) -> Self {
+        PassStore { items }
+    }
+}
+
+/// Password-store path for secret `name`.
+fn item_path(cfg: &PassItems, name: &str) -> String {
+    match cfg.items.get(name) {
+        Some(p) => p.clone(),
+        None => format!("{}/{name}", cfg.prefix.trim_end_matches('/')),
+    }
+}
+
+impl SecretStore for PassStore {
+    /// First line of `pass show <path>`, or `None` if the item does not exist.
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let path = item_path(&self.items, name);
+        let out = Command::new("pass")
+            .args(["show", &path])
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::piped())
+            .output()
+            .context("Cannot run `pass` — is it installed and on PATH?")?;
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            if stderr.contains("is not in the password store") {
+                return Ok(None);
+            }
+            bail!("pass show {path} failed: {}", stderr.trim());
+        }
+        let stdout =
+            String::from_utf8(out.stdout).map_err(|_| anyhow!("pass returned non-UTF-8"))?;
+        Ok(stdout.lines().next().map(str::to_string))
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        let path = item_path(&self.items, name);
+        let mut child = Command::new("pass")
+            .args(["insert", "--multiline", "--force", &path])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .context("Cannot run `pass` — is it installed and on PATH?")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{value}")?;
+        }
+        if !child.wait()?.success() {
+            bail!("pass insert {path} failed");
+        }
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        let path = item_path(&self.items, name);
+        let out = Command::new("pass")
+            .args(["rm", "--force", &path])
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .context("Cannot run `pass` — is it installed and on PATH?")?;
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        if !out.status.success() && !stderr.contains("is not in the password store") {
+            bail!("pass rm {path} failed: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("password store ({}/…)", self.items.prefix)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::config::SecretStore;
+use crate::settings::{Vault, VaultAuth};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Client token obtained by logging in, reused for the rest of the process.
+static TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Secrets in a HashiCorp Vault KV v2 engine.
+pub struct VaultStore {
+    cfg: Vault,
+}
+
+impl VaultStore {
+    pub fn new(cfg: Vault) -> Self {
+        VaultStore { cfg }
+    }
+}
+
+impl SecretStore for VaultStore {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        let cfg = &self.cfg;
+        let (path, field) = locate(cfg, name);
+        Ok(read(cfg, &path)?
+            .and_then(|mut data| data.remove(&field))
+            .and_then(|v| v.as_str().map(str::to_string)))
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        let cfg = &self.cfg;
+        let (path, field) = locate(cfg, name);
+        let mut data = read(cfg, &path)?.unwrap_or_default();
+        data.insert(field, Value::String(value.to_string()));
+        write(cfg, &path, data)
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        let cfg = &self.cfg;
+        let (path, field) = locate(cfg, name);
+        let Some(mut data) = read(cfg, &path)? else {
+            return Ok(());
+        };
+        if data.remove(&field).is_some() {
+            write(cfg, &path, data)?;
+        }
+        Ok(())
+    }
+
+    /// e.g. "Vault https://vault:8200 secret/gh-guard".
+    fn describe(&self) -> String {
+        let cfg = &self.cfg;
+        format!(
+            "Vault {} {}/{}",
+            address(cfg).unwrap_or_else(|_| "(no VAULT_ADDR)".to_string()),
+            cfg.mount,
+            cfg.path
+        )
+    }
+}
+
+/// (secret path, field) for secret `name`.
+fn locate(cfg: &Vault, name: &str) -> (String, String) {
+    match cfg.items.get(name) {
+        Some(item) => match item.split_once('#') {
+            Some((path, field)) => (path.to_string(), field.to_string()),
+            None => (item.clone(), name.to_string()),
+        },
+        None => (cfg.path.clone(), name.to_string()),
+    }
+}
+
+fn address(cfg: &Vault) -> Result<String> {
+    cfg.address
+        .clone()
+        .or_else(|| std::env::var("VAULT_ADDR").ok())
+        .map(|a| a.trim_end_matches('/').to_string())
+        .ok_or_else(|| anyhow!("Set [secrets.vault] address or VAULT_ADDR"))
+}
+
+fn agent(url: &str) -> Result<ureq::Agent> {
+    Ok(crate::http::builder(url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(15))
+        .build())
+}
+
+fn request(cfg: &Vault, method: &str, api_path: &str) -> Result<ureq::Request> {
+    let url = format!("{}/v1/{api_path}", address(cfg)?);
+    let mut req = agent(&url)?.request(method, &url);
+    if let Some(ns) = &cfg.namespace {
+        req = req.set("X-Vault-Namespace", ns);
+    }
+    Ok(req)
+}
+
+/// Fields of a KV v2 secret, or `None` if it does not exist.
+fn read(cfg: &Vault, path: &str) -> Result<Option<Map<String, Value>>> {
+    let token = token(cfg)?;
+    let resp = request(cfg, "GET", &format!("{}/data/{path}", cfg.mount))?
+        .set("X-Vault-Token", token)
+        .call();
+    let body: Value = match resp {
+        Ok(r) => r.into_json()?,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(anyhow!("Vault read {}/{path}: {e}", cfg.mount)),
+    };
+    match body["data"]["data"].clone() {
+        Value::Object(map) => Ok(Some(map)),
+        _ => Ok(None),
+    }
+}
+
+fn write(cfg: &Vault, path: &str, data: Map<String, Value>) -> Result<()> {
+    let token = token(cfg)?;
+    request(cfg, "POST", &format!("{}/data/{path}", cfg.mount))?
+        .set("X-Vault-Token", token)
+        .send_json(json!({ "data": data }))
+        .map_err(|e| anyhow!("Vault write {}/{path}: {e}", cfg.mount))?;
+    Ok(())
+}
+
+fn token(cfg: &Vault) -> Result<&'static str> {
+    if let Some(t) = TOKEN.get() {
+        return Ok(t);
+    }
+    let t = match &cfg.auth {
+        VaultAuth::Token => match std::env::var("VAULT_TOKEN") {
+            Ok(t) => t,
+            Err(_) => {
+                let file = crate::settings::home_dir().join(".vault-token");
+                std::fs::read_to_string(&file)
+                    .with_context(|| format!("No VAULT_TOKEN and cannot read {}", file.display()))?
+                    .trim()
+                    .to_string()
+            }
+        },
+        VaultAuth::AppRole {
+            role_id,
+            secret_id_file,
+            mount,
+        } => {
+            let secret_id = match (std::env::var("GH_GUARD_VAULT_SECRET_ID"), secret_id_file) {
+                (Ok(id), _) => id,
+                (Err(_), Some(f)) => std::fs::read_to_string(crate::settings::expand_home(f))
+                    .with_context(|| format!("Cannot read {}", f.display()))?
+                    .trim()
+                    .to_string(),
+                (Err(_), None) => {
+                    bail!("AppRole login needs GH_GUARD_VAULT_SECRET_ID or secret_id_file")
+                }
+            };
+            login(
+                cfg,
+                mount,
+                json!({ "role_id": role_id, "secret_id": secret_id }),
+            )?
+        }
+        VaultAuth::Kubernetes {
+            role,
+            jwt_file,
+            mount,
+        } => {
+            let jwt = std::fs::read_to_string(jwt_file)
+                .with_context(|| format!("Cannot read {}", jwt_file.display()))?;
+            login(cfg, mount, json!({ "role": role, "jwt": jwt.trim() }))?
+        }
+    };
+    if t.is_empty() {
+        bail!("Empty Vault token");
+    }
+    Ok(TOKEN.get_or_init(|| t))
+}
+
+fn login(cfg: &Vault, mount: &str, body: Value) -> Result<String> {
+    let resp: Value = request(cfg, "POST", &format!("auth/{mount}/login"))?
+        .send_json(body)
+        .map_err(|e| anyhow!("Vault login via auth/{mount}: {e}"))?
+        .into_json()?;
+    resp["auth"]["client_token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Vault login via auth/{mount} returned no token"))
+}
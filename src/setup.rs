@@ -1,15 +1,61 @@
 use anyhow::{anyhow, bail, Result};
+use clap::Subcommand;
 use colored::Colorize;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-pub fn run(sub: Option<&str>) -> Result<()> {
-    match sub {
-        Some("test") => test_notification(),
-        Some("show") => show_config(),
-        Some("pat") => wizard_pat_only(),
-        Some("telegram") => wizard_telegram_only(),
-        Some(unknown) => bail!("Unknown setup subcommand: {unknown}"),
+/// Individual setup steps; with none given the full wizard runs.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Step {
+    /// Show masked credentials from the OS keyring
+    Show,
+    /// Send a test Telegram message
+    Test,
+    /// Update the GitHub PAT only
+    Pat,
+    /// Store the PAT of an [accounts.<name>] entry
+    Account {
+        /// Account name, as in config.toml
+        name: String,
+    },
+    /// Sign in to GitHub in the browser (device flow, needs [oauth])
+    Login,
+    /// Store the GitHub App private key ([github_app] mode)
+    App,
+    /// Update the Telegram bot only
+    Telegram,
+    /// Store the remote audit sink secret
+    AuditSync,
+    /// Store the Twilio auth token ([sms] mode)
+    Sms,
+    /// Put a `gh` shim ahead of the real gh on PATH
+    Install {
+        /// Directory for the shim [default: ~/.local/share/gh-guard/bin]
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Remove the `gh` shim and its PATH entry
+    Uninstall {
+        /// Directory the shim was installed to
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
+pub fn run(step: Option<Step>) -> Result<()> {
+    match step {
+        Some(Step::Test) => test_notification(),
+        Some(Step::Show) => show_config(),
+        Some(Step::Pat) => wizard_pat_only(),
+        Some(Step::Account { name }) => wizard_account(&name),
+        Some(Step::App) => wizard_app(),
+        Some(Step::Login) => wizard_login(),
+        Some(Step::Telegram) => wizard_telegram_only(),
+        Some(Step::AuditSync) => wizard_audit_sync(),
+        Some(Step::Sms) => wizard_sms(),
+        Some(Step::Install { dir }) => crate::shim::install(dir),
+        Some(Step::Uninstall { dir }) => crate::shim::uninstall(dir),
         None => wizard_full(),
     }
 }
@@ -22,15 +68,29 @@ fn wizard_full() -> Result<()> {
     println!("{}", "╚══════════════════════════════════╝".cyan());
     println!();
 
-    wizard_pat_only()?;
+    let settings = crate::settings::load()?;
+    if settings.github_app.is_some() {
+        wizard_app()?;
+    } else if settings.oauth.is_some() {
+        wizard_login()?;
+    } else {
+        wizard_pat_only()?;
+    }
     println!();
-    wizard_telegram_only()?;
+    if settings.sms.is_some() {
+        wizard_sms()?;
+    } else {
+        wizard_telegram_only()?;
+    }
     println!();
 
     println!("{}", "Setup complete!".green().bold());
     println!();
-    println!("Add this alias to your shell config (~/.zshrc or ~/.bashrc):");
-    println!("  {}", "alias gh='gh-guard'".cyan().bold());
+    println!(
+        "Run {} to put a `gh` shim on your PATH",
+        "gh-guard setup install".cyan().bold()
+    );
+    println!("(or add {} to your shell config).", "alias gh='gh-guard'".cyan());
     println!();
     println!("Run {} to send a test message to your phone.", "gh-guard setup test".cyan());
     Ok(())
@@ -40,11 +100,49 @@ fn wizard_full() -> Result<()> {
 
 fn wizard_pat_only() -> Result<()> {
     println!("{}", "── GitHub Personal Access Token ──".bold());
-    println!("Create one at:  https://github.com/settings/tokens");
+    let (pat, info) = prompt_pat()?;
+    crate::config::set_pat(&pat)?;
+    crate::config::delete_oauth_refresh()?;
+    crate::expiry::record(&pat, info.expires_at);
+    stored("PAT");
+    Ok(())
+}
+
+/// The PAT of `[accounts.<name>]`, asked for like the profile's own.
+fn wizard_account(name: &str) -> Result<()> {
+    let settings = crate::settings::load()?;
+    let Some(account) = settings.accounts.get(name) else {
+        bail!(
+            "Add an [accounts.{name}] section with the repos it is for to {} first.",
+            crate::settings::path().display()
+        );
+    };
+    println!("{}", format!("── GitHub account {name} ──").bold());
+    if !account.repos.is_empty() {
+        println!("Used for: {}", account.repos.join(", ").cyan());
+    }
+    let (pat, _) = prompt_pat()?;
+    crate::config::set_account_pat(name, &pat)?;
+    stored(&format!("PAT of account {name}"));
+    Ok(())
+}
+
+/// Ask for a PAT, check it against GitHub and review its scopes and
+/// expiration.
+fn prompt_pat() -> Result<(String, TokenInfo)> {
+    println!(
+        "Create one at:  {}",
+        crate::host::web_url("/settings/tokens")
+    );
     println!("Required scopes: {}", "repo, read:org".yellow());
+    let copy = if cfg!(target_os = "macos") {
+        "gh auth token | pbcopy"
+    } else {
+        "gh auth token | wl-copy"
+    };
     println!(
         "(Tip: run {} to copy your current gh session token.)",
-        "gh auth token | pbcopy".cyan()
+        copy.cyan()
     );
     println!();
 
@@ -56,16 +154,125 @@ fn wizard_pat_only() -> Result<()> {
 
     print!("Validating… ");
     io::stdout().flush()?;
-    match validate_pat(&pat) {
-        Ok(login) => println!("{} (signed in as {})", "✓".green(), login.bold()),
+    let info = match validate_pat(&pat) {
+        Ok(info) => {
+            println!("{} (signed in as {})", "✓".green(), info.login.bold());
+            info
+        }
         Err(e) => {
             println!("{}", "✗".red());
             bail!("{e}\nCheck your token and try again.");
         }
+    };
+    review_scopes(info.scopes.as_deref())?;
+    match info.expires_at {
+        Some(t) => {
+            let days = (t - chrono::Utc::now().fixed_offset()).num_days();
+            let line = format!("Expires {} ({days} days).", t.format("%Y-%m-%d"));
+            if days < 7 {
+                println!("{}", line.yellow());
+            } else {
+                println!("{line}");
+            }
+        }
+        None => println!("{}", "No expiration date set.".dimmed()),
     }
+    Ok((pat, info))
+}
 
-    crate::config::set_pat(&pat)?;
-    println!("{}", "PAT stored in macOS Keychain.".green());
+// ── GitHub device-flow login ──────────────────────────────────────────────────
+
+fn wizard_login() -> Result<()> {
+    let Some(oauth) = crate::settings::load()?.oauth else {
+        bail!(
+            "Add an [oauth] section with your app's client_id to {} first,\n\
+             or run `gh-guard setup pat` to paste a token instead.",
+            crate::settings::path().display()
+        );
+    };
+    println!("{}", "── GitHub sign-in ──".bold());
+    let grant = crate::oauth::login(&oauth)?;
+
+    print!("Validating… ");
+    io::stdout().flush()?;
+    match validate_pat(&grant.access_token) {
+        Ok(info) => println!("{} (signed in as {})", "✓".green(), info.login.bold()),
+        Err(e) => {
+            println!("{}", "✗".red());
+            bail!("{e}");
+        }
+    }
+
+    crate::config::set_pat(&grant.access_token)?;
+    match &grant.refresh {
+        Some(r) => crate::config::set_oauth_refresh(&serde_json::to_string(r)?)?,
+        None => crate::config::delete_oauth_refresh()?,
+    }
+    stored("GitHub token");
+    if grant.refresh.is_some() {
+        println!("It expires after a few hours and is refreshed automatically.");
+    }
+    Ok(())
+}
+
+// ── GitHub App sub-wizard ─────────────────────────────────────────────────────
+
+fn wizard_app() -> Result<()> {
+    let Some(app) = crate::settings::load()?.github_app else {
+        bail!(
+            "Add a [github_app] section with your app_id to {} first.",
+            crate::settings::path().display()
+        );
+    };
+    println!("{}", "── GitHub App ──".bold());
+
+    let pem = match &app.key_file {
+        Some(f) => {
+            println!("Using the private key from {}.", f.display().to_string().cyan());
+            let path = crate::settings::expand_home(f);
+            std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Cannot read {}: {e}", path.display()))?
+        }
+        None => {
+            println!(
+                "Generate a private key on the app's settings page ({}).",
+                crate::host::web_url("/settings/apps").cyan()
+            );
+            print!("Path to the downloaded .pem file: ");
+            io::stdout().flush()?;
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+            let path = crate::settings::expand_home(std::path::Path::new(line.trim()));
+            std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Cannot read {}: {e}", path.display()))?
+        }
+    };
+
+    print!("Validating… ");
+    io::stdout().flush()?;
+    match crate::github_app::verify(&app, &pem) {
+        Ok(slug) => println!("{} (app is {})", "✓".green(), slug.bold()),
+        Err(e) => {
+            println!("{}", "✗".red());
+            bail!("{e:#}\nCheck app_id and the key file and try again.");
+        }
+    }
+
+    if app.key_file.is_none() {
+        crate::config::set_app_key(&pem)?;
+        stored("GitHub App key");
+        println!("You can delete the downloaded .pem file now.");
+    }
+
+    print!("Minting an installation token… ");
+    io::stdout().flush()?;
+    match crate::github_app::token(&app) {
+        Ok(_) => println!("{}", "✓".green()),
+        Err(e) => {
+            println!("{}", "✗".red());
+            println!("{}", format!("{e:#}").yellow());
+        }
+    }
     Ok(())
 }
 
@@ -98,7 +305,7 @@ fn wizard_telegram_only() -> Result<()> {
     };
 
     crate::config::set_telegram_token(&token)?;
-    println!("{}", "Bot token stored in macOS Keychain.".green());
+    stored("Bot token");
     println!();
 
     // Auto-detect chat ID by waiting for the user to send a message to the bot.
@@ -111,39 +318,225 @@ fn wizard_telegram_only() -> Result<()> {
     let chat_id = detect_chat_id(&token)?;
 
     crate::config::set_telegram_chat_id(&chat_id)?;
-    println!("{}", format!("Chat ID {chat_id} stored in macOS Keychain.").green());
+    stored(&format!("Chat ID {chat_id}"));
+    Ok(())
+}
+
+// ── Audit sync secret ─────────────────────────────────────────────────────────
+
+fn wizard_audit_sync() -> Result<()> {
+    println!("{}", "── Remote audit sync ──".bold());
+    println!(
+        "Configure the sink in {} under {}.",
+        crate::settings::path().display().to_string().cyan(),
+        "[audit_sync]".cyan()
+    );
+    println!("This stores the webhook HMAC key, or the S3 secret access key.");
+    println!("(The gist sink uses your PAT and needs no extra secret.)");
+    println!();
+
+    let secret = rpassword::prompt_password("Secret (input hidden): ")?;
+    let secret = secret.trim().to_string();
+    if secret.is_empty() {
+        bail!("Secret cannot be empty.");
+    }
+    crate::config::set_audit_sync_secret(&secret)?;
+    stored("Audit sync secret");
+    Ok(())
+}
+
+// ── Twilio auth token ─────────────────────────────────────────────────────────
+
+fn wizard_sms() -> Result<()> {
+    println!("{}", "── SMS via Twilio ──".bold());
+    println!(
+        "Configure the account and numbers in {} under {}.",
+        crate::settings::path().display().to_string().cyan(),
+        "[sms]".cyan()
+    );
+    println!("This stores the auth token shown beside the account SID in the Twilio console.");
+    println!();
+
+    let token = rpassword::prompt_password("Auth token (input hidden): ")?;
+    let token = token.trim().to_string();
+    if token.is_empty() {
+        bail!("Auth token cannot be empty.");
+    }
+    crate::config::set_twilio_token(&token)?;
+    stored("Twilio auth token");
     Ok(())
 }
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-fn make_agent() -> ureq::Agent {
-    ureq::AgentBuilder::new()
+/// Confirm where a secret went, warning when that store does not survive a
+/// reboot.
+fn stored(what: &str) {
+    println!(
+        "{}",
+        format!("{what} stored in {}.", crate::config::store_name()).green()
+    );
+    if crate::config::store_is_volatile() {
+        println!(
+            "{}",
+            "No Secret Service (GNOME Keyring / KWallet) found — the kernel keyring is \
+             cleared on reboot, so run `gh-guard setup` again after restarting."
+                .yellow()
+        );
+    }
+}
+
+/// `ghp_abc…wxyz`: enough of a token to tell which one it is.
+fn mask(pat: &str) -> String {
+    let start = pat.len().min(7);
+    let end = pat.len().saturating_sub(4);
+    if end > start {
+        format!("{}…{}", &pat[..start], &pat[end..])
+    } else {
+        format!("{}…", &pat[..start])
+    }
+}
+
+fn make_agent() -> Result<ureq::Agent> {
+    Ok(crate::http::builder(crate::http::telegram_api())?
         .timeout_connect(Duration::from_secs(10))
         .timeout(Duration::from_secs(40))
-        .build()
+        .build())
 }
 
 fn tg(token: &str, method: &str) -> String {
-    format!("https://api.telegram.org/bot{token}/{method}")
+    format!("{}/bot{token}/{method}", crate::http::telegram_api())
+}
+
+/// What `GET /user` reveals about a token.
+struct TokenInfo {
+    login: String,
+    /// Classic scopes from `X-OAuth-Scopes`. `None` for fine-grained
+    /// tokens, whose permissions GitHub does not expose to the token itself.
+    scopes: Option<Vec<String>>,
+    /// `None` for tokens created without an expiration.
+    expires_at: Option<chrono::DateTime<chrono::FixedOffset>>,
 }
 
-fn validate_pat(pat: &str) -> Result<String> {
-    let resp: serde_json::Value = ureq::AgentBuilder::new()
+fn validate_pat(pat: &str) -> Result<TokenInfo> {
+    let url = crate::host::api_url("/user");
+    let resp = crate::http::builder(&url)?
         .timeout(Duration::from_secs(15))
         .build()
-        .get("https://api.github.com/user")
+        .get(&url)
         .set("Authorization", &format!("Bearer {pat}"))
         .set("User-Agent", "gh-guard/0.1")
         .call()
-        .map_err(|e| anyhow!("GitHub API: {e}"))?
-        .into_json()?;
+        .map_err(|e| anyhow!("GitHub API: {e}"))?;
+    let scopes = resp.header("X-OAuth-Scopes").map(|h| {
+        h.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    });
+    let expires_at = resp
+        .header("github-authentication-token-expiration")
+        .and_then(crate::expiry::parse_header);
+    let body: serde_json::Value = resp.into_json()?;
+
+    Ok(TokenInfo {
+        login: body["login"].as_str().unwrap_or("unknown").to_string(),
+        scopes,
+        expires_at,
+    })
+}
 
-    Ok(resp["login"].as_str().unwrap_or("unknown").to_string())
+/// Classic scopes that reach well beyond opening PRs and calling the API on
+/// a repository. A leaked token with these can do far more damage.
+const EXCESS_SCOPES: &[&str] = &[
+    "delete_repo",
+    "admin:org",
+    "write:org",
+    "admin:enterprise",
+    "site_admin",
+    "admin:repo_hook",
+    "admin:org_hook",
+    "admin:public_key",
+    "admin:gpg_key",
+    "admin:ssh_signing_key",
+    "write:packages",
+    "delete:packages",
+    "codespace",
+    "user",
+    "project",
+    "audit_log",
+];
+
+/// Refuse a token without `repo` access; list missing and excess scopes and
+/// ask before storing an over-privileged one.
+fn review_scopes(scopes: Option<&[String]>) -> Result<()> {
+    let Some(scopes) = scopes else {
+        println!(
+            "{}",
+            "Fine-grained token: gh-guard cannot check its permissions. Grant only Contents, \
+             Pull requests and Issues (read & write) plus Metadata (read) on the repositories \
+             your agent works on."
+                .dimmed()
+        );
+        return Ok(());
+    };
+    let has = |s: &str| scopes.iter().any(|x| x == s);
+
+    if !has("repo") {
+        if has("public_repo") {
+            println!(
+                "{}",
+                "⚠  Token has public_repo only: private repositories will fail.".yellow()
+            );
+        } else {
+            bail!(
+                "Token is missing the repo scope (has: {}).\n\
+                 Create one with repo and read:org and try again.",
+                if scopes.is_empty() {
+                    "none".to_string()
+                } else {
+                    scopes.join(", ")
+                }
+            );
+        }
+    }
+    if !["read:org", "write:org", "admin:org"].iter().any(|s| has(s)) {
+        println!(
+            "{}",
+            "⚠  Missing read:org: gh cannot resolve team reviewers or org projects.".yellow()
+        );
+    }
+
+    let excess: Vec<&str> = scopes
+        .iter()
+        .map(String::as_str)
+        .filter(|s| EXCESS_SCOPES.contains(s))
+        .collect();
+    if excess.is_empty() {
+        return Ok(());
+    }
+    println!(
+        "{}",
+        format!(
+            "⚠  Token has more scopes than gh-guard needs: {}",
+            excess.join(", ")
+        )
+        .yellow()
+        .bold()
+    );
+    println!("   Only repo and read:org are required; anything else widens the damage a leak can do.");
+    print!("Store this token anyway? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        bail!("Not stored. Create a narrower token and run `gh-guard setup pat` again.");
+    }
+    Ok(())
 }
 
 fn get_bot_info(token: &str) -> Result<String> {
-    let resp: serde_json::Value = make_agent()
+    let resp: serde_json::Value = make_agent()?
         .get(&tg(token, "getMe"))
         .call()
         .map_err(|e| anyhow!("Telegram API: {e}"))?
@@ -166,7 +559,7 @@ fn get_bot_info(token: &str) -> Result<String> {
 /// Returns the chat ID as a string once a message arrives.
 fn detect_chat_id(token: &str) -> Result<String> {
     let deadline = Instant::now() + Duration::from_secs(120);
-    let a = make_agent();
+    let a = make_agent()?;
     let mut offset: Option<i64> = None;
 
     loop {
@@ -224,6 +617,13 @@ fn detect_chat_id(token: &str) -> Result<String> {
 }
 
 fn test_notification() -> Result<()> {
+    if let Some(sms) = crate::sms::configured() {
+        println!("Sending test text message via Twilio…");
+        let text = "👋 gh-guard · test notification\n\nSetup is working correctly!";
+        crate::sms::send_note(sms, text)?;
+        println!("{}", format!("Sent! Check {}.", sms.to).green());
+        return Ok(());
+    }
     let token = crate::config::get_telegram_token()?;
     let chat_id = crate::config::get_telegram_chat_id()?;
 
@@ -235,7 +635,7 @@ fn test_notification() -> Result<()> {
         "parse_mode": "HTML"
     });
 
-    let resp: serde_json::Value = make_agent()
+    let resp: serde_json::Value = make_agent()?
         .post(&tg(&token, "sendMessage"))
         .set("Content-Type", "application/json")
         .send_json(&payload)?
@@ -256,18 +656,31 @@ fn show_config() -> Result<()> {
     println!("{}", "gh-guard configuration".bold());
     println!("{}", "──────────────────────".dimmed());
 
-    match crate::config::get_pat() {
-        Ok(pat) => {
-            let start = pat.len().min(7);
-            let end = pat.len().saturating_sub(4);
-            let masked = if end > start {
-                format!("{}…{}", &pat[..start], &pat[end..])
-            } else {
-                format!("{}…", &pat[..start])
-            };
-            println!("  GitHub PAT      {}", masked.green());
+    println!(
+        "  Profile         {}",
+        crate::profile::active().unwrap_or("default")
+    );
+    println!("  GitHub host     {}", crate::host::name());
+    if let Some(app) = crate::settings::load()?.github_app {
+        let key = match &app.key_file {
+            Some(f) => format!("key {}", f.display()).green(),
+            None if crate::config::get_app_key().is_ok() => "key stored".green(),
+            None => "key not configured".red(),
+        };
+        println!("  GitHub App      id {}, {key}", app.app_id);
+    } else {
+        match crate::config::get_pat() {
+            Ok(pat) => println!("  GitHub PAT      {}", mask(&pat).green()),
+            Err(_) => println!("  GitHub PAT      {}", "not configured".red()),
         }
-        Err(_) => println!("  GitHub PAT      {}", "not configured".red()),
+    }
+
+    for name in crate::settings::load()?.accounts.keys() {
+        let state = match crate::config::get_account_pat(name) {
+            Ok(pat) => mask(&pat).green(),
+            Err(_) => "not configured".red(),
+        };
+        println!("  Account {name:<8} {state}");
     }
 
     match crate::config::get_telegram_token() {
@@ -283,5 +696,28 @@ fn show_config() -> Result<()> {
         Err(_) => println!("  Telegram chat   {}", "not configured".red()),
     }
 
+    if let Some(sms) = crate::settings::load()?.sms {
+        let state = match crate::config::get_twilio_token() {
+            Ok(_) => format!("{} → {}", sms.from, sms.to).green(),
+            Err(_) => "auth token not configured".red(),
+        };
+        println!("  SMS (Twilio)    {state}");
+    }
+
+    println!("  Secret store    {}", crate::config::store_name());
+
+    let overrides = crate::config::active_env_overrides();
+    if !overrides.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "⚠  Overridden by environment: {} (takes precedence over the secret store)",
+                overrides.join(", ")
+            )
+            .yellow()
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,152 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BEGIN: &str = "# >>> gh-guard shim >>>";
+const END: &str = "# <<< gh-guard shim <<<";
+
+/// Shell startup files that get the PATH block. `.zshenv` is read by every
+/// zsh, including the non-interactive ones agents spawn; `.bashrc` covers
+/// interactive bash and `bash -c` under sshd; `.profile` covers login shells.
+const RC_FILES: &[&str] = &[".zshenv", ".bashrc", ".profile"];
+
+/// Default shim directory: `~/.local/share/gh-guard/bin`.
+pub fn default_dir() -> PathBuf {
+    crate::settings::home_dir().join(".local/share/gh-guard/bin")
+}
+
+/// `gh-guard setup install` — symlink `<dir>/gh` to this binary and prepend
+/// `<dir>` to PATH in the shell startup files, so every shell (interactive
+/// or not) resolves `gh` to gh-guard.
+pub fn install(dir: Option<PathBuf>) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_dir);
+    let exe = std::env::current_exe()?
+        .canonicalize()
+        .context("Could not resolve the gh-guard executable")?;
+    let real_gh = crate::gh::find_real_gh()?;
+
+    fs::create_dir_all(&dir).with_context(|| format!("Could not create {}", dir.display()))?;
+    let shim = dir.join("gh");
+    match fs::symlink_metadata(&shim) {
+        Ok(_) if points_to(&shim, &exe) => {}
+        Ok(meta) if meta.file_type().is_symlink() => fs::remove_file(&shim)?,
+        Ok(_) => bail!(
+            "{} already exists and is not a gh-guard shim; pick another --dir",
+            shim.display()
+        ),
+        Err(_) => {}
+    }
+    if !shim.exists() {
+        std::os::unix::fs::symlink(&exe, &shim)
+            .with_context(|| format!("Could not create {}", shim.display()))?;
+    }
+    println!("{} {} → {}", "Shim:".bold(), shim.display(), exe.display());
+    println!("{} {}", "Real gh:".bold(), real_gh.display());
+
+    let block = format!("{BEGIN}\nexport PATH=\"{}:$PATH\"\n{END}\n", dir.display());
+    for rc in rc_files() {
+        let current = fs::read_to_string(&rc).unwrap_or_default();
+        let updated = with_block(&current, Some(&block));
+        if updated != current {
+            fs::write(&rc, updated)
+                .with_context(|| format!("Could not update {}", rc.display()))?;
+            println!("{} {}", "Updated".green(), rc.display());
+        }
+    }
+
+    println!();
+    println!(
+        "Open a new shell (or run {}) and check:",
+        format!("export PATH=\"{}:$PATH\"", dir.display()).cyan()
+    );
+    println!("  {}", "command -v gh".cyan());
+    println!(
+        "Agents started from launchd/systemd or IDEs may not read shell startup files; \
+         give them the same PATH."
+    );
+    Ok(())
+}
+
+/// `gh-guard setup uninstall` — remove the PATH block and the shim. Leaves a
+/// `gh` in `dir` alone unless it points at gh-guard.
+pub fn uninstall(dir: Option<PathBuf>) -> Result<()> {
+    let dir = dir.unwrap_or_else(default_dir);
+    for rc in rc_files() {
+        let Ok(current) = fs::read_to_string(&rc) else {
+            continue;
+        };
+        if current.contains(BEGIN) {
+            let updated = with_block(&current, None);
+            fs::write(&rc, updated)
+                .with_context(|| format!("Could not update {}", rc.display()))?;
+            println!("{} {}", "Updated".green(), rc.display());
+        }
+    }
+
+    let shim = dir.join("gh");
+    let exe = std::env::current_exe()?.canonicalize()?;
+    if fs::symlink_metadata(&shim).is_ok_and(|m| m.file_type().is_symlink())
+        && points_to(&shim, &exe)
+    {
+        fs::remove_file(&shim)?;
+        println!("{} {}", "Removed".green(), shim.display());
+        let _ = fs::remove_dir(&dir);
+    } else {
+        println!("No gh-guard shim at {}", shim.display());
+    }
+    println!("Open a new shell for the PATH change to take effect.");
+    Ok(())
+}
+
+/// Startup files to touch: those that exist, plus the one matching the
+/// login shell so a fresh account still gets the block.
+fn rc_files() -> Vec<PathBuf> {
+    let home = crate::settings::home_dir();
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    RC_FILES
+        .iter()
+        .filter(|name| {
+            home.join(name).exists()
+                || (shell.ends_with("/zsh") && **name == ".zshenv")
+                || (shell.ends_with("/bash") && **name == ".bashrc")
+        })
+        .map(|name| home.join(name))
+        .collect()
+}
+
+fn points_to(link: &Path, exe: &Path) -> bool {
+    link.canonicalize().is_ok_and(|p| p == exe)
+}
+
+/// `text` with any existing gh-guard block removed and, if given, `block`
+/// appended.
+fn with_block(text: &str, block: Option<&str>) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    for line in text.lines() {
+        match line.trim() {
+            BEGIN => inside = true,
+            END => inside = false,
+            _ if !inside => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+    match block {
+        Some(block) => {
+            if !out.is_empty() && !out.ends_with("\n\n") {
+                out.push('\n');
+            }
+            out.push_str(block);
+        }
+        None => {
+            while out.ends_with("\n\n") {
+                out.pop();
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,79 @@
+//! "Approve & allow similar": with `[allow_similar]` set, cards offer a
+//! button that approves and then lets the same class of request (see
+//! [`class`]) in the same repository run without asking for `minutes`.
+
+use crate::guard::{Kind, Request};
+use crate::settings::AllowSimilar;
+use crate::{api, state};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "similar.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Rules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Rule {
+    /// Lowercase `owner/name`.
+    repo: String,
+    class: String,
+    /// Unix time it ends.
+    until: i64,
+    /// Who tapped the button.
+    by: Option<String>,
+}
+
+/// What counts as a similar request: the gh command and, for `gh api`,
+/// the method (e.g. "api PATCH", "release upload").
+pub fn class(req: &Request) -> String {
+    match req.kind {
+        Kind::PrCreate => "pr create".to_string(),
+        Kind::Api => {
+            let method = api::parse_api_args(req.gh_args.get(1..).unwrap_or_default()).method;
+            format!("api {method}")
+        }
+        Kind::RepoFork => "repo fork".to_string(),
+        Kind::ReleaseUpload => "release upload".to_string(),
+        Kind::RepoDelete => "repo delete".to_string(),
+    }
+}
+
+/// Let requests like `req` run without asking for `minutes` from now, as
+/// approved by `by`. Returns when that ends.
+pub fn allow(req: &Request, minutes: u64, by: Option<&str>) -> Result<DateTime<Local>> {
+    let now = Local::now().timestamp();
+    let secs = i64::try_from(minutes.saturating_mul(60)).unwrap_or(i64::MAX);
+    let rule = Rule {
+        repo: req.repo.to_lowercase(),
+        class: class(req),
+        until: now.saturating_add(secs),
+        by: by.map(str::to_string),
+    };
+    let until = rule.until;
+    state::update(STATE_FILE, |r: &mut Rules| {
+        r.rules.retain(|old| old.until > now);
+        r.rules.push(rule);
+    })?;
+    Ok(DateTime::from_timestamp(until, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local))
+}
+
+/// Whether `req` may run without asking under `policy`: an approval still
+/// in effect allowed its class in its repository, it is not too severe,
+/// and nothing insists on asking.
+pub fn covers(policy: &AllowSimilar, req: &Request) -> bool {
+    if req.always_ask || req.severity > policy.max_severity {
+        return false;
+    }
+    let (repo, class) = (req.repo.to_lowercase(), class(req));
+    let now = Local::now().timestamp();
+    state::load::<Rules>(STATE_FILE)
+        .rules
+        .iter()
+        .any(|r| r.repo == repo && r.class == class && r.until > now)
+}
@@ -0,0 +1,279 @@
+//! Approval cards as text messages through Twilio, for approvers without a
+//! smartphone or where chat apps are blocked. A card becomes a short
+//! plain-text summary ending in its request ID; the approver replies
+//! "YES <id>" or "NO <id>". Replies are polled from the account's inbound
+//! messages, so no public webhook is needed.
+//!
+//! Text messages are a weaker channel than Telegram: only the sender's
+//! number vouches for a reply, and sender numbers can be spoofed. What
+//! keeps a forged reply out is the request ID, which only the approver's
+//! phone has seen, and that replies older than the card are ignored.
+
+use crate::i18n;
+use crate::notify::{self, Approval, ApprovalResult};
+use crate::settings::Sms;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Longest summary sent, in characters; with the reply line it fits two
+/// SMS segments.
+const SUMMARY_CHARS: usize = 200;
+/// Pause between looks at the inbox.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// `[sms]` settings, read once per process.
+static SMS: OnceLock<Option<Sms>> = OnceLock::new();
+/// Cards sent from this process and not settled yet, by request ID.
+static SENT: Mutex<BTreeMap<String, Sent>> = Mutex::new(BTreeMap::new());
+
+struct Sent {
+    /// When Twilio took the card, by its clock; earlier replies are stale.
+    at: DateTime<FixedOffset>,
+    /// What the approval must carry after the ID, for requests that ask
+    /// for a typed confirmation.
+    confirm: Option<String>,
+}
+
+/// `[sms]`, when cards go out as text messages instead of to Telegram.
+/// From the file alone, like `[network]`: profiles do not override it.
+pub fn configured() -> Option<&'static Sms> {
+    SMS.get_or_init(|| crate::settings::load_file().ok().and_then(|s| s.sms))
+        .as_ref()
+}
+
+/// Text the card `html` to the approver, with the replies that decide it;
+/// returns the request ID. With `confirm`, only a "YES" followed by that
+/// text approves.
+pub fn send_with_approval(sms: &Sms, html: &str, confirm: Option<&str>) -> Result<String> {
+    // As long as a Telegram request ID: it is all a forged reply lacks.
+    let request_id = Uuid::new_v4().simple().to_string()[..8].to_string();
+    let reply = (i18n::text().sms_reply)(&request_id, confirm);
+    let sent = send(sms, &format!("{}\n\n{reply}", summary(html)))?;
+    let at = sent
+        .date_created
+        .as_deref()
+        .and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+        .unwrap_or_else(|| Utc::now().fixed_offset());
+    let sent = Sent {
+        at,
+        confirm: confirm.map(str::to_string),
+    };
+    let mut pending = SENT.lock().unwrap_or_else(|e| e.into_inner());
+    pending.insert(request_id.clone(), sent);
+    Ok(request_id)
+}
+
+/// Text `html` to the approver, as a summary like a card's.
+pub fn send_note(sms: &Sms, html: &str) -> Result<()> {
+    send(sms, &summary(html)).map(|_| ())
+}
+
+#[derive(Deserialize)]
+struct Page {
+    messages: Vec<Message>,
+}
+
+/// A message as Twilio lists it; dates are RFC 2822.
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    body: String,
+    date_created: Option<String>,
+    date_sent: Option<String>,
+}
+
+impl Message {
+    /// Whether it was sent at or after `at`; undated messages are not.
+    fn sent_since(&self, at: DateTime<FixedOffset>) -> bool {
+        self.date_sent
+            .as_deref()
+            .and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+            .is_some_and(|sent| sent >= at)
+    }
+}
+
+/// Look through the approver's texts every few seconds until one decides
+/// `request_id` or `timeout_secs` pass.
+pub fn poll_for_approval(sms: &Sms, request_id: &str, timeout_secs: u64) -> Result<Approval> {
+    let Some(sent) = SENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(request_id)
+    else {
+        return Err(anyhow!("No text was sent for request {request_id}"));
+    };
+    // Twilio filters by day only; `sent_since` does the rest.
+    let since = sent.at.with_timezone(&Utc).format("%Y-%m-%d").to_string();
+    let url = messages_url(sms);
+    let agent = agent(&url)?;
+    let auth = authorization(sms)?;
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        let page = agent
+            .get(&url)
+            .set("Authorization", &auth)
+            .query("From", &sms.to)
+            .query("To", &sms.from)
+            .query("DateSent>", &since)
+            .call()
+            .map_err(|e| anyhow!("Twilio: {e}"))
+            .and_then(|resp| Ok(resp.into_json::<Page>()?));
+        match page {
+            Ok(page) => {
+                let decided = page
+                    .messages
+                    .iter()
+                    .filter(|m| m.sent_since(sent.at))
+                    .find_map(|m| decision(&m.body, request_id, sent.confirm.as_deref()));
+                if let Some(result) = decided {
+                    return Ok(Approval {
+                        result,
+                        approver: Some(sms.to.clone()),
+                        choice: None,
+                        delegation: Vec::new(),
+                    });
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "could not read SMS replies"),
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(Approval {
+                result: ApprovalResult::Timeout,
+                approver: None,
+                choice: None,
+                delegation: Vec::new(),
+            });
+        }
+        std::thread::sleep(POLL_INTERVAL.min(left));
+    }
+}
+
+/// The decision `body` makes on `request_id`: "YES <id>" (followed by
+/// `confirm`, when set) or "NO <id>", in any case. Anything else, or a
+/// reply about another request, decides nothing.
+fn decision(body: &str, request_id: &str, confirm: Option<&str>) -> Option<ApprovalResult> {
+    let body = body.trim();
+    let (verdict, rest) = body.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (id, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if !id.eq_ignore_ascii_case(request_id) {
+        return None;
+    }
+    match verdict.to_ascii_uppercase().as_str() {
+        "YES" if rest.trim() == confirm.unwrap_or("") => Some(ApprovalResult::Approved),
+        "NO" => Some(ApprovalResult::Rejected),
+        _ => None,
+    }
+}
+
+/// `html` as plain text for a text message: tags dropped, entities
+/// decoded, blank lines squeezed out, cut to [`SUMMARY_CHARS`].
+fn summary(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    notify::truncate(&lines.join("\n"), SUMMARY_CHARS).into_owned()
+}
+
+fn send(sms: &Sms, body: &str) -> Result<Message> {
+    let url = messages_url(sms);
+    let sent = agent(&url)?
+        .post(&url)
+        .set("Authorization", &authorization(sms)?)
+        .send_form(&[("To", &sms.to), ("From", &sms.from), ("Body", body)])
+        .map_err(|e| anyhow!("Twilio: {e}"))?
+        .into_json()?;
+    Ok(sent)
+}
+
+fn messages_url(sms: &Sms) -> String {
+    let api = sms
+        .api
+        .as_deref()
+        .map_or("https://api.twilio.com", |u| u.trim_end_matches('/'));
+    format!(
+        "{api}/2010-04-01/Accounts/{}/Messages.json",
+        sms.account_sid
+    )
+}
+
+fn agent(url: &str) -> Result<ureq::Agent> {
+    Ok(crate::http::builder(url)?
+        .timeout_connect(Duration::from_secs(5))
+        .timeout(Duration::from_secs(15))
+        .build())
+}
+
+/// HTTP basic auth with the account SID and the stored auth token.
+fn authorization(sms: &Sms) -> Result<String> {
+    let token = crate::config::get_twilio_token()?;
+    let pair = format!("{}:{token}", sms.account_sid);
+    let encoded = base64::engine::general_purpose::STANDARD.encode(pair);
+    Ok(format!("Basic {encoded}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_decide_their_own_request_only() {
+        let yes = decision("yes a1b2c3d4", "a1b2c3d4", None);
+        assert!(matches!(yes, Some(ApprovalResult::Approved)));
+        let no = decision("  NO   A1B2C3D4 \n", "a1b2c3d4", None);
+        assert!(matches!(no, Some(ApprovalResult::Rejected)));
+        assert!(decision("YES ffffffff", "a1b2c3d4", None).is_none());
+        assert!(decision("YES", "a1b2c3d4", None).is_none());
+        assert!(decision("maybe a1b2c3d4", "a1b2c3d4", None).is_none());
+
+        let confirmed = decision("YES a1b2c3d4 acme/app", "a1b2c3d4", Some("acme/app"));
+        assert!(matches!(confirmed, Some(ApprovalResult::Approved)));
+        assert!(decision("YES a1b2c3d4", "a1b2c3d4", Some("acme/app")).is_none());
+        let no = decision("no a1b2c3d4", "a1b2c3d4", Some("acme/app"));
+        assert!(matches!(no, Some(ApprovalResult::Rejected)));
+    }
+
+    #[test]
+    fn replies_count_from_when_the_card_was_sent() {
+        let card = DateTime::parse_from_rfc2822("Thu, 15 Oct 2026 09:30:00 +0000").unwrap();
+        let reply = |date_sent: Option<&str>| Message {
+            body: String::new(),
+            date_created: None,
+            date_sent: date_sent.map(str::to_string),
+        };
+        assert!(reply(Some("Thu, 15 Oct 2026 09:31:10 +0000")).sent_since(card));
+        assert!(!reply(Some("Thu, 15 Oct 2026 09:29:59 +0000")).sent_since(card));
+        assert!(!reply(None).sent_since(card));
+    }
+
+    #[test]
+    fn summary_is_plain_text() {
+        let html = "🔀 <b>PR</b> in <code>acme/app</code>\n\n<i>a &lt;b&gt; &amp; c</i>";
+        assert_eq!(summary(html), "🔀 PR in acme/app\na <b> & c");
+        assert!(summary(&"x".repeat(500)).chars().count() <= SUMMARY_CHARS + 1);
+    }
+}
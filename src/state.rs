@@ -0,0 +1,58 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Directory for gh-guard's runtime state (`~/.local/state/gh-guard`).
+/// `XDG_STATE_HOME` is honoured when set.
+pub fn dir() -> PathBuf {
+    #[cfg(test)]
+    let base = crate::settings::test_home().join("state");
+    #[cfg(not(test))]
+    let base = std::env::var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| crate::settings::home_dir().join(".local").join("state"));
+    base.join("gh-guard")
+}
+
+/// Read a JSON state file, returning the default value when it is missing
+/// or unreadable — state is advisory and must never block a command.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    std::fs::read_to_string(dir().join(name))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Load, change and save a JSON state file under an exclusive lock on
+/// `<name>.lock`, so gh-guard processes running side by side do not lose
+/// each other's changes. Returns what `change` returns.
+pub fn update<T, R>(name: &str, change: impl FnOnce(&mut T) -> R) -> Result<R>
+where
+    T: DeserializeOwned + Serialize + Default,
+{
+    let dir = dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Cannot create state directory {}", dir.display()))?;
+    let lock_path = dir.join(format!("{name}.lock"));
+    let lock = std::fs::File::create(&lock_path)
+        .with_context(|| format!("Cannot open {}", lock_path.display()))?;
+    lock.lock()
+        .with_context(|| format!("Cannot lock {}", lock_path.display()))?;
+    let mut value = load(name);
+    let out = change(&mut value);
+    save(name, &value)?;
+    Ok(out)
+}
+
+/// Write a JSON state file atomically (temp file + rename).
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let dir = dir();
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Cannot create state directory {}", dir.display()))?;
+    let path = dir.join(name);
+    let tmp = dir.join(format!(".{name}.{}", std::process::id()));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(value)?)
+        .with_context(|| format!("Cannot write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Cannot write {}", path.display()))
+}
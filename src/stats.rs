@@ -0,0 +1,288 @@
+use crate::audit::{self, Decision, Entry};
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Aggregate view over guarded (non-passthrough) audit entries.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub total: usize,
+    pub approved: usize,
+    pub rejected: usize,
+    pub timeouts: usize,
+    pub auto_rejected: usize,
+    pub bypassed: usize,
+    /// Run without asking because a policy allows it.
+    pub auto_approved: usize,
+    /// Repositories by number of requests, busiest first.
+    pub top_repos: Vec<(String, usize)>,
+    /// Intercepted commands/endpoints by frequency, numeric IDs collapsed.
+    pub top_endpoints: Vec<(String, usize)>,
+    /// Command lines of rejected requests, oldest first.
+    pub rejected_actions: Vec<String>,
+    /// Mean time from notification to decision, over decided requests.
+    pub avg_latency_ms: Option<u64>,
+    /// The same at the 50th, 90th and 99th percentile.
+    pub latency_percentiles_ms: Option<Percentiles>,
+}
+
+/// Nearest-rank percentiles of a set of durations, in ms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+}
+
+impl Percentiles {
+    /// `None` for no durations at all.
+    pub fn of(mut ms: Vec<u64>) -> Option<Self> {
+        if ms.is_empty() {
+            return None;
+        }
+        ms.sort_unstable();
+        let at = |p: usize| ms[(ms.len() * p).div_ceil(100) - 1];
+        Some(Percentiles {
+            p50: at(50),
+            p90: at(90),
+            p99: at(99),
+        })
+    }
+}
+
+impl Summary {
+    pub fn compute<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Self {
+        let mut s = Summary::default();
+        let mut repos: HashMap<&str, usize> = HashMap::new();
+        let mut endpoints: HashMap<String, usize> = HashMap::new();
+        let mut latencies: Vec<u64> = Vec::new();
+
+        for e in entries {
+            if matches!(e.decision, Decision::Passthrough | Decision::Queued) {
+                continue;
+            }
+            s.total += 1;
+            match e.decision {
+                Decision::Approved => s.approved += 1,
+                Decision::Rejected => {
+                    s.rejected += 1;
+                    s.rejected_actions.push(e.args.join(" "));
+                }
+                Decision::Timeout => s.timeouts += 1,
+                Decision::AutoRejected => s.auto_rejected += 1,
+                Decision::Bypassed => s.bypassed += 1,
+                Decision::AutoApproved => s.auto_approved += 1,
+                Decision::Panic
+                | Decision::Cancelled
+                | Decision::Passthrough
+                | Decision::Queued
+                | Decision::Error => {}
+            }
+            if let Some(repo) = e.repo.as_deref() {
+                *repos.entry(repo).or_default() += 1;
+            }
+            *endpoints.entry(endpoint_key(&e.args)).or_default() += 1;
+            if matches!(e.decision, Decision::Approved | Decision::Rejected) {
+                latencies.extend(e.latency_ms);
+            }
+        }
+
+        s.top_repos = top_n(repos, 5);
+        s.top_endpoints = top_n(endpoints.iter().map(|(k, &c)| (k.as_str(), c)).collect(), 5);
+        if !latencies.is_empty() {
+            s.avg_latency_ms = Some(latencies.iter().sum::<u64>() / latencies.len() as u64);
+        }
+        s.latency_percentiles_ms = Percentiles::of(latencies);
+        s
+    }
+
+    /// Approved share of requests that reached a human decision.
+    pub fn approval_rate(&self) -> Option<f64> {
+        let decided = self.approved + self.rejected;
+        (decided > 0).then(|| self.approved as f64 / decided as f64)
+    }
+}
+
+/// Group key for an intercepted command: "PATCH /repos/o/r/pulls/:n" for API
+/// calls, "pr create" for everything else.
+fn endpoint_key(args: &[String]) -> String {
+    if args.first().map(String::as_str) == Some("api") {
+        let api = crate::api::parse_api_args(&args[1..]);
+        let path = crate::api::endpoint_segments(api.endpoint.as_deref().unwrap_or(""))
+            .iter()
+            .map(|seg| {
+                if seg.chars().all(|c| c.is_ascii_digit()) {
+                    ":n"
+                } else {
+                    seg
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{} /{path}", api.method)
+    } else {
+        args.iter().take(2).cloned().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// The `n` most frequent keys, ties broken alphabetically.
+pub fn top_n(counts: HashMap<&str, usize>, n: usize) -> Vec<(String, usize)> {
+    let mut v: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(k, c)| (k.to_string(), c))
+        .collect();
+    v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    v.truncate(n);
+    v
+}
+
+/// "850 ms", "12.4 s", "3.1 min".
+pub fn fmt_duration_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms} ms")
+    } else if ms < 60_000 {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    } else {
+        format!("{:.1} min", ms as f64 / 60_000.0)
+    }
+}
+
+/// Options accepted by `gh-guard stats`.
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// Only count requests after this point (YYYY-MM-DD, RFC 3339, or e.g. 30d)
+    #[arg(long, value_parser = crate::history::parse_when)]
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Print the summary as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// `gh-guard stats` entry point.
+pub fn run(args: &StatsArgs) -> Result<()> {
+    let StatsArgs { since, json } = *args;
+    let entries = audit::read_all()?;
+    let summary = Summary::compute(entries.iter().filter(|e| {
+        since.is_none_or(|since| DateTime::parse_from_rfc3339(&e.ts).is_ok_and(|t| t >= since))
+    }));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    let period = match since {
+        Some(t) => format!("since {}", t.format("%Y-%m-%d %H:%M")),
+        None => "all time".to_string(),
+    };
+    println!("{}", format!("gh-guard stats · {period}").bold());
+    println!("{}", "──────────────────────────────".dimmed());
+    if summary.total == 0 {
+        println!("  {}", "No guarded requests recorded.".dimmed());
+        return Ok(());
+    }
+
+    let pct = |n: usize| format!("({:.0}%)", n as f64 * 100.0 / summary.total as f64);
+    println!("  Requests       {:>5}", summary.total);
+    println!(
+        "  Approved       {:>5}  {}",
+        summary.approved.to_string().green(),
+        pct(summary.approved).dimmed()
+    );
+    println!(
+        "  Rejected       {:>5}  {}",
+        summary.rejected.to_string().red(),
+        pct(summary.rejected).dimmed()
+    );
+    println!(
+        "  Timed out      {:>5}  {}",
+        summary.timeouts.to_string().yellow(),
+        pct(summary.timeouts).dimmed()
+    );
+    println!(
+        "  Auto-rejected  {:>5}  {}",
+        summary.auto_rejected.to_string().yellow(),
+        pct(summary.auto_rejected).dimmed()
+    );
+    if summary.bypassed > 0 {
+        println!(
+            "  Bypassed       {:>5}  {}",
+            summary.bypassed.to_string().red(),
+            pct(summary.bypassed).dimmed()
+        );
+    }
+    if summary.auto_approved > 0 {
+        println!(
+            "  Auto-approved  {:>5}  {}",
+            summary.auto_approved.to_string().green(),
+            pct(summary.auto_approved).dimmed()
+        );
+    }
+    if let Some(ms) = summary.avg_latency_ms {
+        println!("  Avg latency    {:>5}", fmt_duration_ms(ms));
+    }
+    if let Some(p) = summary.latency_percentiles_ms {
+        let timeout = crate::settings::load()?.approval_timeout();
+        println!(
+            "  Latency p50    {:>5}  {}",
+            fmt_duration_ms(p.p50),
+            format!(
+                "p90 {} · p99 {}",
+                fmt_duration_ms(p.p90),
+                fmt_duration_ms(p.p99)
+            )
+            .dimmed()
+        );
+        println!("  Timeout        {:>5}", fmt_duration_ms(timeout * 1000));
+    }
+
+    print_ranking("Busiest repos", &summary.top_repos);
+    print_ranking("Top endpoints", &summary.top_endpoints);
+    Ok(())
+}
+
+fn print_ranking(title: &str, rows: &[(String, usize)]) {
+    if rows.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}", title.bold());
+    let width = rows
+        .iter()
+        .map(|(k, _)| k.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, n) in rows {
+        println!("  {key:<width$}  {n:>5}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        assert_eq!(Percentiles::of(Vec::new()), None);
+        let ms = (1..=100).rev().map(|n| n * 1000).collect();
+        assert_eq!(
+            Percentiles::of(ms),
+            Some(Percentiles {
+                p50: 50_000,
+                p90: 90_000,
+                p99: 99_000,
+            })
+        );
+        assert_eq!(
+            Percentiles::of(vec![7, 300_000]),
+            Some(Percentiles {
+                p50: 7,
+                p90: 300_000,
+                p99: 300_000,
+            })
+        );
+    }
+}
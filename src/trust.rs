@@ -0,0 +1,66 @@
+//! Trust on first use: with `[trust]` set, the first change to a
+//! repository is always asked about, and its card offers "Approve & trust
+//! repo". Requests in a trusted repository up to `[trust] auto_approve`
+//! severity then run without asking.
+
+use crate::settings::Trust;
+use crate::{guard::Request, state};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STATE_FILE: &str = "trusted_repos.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Trusted {
+    /// By lowercase `owner/name`.
+    repos: BTreeMap<String, Grant>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Grant {
+    /// Local time the repository was trusted, "YYYY-MM-DD HH:MM".
+    since: String,
+    /// Who tapped "Approve & trust repo".
+    by: Option<String>,
+}
+
+/// Whether `repo` names a repository that can be trusted, rather than
+/// standing in for one gh-guard could not tell.
+pub fn applies(repo: &str) -> bool {
+    repo.split_once('/')
+        .is_some_and(|(owner, name)| !owner.is_empty() && !name.is_empty())
+}
+
+/// Whether "Approve & trust repo" was tapped for `repo` before.
+pub fn is_trusted(repo: &str) -> bool {
+    state::load::<Trusted>(STATE_FILE)
+        .repos
+        .contains_key(&repo.to_lowercase())
+}
+
+/// Trust `repo` from now on, as approved by `by`.
+pub fn trust(repo: &str, by: Option<&str>) -> Result<()> {
+    let grant = Grant {
+        since: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        by: by.map(str::to_string),
+    };
+    state::update(STATE_FILE, |t: &mut Trusted| {
+        t.repos.insert(repo.to_lowercase(), grant);
+    })
+}
+
+/// Why `req` may run without asking under `policy`, if it may: its own
+/// reason, or being low enough in a trusted repository. A repository not
+/// trusted yet is always asked about.
+pub fn auto_approval(policy: &Trust, req: &Request) -> Option<String> {
+    if !applies(&req.repo) || !is_trusted(&req.repo) {
+        return None;
+    }
+    let low_enough = policy.auto_approve.is_some_and(|max| req.severity <= max);
+    match req.auto_approved() {
+        Some(reason) => Some(reason.to_string()),
+        None if low_enough && !req.always_ask => Some("trusted repo".to_string()),
+        None => None,
+    }
+}
@@ -0,0 +1,731 @@
+//! Shared fixtures for the end-to-end tests: a throwaway home for gh-guard,
+//! a mock Telegram Bot API and a mock Twilio.
+
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+// ── gh-guard environment ─────────────────────────────────────────────────────
+
+/// A home, config and state directory of its own, with secrets from the
+/// environment and a recording stand-in `gh` first on PATH.
+pub struct Env {
+    dir: TempDir,
+}
+
+impl Env {
+    /// `config` is written to `config.toml` as is.
+    pub fn new(config: &str) -> Env {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let env = Env { dir };
+        fs::create_dir_all(env.bin()).unwrap();
+        fs::create_dir_all(env.home()).unwrap();
+        fs::write(env.config_path(), config).unwrap();
+        env.install_gh();
+        // `ghp_test` is known to be test-bot's, so GitHub is not asked.
+        fs::create_dir_all(env.state_dir()).unwrap();
+        fs::write(
+            env.state_dir().join("identities.json"),
+            r#"{"8f23f423a895e689": "test-bot"}"#,
+        )
+        .unwrap();
+        env
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    fn bin(&self) -> PathBuf {
+        self.path().join("bin")
+    }
+
+    fn home(&self) -> PathBuf {
+        self.path().join("home")
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.path().join("config.toml")
+    }
+
+    pub fn state_dir(&self) -> PathBuf {
+        self.path().join("state").join("gh-guard")
+    }
+
+    fn calls_dir(&self) -> PathBuf {
+        self.path().join("gh-calls")
+    }
+
+    /// A `gh` that saves its arguments and environment, NUL-separated, to
+    /// `gh-calls/<n>/` and exits with the code in `gh-exit` (default 0).
+    fn install_gh(&self) {
+        let script = format!(
+            "#!/bin/sh\n\
+             calls='{calls}'\n\
+             mkdir -p \"$calls\"\n\
+             n=$(ls \"$calls\" | wc -l | tr -d ' ')\n\
+             until mkdir \"$calls/$n\" 2>/dev/null; do n=$((n + 1)); done\n\
+             call=\"$calls/$n\"\n\
+             printf '%s\\0' \"$@\" > \"$call/argv\"\n\
+             env -0 > \"$call/env\"\n\
+             exit \"$(cat '{exit}' 2>/dev/null || echo 0)\"\n",
+            calls = self.calls_dir().display(),
+            exit = self.path().join("gh-exit").display(),
+        );
+        let gh = self.bin().join("gh");
+        fs::write(&gh, script).unwrap();
+        fs::set_permissions(&gh, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Make the stand-in `gh` exit with `code` from now on.
+    pub fn set_gh_exit(&self, code: i32) {
+        fs::write(self.path().join("gh-exit"), code.to_string()).unwrap();
+    }
+
+    /// A `pass` first on PATH that knows `items` (path, first line) and
+    /// nothing else, for secrets the environment cannot hold.
+    pub fn install_pass(&self, items: &[(&str, &str)]) {
+        let mut script = "#!/bin/sh\ncase \"$2\" in\n".to_string();
+        for (path, value) in items {
+            script.push_str(&format!("  '{path}') echo '{value}' ;;\n"));
+        }
+        script.push_str(
+            "  *) echo \"Error: $2 is not in the password store.\" >&2; exit 1 ;;\nesac\n",
+        );
+        let pass = self.bin().join("pass");
+        fs::write(&pass, script).unwrap();
+        fs::set_permissions(&pass, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Every run of the stand-in `gh`, in order.
+    pub fn gh_calls(&self) -> Vec<GhCall> {
+        let mut calls = Vec::new();
+        for n in 0.. {
+            let dir = self.calls_dir().join(n.to_string());
+            let Ok(argv) = fs::read(dir.join("argv")) else {
+                break;
+            };
+            let env = fs::read(dir.join("env")).unwrap();
+            calls.push(GhCall {
+                args: split_nul(&argv),
+                env: split_nul(&env)
+                    .into_iter()
+                    .filter_map(|kv| {
+                        let (k, v) = kv.split_once('=')?;
+                        Some((k.to_string(), v.to_string()))
+                    })
+                    .collect(),
+            });
+        }
+        calls
+    }
+
+    /// `gh-guard <args>`, isolated from the caller's config, secrets, proxy
+    /// and gh credentials.
+    pub fn gh_guard(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_gh-guard"));
+        let path = format!(
+            "{}:{}",
+            self.bin().display(),
+            std::env::var("PATH").unwrap_or_default()
+        );
+        for var in [
+            "GH_TOKEN",
+            "GH_ENTERPRISE_TOKEN",
+            "GH_HOST",
+            "GH_REPO",
+            "GH_GUARD_ACTIVE",
+            "GH_GUARD_PROFILE",
+            "GH_GUARD_LOG",
+            "GH_GUARD_CI_POLICY",
+            "GH_GUARD_CI_PUBLIC_KEY",
+            "GH_GUARD_APPROVAL_TOKEN",
+            "CI",
+            "HTTPS_PROXY",
+            "https_proxy",
+            "ALL_PROXY",
+            "all_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+            "LC_ALL",
+            "LC_MESSAGES",
+            "LANG",
+        ] {
+            cmd.env_remove(var);
+        }
+        cmd.args(args)
+            .current_dir(self.path())
+            .env("PATH", path)
+            .env("HOME", self.home())
+            .env("XDG_STATE_HOME", self.path().join("state"))
+            .env("XDG_CONFIG_HOME", self.path().join("xdg-config"))
+            .env("GH_GUARD_CONFIG", self.config_path())
+            .env("GH_GUARD_PAT", "ghp_test")
+            .env("GH_GUARD_TG_TOKEN", "123:test")
+            .env("GH_GUARD_TG_CHAT", "42")
+            .env("GH_GUARD_TWILIO_TOKEN", "twilio-test")
+            .env("GH_GUARD_AGENT", "test");
+        cmd
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.gh_guard(args).output().expect("gh-guard runs")
+    }
+
+    /// `gh-guard <args>` with `input` on stdin.
+    pub fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+        let mut child = self
+            .gh_guard(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("gh-guard runs");
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(input.as_bytes()).unwrap();
+        drop(stdin);
+        child.wait_with_output().expect("gh-guard runs")
+    }
+
+    /// Start gh-guard without waiting for it, its output captured.
+    pub fn spawn(&self, args: &[&str]) -> Child {
+        self.gh_guard(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("gh-guard runs")
+    }
+
+    /// `git <args>` in the directory gh-guard runs in, which must succeed.
+    /// A repository made with `init` ignores the test's own files.
+    pub fn git(&self, args: &[&str]) {
+        let out = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(args)
+            .current_dir(self.path())
+            .output()
+            .expect("git runs");
+        assert!(out.status.success(), "git {args:?}: {out:?}");
+        if args.first() == Some(&"init") {
+            let exclude = self.path().join(".git/info/exclude");
+            fs::write(exclude, "/bin/\n/home/\n/state/\n/config.toml\n").unwrap();
+        }
+    }
+
+    /// Entries of the audit log, oldest first.
+    pub fn audit(&self) -> Vec<Value> {
+        fs::read_to_string(self.state_dir().join("audit.jsonl"))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("audit line is JSON"))
+            .collect()
+    }
+}
+
+/// One run of the stand-in `gh`.
+#[derive(Debug)]
+pub struct GhCall {
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+}
+
+impl GhCall {
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.env.get(name).map(String::as_str)
+    }
+}
+
+fn split_nul(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|b| *b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect()
+}
+
+// ── Mock Telegram ────────────────────────────────────────────────────────────
+
+/// What the approver does with the card.
+#[derive(Clone, Copy)]
+pub enum Answer {
+    Approve,
+    Reject,
+    /// The approver taps "Approve & trust repo".
+    Trust,
+    /// The approver taps "Allow all for 30 min".
+    AllowAll,
+    /// The approver taps "Approve & allow similar".
+    AllowSimilar,
+    /// The approver replies to the card's prompt with something else, taps
+    /// Approve, replies with this, and taps Approve again.
+    Type(&'static str),
+    /// The approver taps "Delegate", then Approve on the old card, and the
+    /// backup (user 3 in chat 43) taps Approve on their copy.
+    Delegate,
+    /// Someone outside `[group] members` (user 2) sends `/approve_<id>`,
+    /// then the approver sends it too.
+    Command,
+    /// The approver taps Approve twice, then a second person does.
+    TwoApprovers,
+    /// Nothing, ever.
+    Ignore,
+    /// Approve once this many cards are waiting, all in one batch of
+    /// updates, as if several gh-guard processes were asking at once.
+    ApproveTogether(usize),
+    /// This many people vote Approve in the card's poll, the first after
+    /// taking back a vote to Reject.
+    Vote(usize),
+}
+
+/// The parts of the Bot API gh-guard uses, answering every card with
+/// `answer`.
+pub struct MockTelegram {
+    server: MockServer,
+}
+
+/// Request IDs of the cards sent and not answered yet, taken from their
+/// Approve buttons.
+type Pending = Arc<Mutex<Vec<String>>>;
+
+impl MockTelegram {
+    pub async fn start(answer: Answer) -> MockTelegram {
+        let server = MockServer::start().await;
+        let pending = Pending::default();
+
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/bot[^/]+/sendMessage$"))
+            .respond_with(SendMessage(pending.clone()))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/bot[^/]+/sendPoll$"))
+            .respond_with(SendPoll(pending.clone()))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/bot[^/]+/getUpdates$"))
+            .respond_with(GetUpdates { pending, answer })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/bot[^/]+/copyMessage$"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"ok": true, "result": {"message_id": 11}})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(r"^/bot[^/]+/sendDocument$"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"ok": true, "result": {"message_id": 8}})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path_regex(
+                r"^/bot[^/]+/(answerCallbackQuery|editMessageReplyMarkup|editMessageText|deleteMessage|stopPoll)$",
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"ok": true, "result": true})),
+            )
+            .mount(&server)
+            .await;
+
+        MockTelegram { server }
+    }
+
+    /// `[network] telegram_api` pointing here.
+    pub fn config(&self) -> String {
+        format!("[network]\ntelegram_api = \"{}\"\n", self.server.uri())
+    }
+
+    /// Bodies of the calls made to `method`, in order: JSON as sent, and
+    /// forms (`sendDocument`) as an object of their parts.
+    pub async fn calls(&self, method: &str) -> Vec<Value> {
+        let suffix = format!("/{method}");
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| r.url.path().ends_with(&suffix))
+            .map(|r| r.body_json().unwrap_or_else(|_| form_parts(r)))
+            .collect()
+    }
+
+    /// The label the card's buttons were replaced with at the end.
+    pub async fn final_label(&self) -> Option<String> {
+        let edits = self.calls("editMessageReplyMarkup").await;
+        let last = edits.last()?;
+        last["reply_markup"]["inline_keyboard"][0][0]["text"]
+            .as_str()
+            .map(str::to_string)
+    }
+}
+
+/// The parts of a multipart/form-data body, by name.
+fn form_parts(request: &Request) -> Value {
+    let boundary = request
+        .headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split_once("boundary="))
+        .map(|(_, b)| b.to_string())
+        .expect("Bot API calls are JSON or forms");
+    let body = String::from_utf8_lossy(&request.body);
+    let mut parts = serde_json::Map::new();
+    for part in body.split(&format!("--{boundary}")) {
+        let Some((head, value)) = part.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let Some(name) = head
+            .split("name=\"")
+            .nth(1)
+            .and_then(|n| n.split('"').next())
+        else {
+            continue;
+        };
+        let value = value.strip_suffix("\r\n").unwrap_or(value);
+        parts.insert(name.to_string(), value.into());
+    }
+    Value::Object(parts)
+}
+
+struct SendMessage(Pending);
+
+impl Respond for SendMessage {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: Value = request.body_json().unwrap_or_default();
+        let approve = body["reply_markup"]["inline_keyboard"][0][0]["callback_data"].as_str();
+        if let Some(id) = approve.and_then(|d| d.strip_prefix("approve:")) {
+            self.0.lock().unwrap().push(id.to_string());
+        }
+        ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": {"message_id": 7, "chat": {"id": 42}},
+        }))
+    }
+}
+
+/// Polls are pending under their poll ID.
+struct SendPoll(Pending);
+
+impl Respond for SendPoll {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let mut pending = self.0.lock().unwrap();
+        let poll_id = format!("poll{}", pending.len() + 1);
+        pending.push(poll_id.clone());
+        ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": {"message_id": 9, "poll": {"id": poll_id}},
+        }))
+    }
+}
+
+struct GetUpdates {
+    pending: Pending,
+    answer: Answer,
+}
+
+impl Respond for GetUpdates {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let approver = ("approve", 1, "approver");
+        let taps = match self.answer {
+            Answer::Approve => vec![approver],
+            Answer::Reject => vec![("reject", 1, "approver")],
+            Answer::Trust => vec![("trust", 1, "approver")],
+            Answer::AllowAll => vec![("allow30", 1, "approver")],
+            Answer::AllowSimilar => vec![("similar60", 1, "approver")],
+            Answer::Type(_) => vec![],
+            Answer::Delegate => vec![],
+            Answer::Command => vec![],
+            Answer::TwoApprovers => vec![approver, approver, ("approve", 2, "second")],
+            Answer::Ignore => vec![],
+            Answer::ApproveTogether(_) => vec![approver],
+            Answer::Vote(_) => vec![],
+        };
+        if let Answer::Vote(voters) = self.answer {
+            return votes(&mut self.pending.lock().unwrap(), voters);
+        }
+        if let Answer::Type(text) = self.answer {
+            return typed(&mut self.pending.lock().unwrap(), text);
+        }
+        if let Answer::Delegate = self.answer {
+            return delegated(&mut self.pending.lock().unwrap());
+        }
+        if let Answer::Command = self.answer {
+            return commands(&mut self.pending.lock().unwrap());
+        }
+        let together = match self.answer {
+            Answer::ApproveTogether(n) => n,
+            _ => 1,
+        };
+        let mut pending = self.pending.lock().unwrap();
+        match std::mem::take(&mut *pending) {
+            ids if !taps.is_empty() && ids.len() >= together => {
+                let updates: Vec<Value> = ids
+                    .iter()
+                    .flat_map(|id| taps.iter().map(move |tap| (id, tap)))
+                    .enumerate()
+                    .map(|(n, (id, (tap, user, name)))| {
+                        json!({
+                            "update_id": n + 1,
+                            "callback_query": {
+                                "id": format!("cq{}", n + 1),
+                                "data": format!("{tap}:{id}"),
+                                "from": {"id": user, "username": name},
+                            },
+                        })
+                    })
+                    .collect();
+                ResponseTemplate::new(200).set_body_json(json!({"ok": true, "result": updates}))
+            }
+            // Stand in for a long poll that ends without news.
+            ids => {
+                *pending = ids;
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"ok": true, "result": []}))
+                    .set_delay(Duration::from_millis(300))
+            }
+        }
+    }
+}
+
+/// A wrong reply and a right one to each pending card's prompt, each
+/// followed by a tap on Approve.
+fn typed(pending: &mut Vec<String>, text: &'static str) -> ResponseTemplate {
+    let mut updates = Vec::new();
+    for id in std::mem::take(pending) {
+        for reply in ["not it", text] {
+            // The mock gives every message ID 7, the prompt's included.
+            updates.push(json!({
+                "update_id": updates.len() + 1,
+                "message": {
+                    "message_id": 100 + updates.len(),
+                    "chat": {"id": 42},
+                    "from": {"id": 1, "username": "approver"},
+                    "text": reply,
+                    "reply_to_message": {"message_id": 7},
+                },
+            }));
+            updates.push(json!({
+                "update_id": updates.len() + 1,
+                "callback_query": {
+                    "id": format!("cq{}", updates.len() + 1),
+                    "data": format!("approve:{id}"),
+                    "from": {"id": 1, "username": "approver"},
+                },
+            }));
+        }
+    }
+    let delay = if updates.is_empty() { 300 } else { 0 };
+    ResponseTemplate::new(200)
+        .set_body_json(json!({"ok": true, "result": updates}))
+        .set_delay(Duration::from_millis(delay))
+}
+
+/// A delegation of each pending card, a tap on the card left behind, and
+/// the backup's approval.
+fn delegated(pending: &mut Vec<String>) -> ResponseTemplate {
+    let mut updates = Vec::new();
+    for id in std::mem::take(pending) {
+        for (tap, user, name, chat) in [
+            ("delegate", 1, "approver", 42),
+            ("approve", 1, "approver", 42),
+            ("approve", 3, "backup", 43),
+        ] {
+            updates.push(json!({
+                "update_id": updates.len() + 1,
+                "callback_query": {
+                    "id": format!("cq{}", updates.len() + 1),
+                    "data": format!("{tap}:{id}"),
+                    "from": {"id": user, "username": name},
+                    "message": {"message_id": 7, "chat": {"id": chat}},
+                },
+            }));
+        }
+    }
+    let delay = if updates.is_empty() { 300 } else { 0 };
+    ResponseTemplate::new(200)
+        .set_body_json(json!({"ok": true, "result": updates}))
+        .set_delay(Duration::from_millis(delay))
+}
+
+/// `/approve_<id>` for each pending card from a stranger, then from the
+/// approver, the way a client without buttons sends it in a group.
+fn commands(pending: &mut Vec<String>) -> ResponseTemplate {
+    let mut updates = Vec::new();
+    for id in std::mem::take(pending) {
+        for (user, name) in [(2, "stranger"), (1, "approver")] {
+            updates.push(json!({
+                "update_id": updates.len() + 1,
+                "message": {
+                    "message_id": 100 + updates.len(),
+                    "chat": {"id": 42},
+                    "from": {"id": user, "username": name},
+                    "text": format!("/approve_{id}@gh_guard_bot"),
+                },
+            }));
+        }
+    }
+    let delay = if updates.is_empty() { 300 } else { 0 };
+    ResponseTemplate::new(200)
+        .set_body_json(json!({"ok": true, "result": updates}))
+        .set_delay(Duration::from_millis(delay))
+}
+
+/// `voters` answers to each pending poll, the first of them changing from
+/// Reject to Approve.
+fn votes(pending: &mut Vec<String>, voters: usize) -> ResponseTemplate {
+    let mut updates = Vec::new();
+    for poll_id in std::mem::take(pending) {
+        let mut vote = |user: usize, option: Option<usize>| {
+            updates.push(json!({
+                "update_id": updates.len() + 1,
+                "poll_answer": {
+                    "poll_id": poll_id,
+                    "user": {"id": user, "username": format!("voter{user}")},
+                    "option_ids": option.into_iter().collect::<Vec<_>>(),
+                },
+            }));
+        };
+        vote(1, Some(1));
+        vote(1, None);
+        for user in 1..=voters {
+            vote(user, Some(0));
+        }
+    }
+    let delay = if updates.is_empty() { 300 } else { 0 };
+    ResponseTemplate::new(200)
+        .set_body_json(json!({"ok": true, "result": updates}))
+        .set_delay(Duration::from_millis(delay))
+}
+
+// ── Twilio mock ──────────────────────────────────────────────────────────────
+
+/// The Messages resource of the Twilio API: texts are accepted, and every
+/// card sent is answered with `reply` and its request ID — or, with an
+/// empty `reply`, only by a stale "YES <id>" from before it was sent.
+pub struct MockTwilio {
+    server: MockServer,
+}
+
+const TWILIO_MESSAGES: &str = r"^/2010-04-01/Accounts/AC123/Messages\.json$";
+
+impl MockTwilio {
+    pub async fn start(reply: &'static str) -> MockTwilio {
+        let server = MockServer::start().await;
+        let pending = Pending::default();
+
+        Mock::given(method("POST"))
+            .and(path_regex(TWILIO_MESSAGES))
+            .respond_with(SendText(pending.clone()))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(TWILIO_MESSAGES))
+            .respond_with(Inbox { pending, reply })
+            .mount(&server)
+            .await;
+
+        MockTwilio { server }
+    }
+
+    /// `[sms]` with its API pointing here.
+    pub fn config(&self) -> String {
+        format!(
+            "[sms]\naccount_sid = \"AC123\"\nfrom = \"+15550001\"\nto = \"+15550002\"\napi = \"{}\"\n",
+            self.server.uri()
+        )
+    }
+
+    /// Bodies of the texts sent, in order.
+    pub async fn texts(&self) -> Vec<String> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| r.method.as_str() == "POST")
+            .filter_map(|r| form_field(&r.body, "Body"))
+            .collect()
+    }
+}
+
+/// Texts are pending under the request ID their reply line names.
+struct SendText(Pending);
+
+impl Respond for SendText {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body = form_field(&request.body, "Body").unwrap_or_default();
+        let id = body.split("YES ").nth(1).and_then(|s| s.split(' ').next());
+        if let Some(id) = id {
+            self.0.lock().unwrap().push(id.to_string());
+        }
+        ResponseTemplate::new(201).set_body_json(json!({
+            "sid": "SM1",
+            "status": "queued",
+            "date_created": chrono::Utc::now().to_rfc2822(),
+        }))
+    }
+}
+
+/// Newest first, like Twilio: the replies, then an approval from long
+/// before the card.
+struct Inbox {
+    pending: Pending,
+    reply: &'static str,
+}
+
+impl Respond for Inbox {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let now = chrono::Utc::now().to_rfc2822();
+        let mut messages = Vec::new();
+        for id in self.pending.lock().unwrap().iter() {
+            if !self.reply.is_empty() {
+                let body = format!("{} {id}", self.reply);
+                messages.push(json!({"sid": "SM2", "body": body, "date_sent": now}));
+            }
+            let stale = "Wed, 18 Aug 2010 20:01:40 +0000";
+            messages.push(json!({"sid": "SM0", "body": format!("YES {id}"), "date_sent": stale}));
+        }
+        ResponseTemplate::new(200).set_body_json(json!({ "messages": messages }))
+    }
+}
+
+/// Field `name` of a form-urlencoded body, decoded.
+fn form_field(body: &[u8], name: &str) -> Option<String> {
+    let body = String::from_utf8_lossy(body);
+    let value = body
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(&format!("{name}=")))?;
+    let mut bytes = Vec::new();
+    let mut chars = value.bytes();
+    while let Some(b) = chars.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [chars.next()?, chars.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            b => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
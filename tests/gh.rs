@@ -0,0 +1,306 @@
+//! What reaches the real gh: arguments, token and loop guard, for
+//! passthrough and after approval.
+
+mod common;
+
+use common::{Answer, Env, MockTelegram};
+
+/// No Telegram: anything that tries to send a card fails fast.
+const OFFLINE: &str = "[network]\ntelegram_api = \"http://127.0.0.1:9\"\n";
+
+#[test]
+fn passthrough_injects_token_and_loop_guard() {
+    let env = Env::new(OFFLINE);
+
+    let out = env.run(&["issue", "list", "--label", "a b"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let calls = env.gh_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].args, ["issue", "list", "--label", "a b"]);
+    assert_eq!(calls[0].var("GH_TOKEN"), Some("ghp_test"));
+    assert_eq!(calls[0].var("GH_GUARD_ACTIVE"), Some("1"));
+    assert_eq!(calls[0].var("GH_HOST"), None);
+    assert_eq!(env.audit().last().unwrap()["decision"], "passthrough");
+}
+
+#[test]
+fn passthrough_keeps_gh_exit_code() {
+    let env = Env::new(OFFLINE);
+    env.set_gh_exit(2);
+
+    let out = env.run(&["pr", "view", "1"]);
+
+    assert_eq!(out.status.code(), Some(2), "{out:?}");
+}
+
+#[test]
+fn local_commands_get_no_token() {
+    let env = Env::new(OFFLINE);
+
+    env.run(&["--version"]);
+    env.run(&["pr", "list", "--help"]);
+
+    let calls = env.gh_calls();
+    assert_eq!(calls.len(), 2);
+    assert!(
+        calls.iter().all(|c| c.var("GH_TOKEN").is_none()),
+        "{calls:?}"
+    );
+}
+
+#[test]
+fn guard_flags_are_not_passed_on() {
+    let env = Env::new(OFFLINE);
+
+    env.run(&["--guard-json", "repo", "view"]);
+
+    assert_eq!(env.gh_calls()[0].args, ["repo", "view"]);
+}
+
+#[test]
+fn reentered_instance_passes_mutations_straight_through() {
+    let env = Env::new(OFFLINE);
+
+    let out = env
+        .gh_guard(&["api", "-X", "DELETE", "repos/o/r"])
+        .env("GH_GUARD_ACTIVE", "1")
+        .env("GH_TOKEN", "ghp_inherited")
+        .output()
+        .unwrap();
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let calls = env.gh_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].args, ["api", "-X", "DELETE", "repos/o/r"]);
+    assert_eq!(calls[0].var("GH_TOKEN"), Some("ghp_inherited"));
+}
+
+#[test]
+fn reentered_instance_without_token_gets_one() {
+    let env = Env::new(OFFLINE);
+
+    env.gh_guard(&["issue", "list"])
+        .env("GH_GUARD_ACTIVE", "1")
+        .output()
+        .unwrap();
+
+    assert_eq!(env.gh_calls()[0].var("GH_TOKEN"), Some("ghp_test"));
+}
+
+#[tokio::test]
+async fn approved_command_runs_once_with_exact_args() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.set_gh_exit(9);
+    let args = [
+        "api",
+        "--method",
+        "PATCH",
+        "repos/o/r",
+        "-f",
+        "description=x; rm -rf /",
+    ];
+
+    let out = env.run(&args);
+
+    assert_eq!(out.status.code(), Some(9), "{out:?}");
+    let calls = env.gh_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].args, args);
+    assert_eq!(calls[0].var("GH_TOKEN"), Some("ghp_test"));
+    assert_eq!(calls[0].var("GH_GUARD_ACTIVE"), Some("1"));
+    assert!(!calls[0].args.iter().any(|a| a.contains("ghp_test")));
+    assert_eq!(env.audit().last().unwrap()["exit_code"], 9);
+}
+
+#[test]
+fn unreachable_telegram_never_runs_gh() {
+    let env = Env::new(OFFLINE);
+
+    let out = env.run(&["api", "-X", "POST", "repos/o/r/issues", "-f", "title=x"]);
+
+    assert_eq!(out.status.code(), Some(5), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    let audit = env.audit();
+    assert_eq!(audit.len(), 1);
+    assert_eq!(audit[0]["decision"], "error");
+    let reason = audit[0]["reason"].as_str().unwrap();
+    assert!(reason.contains("Failed to reach Telegram API"), "{reason}");
+}
+
+#[test]
+fn failures_are_reported_by_kind() {
+    let env = Env::new(OFFLINE);
+
+    let out = env.run(&["--guard-json", "api", "-X", "DELETE", "repos/o/r"]);
+
+    assert_eq!(out.status.code(), Some(5), "{out:?}");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let result: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(result["decision"], "error");
+    assert_eq!(result["kind"], "telegram-unreachable");
+    assert!(!stdout.contains("123:test"), "{stdout}");
+}
+
+#[test]
+fn untitled_pr_without_a_terminal_is_refused() {
+    let env = Env::new(OFFLINE);
+
+    let out = env.run(&["--guard-json", "pr", "create", "--draft"]);
+
+    assert_eq!(out.status.code(), Some(5), "{out:?}");
+    let result: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(result["kind"], "not-interceptable");
+    assert!(env.gh_calls().is_empty());
+}
+
+#[test]
+fn blocked_title_never_reaches_gh() {
+    let env = Env::new(&format!(
+        "{OFFLINE}[pr_create]\ntitle_pattern = '^(feat|fix)(\\(.+\\))?: '\ntitle_check = \"block\"\n"
+    ));
+
+    let out = env.run(&["pr", "create", "-t", "Update stuff", "-b", ""]);
+
+    assert_eq!(out.status.code(), Some(6), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    let entry = env.audit().last().cloned().unwrap();
+    assert_eq!(entry["decision"], "auto-rejected");
+    assert_eq!(entry["reason"], "title pattern");
+}
+
+#[test]
+fn api_rules_decide_before_any_card() {
+    let env = Env::new(&format!(
+        "{OFFLINE}{}",
+        r#"
+[[api_rule]]
+endpoint = '^/repos/[^/]+/[^/]+/issues/\d+/labels$'
+methods = ["POST"]
+action = "auto-approve"
+
+[[api_rule]]
+endpoint = '^/admin(/|$)'
+action = "deny"
+"#
+    ));
+
+    let labels = ["api", "-X", "POST", "repos/o/r/issues/7/labels"];
+    let out = env.run(&labels);
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+
+    let out = env.run(&["api", "-X", "DELETE", "/admin/users/eve"]);
+    assert_eq!(out.status.code(), Some(6), "{out:?}");
+
+    assert_eq!(env.gh_calls().len(), 1);
+    assert_eq!(env.gh_calls()[0].args, labels);
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "auto-approved");
+    assert_eq!(audit[0]["reason"], "api rule");
+    assert_eq!(audit[1]["decision"], "auto-rejected");
+    assert_eq!(audit[1]["reason"], "api rule");
+}
+
+#[test]
+fn dry_run_reports_without_sending_or_running() {
+    let env = Env::new(&format!(
+        "{OFFLINE}[rate_limit]\nmax_approvals = 0\n[bypass]\nper_day = 1\n"
+    ));
+
+    let guarded = env.run(&[
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-dry-run",
+        "--guard-json",
+    ]);
+    let bypassed = env.run(&[
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-dry-run",
+        "--guard-bypass=hotfix",
+    ]);
+    let passed = env
+        .gh_guard(&["issue", "list"])
+        .env("GH_GUARD_DRY_RUN", "1")
+        .output()
+        .unwrap();
+
+    assert_eq!(guarded.status.code(), Some(6), "{guarded:?}");
+    assert_eq!(bypassed.status.code(), Some(0), "{bypassed:?}");
+    assert_eq!(passed.status.code(), Some(0), "{passed:?}");
+    let result: serde_json::Value = serde_json::from_slice(&guarded.stdout).unwrap();
+    assert_eq!(result["decision"], "dry-run");
+    assert_eq!(result["would"], "refuse");
+    assert_eq!(result["reason"], "rate limit");
+    let stderr = String::from_utf8_lossy(&bypassed.stderr);
+    assert!(
+        stderr.contains("run gh without asking (hotfix)"),
+        "{stderr}"
+    );
+    let stderr = String::from_utf8_lossy(&passed.stderr);
+    assert!(stderr.contains("pass through to gh"), "{stderr}");
+    assert!(env.gh_calls().is_empty());
+    assert!(env.audit().is_empty());
+}
+
+#[test]
+fn explain_shows_how_a_command_would_be_handled() {
+    let env = Env::new(OFFLINE);
+
+    let out = env.run(&["explain", "api", "-X", "DELETE", "/repos/o/r"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    for line in [
+        "DELETE",
+        "/repos/o/r",
+        "Repo: o/r",
+        "Severity: critical",
+        "ask for approval on Telegram",
+    ] {
+        assert!(stderr.contains(line), "{line}: {stderr}");
+    }
+    assert!(env.gh_calls().is_empty());
+    assert!(env.audit().is_empty());
+}
+
+#[test]
+fn hook_denies_refused_commands_and_leaves_the_rest_alone() {
+    let env = Env::new(&format!("{OFFLINE}[rate_limit]\nmax_approvals = 0\n"));
+    let claude = |command: &str| {
+        serde_json::json!({
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Bash",
+            "tool_input": {"command": command},
+        })
+        .to_string()
+    };
+
+    let unrelated = env.run_with_input(&["hook"], &claude("cargo test && gh pr list"));
+    let limited = env.run_with_input(&["hook"], &claude("gh api -X DELETE repos/o/r/labels/a"));
+    env.run(&["readonly", "on"]);
+    let read_only = env.run_with_input(
+        &["hook"],
+        &serde_json::json!({"command": "gh issue create -t x", "cwd": "/tmp"}).to_string(),
+    );
+
+    for out in [&unrelated, &limited, &read_only] {
+        assert_eq!(out.status.code(), Some(0), "{out:?}");
+    }
+    assert!(unrelated.stdout.is_empty(), "{unrelated:?}");
+    let answer: serde_json::Value = serde_json::from_slice(&limited.stdout).unwrap();
+    let answer = &answer["hookSpecificOutput"];
+    assert_eq!(answer["permissionDecision"], "deny");
+    let reason = answer["permissionDecisionReason"].as_str().unwrap();
+    assert!(reason.contains("auto-rejected (rate limit)"), "{reason}");
+    let answer: serde_json::Value = serde_json::from_slice(&read_only.stdout).unwrap();
+    assert_eq!(answer["permission"], "deny");
+    assert!(env.gh_calls().is_empty());
+    let reasons: Vec<_> = env.audit().iter().map(|e| e["reason"].clone()).collect();
+    assert_eq!(reasons, ["rate limit", "read-only"]);
+}
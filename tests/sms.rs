@@ -0,0 +1,110 @@
+//! The approval round trip by text message against a mock Twilio: the
+//! summary goes out, a "YES <id>" or "NO <id>" reply comes back, and gh
+//! runs (or not) accordingly.
+
+mod common;
+
+use common::{Env, MockTwilio};
+
+const MUTATION: &[&str] = &["api", "-X", "POST", "repos/o/r/issues", "-f", "title=x"];
+
+#[tokio::test]
+async fn yes_reply_approves() {
+    let twilio = MockTwilio::start("yes").await;
+    let env = Env::new(&twilio.config());
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let texts = twilio.texts().await;
+    assert_eq!(texts.len(), 1);
+    let text = &texts[0];
+    assert!(text.contains("POST repos/o/r/issues"), "{text}");
+    assert!(!text.contains('<'), "{text}");
+    assert!(text.contains("to approve or NO "), "{text}");
+
+    let audit = env.audit();
+    assert_eq!(audit.last().unwrap()["decision"], "approved");
+    assert_eq!(audit.last().unwrap()["approver"], "+15550002");
+}
+
+#[tokio::test]
+async fn no_reply_rejects() {
+    let twilio = MockTwilio::start("NO").await;
+    let env = Env::new(&twilio.config());
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(3), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    assert_eq!(env.audit().last().unwrap()["decision"], "rejected");
+}
+
+#[tokio::test]
+async fn requests_needing_two_approvers_are_refused() {
+    let twilio = MockTwilio::start("yes").await;
+    let env = Env::new(&twilio.config());
+
+    let out = env.run(&[
+        "api",
+        "repos/o/r/hooks",
+        "-f",
+        "config[url]=https://collector.example/in",
+    ]);
+
+    assert_eq!(out.status.code(), Some(6), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    assert!(twilio.texts().await.is_empty());
+    let audit = env.audit();
+    assert_eq!(audit.last().unwrap()["decision"], "auto-rejected");
+    assert_eq!(
+        audit.last().unwrap()["reason"],
+        "sms cannot collect several approvals"
+    );
+}
+
+#[tokio::test]
+async fn replies_older_than_the_card_are_ignored() {
+    let twilio = MockTwilio::start("").await;
+    let env = Env::new(&format!("approval_timeout_secs = 1\n{}", twilio.config()));
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(4), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    let text = &twilio.texts().await[0];
+    let id = text
+        .split("YES ")
+        .nth(1)
+        .unwrap()
+        .split(' ')
+        .next()
+        .unwrap();
+    assert_eq!(id.len(), 8, "{text}");
+}
+
+#[tokio::test]
+async fn unlock_needs_no_telegram_bot() {
+    let twilio = MockTwilio::start("YES").await;
+    let env = Env::new(&twilio.config());
+    std::fs::create_dir_all(env.state_dir()).unwrap();
+    let lock =
+        r#"{"streaks": {}, "locked": {"source": "host/agent", "since": "09:00", "rejections": 3}}"#;
+    std::fs::write(env.state_dir().join("lockout.json"), lock).unwrap();
+
+    let out = env
+        .gh_guard(&["unlock"])
+        .env_remove("GH_GUARD_TG_TOKEN")
+        .env_remove("GH_GUARD_TG_CHAT")
+        .output()
+        .unwrap();
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let texts = twilio.texts().await;
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].contains("to approve or NO "), "{}", texts[0]);
+    let state = std::fs::read_to_string(env.state_dir().join("lockout.json")).unwrap();
+    let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+    assert!(state["locked"].is_null(), "{state}");
+}
@@ -0,0 +1,1693 @@
+//! The approval round trip against a mock Bot API: the card goes out, the
+//! decision comes back, and gh runs (or not) accordingly.
+
+mod common;
+
+use common::{Answer, Env, MockTelegram};
+use serde_json::json;
+
+const MUTATION: &[&str] = &["api", "-X", "POST", "repos/o/r/issues", "-f", "title=x"];
+
+#[tokio::test]
+async fn approved_request_runs_gh() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["chat_id"], "42");
+    let card = sent[0]["text"].as_str().unwrap();
+    assert!(card.contains("POST repos/o/r/issues"), "{card}");
+    assert!(card.ends_with("\n\n👤 as @test-bot"), "{card}");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("as @test-bot"), "{stderr}");
+    assert_eq!(tg.calls("answerCallbackQuery").await.len(), 1);
+    assert_eq!(tg.final_label().await.as_deref(), Some("✅ Approved"));
+
+    let audit = env.audit();
+    assert_eq!(audit.last().unwrap()["decision"], "approved");
+    assert_eq!(audit.last().unwrap()["approver"], "@approver");
+}
+
+#[tokio::test]
+async fn rejected_request_exits_3() {
+    let tg = MockTelegram::start(Answer::Reject).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(3), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    assert_eq!(tg.final_label().await.as_deref(), Some("❌ Rejected"));
+    assert_eq!(env.audit().last().unwrap()["decision"], "rejected");
+}
+
+#[tokio::test]
+async fn unanswered_request_times_out() {
+    let tg = MockTelegram::start(Answer::Ignore).await;
+    let env = Env::new(&format!("approval_timeout_secs = 2\n{}", tg.config()));
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(4), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    assert!(!tg.calls("getUpdates").await.is_empty());
+    assert_eq!(tg.final_label().await.as_deref(), Some("⏱ Expired"));
+    assert_eq!(env.audit().last().unwrap()["decision"], "timeout");
+}
+
+#[tokio::test]
+async fn approver_sees_the_configured_language() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!("language = \"de\"\n{}", tg.config()));
+
+    let out = env
+        .gh_guard(MUTATION)
+        .env("LANG", "fr_FR.UTF-8")
+        .output()
+        .unwrap();
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    let card = sent[0]["text"].as_str().unwrap();
+    assert!(card.contains("API-Änderung"), "{card}");
+    let buttons = &sent[0]["reply_markup"]["inline_keyboard"][0];
+    assert_eq!(buttons[0]["text"], "✅ Genehmigen");
+    assert_eq!(buttons[1]["text"], "❌ Ablehnen");
+    assert_eq!(tg.final_label().await.as_deref(), Some("✅ Genehmigt"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Genehmigt!"));
+}
+
+#[tokio::test]
+async fn card_template_replaces_the_built_in_card() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let template =
+        "[templates]\napi = '{{ agent }}: {{ title }} ({{ repo }}, {{ fields[0].value }})'\n";
+    let env = Env::new(&format!("{template}{}", tg.config()));
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(
+        sent[0]["text"],
+        "test: POST repos/o/r/issues (o/r, x)\n\n👤 as @test-bot"
+    );
+    assert_eq!(env.gh_calls().len(), 1);
+}
+
+#[tokio::test]
+async fn captured_output_is_plain_lines() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(MUTATION);
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    for line in [
+        "Method: POST",
+        "Field: title=x",
+        "Approved! Running API call…",
+    ] {
+        assert!(stderr.lines().any(|l| l == line), "{line}: {stderr}");
+    }
+    assert!(!stderr.contains(['═', '\x1b']), "{stderr}");
+}
+
+#[tokio::test]
+async fn quiet_mode_prints_the_outcome_only() {
+    let tg = MockTelegram::start(Answer::Reject).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[&["--guard-quiet"], MUTATION].concat());
+
+    assert_eq!(out.status.code(), Some(3), "{out:?}");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.trim(), "Rejected. API call cancelled.");
+}
+
+#[tokio::test]
+async fn verbose_mode_shows_request_ids_and_polls() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[MUTATION, &["--guard-verbose"]].concat());
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls()[0].args, MUTATION);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let audit = env.audit();
+    let id = audit.last().unwrap()["id"].as_str().unwrap();
+    assert!(stderr.contains(&format!("request_id={id}")), "{stderr}");
+    assert!(stderr.contains("method=\"getUpdates\" cycle=1"), "{stderr}");
+    assert!(stderr.contains("time.busy"), "{stderr}");
+}
+
+#[tokio::test]
+async fn fill_shows_what_gh_will_fill_in() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q", "-b", "main"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Initial"]);
+    env.git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    env.git(&[
+        "symbolic-ref",
+        "refs/remotes/origin/HEAD",
+        "refs/remotes/origin/main",
+    ]);
+    env.git(&["checkout", "-q", "-b", "fix-login"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Check <token>"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Refresh it"]);
+
+    let out = env.run(&["pr", "create", "--fill"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    let card = sent[0]["text"].as_str().unwrap();
+    assert!(card.contains("fix login"), "{card}");
+    assert!(
+        card.contains("• <b>Check &lt;token&gt;</b>\n• <b>Refresh it</b>"),
+        "{card}"
+    );
+    assert_eq!(env.gh_calls()[0].args, ["pr", "create", "--fill"]);
+}
+
+#[tokio::test]
+async fn pr_template_fills_an_empty_body_when_asked() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "[pr_create]\nfill_template = true\n{}",
+        tg.config()
+    ));
+    env.git(&["init", "-q"]);
+    std::fs::create_dir(env.path().join(".github")).unwrap();
+    std::fs::write(
+        env.path().join(".github/pull_request_template.md"),
+        "## Why\n",
+    )
+    .unwrap();
+
+    let out = env.run(&["pr", "create", "-t", "T"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(card.as_str().unwrap().contains("<b>Why</b>"), "{card}");
+    assert_eq!(
+        env.gh_calls()[0].args,
+        ["pr", "create", "-t", "T", "--body", "## Why\n"]
+    );
+}
+
+#[tokio::test]
+async fn unused_pr_template_is_pointed_out() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q"]);
+    std::fs::write(env.path().join("PULL_REQUEST_TEMPLATE.md"), "## Why\n").unwrap();
+
+    env.run(&["pr", "create", "-t", "T"]);
+
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .ends_with("PR template (PULL_REQUEST_TEMPLATE.md).</i>\n\n👤 as @test-bot"),
+        "{card}"
+    );
+    assert_eq!(env.gh_calls()[0].args, ["pr", "create", "-t", "T"]);
+}
+
+#[tokio::test]
+async fn named_repository_is_the_target() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q", "-b", "topic"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "x"]);
+
+    env.run(&["pr", "create", "-t", "T", "-R", "github.com/acme/api"]);
+    env.gh_guard(&["api", "-X", "POST", "repos/{owner}/{repo}/issues"])
+        .env("GH_REPO", "acme/web")
+        .output()
+        .unwrap();
+
+    let sent = tg.calls("sendMessage").await;
+    let pr = sent[0]["text"].as_str().unwrap();
+    assert!(pr.contains("topic → acme/api (default branch)"), "{pr}");
+    let api = sent[1]["text"].as_str().unwrap();
+    assert!(api.contains("POST repos/acme/web/issues"), "{api}");
+    let repos: Vec<_> = env.audit().iter().map(|e| e["repo"].clone()).collect();
+    assert_eq!(repos, ["acme/api", "acme/web"]);
+    assert_eq!(env.gh_calls()[1].args[3], "repos/{owner}/{repo}/issues");
+}
+
+#[tokio::test]
+async fn field_from_a_file_shows_its_contents() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    std::fs::write(env.path().join("body.md"), "Fixes <everything>").unwrap();
+    let args = ["api", "repos/o/r/issues", "-F", "body=@body.md"];
+
+    let out = env.run(&args);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .contains("body = @body.md (18 B)\nFixes &lt;everything&gt;"),
+        "{card}"
+    );
+    assert_eq!(env.gh_calls()[0].args, args);
+}
+
+#[tokio::test]
+async fn hostname_picks_that_hosts_profile() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let profile = "[profiles.work]\nhost = \"https://GHE.corp/\"\n";
+    let env = Env::new(&format!("{}{profile}", tg.config()));
+    let args = [
+        "api",
+        "--hostname",
+        "ghe.corp",
+        "-X",
+        "POST",
+        "repos/o/r/issues",
+    ];
+
+    let out = env.run(&args);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str().unwrap().contains("<b>Host</b>  ghe.corp"),
+        "{card}"
+    );
+    assert_eq!(env.audit().last().unwrap()["profile"], "work");
+    let gh = &env.gh_calls()[0];
+    assert_eq!(gh.args, args);
+    assert_eq!(gh.var("GH_HOST"), Some("ghe.corp"));
+}
+
+#[tokio::test]
+async fn target_repos_pick_their_profile() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let profile = "[profiles.work]\nrepos = [\"acme/*\"]\n";
+    let env = Env::new(&format!("{}{profile}", tg.config()));
+
+    let commands: [&[&str]; 3] = [
+        &["api", "-X", "POST", "repos/acme/app/issues"],
+        &["release", "upload", "v1", "a.txt", "-R", "acme/app"],
+        &["pr", "create", "-t", "x", "-b", "y", "-R", "acme/app"],
+    ];
+    for args in commands {
+        let out = env.run(args);
+        assert_eq!(out.status.code(), Some(0), "{out:?}");
+    }
+
+    let profiles: Vec<_> = env.audit().iter().map(|e| e["profile"].clone()).collect();
+    assert_eq!(profiles, [json!("work"), json!("work"), json!("work")]);
+}
+
+#[tokio::test]
+async fn ci_lookup_uses_the_target_repos_profile() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = "[secrets]\nbackend = \"pass\"\n\n[pr_create]\nci_status = true\n\n\
+                  [profiles.work]\nrepos = [\"acme/*\"]\nhost = \"127.0.0.1:9\"\n";
+    let env = Env::new(&format!("{}{config}", tg.config()));
+    env.install_pass(&[("gh-guard/work/github-pat", "ghp_work")]);
+
+    let out = env
+        .gh_guard(&[
+            "pr", "create", "-t", "x", "-b", "y", "-R", "acme/app", "-H", "f",
+        ])
+        .env_remove("GH_GUARD_PAT")
+        .output()
+        .unwrap();
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.audit()[0]["profile"], "work");
+    let gh = &env.gh_calls()[0];
+    assert_eq!(gh.var("GH_ENTERPRISE_TOKEN"), Some("ghp_work"));
+}
+
+#[tokio::test]
+async fn mapped_repos_run_as_their_account() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = "[secrets]\nbackend = \"pass\"\n\n[accounts.work]\nrepos = [\"acme/*\"]\n";
+    let env = Env::new(&format!("{}{config}", tg.config()));
+    env.install_pass(&[
+        ("gh-guard/github-pat", "ghp_test"),
+        ("gh-guard/github-pat@work", "ghp_work"),
+    ]);
+    std::fs::write(
+        env.state_dir().join("identities.json"),
+        r#"{"8f23f423a895e689": "test-bot", "77784e562978ec76": "acme-bot"}"#,
+    )
+    .unwrap();
+    let run = |repo: &str| {
+        env.gh_guard(&["api", "-X", "POST", &format!("repos/{repo}/issues")])
+            .env_remove("GH_GUARD_PAT")
+            .output()
+            .unwrap()
+    };
+
+    let work = run("acme/api");
+    let own = run("o/r");
+
+    assert_eq!(work.status.code(), Some(0), "{work:?}");
+    assert_eq!(own.status.code(), Some(0), "{own:?}");
+    let cards = tg.calls("sendMessage").await;
+    let card = cards[0]["text"].as_str().unwrap();
+    assert!(card.ends_with("👤 as @acme-bot (account work)"), "{card}");
+    let card = cards[1]["text"].as_str().unwrap();
+    assert!(card.ends_with("👤 as @test-bot"), "{card}");
+    let tokens: Vec<_> = env
+        .gh_calls()
+        .iter()
+        .map(|c| c.var("GH_TOKEN").map(str::to_string))
+        .collect();
+    assert_eq!(tokens, [Some("ghp_work".into()), Some("ghp_test".into())]);
+    let audit = env.audit();
+    assert_eq!(audit[0]["account"], "work");
+    assert!(audit[1].get("account").is_none());
+}
+
+#[tokio::test]
+async fn forks_run_in_the_source_repos_profile_not_the_checkouts() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = "host = \"127.0.0.1:9\"\n[secrets]\nbackend = \"pass\"\n\n\
+                  [profiles.work]\nrepos = [\"acme/*\"]\n";
+    let env = Env::new(&format!("{config}{}", tg.config()));
+    env.install_pass(&[
+        ("gh-guard/github-pat", "ghp_test"),
+        ("gh-guard/work/github-pat", "ghp_work"),
+    ]);
+    std::fs::write(
+        env.state_dir().join("identities.json"),
+        r#"{"8f23f423a895e689": "test-bot", "77784e562978ec76": "acme-bot"}"#,
+    )
+    .unwrap();
+    env.git(&["init", "-q", "-b", "main"]);
+    env.git(&["remote", "add", "origin", "https://github.com/acme/app.git"]);
+
+    let out = env
+        .gh_guard(&["repo", "fork", "o/r"])
+        .env_remove("GH_GUARD_PAT")
+        .output()
+        .unwrap();
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(card.as_str().unwrap().contains("test-bot/r"), "{card}");
+    assert!(env.audit()[0]["profile"].is_null());
+    let gh = &env.gh_calls()[0];
+    assert_eq!(gh.var("GH_ENTERPRISE_TOKEN"), Some("ghp_test"));
+}
+
+#[tokio::test]
+async fn long_description_follows_the_card_as_a_file() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    let body = format!("{}\nthe part that matters", "context ".repeat(400));
+
+    let out = env.run(&["pr", "create", "-t", "T", "-b", &body]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .ends_with("the full text follows as description.md.</i>\n\n👤 as @test-bot"),
+        "{card}"
+    );
+    let docs = tg.calls("sendDocument").await;
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0]["document"], body.trim());
+    assert_eq!(docs[0]["chat_id"], "42");
+    assert_eq!(docs[0]["reply_parameters"], r#"{"message_id":7}"#);
+}
+
+#[tokio::test]
+async fn cards_link_to_github() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    env.run(&[
+        "pr", "create", "-t", "T", "-R", "acme/api", "-B", "main", "-H", "me:fix#1",
+    ]);
+    env.run(MUTATION);
+
+    let sent = tg.calls("sendMessage").await;
+    let links = |i: usize| -> Vec<(String, String)> {
+        let row = sent[i]["reply_markup"]["inline_keyboard"][1]
+            .as_array()
+            .unwrap();
+        row.iter()
+            .map(|b| {
+                (
+                    b["text"].as_str().unwrap().into(),
+                    b["url"].as_str().unwrap().into(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(
+        links(0),
+        [
+            (
+                "🔍 View compare".to_string(),
+                "https://github.com/acme/api/compare/main...me:fix%231".to_string()
+            ),
+            (
+                "📂 Open repo".to_string(),
+                "https://github.com/acme/api".to_string()
+            )
+        ]
+    );
+    assert_eq!(
+        links(1),
+        [(
+            "📂 Open repo".to_string(),
+            "https://github.com/o/r".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn default_branch_policy_needs_two_approvers_and_the_diff() {
+    let tg = MockTelegram::start(Answer::TwoApprovers).await;
+    let policy = "[[branch_policy]]\ndefault_branch = true\napprovals = 2\n\
+                  attach_diff = true\napproval_timeout_secs = 900\n";
+    let env = Env::new(&format!("{}{policy}", tg.config()));
+    env.git(&["init", "-q", "-b", "main"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Initial"]);
+    env.git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    env.git(&[
+        "symbolic-ref",
+        "refs/remotes/origin/HEAD",
+        "refs/remotes/origin/main",
+    ]);
+    env.git(&["checkout", "-q", "-b", "fix"]);
+    std::fs::write(env.path().join("fix.txt"), "the fix\n").unwrap();
+    env.git(&["add", "fix.txt"]);
+    env.git(&["commit", "-q", "-m", "Fix"]);
+
+    let out = env.run(&["pr", "create", "-t", "T"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(card.contains("Needs 2 approvals"), "{card}");
+    assert!(
+        card.ends_with("The diff follows as diff.patch.</i>\n\n👤 as @test-bot"),
+        "{card}"
+    );
+    let docs = tg.calls("sendDocument").await;
+    assert_eq!(
+        docs[0]["document"].as_str().map(|d| d.contains("+the fix")),
+        Some(true)
+    );
+    let answers: Vec<_> = tg
+        .calls("answerCallbackQuery")
+        .await
+        .iter()
+        .map(|a| a["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        answers,
+        [
+            "✅ Approval 1 of 2; waiting for someone else.",
+            "You have already approved this.",
+            "✅ Approving…"
+        ]
+    );
+    assert_eq!(
+        env.audit().last().unwrap()["approver"],
+        "@approver, @second"
+    );
+    assert!(String::from_utf8_lossy(&out.stderr).contains("15 min"));
+}
+
+#[tokio::test]
+async fn webhooks_show_their_url_and_need_two_approvers() {
+    let tg = MockTelegram::start(Answer::TwoApprovers).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[
+        "api",
+        "repos/o/r/hooks",
+        "-f",
+        "config[url]=https://collector.example/in",
+        "-f",
+        "events[]=push",
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(
+        card.contains(
+            "<code>POST repos/o/r/hooks</code>\n\
+             <b>🎯 Delivers to</b>  <code>https://collector.example/in</code>"
+        ),
+        "{card}"
+    );
+    assert!(card.contains("Needs 2 approvals"), "{card}");
+    let audit = env.audit();
+    assert_eq!(audit[0]["severity"], "critical");
+    assert_eq!(audit[0]["approver"], "@approver, @second");
+}
+
+#[tokio::test]
+async fn branch_protection_changes_are_spelled_out() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let policy = "[api]\nwebhook_approvals = 1\n";
+    let env = Env::new(&format!("{}{policy}", tg.config()));
+    let body = env.path().join("protection.json");
+    std::fs::write(
+        &body,
+        r#"{"required_status_checks": null, "enforce_admins": true,
+            "required_pull_request_reviews": {"required_approving_review_count": 1},
+            "restrictions": null, "allow_force_pushes": true}"#,
+    )
+    .unwrap();
+
+    let out = env.run(&[
+        "api",
+        "-X",
+        "PUT",
+        "repos/o/r/branches/main/protection",
+        "--input",
+        body.to_str().unwrap(),
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(
+        card.contains(
+            "<b>🛡 Branch</b>  <code>main</code>\n\
+             <b>➖ Turns off</b>  <code>allow_force_pushes, required_status_checks, \
+             restrictions</code>\n\
+             <b>✏️ Sets</b>  <code>enforce_admins, required_pull_request_reviews</code>"
+        ),
+        "{card}"
+    );
+    assert!(!card.contains("Needs"), "{card}");
+    assert_eq!(env.audit()[0]["severity"], "critical");
+}
+
+#[tokio::test]
+async fn access_changes_say_who_gets_what() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[
+        "api",
+        "-X",
+        "PUT",
+        "orgs/acme/teams/bots/repos/acme/api",
+        "-f",
+        "permission=admin",
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(
+        card.contains("<b>👥 Access</b>  <code>grant admin to team acme/bots on acme/api</code>"),
+        "{card}"
+    );
+    let audit = env.audit();
+    assert_eq!(audit[0]["repo"], "acme/api");
+    assert_eq!(audit[0]["severity"], "critical");
+}
+
+#[tokio::test]
+async fn membership_changes_say_who_joins_what() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[
+        "api",
+        "-X",
+        "PUT",
+        "orgs/acme/teams/devs/memberships/eve",
+        "-f",
+        "role=maintainer",
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(
+        card.contains("<b>👤 Members</b>  <code>add @eve to team acme/devs as maintainer</code>"),
+        "{card}"
+    );
+    assert_eq!(env.audit()[0]["severity"], "critical");
+}
+
+#[tokio::test]
+async fn workflow_writes_show_the_decoded_file() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    // "on: push\njobs: {}\n"
+    let body = env.path().join("body.json");
+    std::fs::write(
+        &body,
+        r#"{"message": "ci", "content": "b246IHB1c2gKam9iczoge30K"}"#,
+    )
+    .unwrap();
+
+    let out = env.run(&[
+        "api",
+        "-X",
+        "PUT",
+        "repos/o/r/contents/.github/workflows/ci.yml",
+        "--input",
+        body.to_str().unwrap(),
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let card = card.as_str().unwrap();
+    assert!(
+        card.contains("📜 <b>.github/workflows/ci.yml</b>\n<pre>on: push\njobs: {}</pre>"),
+        "{card}"
+    );
+    assert_eq!(env.audit()[0]["severity"], "critical");
+}
+
+#[tokio::test]
+async fn read_only_call_skips_telegram() {
+    let tg = MockTelegram::start(Answer::Reject).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&["api", "repos/o/r/pulls"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert!(tg.calls("sendMessage").await.is_empty());
+    assert_eq!(env.gh_calls().len(), 1);
+}
+
+#[tokio::test]
+async fn mismatched_title_is_flagged_on_the_card() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "{}[pr_create]\ntitle_pattern = '^[A-Z]+-\\d+ '\n",
+        tg.config()
+    ));
+
+    let out = env.run(&["pr", "create", "-t", "Fix <login>", "-b", ""]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .contains("⚠️ <b>The title does not match</b> <code>^[A-Z]+-\\d+ </code>"),
+        "{card}"
+    );
+    assert_eq!(env.gh_calls().len(), 1);
+}
+
+#[tokio::test]
+async fn branch_behind_origin_is_flagged() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q", "-b", "fix"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Fix"]);
+    env.git(&["commit", "-q", "--allow-empty", "-m", "Fix more"]);
+    env.git(&["update-ref", "refs/remotes/origin/fix", "HEAD"]);
+    env.git(&["reset", "-q", "--hard", "HEAD~"]);
+
+    let out = env.run(&["pr", "create", "-t", "T", "-b", ""]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .ends_with("⚠️ <i>The branch is 1 commit behind origin/fix.</i>\n\n👤 as @test-bot"),
+        "{card}"
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("1 commit behind origin/fix"), "{stderr}");
+}
+
+#[tokio::test]
+async fn uncommitted_files_are_counted() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q"]);
+    std::fs::write(env.path().join("a.txt"), "a").unwrap();
+    std::fs::write(env.path().join("b.txt"), "b").unwrap();
+
+    let out = env.run(&["pr", "create", "-t", "T", "-b", ""]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(
+        card.as_str()
+            .unwrap()
+            .ends_with("⚠️ <i>2 uncommitted files are not included.</i>\n\n👤 as @test-bot"),
+        "{card}"
+    );
+}
+
+#[tokio::test]
+async fn cards_show_the_checkout() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    env.git(&["init", "-q", "-b", "fix"]);
+    std::fs::write(env.path().join("a.txt"), "a").unwrap();
+
+    let out = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/bug"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    let checkout = format!(
+        "📂 <code>{}</code> · <b>fix</b> · 1 untracked\n\n👤 as @test-bot",
+        env.path().display()
+    );
+    assert!(card.as_str().unwrap().ends_with(&checkout), "{card}");
+}
+
+#[tokio::test]
+async fn concurrent_requests_each_get_their_own_tap() {
+    let tg = MockTelegram::start(Answer::ApproveTogether(2)).await;
+    let env = Env::new(&format!("approval_timeout_secs = 20\n{}", tg.config()));
+
+    let first = env.spawn(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+    let second = env.spawn(&["api", "-X", "DELETE", "repos/o/r/labels/b"]);
+
+    for child in [first, second] {
+        let out = child.wait_with_output().unwrap();
+        assert_eq!(out.status.code(), Some(0), "{out:?}");
+    }
+    assert_eq!(env.gh_calls().len(), 2);
+    let answers = tg.calls("answerCallbackQuery").await;
+    assert!(
+        answers.iter().all(|a| a["text"] == "✅ Approving…"),
+        "{answers:?}"
+    );
+}
+
+#[tokio::test]
+async fn cards_left_by_a_killed_process_are_marked_expired() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    std::fs::create_dir_all(env.state_dir()).unwrap();
+    let open = json!([
+        {"chat_id": "42", "message_id": 3, "expires_at": 1_000},
+        {"chat_id": "42", "message_id": 4, "expires_at": 4_000_000_000u64},
+    ]);
+    std::fs::write(env.state_dir().join("open_cards.json"), open.to_string()).unwrap();
+
+    let out = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let edits: Vec<_> = tg
+        .calls("editMessageReplyMarkup")
+        .await
+        .iter()
+        .map(|e| {
+            let label = &e["reply_markup"]["inline_keyboard"][0][0]["text"];
+            (
+                e["message_id"].as_i64().unwrap(),
+                label.as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        edits,
+        [(3, "⏱ Expired".to_string()), (7, "✅ Approved".to_string())]
+    );
+    let left = std::fs::read_to_string(env.state_dir().join("open_cards.json")).unwrap();
+    let left: serde_json::Value = serde_json::from_str(&left).unwrap();
+    assert_eq!(left.as_array().unwrap().len(), 1, "{left}");
+    assert_eq!(left[0]["message_id"], 4);
+}
+
+#[tokio::test]
+async fn settled_cards_are_deleted_with_their_files() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!("{}[cleanup]\nafter_hours = 0\n", tg.config()));
+    let body = "context ".repeat(600);
+
+    let first = env.run(&["pr", "create", "-t", "T", "-b", &body]);
+    assert_eq!(first.status.code(), Some(0), "{first:?}");
+    assert!(tg.calls("deleteMessage").await.is_empty());
+    let second = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+
+    assert_eq!(second.status.code(), Some(0), "{second:?}");
+    let deleted: Vec<_> = tg
+        .calls("deleteMessage")
+        .await
+        .iter()
+        .map(|d| d["message_id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(deleted, [7, 8]);
+    let left = std::fs::read_to_string(env.state_dir().join("settled_cards.json")).unwrap();
+    let left: serde_json::Value = serde_json::from_str(&left).unwrap();
+    assert_eq!(left.as_array().unwrap().len(), 1, "{left}");
+}
+
+#[tokio::test]
+async fn configured_buttons_replace_the_built_in_ones() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = format!(
+        "[buttons]\napprove = \"OK-7342\"\napproved = \"OK given\"\npr_icon = \"🟢\"\n{}",
+        tg.config()
+    );
+    let env = Env::new(&config);
+
+    let out = env.run(&["pr", "create", "-t", "T", "-b", "B"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0];
+    let buttons = &card["reply_markup"]["inline_keyboard"][0];
+    assert_eq!(buttons[0]["text"], "OK-7342");
+    assert_eq!(buttons[1]["text"], "❌ Reject");
+    assert!(
+        card["text"].as_str().unwrap().starts_with("🟢 <b>"),
+        "{card}"
+    );
+    assert_eq!(tg.final_label().await.as_deref(), Some("OK given"));
+}
+
+#[tokio::test]
+async fn cards_go_to_their_forum_topic() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = format!(
+        "{}[topics]\npr_create = 12\napi = 34\n[topics.repos]\n\"acme/*\" = 5\n",
+        tg.config()
+    );
+    let env = Env::new(&config);
+    let body = "context ".repeat(600);
+
+    env.run(&["pr", "create", "-t", "T", "-b", &body]);
+    env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+    env.run(&["api", "-X", "POST", "repos/acme/web/issues"]);
+
+    let topics: Vec<_> = tg
+        .calls("sendMessage")
+        .await
+        .iter()
+        .map(|m| m["message_thread_id"].as_i64())
+        .collect();
+    assert_eq!(topics, [Some(12), Some(34), Some(5)]);
+    let docs = tg.calls("sendDocument").await;
+    assert_eq!(docs[0]["message_thread_id"], "12");
+}
+
+#[tokio::test]
+async fn only_group_members_can_decide() {
+    let tg = MockTelegram::start(Answer::TwoApprovers).await;
+    let env = Env::new(&format!("{}[group]\nmembers = [2]\n", tg.config()));
+
+    let out = env.run(&["pr", "create", "-t", "T", "-b", "B"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let answers: Vec<_> = tg
+        .calls("answerCallbackQuery")
+        .await
+        .iter()
+        .map(|a| a["text"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        answers,
+        [
+            "Only the group's approvers can decide this.",
+            "Only the group's approvers can decide this.",
+            "✅ Approving…",
+        ]
+    );
+    assert_eq!(
+        tg.final_label().await.as_deref(),
+        Some("✅ Approved · @second")
+    );
+    assert_eq!(env.audit()[0]["approver"], "@second");
+}
+
+#[tokio::test]
+async fn a_quorum_of_votes_decides() {
+    let tg = MockTelegram::start(Answer::Vote(2)).await;
+    let env = Env::new(&format!("{}[voting]\nquorum = 2\n", tg.config()));
+
+    let out = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let card = &tg.calls("sendMessage").await[0];
+    assert!(!card.to_string().contains("callback_data"), "{card}");
+    let polls = tg.calls("sendPoll").await;
+    assert_eq!(polls.len(), 1);
+    assert_eq!(polls[0]["question"], "Approve this request?");
+    assert_eq!(polls[0]["is_anonymous"], false);
+    assert_eq!(polls[0]["reply_parameters"]["message_id"], 7);
+    assert_eq!(tg.final_label().await.as_deref(), Some("✅ Approved"));
+    assert_eq!(tg.calls("stopPoll").await[0]["message_id"], 9);
+    assert_eq!(env.audit()[0]["approver"], "@voter1, @voter2");
+}
+
+#[tokio::test]
+async fn bypass_runs_gh_and_reports_it() {
+    let tg = MockTelegram::start(Answer::Ignore).await;
+    let env = Env::new(&format!("{}[bypass]\nper_day = 5\n", tg.config()));
+
+    let blank = env.run(&[
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-bypass=",
+    ]);
+    let out = env.run(&[
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-bypass",
+        "hotfix: prod down",
+    ]);
+
+    assert_eq!(blank.status.code(), Some(5), "{blank:?}");
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    assert_eq!(
+        env.gh_calls()[0].args,
+        ["api", "-X", "DELETE", "repos/o/r/labels/a"]
+    );
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].get("reply_markup").is_none(), "{}", sent[0]);
+    let note = sent[0]["text"].as_str().unwrap();
+    assert!(note.contains("<b>Reason</b>  hotfix: prod down"), "{note}");
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "bypassed");
+    assert_eq!(audit[0]["reason"], "hotfix: prod down");
+}
+
+#[tokio::test]
+async fn forks_within_the_owner_are_approved_by_policy() {
+    let tg = MockTelegram::start(Answer::Reject).await;
+    let env = Env::new(&format!(
+        "{}[repo_fork]\nauto_approve_same_org = true\n",
+        tg.config()
+    ));
+
+    let out = env.run(&["repo", "fork", "o/r", "--org", "o", "--fork-name", "r2"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert!(tg.calls("sendMessage").await.is_empty());
+    assert_eq!(
+        env.gh_calls()[0].args,
+        ["repo", "fork", "o/r", "--org", "o", "--fork-name", "r2"]
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Approved by policy (same owner)"),
+        "{stderr}"
+    );
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "auto-approved");
+    assert_eq!(audit[0]["reason"], "same owner");
+    assert_eq!(audit[0]["severity"], "low");
+}
+
+#[tokio::test]
+async fn denied_calls_are_refused_and_reported() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "{}[api]\ndeny = [\"DELETE /repos/my-org/*\"]\n",
+        tg.config()
+    ));
+
+    let out = env.run(&["api", "-X", "DELETE", "repos/my-org/app"]);
+
+    assert_eq!(out.status.code(), Some(6), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("Never allowed from this machine"),
+        "{stderr}"
+    );
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(sent.len(), 1);
+    let note = sent[0]["text"].as_str().unwrap();
+    assert!(note.contains("DELETE repos/my-org/app"), "{note}");
+    assert!(
+        note.contains("<code>DELETE /repos/my-org/*</code>"),
+        "{note}"
+    );
+    assert!(sent[0].get("reply_markup").is_none());
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "auto-rejected");
+    assert_eq!(audit[0]["reason"], "deny rule");
+}
+
+#[tokio::test]
+async fn deny_rules_cover_the_calls_gh_commands_make() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "{}[api]\ndeny = [\"DELETE /repos/my-org/*\", \"POST /repos/*/forks\"]\n",
+        tg.config()
+    ));
+
+    let deleted = env.run(&["repo", "delete", "my-org/app", "--yes"]);
+    let forked = env.run(&["repo", "fork", "acme/app", "--clone=false"]);
+
+    assert_eq!(deleted.status.code(), Some(6), "{deleted:?}");
+    assert_eq!(forked.status.code(), Some(6), "{forked:?}");
+    assert!(env.gh_calls().is_empty());
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(sent.len(), 2);
+    let note = sent[0]["text"].as_str().unwrap();
+    assert!(
+        note.contains("<code>DELETE /repos/my-org/*</code>"),
+        "{note}"
+    );
+    assert!(sent[0].get("reply_markup").is_none());
+    let audit = env.audit();
+    assert_eq!(audit.len(), 2);
+    for entry in &audit {
+        assert_eq!(entry["decision"], "auto-rejected");
+        assert_eq!(entry["reason"], "deny rule");
+    }
+}
+
+#[tokio::test]
+async fn repositories_are_trusted_on_first_use() {
+    let tg = MockTelegram::start(Answer::Trust).await;
+    let env = Env::new(&format!(
+        "{}[trust]\nauto_approve = \"medium\"\n",
+        tg.config()
+    ));
+    let upload = ["release", "upload", "v1", "a.txt", "-R", "o/r"];
+
+    let out = env.run(&upload);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    let keyboard = &sent[0]["reply_markup"]["inline_keyboard"];
+    assert_eq!(keyboard[1][0]["text"], "✅ Approve & trust repo");
+    assert!(keyboard[1][0]["callback_data"]
+        .as_str()
+        .unwrap()
+        .starts_with("trust:"));
+
+    let out = env.run(&upload);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(tg.calls("sendMessage").await.len(), 1);
+    assert_eq!(env.gh_calls().len(), 2);
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "approved");
+    assert_eq!(audit[0]["reason"], "repo trusted");
+    assert_eq!(audit[0]["approver"], "@approver");
+    assert_eq!(audit[1]["decision"], "auto-approved");
+    assert_eq!(audit[1]["reason"], "trusted repo");
+}
+
+#[tokio::test]
+async fn allow_all_skips_cards_up_to_its_severity() {
+    let tg = MockTelegram::start(Answer::AllowAll).await;
+    let env = Env::new(&format!(
+        "{}[allow_all]\nmax_severity = \"medium\"\n",
+        tg.config()
+    ));
+    let upload = ["release", "upload", "v1", "a.txt", "-R", "o/r"];
+
+    let out = env.run(&upload);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    let button = &sent[0]["reply_markup"]["inline_keyboard"][1][0];
+    assert_eq!(button["text"], "🔓 Allow all for 30 min");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("without asking until"), "{stderr}");
+
+    env.run(&upload);
+    assert_eq!(tg.calls("sendMessage").await.len(), 1);
+
+    env.run(&["release", "upload", "v1", "a.txt", "-R", "o/r", "--clobber"]);
+    assert_eq!(tg.calls("sendMessage").await.len(), 2);
+
+    assert_eq!(env.gh_calls().len(), 3);
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "approved");
+    assert!(audit[0]["reason"]
+        .as_str()
+        .unwrap()
+        .starts_with("allow all until "));
+    assert_eq!(audit[1]["decision"], "auto-approved");
+    assert_eq!(audit[1]["reason"], "allow all");
+    assert_eq!(audit[2]["decision"], "approved");
+}
+
+#[tokio::test]
+async fn allow_all_does_not_reach_untrusted_repositories() {
+    let tg = MockTelegram::start(Answer::AllowAll).await;
+    let env = Env::new(&format!(
+        "{}[trust]\n[allow_all]\nmax_severity = \"medium\"\n",
+        tg.config()
+    ));
+    let upload = ["release", "upload", "v1", "a.txt", "-R", "o/r"];
+
+    let out = env.run(&upload);
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+
+    let out = env.run(&upload);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(tg.calls("sendMessage").await.len(), 2);
+    let audit = env.audit();
+    assert_eq!(audit[1]["decision"], "approved");
+}
+
+#[tokio::test]
+async fn auto_approvals_count_towards_the_rate_limit() {
+    let tg = MockTelegram::start(Answer::AllowSimilar).await;
+    let env = Env::new(&format!(
+        "{}[allow_similar]\n[rate_limit]\nmax_approvals = 2\n",
+        tg.config()
+    ));
+    let patch = |homepage: &str| {
+        let field = format!("homepage={homepage}");
+        env.run(&["api", "-X", "PATCH", "repos/o/r", "-f", &field])
+    };
+
+    for homepage in ["a", "b", "c"] {
+        let out = patch(homepage);
+        assert_eq!(out.status.code(), Some(0), "{out:?}");
+    }
+
+    assert_eq!(tg.calls("sendMessage").await.len(), 2);
+    let decisions: Vec<_> = env.audit().iter().map(|e| e["decision"].clone()).collect();
+    assert_eq!(decisions, ["approved", "auto-approved", "approved"]);
+}
+
+#[tokio::test]
+async fn allow_similar_covers_the_same_call_in_the_same_repo() {
+    let tg = MockTelegram::start(Answer::AllowSimilar).await;
+    let env = Env::new(&format!("{}[allow_similar]\n", tg.config()));
+
+    let out = env.run(&["api", "-X", "PATCH", "repos/o/r", "-f", "homepage=a"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let sent = tg.calls("sendMessage").await;
+    let button = &sent[0]["reply_markup"]["inline_keyboard"][1][0];
+    assert_eq!(button["text"], "✅ Approve & allow similar");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("More gh api PATCH on o/r"), "{stderr}");
+
+    env.run(&["api", "-X", "PATCH", "repos/o/r", "-f", "homepage=b"]);
+    assert_eq!(tg.calls("sendMessage").await.len(), 1);
+
+    env.run(&["api", "-X", "PATCH", "repos/o/other", "-f", "homepage=c"]);
+    env.run(&["api", "-X", "POST", "repos/o/r/issues", "-f", "title=d"]);
+    assert_eq!(tg.calls("sendMessage").await.len(), 3);
+
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "approved");
+    assert!(audit[0]["reason"]
+        .as_str()
+        .unwrap()
+        .starts_with("allow similar until "));
+    assert_eq!(audit[1]["decision"], "auto-approved");
+    assert_eq!(audit[1]["reason"], "similar approved");
+    assert_eq!(env.gh_calls().len(), 4);
+}
+
+#[tokio::test]
+async fn allow_similar_does_not_reach_untrusted_repositories() {
+    let tg = MockTelegram::start(Answer::AllowSimilar).await;
+    let env = Env::new(&format!("{}[trust]\n[allow_similar]\n", tg.config()));
+
+    let out = env.run(&["api", "-X", "PATCH", "repos/o/r", "-f", "homepage=a"]);
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+
+    let out = env.run(&["api", "-X", "PATCH", "repos/o/r", "-f", "homepage=b"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(tg.calls("sendMessage").await.len(), 2);
+    let audit = env.audit();
+    assert_eq!(audit[1]["decision"], "approved");
+}
+
+#[tokio::test]
+async fn deleting_a_repository_takes_its_name_typed_out() {
+    let tg = MockTelegram::start(Answer::Type("o/r")).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&["repo", "delete", "o/r", "--yes"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls()[0].args, ["repo", "delete", "o/r", "--yes"]);
+    let sent = tg.calls("sendMessage").await;
+    let card = sent[0]["text"].as_str().unwrap();
+    assert!(card.contains("<code>o/r</code>"), "{card}");
+    assert!(card.contains("cannot be undone"), "{card}");
+    let prompt = &sent[1];
+    assert!(prompt["text"]
+        .as_str()
+        .unwrap()
+        .contains("reply with <code>o/r</code>"));
+    assert_eq!(prompt["reply_markup"]["force_reply"], true);
+    assert!(sent[2]["text"].as_str().unwrap().starts_with("That is not"));
+    let answers: Vec<_> = tg
+        .calls("answerCallbackQuery")
+        .await
+        .iter()
+        .map(|a| a["text"].clone())
+        .collect();
+    assert_eq!(answers[0], "Reply with the repository name first.");
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "approved");
+    assert_eq!(audit[0]["severity"], "critical");
+}
+
+#[tokio::test]
+async fn delegating_hands_the_decision_to_the_backup() {
+    let tg = MockTelegram::start(Answer::Delegate).await;
+    let config = "[delegate]\nchat_id = \"43\"\nname = \"Backup\"\n";
+    let env = Env::new(&format!("{config}{}", tg.config()));
+
+    let out = env.run(&["pr", "create", "--title", "x"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let sent = tg.calls("sendMessage").await;
+    let keyboard = &sent[0]["reply_markup"]["inline_keyboard"];
+    assert_eq!(keyboard[1][0]["text"], "➡️ Delegate to Backup");
+    let copy = &tg.calls("copyMessage").await[0];
+    assert_eq!(copy["chat_id"], "43");
+    assert_eq!(copy["from_chat_id"], "42");
+    assert_eq!(copy["message_id"], 7);
+    assert_eq!(sent[1]["chat_id"], "43");
+    assert!(sent[1]["text"]
+        .as_str()
+        .unwrap()
+        .contains("@approver handed"));
+    let answers = tg.calls("answerCallbackQuery").await;
+    assert_eq!(
+        answers[1]["text"],
+        "This request was handed to someone else."
+    );
+    let edits = tg.calls("editMessageReplyMarkup").await;
+    assert_eq!(edits[0]["chat_id"], "42");
+    assert_eq!(
+        edits[0]["reply_markup"]["inline_keyboard"][0][0]["text"],
+        "➡️ Delegated to Backup"
+    );
+    assert_eq!(edits[1]["chat_id"], "43");
+    assert_eq!(edits[1]["message_id"], 11);
+    assert_eq!(tg.final_label().await.as_deref(), Some("✅ Approved"));
+    let audit = env.audit();
+    assert_eq!(audit[0]["approver"], "@backup");
+    assert_eq!(audit[0]["delegation"], json!(["@approver", "Backup"]));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Delegated by @approver to Backup."));
+}
+
+#[tokio::test]
+async fn cards_go_to_whoever_is_on_call() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let config = "[group]\nmembers = [2]\n\n[[on_call]]\nname = \"Sam\"\nchat_id = \"77\"\n";
+    let env = Env::new(&format!("{config}{}", tg.config()));
+
+    let out = env.run(&["pr", "create", "--title", "x"]);
+
+    // The shift's chat, where anyone may decide: [group] is the usual chat's.
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(tg.calls("sendMessage").await[0]["chat_id"], "77");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Sam is on call and decides this."));
+    assert_eq!(env.audit()[0]["approver"], "@approver");
+}
+
+#[tokio::test]
+async fn approve_command_decides_like_the_button() {
+    let tg = MockTelegram::start(Answer::Command).await;
+    let config = "[group]\nmembers = [1]\n\n[buttons]\ncommands = true\n";
+    let env = Env::new(&format!("{config}{}", tg.config()));
+
+    let out = env.run(&["pr", "create", "--title", "x"]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let sent = tg.calls("sendMessage").await;
+    let card = sent[0]["text"].as_str().unwrap();
+    let id = env.audit()[0]["id"].as_str().unwrap().to_string();
+    assert!(
+        card.ends_with(&format!("/approve_{id} · /reject_{id}")),
+        "{card}"
+    );
+    assert_eq!(
+        sent[1]["text"],
+        "Only the group's approvers can decide this."
+    );
+    assert_eq!(sent[1]["reply_parameters"]["message_id"], 100);
+    assert_eq!(env.audit()[0]["approver"], "@approver");
+    assert_eq!(
+        tg.final_label().await.as_deref(),
+        Some("✅ Approved · @approver")
+    );
+}
+
+#[tokio::test]
+async fn release_upload_names_the_assets_and_flags_clobber() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[
+        "release",
+        "upload",
+        "v1.2.0",
+        "dist/app.tar.gz",
+        "dist/app.zip#Windows build",
+        "-R",
+        "o/r",
+        "--clobber",
+    ]);
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let sent = tg.calls("sendMessage").await;
+    let card = sent[0]["text"].as_str().unwrap();
+    assert!(card.contains("<b>Tag</b>  <code>v1.2.0</code>"), "{card}");
+    assert!(card.contains("• <code>app.tar.gz</code>"), "{card}");
+    assert!(
+        card.contains("• <code>app.zip (Windows build)</code>"),
+        "{card}"
+    );
+    assert!(
+        card.contains("⚠️ <b>--clobber: assets of the same name are replaced.</b>"),
+        "{card}"
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("--clobber"), "{stderr}");
+    let audit = env.audit();
+    assert_eq!(audit[0]["repo"], "o/r");
+    assert_eq!(audit[0]["severity"], "high");
+}
+
+#[tokio::test]
+async fn bypass_is_off_without_a_budget() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let out = env.run(&[
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-bypass=hotfix",
+    ]);
+    let help = env.run(&["--guard-help"]);
+
+    assert_eq!(out.status.code(), Some(6), "{out:?}");
+    assert!(env.gh_calls().is_empty());
+    assert!(tg.calls("sendMessage").await.is_empty());
+    assert_eq!(env.audit()[0]["reason"], "bypass off");
+    let help = String::from_utf8_lossy(&help.stdout);
+    assert!(help.contains("--guard-dry-run"), "{help}");
+    assert!(!help.contains("--guard-bypass"), "{help}");
+}
+
+#[tokio::test]
+async fn bypasses_beyond_the_budget_lock_gh_guard() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!("{}[bypass]\nper_day = 1\n", tg.config()));
+    let bypass = [
+        "api",
+        "-X",
+        "DELETE",
+        "repos/o/r/labels/a",
+        "--guard-bypass=hotfix",
+    ];
+
+    let first = env.run(&bypass);
+    let second = env.run(&bypass);
+    let asked = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+
+    assert_eq!(first.status.code(), Some(0), "{first:?}");
+    assert_eq!(second.status.code(), Some(6), "{second:?}");
+    assert_eq!(asked.status.code(), Some(6), "{asked:?}");
+    assert_eq!(env.gh_calls().len(), 1);
+    let notes = tg.calls("sendMessage").await;
+    assert_eq!(notes.len(), 2);
+    let lock_note = notes[1]["text"].as_str().unwrap();
+    assert!(lock_note.contains("2 bypasses within a day"), "{lock_note}");
+    let reasons: Vec<_> = env.audit().iter().map(|e| e["reason"].clone()).collect();
+    assert_eq!(
+        reasons,
+        [json!("hotfix"), json!("bypass budget"), json!("locked")]
+    );
+}
+
+#[tokio::test]
+async fn repository_deletions_are_never_run_without_a_card() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "{}[bypass]\nper_day = 5\n[ci]\npolicy = \"passthrough\"\n",
+        tg.config()
+    ));
+
+    let bypassed = env.run(&["repo", "delete", "o/r", "--yes", "--guard-bypass=cleanup"]);
+    let in_ci = env
+        .gh_guard(&["repo", "delete", "o/r", "--yes"])
+        .env("CI", "true")
+        .output()
+        .unwrap();
+
+    assert_eq!(bypassed.status.code(), Some(6), "{bypassed:?}");
+    assert_eq!(in_ci.status.code(), Some(6), "{in_ci:?}");
+    assert!(env.gh_calls().is_empty());
+    assert!(tg.calls("sendMessage").await.is_empty());
+    let reasons: Vec<_> = env.audit().iter().map(|e| e["reason"].clone()).collect();
+    assert_eq!(
+        reasons,
+        [
+            json!("deletion needs a card"),
+            json!("deletion needs a card")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn ci_runs_on_an_approval_token_issued_from_the_phone() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!(
+        "{}[secrets]\nbackend = \"file\"\nfile = \"~/secrets.enc\"\nkey_file = \"~/secrets.key\"\n",
+        tg.config()
+    ));
+    std::fs::write(env.path().join("home/secrets.key"), [7u8; 32]).unwrap();
+
+    let issued = env.run(&["ci-token", "--repo", "o/*", "--hours", "1"]);
+    assert_eq!(issued.status.code(), Some(0), "{issued:?}");
+    let token = String::from_utf8(issued.stdout).unwrap();
+    let key = String::from_utf8(env.run(&["ci-token", "--public-key"]).stdout).unwrap();
+    let config = env.path().join("config.toml");
+    let ci = format!(
+        "[ci]\npolicy = \"token\"\npublic_key = \"{}\"\n",
+        key.trim()
+    );
+    std::fs::write(&config, std::fs::read_to_string(&config).unwrap() + &ci).unwrap();
+    let in_ci = |policy: &str, token: &str, repo: &str| {
+        env.gh_guard(&["api", "-X", "DELETE", &format!("repos/{repo}/labels/a")])
+            .env("CI", "true")
+            .env("GH_GUARD_CI_POLICY", policy)
+            .env("GH_GUARD_APPROVAL_TOKEN", token.trim())
+            .output()
+            .unwrap()
+    };
+
+    let codes: Vec<_> = [
+        in_ci("token", &token, "o/r"),
+        in_ci("token", &token, "x/r"),
+        in_ci("token", "ghguard1.00.00", "o/r"),
+        in_ci("token", "", "o/r"),
+        in_ci("reject", &token, "o/r"),
+        in_ci("passthrough", "", "x/r"),
+    ]
+    .iter()
+    .map(|out| out.status.code())
+    .collect();
+
+    // Passthrough from the environment is looser than the config: ignored.
+    assert_eq!(
+        codes,
+        [Some(0), Some(6), Some(6), Some(6), Some(6), Some(6)]
+    );
+    assert_eq!(env.gh_calls().len(), 1);
+    let cards = tg.calls("sendMessage").await;
+    assert_eq!(cards.len(), 1);
+    assert!(cards[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("<code>o/*</code>"));
+    let audit = env.audit();
+    let reasons: Vec<_> = audit.iter().map(|e| e["reason"].clone()).collect();
+    assert!(
+        reasons[0].as_str().unwrap().starts_with("ci token "),
+        "{reasons:?}"
+    );
+    assert_eq!(
+        reasons[1..],
+        [
+            json!("ci token for another repo"),
+            json!("ci token invalid"),
+            json!("ci token missing"),
+            json!("ci"),
+            json!("ci token missing")
+        ]
+    );
+    assert_eq!(audit[0]["decision"], "approved");
+}
+
+#[tokio::test]
+async fn ci_policy_from_the_environment_cannot_loosen_the_config() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&format!("{}[ci]\npolicy = \"ask\"\n", tg.config()));
+    let in_ci = |policy: &str| {
+        env.gh_guard(&["api", "-X", "DELETE", "repos/o/r/labels/a"])
+            .env("CI", "true")
+            .env("GH_GUARD_CI_POLICY", policy)
+            .output()
+            .unwrap()
+    };
+
+    let passthrough = in_ci("passthrough");
+    let reject = in_ci("reject");
+
+    assert_eq!(passthrough.status.code(), Some(0), "{passthrough:?}");
+    assert_eq!(reject.status.code(), Some(6), "{reject:?}");
+    // Asked on the phone, not passed through.
+    assert_eq!(tg.calls("sendMessage").await.len(), 1);
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "approved");
+    assert_eq!(audit[1]["reason"], "ci");
+}
+
+#[tokio::test]
+async fn read_only_mode_refuses_mutations_until_lifted_from_the_phone() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+
+    let on = env.run(&["readonly", "on"]);
+    let api = env.run(&["api", "-X", "DELETE", "repos/o/r/labels/a"]);
+    let create = env.run(&["issue", "create", "-t", "x"]);
+    let list = env.run(&["issue", "list"]);
+
+    assert_eq!(on.status.code(), Some(0), "{on:?}");
+    assert_eq!(api.status.code(), Some(6), "{api:?}");
+    assert_eq!(create.status.code(), Some(6), "{create:?}");
+    assert_eq!(list.status.code(), Some(0), "{list:?}");
+    assert!(String::from_utf8_lossy(&create.stderr).contains("Read-only mode"));
+    assert!(tg.calls("sendMessage").await.is_empty());
+
+    let off = env.run(&["readonly", "off"]);
+    let after = env.run(&["issue", "create", "-t", "x"]);
+
+    assert_eq!(off.status.code(), Some(0), "{off:?}");
+    assert_eq!(after.status.code(), Some(0), "{after:?}");
+    let cards = tg.calls("sendMessage").await;
+    assert_eq!(cards.len(), 1);
+    assert!(cards[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Leave read-only mode?"));
+    let ran: Vec<_> = env.gh_calls().into_iter().map(|c| c.args).collect();
+    assert_eq!(
+        ran,
+        [vec!["issue", "list"], vec!["issue", "create", "-t", "x"]]
+    );
+    let reasons: Vec<_> = env.audit().iter().map(|e| e["reason"].clone()).collect();
+    assert_eq!(reasons[..2], [json!("read-only"), json!("read-only")]);
+}
+
+#[tokio::test]
+async fn hook_allows_an_agent_command_once_approved() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let env = Env::new(&tg.config());
+    let input = json!({
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "gh api -X POST repos/o/r/issues -f title=x | jq .number"},
+    });
+
+    let out = env.run_with_input(&["hook"], &input.to_string());
+
+    assert_eq!(out.status.code(), Some(0), "{out:?}");
+    let answer: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(answer["hookSpecificOutput"]["permissionDecision"], "allow");
+    let sent = tg.calls("sendMessage").await;
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("POST repos/o/r/issues"));
+    // The agent runs the command; gh-guard only answers.
+    assert!(env.gh_calls().is_empty());
+    assert_eq!(env.audit().last().unwrap()["decision"], "approved");
+}
+
+#[test]
+fn hook_never_queues_a_request_it_has_denied() {
+    // Nothing listens here, so Telegram is unreachable.
+    let env = Env::new(
+        "[network]\ntelegram_api = \"http://127.0.0.1:9\"\n[offline_queue]\nmax_age_hours = 1\n",
+    );
+    let input = json!({
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "env -i gh api -X DELETE repos/o/r/labels/a"},
+    });
+
+    let out = env.run_with_input(&["hook"], &input.to_string());
+
+    let answer: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(answer["hookSpecificOutput"]["permissionDecision"], "deny");
+    assert!(!env.state_dir().join("queue.json").exists());
+}
+
+#[tokio::test]
+async fn quiet_hours_queue_requests_for_the_daemon() {
+    let tg = MockTelegram::start(Answer::Approve).await;
+    let now = chrono::Local::now();
+    let window = format!(
+        "[quiet_hours]\nstart = \"{}\"\nend = \"{}\"\nlow = \"queue\"\nmedium = \"queue\"\n\
+         high = \"queue\"\n",
+        (now - chrono::Duration::hours(1)).format("%H:%M"),
+        (now + chrono::Duration::hours(1)).format("%H:%M"),
+    );
+    let env = Env::new(&format!("{}{window}", tg.config()));
+
+    let out = env.run(MUTATION);
+
+    assert_eq!(out.status.code(), Some(7), "{out:?}");
+    let queue = std::fs::read_to_string(env.state_dir().join("queue.json")).unwrap();
+    let queue: serde_json::Value = serde_json::from_str(&queue).unwrap();
+    let id = queue["requests"][0]["id"].as_str().unwrap().to_string();
+    let audit = env.audit();
+    assert_eq!(audit[0]["decision"], "queued");
+    assert_eq!(audit[0]["reason"], "quiet hours");
+
+    // Still quiet: left in the queue, nothing sent.
+    let early = env.run(&["queue", "run", &id]);
+    assert_eq!(early.status.code(), Some(7), "{early:?}");
+    assert!(tg.calls("sendMessage").await.is_empty());
+    assert_eq!(env.audit().len(), 1);
+
+    std::fs::write(env.path().join("config.toml"), tg.config()).unwrap();
+    let delivered = env.run(&["queue", "run", &id]);
+
+    assert_eq!(delivered.status.code(), Some(0), "{delivered:?}");
+    let card = &tg.calls("sendMessage").await[0]["text"];
+    assert!(card.as_str().unwrap().starts_with("📥 <i>Held "), "{card}");
+    assert_eq!(env.gh_calls().len(), 1);
+    assert_eq!(env.audit()[1]["decision"], "approved");
+}